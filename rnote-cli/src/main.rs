@@ -0,0 +1,308 @@
+#![warn(missing_debug_implementations)]
+
+//! A command line interface to export, inspect and convert rnote documents without a display.
+
+use std::path::Path;
+
+use anyhow::Context;
+use rnote_engine::RnoteEngine;
+
+const USAGE: &str = "\
+Usage: rnote-cli <COMMAND> [ARGS]
+
+Commands:
+    export <INPUT> <OUTPUT> [--no-background]
+        Exports the document. The format is chosen by the extension of the output file: .pdf, .svg or .png
+    convert <INPUT> <OUTPUT>
+        Converts between the .rnote and the Xournal++ .xopp format, chosen by the extension of the output file
+    info <INPUT>...
+        Prints information about the documents, like the number of pages and the stroke counts
+    help
+        Prints this message
+
+The input files can be .rnote or Xournal++ .xopp files.";
+
+/// The file types, determined by the file extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileType {
+    Rnote,
+    Xopp,
+    Pdf,
+    Svg,
+    Png,
+}
+
+impl FileType {
+    fn from_path(path: &Path) -> anyhow::Result<Self> {
+        match path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .as_deref()
+        {
+            Some("rnote") => Ok(Self::Rnote),
+            Some("xopp") => Ok(Self::Xopp),
+            Some("pdf") => Ok(Self::Pdf),
+            Some("svg") => Ok(Self::Svg),
+            Some("png") => Ok(Self::Png),
+            _ => Err(anyhow::anyhow!(
+                "unsupported file type of file `{}`",
+                path.display()
+            )),
+        }
+    }
+}
+
+fn main() {
+    pretty_env_logger::init();
+
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+
+    if let Err(e) = run(&args) {
+        eprintln!("Error: {:?}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(args: &[String]) -> anyhow::Result<()> {
+    let (command, args) = match args.split_first() {
+        Some((command, args)) => (command.as_str(), args),
+        None => {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+    };
+
+    match command {
+        "export" => {
+            let with_background = !args.iter().any(|arg| arg == "--no-background");
+            let (input, output) = input_output_args(args, &["--no-background"])?;
+
+            export(input, output, with_background)
+        }
+        "convert" => {
+            let (input, output) = input_output_args(args, &[])?;
+
+            convert(input, output)
+        }
+        "info" => {
+            if args.is_empty() {
+                return Err(anyhow::anyhow!("missing input files\n\n{}", USAGE));
+            }
+
+            for input in args {
+                info(Path::new(input))?;
+            }
+            Ok(())
+        }
+        "help" | "-h" | "--help" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        command => Err(anyhow::anyhow!(
+            "unknown command `{}`\n\n{}",
+            command,
+            USAGE
+        )),
+    }
+}
+
+/// Extracts the input and output path from the args, skipping the given options. Other options are rejected
+fn input_output_args<'a>(
+    args: &'a [String],
+    options: &[&str],
+) -> anyhow::Result<(&'a Path, &'a Path)> {
+    if let Some(unknown_option) = args
+        .iter()
+        .find(|arg| arg.starts_with("--") && !options.contains(&arg.as_str()))
+    {
+        return Err(anyhow::anyhow!(
+            "unknown option `{}`\n\n{}",
+            unknown_option,
+            USAGE
+        ));
+    }
+
+    let paths = args
+        .iter()
+        .filter(|arg| !arg.starts_with("--"))
+        .collect::<Vec<&String>>();
+
+    match paths.as_slice() {
+        [input, output] => Ok((Path::new(input.as_str()), Path::new(output.as_str()))),
+        _ => Err(anyhow::anyhow!(
+            "expected an input and an output file\n\n{}",
+            USAGE
+        )),
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|file_name| file_name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|file_stem| file_stem.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Opens the .rnote or .xopp file into a new engine
+fn open_doc(input: &Path) -> anyhow::Result<RnoteEngine> {
+    let bytes = std::fs::read(input)
+        .with_context(|| format!("reading file `{}` failed", input.display()))?;
+    let mut engine = RnoteEngine::new(None);
+
+    match FileType::from_path(input)? {
+        FileType::Rnote => engine.open_from_rnote_bytes_headless(bytes),
+        FileType::Xopp => engine.open_from_xopp_bytes_headless(bytes),
+        _ => Err(anyhow::anyhow!("input file must be a .rnote or .xopp file")),
+    }
+    .with_context(|| format!("opening file `{}` failed", input.display()))?;
+
+    // The title is displayed in the page headers and footers. It is not part of the file, the app also uses the file name
+    engine.document.title = file_stem(input);
+
+    Ok(engine)
+}
+
+fn write_file(output: &Path, bytes: &[u8]) -> anyhow::Result<()> {
+    std::fs::write(output, bytes)
+        .with_context(|| format!("writing file `{}` failed", output.display()))
+}
+
+fn export(input: &Path, output: &Path, with_background: bool) -> anyhow::Result<()> {
+    let engine = open_doc(input)?;
+
+    let bytes = match FileType::from_path(output)? {
+        FileType::Pdf => {
+            engine.export_doc_as_pdf_bytes_headless(file_name(output), with_background)?
+        }
+        FileType::Svg => engine
+            .export_doc_as_svg_string(with_background)?
+            .into_bytes(),
+        FileType::Png => engine.export_doc_as_png_bytes(with_background)?,
+        FileType::Rnote | FileType::Xopp => {
+            return Err(anyhow::anyhow!(
+                "exporting to .rnote or .xopp files is done with the `convert` command"
+            ));
+        }
+    };
+
+    write_file(output, &bytes)
+}
+
+fn convert(input: &Path, output: &Path) -> anyhow::Result<()> {
+    let engine = open_doc(input)?;
+
+    let bytes = match FileType::from_path(output)? {
+        FileType::Rnote => engine.save_as_rnote_bytes_headless(file_name(output))?,
+        FileType::Xopp => engine.export_doc_as_xopp_bytes(&file_name(output))?,
+        FileType::Pdf | FileType::Svg | FileType::Png => {
+            return Err(anyhow::anyhow!(
+                "converting to .pdf, .svg or .png files is done with the `export` command"
+            ));
+        }
+    };
+
+    write_file(output, &bytes)
+}
+
+fn info(input: &Path) -> anyhow::Result<()> {
+    let doc_info = open_doc(input)?.doc_info();
+
+    println!("{}", input.display());
+    println!("    title: {}", doc_info.title);
+    println!("    layout: {:?}", doc_info.layout);
+    println!("    size: {:.1} x {:.1}", doc_info.width, doc_info.height);
+    println!(
+        "    pages: {} ({} with content)",
+        doc_info.n_pages, doc_info.n_pages_w_content
+    );
+    println!("    strokes: {}", doc_info.n_strokes());
+    println!("        brushstrokes: {}", doc_info.n_brushstrokes);
+    println!("        shapestrokes: {}", doc_info.n_shapestrokes);
+    println!("        textstrokes: {}", doc_info.n_textstrokes);
+    println!("        vectorimages: {}", doc_info.n_vectorimages);
+    println!("        bitmapimages: {}", doc_info.n_bitmapimages);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn file_type_from_extension() {
+        assert_eq!(
+            FileType::from_path(Path::new("notes.rnote")).unwrap(),
+            FileType::Rnote
+        );
+        assert_eq!(
+            FileType::from_path(Path::new("dir/notes.xopp")).unwrap(),
+            FileType::Xopp
+        );
+        assert_eq!(
+            FileType::from_path(Path::new("notes.PDF")).unwrap(),
+            FileType::Pdf
+        );
+        assert_eq!(
+            FileType::from_path(Path::new("notes.svg")).unwrap(),
+            FileType::Svg
+        );
+        assert_eq!(
+            FileType::from_path(Path::new("notes.png")).unwrap(),
+            FileType::Png
+        );
+    }
+
+    #[test]
+    fn unsupported_file_types() {
+        assert!(FileType::from_path(Path::new("notes.txt")).is_err());
+        assert!(FileType::from_path(Path::new("notes")).is_err());
+    }
+
+    #[test]
+    fn input_output_args_skip_options() {
+        let args = args(&["in.rnote", "--no-background", "out.pdf"]);
+
+        assert_eq!(
+            input_output_args(&args, &["--no-background"]).unwrap(),
+            (Path::new("in.rnote"), Path::new("out.pdf"))
+        );
+    }
+
+    #[test]
+    fn input_output_args_reject_unknown_options() {
+        let args = args(&["in.rnote", "out.pdf", "--no-backgrond"]);
+
+        assert!(input_output_args(&args, &["--no-background"]).is_err());
+        assert!(input_output_args(&args, &[]).is_err());
+    }
+
+    #[test]
+    fn input_output_args_need_two_paths() {
+        assert!(input_output_args(&args(&["in.rnote"]), &[]).is_err());
+        assert!(input_output_args(&args(&["in.rnote", "out.xopp", "other.xopp"]), &[]).is_err());
+    }
+
+    #[test]
+    fn unknown_commands_and_options_are_rejected() {
+        assert!(run(&args(&["import", "in.rnote"])).is_err());
+        assert!(run(&args(&[
+            "convert",
+            "in.rnote",
+            "out.xopp",
+            "--no-background"
+        ]))
+        .is_err());
+        assert!(run(&args(&["info"])).is_err());
+        assert!(run(&args(&["help"])).is_ok());
+    }
+}
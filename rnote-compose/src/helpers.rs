@@ -456,10 +456,17 @@ pub fn scale_inner_bounds_in_context_new_outer_bounds(
         new_outer_bounds.mins[1] - old_outer_bounds.mins[1]
     ];
 
-    let scalevector = na::vector![
-        (new_outer_bounds.extents()[0]) / (old_outer_bounds.extents()[0]),
-        (new_outer_bounds.extents()[1]) / (old_outer_bounds.extents()[1])
-    ];
+    // Axes without an extent can't be scaled
+    let scalevector = new_outer_bounds.extents().zip_map(
+        &old_outer_bounds.extents(),
+        |new_extent, old_extent| {
+            if old_extent > 0.0 {
+                new_extent / old_extent
+            } else {
+                1.0
+            }
+        },
+    );
 
     AABB::new(
         na::point![
@@ -506,3 +513,22 @@ impl KurboHelpers for kurbo::Line {}
 impl KurboHelpers for kurbo::QuadBez {}
 impl KurboHelpers for kurbo::Rect {}
 impl KurboHelpers for kurbo::RoundedRect {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scaling_inner_bounds_keeps_axes_without_extent() {
+        // A horizontal line
+        let old_bounds = AABB::new(na::point![0.0, 10.0], na::point![100.0, 10.0]);
+        let new_bounds = AABB::new(na::point![0.0, 20.0], na::point![200.0, 20.0]);
+        let inner_bounds = AABB::new(na::point![50.0, 10.0], na::point![100.0, 10.0]);
+
+        let scaled_bounds =
+            scale_inner_bounds_in_context_new_outer_bounds(inner_bounds, old_bounds, new_bounds);
+
+        assert_eq!(scaled_bounds.mins, na::point![100.0, 20.0]);
+        assert_eq!(scaled_bounds.maxs, na::point![200.0, 20.0]);
+    }
+}
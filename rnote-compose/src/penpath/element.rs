@@ -13,6 +13,15 @@ pub struct Element {
     #[serde(rename = "pressure")]
     /// The pen pressure. The valid range is [0.0, 1.0]
     pub pressure: f64,
+    #[serde(rename = "tilt", skip_serializing_if = "Option::is_none")]
+    /// The pen tilt, as angle from the perpendicular of the surface in radians. The valid range is [0.0, π/2]
+    pub tilt: Option<f64>,
+    #[serde(rename = "azimuth", skip_serializing_if = "Option::is_none")]
+    /// The direction the pen is tilted towards, as angle from the x-axis in radians
+    pub azimuth: Option<f64>,
+    #[serde(rename = "timestamp", skip_serializing_if = "Option::is_none")]
+    /// The time of the input in seconds. Only the difference between the timestamps of elements is meaningful
+    pub timestamp: Option<f64>,
 }
 
 impl Default for Element {
@@ -30,6 +39,40 @@ impl Element {
         Self {
             pos,
             pressure: pressure.clamp(0.0, 1.0),
+            tilt: None,
+            azimuth: None,
+            timestamp: None,
+        }
+    }
+
+    /// A new element with the tilt and azimuth computed from the tilt angles along the x and y axis in radians
+    pub fn with_axes_tilt(mut self, x_tilt: f64, y_tilt: f64) -> Self {
+        let tilt_vec = na::vector![x_tilt.tan(), y_tilt.tan()];
+
+        self.tilt = Some(
+            tilt_vec
+                .magnitude()
+                .atan()
+                .clamp(0.0, std::f64::consts::FRAC_PI_2),
+        );
+        self.azimuth = Some(tilt_vec[1].atan2(tilt_vec[0]));
+        self
+    }
+
+    /// A new element with the timestamp in seconds
+    pub fn with_timestamp(mut self, timestamp: f64) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// The velocity when moving from the previous element to this element. None if the timestamps are not available
+    pub fn velocity(&self, prev: &Self) -> Option<f64> {
+        let delta_time = self.timestamp? - prev.timestamp?;
+
+        if delta_time > 0.0 {
+            Some((self.pos - prev.pos).magnitude() / delta_time)
+        } else {
+            None
         }
     }
 
@@ -46,6 +89,10 @@ impl Element {
     /// Transforms the element position by the transform
     pub fn transform_by(&mut self, transform: na::Affine2<f64>) {
         self.pos = (transform * na::Point2::from(self.pos)).coords;
+        self.azimuth = self.azimuth.map(|azimuth| {
+            let direction = transform * na::vector![azimuth.cos(), azimuth.sin()];
+            direction[1].atan2(direction[0])
+        });
     }
 
     /// transform pen input data entries
@@ -55,3 +102,48 @@ impl Element {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn velocity_from_timestamps() {
+        let prev = Element::new(na::vector![0.0, 0.0], 0.5).with_timestamp(1.0);
+        let next = Element::new(na::vector![30.0, 40.0], 0.5).with_timestamp(1.5);
+
+        assert_eq!(next.velocity(&prev), Some(100.0));
+        // no velocity without a positive time difference
+        assert_eq!(prev.velocity(&next), None);
+        assert_eq!(prev.velocity(&prev), None);
+        assert_eq!(
+            Element::new(na::vector![30.0, 40.0], 0.5).velocity(&prev),
+            None
+        );
+    }
+
+    #[test]
+    fn tilt_from_axes() {
+        let upright = Element::default().with_axes_tilt(0.0, 0.0);
+        assert_eq!(upright.tilt, Some(0.0));
+
+        let tilted = Element::default().with_axes_tilt(0.0, std::f64::consts::FRAC_PI_4);
+        assert!((tilted.tilt.unwrap() - std::f64::consts::FRAC_PI_4).abs() < 1e-9);
+        assert!((tilted.azimuth.unwrap() - std::f64::consts::FRAC_PI_2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn load_without_optional_fields() {
+        let element: Element = serde_json::from_str(r#"{"pos":[1.0,2.0],"pressure":0.7}"#).unwrap();
+        assert_eq!(element.pos, na::vector![1.0, 2.0]);
+        assert_eq!(element.pressure, 0.7);
+        assert_eq!(element.tilt, None);
+        assert_eq!(element.timestamp, None);
+
+        // and the missing fields are not written out
+        assert_eq!(
+            serde_json::to_string(&element).unwrap(),
+            r#"{"pos":[1.0,2.0],"pressure":0.7}"#
+        );
+    }
+}
@@ -79,6 +79,105 @@ impl PenPath {
         Self(segment_vec)
     }
 
+    /// The spacing between the samples when estimating the centerline of an outline
+    pub const CENTERLINE_SAMPLE_SPACING: f64 = 4.0;
+
+    /// Estimates the centerline of a closed outline polygon, for example the outline of a variable width stroke.
+    ///
+    /// The outline is split at its two most distant points into two sides, which are then sampled at the same relative lengths.
+    /// The centerline runs through the midpoints between the samples of both sides, and their distance is the local width.
+    /// Returns the path together with the maximum width. The pressure of the elements is the local width relative to it.
+    pub fn from_closed_outline(outline: &[na::Vector2<f64>]) -> Option<(Self, f64)> {
+        if outline.len() < 3 {
+            return None;
+        }
+
+        // The ends of the centerline are the most distant points of the outline
+        let (first, second) = farthest_points(outline)?;
+
+        // both sides run from the first to the second point
+        let side_a = outline[first..=second].to_vec();
+        let side_b = outline[second..]
+            .iter()
+            .chain(outline[..=first].iter())
+            .rev()
+            .copied()
+            .collect::<Vec<na::Vector2<f64>>>();
+
+        let n_samples = ((polyline_length(&side_a).max(polyline_length(&side_b))
+            / Self::CENTERLINE_SAMPLE_SPACING)
+            .ceil() as usize)
+            .max(2);
+        let samples_a = resample_polyline(&side_a, n_samples);
+        let samples_b = resample_polyline(&side_b, n_samples);
+
+        let (centers, widths): (Vec<na::Vector2<f64>>, Vec<f64>) = samples_a
+            .iter()
+            .zip(samples_b.iter())
+            .map(|(a, b)| ((a + b) * 0.5, (b - a).magnitude()))
+            .unzip();
+
+        let max_width = widths.iter().copied().fold(0.0, f64::max);
+        if max_width <= 0.0 {
+            return None;
+        }
+
+        let elements = centers
+            .into_iter()
+            .zip(widths)
+            .map(|(pos, width)| Element::new(pos, width / max_width))
+            .collect::<Vec<Element>>();
+
+        let path = elements
+            .windows(2)
+            .map(|elements| Segment::Line {
+                start: elements[0],
+                end: elements[1],
+            })
+            .collect::<Self>();
+
+        Some((path, max_width))
+    }
+
+    /// A decimated version of the path for rendering with a reduced level of detail.
+    ///
+    /// Only keeps the elements which are at least min_distance apart from the previously kept element, and connects them with lines.
+    /// The curvature of bezier segments is lost. The first and the last element are always kept,
+    /// except for paths where all elements are closer than min_distance to the first one, which become a dot.
+    pub fn decimated(&self, min_distance: f64) -> Self {
+        let min_distance_squared = min_distance.powi(2);
+        let mut elements = self.clone().into_elements().into_iter();
+
+        let first = match elements.next() {
+            Some(first) => first,
+            None => return Self::default(),
+        };
+        let mut last = first;
+        let mut kept = vec![first];
+
+        for element in elements {
+            last = element;
+
+            if (element.pos - kept[kept.len() - 1].pos).magnitude_squared() >= min_distance_squared
+            {
+                kept.push(element);
+            }
+        }
+        if kept.len() == 1 {
+            return Self::new_w_dot(first);
+        }
+        if kept[kept.len() - 1].pos != last.pos {
+            kept.push(last);
+        }
+
+        kept.windows(2)
+            .map(|elements| Segment::Line {
+                start: elements[0],
+                end: elements[1],
+            })
+            .collect()
+    }
+
     /// extracts the elements from the path. the path shape will be lost, as only the actual input elements are returned.
     pub fn into_elements(self) -> Vec<Element> {
         self.0
@@ -103,3 +202,263 @@ impl std::iter::FromIterator<Segment> for PenPath {
         Self(VecDeque::from_iter(iter))
     }
 }
+
+/// The indices of the convex hull of the points, in counter clockwise order. Collinear points are left out
+fn convex_hull(points: &[na::Vector2<f64>]) -> Vec<usize> {
+    // the z component of the cross product of (a - o) and (b - o), positive for counter clockwise turns
+    let cross = |o: usize, a: usize, b: usize| {
+        let (oa, ob) = (points[a] - points[o], points[b] - points[o]);
+        oa[0] * ob[1] - oa[1] * ob[0]
+    };
+    let mut indices = (0..points.len()).collect::<Vec<usize>>();
+    indices.sort_unstable_by(|&a, &b| {
+        points[a][0]
+            .total_cmp(&points[b][0])
+            .then(points[a][1].total_cmp(&points[b][1]))
+    });
+
+    let mut hull: Vec<usize> = Vec::with_capacity(indices.len() + 1);
+
+    // The lower and then the upper part of the hull
+    for &i in indices.iter() {
+        while hull.len() >= 2 && cross(hull[hull.len() - 2], hull[hull.len() - 1], i) <= 0.0 {
+            hull.pop();
+        }
+        hull.push(i);
+    }
+    let lower_len = hull.len() + 1;
+    for &i in indices.iter().rev().skip(1) {
+        while hull.len() >= lower_len && cross(hull[hull.len() - 2], hull[hull.len() - 1], i) <= 0.0
+        {
+            hull.pop();
+        }
+        hull.push(i);
+    }
+    // the last point is the first one again
+    hull.pop();
+
+    hull
+}
+
+/// The indices of the two most distant points, in ascending order. None if there are no two distinct points.
+///
+/// Uses rotating calipers on the convex hull, so it runs in O(n log n)
+fn farthest_points(points: &[na::Vector2<f64>]) -> Option<(usize, usize)> {
+    let hull = convex_hull(points);
+    let n = hull.len();
+    if n < 2 {
+        return None;
+    }
+    let dist = |a: usize, b: usize| (points[b] - points[a]).magnitude_squared();
+    // twice the area of the triangle spanned by the hull edge from i to the next point and the hull point j
+    let area = |i: usize, j: usize| {
+        let (a, b, c) = (points[hull[i]], points[hull[(i + 1) % n]], points[hull[j]]);
+        ((b - a)[0] * (c - a)[1] - (b - a)[1] * (c - a)[0]).abs()
+    };
+
+    let mut farthest = (hull[0], hull[1], dist(hull[0], hull[1]));
+    let mut j = 1;
+
+    for i in 0..n {
+        // advance to the hull point that is the most distant from the edge
+        while area(i, (j + 1) % n) > area(i, j) {
+            j = (j + 1) % n;
+        }
+
+        for (a, b) in [(hull[i], hull[j]), (hull[(i + 1) % n], hull[j])] {
+            let d = dist(a, b);
+            if d > farthest.2 {
+                farthest = (a, b, d);
+            }
+        }
+    }
+
+    let (a, b, d) = farthest;
+    if d <= 0.0 {
+        return None;
+    }
+
+    Some((a.min(b), a.max(b)))
+}
+
+fn polyline_length(points: &[na::Vector2<f64>]) -> f64 {
+    points
+        .windows(2)
+        .map(|points| (points[1] - points[0]).magnitude())
+        .sum()
+}
+
+/// Samples the polyline at n_samples positions, evenly spaced along its length. Includes the start and the end.
+/// The polyline must not be empty.
+fn resample_polyline(points: &[na::Vector2<f64>], n_samples: usize) -> Vec<na::Vector2<f64>> {
+    let length = polyline_length(points);
+    let mut samples = Vec::with_capacity(n_samples);
+    let mut lines = points.windows(2);
+    let mut current_line = lines.next();
+    // the length along the polyline up to the start of the current line
+    let mut line_start_length = 0.0;
+
+    for i in 0..n_samples {
+        let sample_length = length * i as f64 / (n_samples - 1).max(1) as f64;
+
+        // advance to the line which contains the sample
+        while let Some(line) = current_line {
+            let line_length = (line[1] - line[0]).magnitude();
+            if line_start_length + line_length >= sample_length {
+                break;
+            }
+
+            line_start_length += line_length;
+            current_line = lines.next();
+        }
+
+        let sample = match current_line {
+            Some(line) => {
+                let line_length = (line[1] - line[0]).magnitude();
+                let t = if line_length > 0.0 {
+                    ((sample_length - line_start_length) / line_length).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+
+                line[0].lerp(&line[1], t)
+            }
+            // floating point inaccuracies might move the last samples beyond the end
+            None => points[points.len() - 1],
+        };
+        samples.push(sample);
+    }
+
+    samples
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn farthest_points_match_brute_force() {
+        let point_sets: Vec<Vec<na::Vector2<f64>>> = vec![
+            vec![na::vector![0.0, 0.0], na::vector![3.0, 4.0]],
+            (0..40)
+                .map(|i| {
+                    let angle = f64::from(i) / 40.0 * std::f64::consts::TAU;
+                    na::vector![angle.cos() * 30.0, angle.sin() * 5.0]
+                })
+                .collect(),
+            (0..60)
+                .map(|i| na::vector![f64::from((i * 37) % 11), f64::from((i * 13) % 7)])
+                .collect(),
+        ];
+
+        for points in point_sets {
+            let (first, second) = farthest_points(&points).unwrap();
+            assert!(first < second);
+
+            let brute_force = points
+                .iter()
+                .flat_map(|a| points.iter().map(move |b| (b - a).magnitude_squared()))
+                .fold(0.0, f64::max);
+            assert!(
+                ((points[second] - points[first]).magnitude_squared() - brute_force).abs() < 1e-9
+            );
+        }
+    }
+
+    #[test]
+    fn farthest_points_of_coincident_points() {
+        assert_eq!(farthest_points(&[]), None);
+        assert_eq!(farthest_points(&[na::vector![1.0, 1.0]; 3]), None);
+    }
+
+    #[test]
+    fn centerline_of_stadium_outline() {
+        // A stadium from (0.0, 0.0) to (100.0, 0.0) with a radius of 5.0, counter clockwise
+        let n_cap = 16;
+        let cap = |center_x: f64, start_angle: f64| {
+            (0..=n_cap).map(move |i| {
+                let angle = start_angle + f64::from(i) / f64::from(n_cap) * std::f64::consts::PI;
+                na::vector![center_x + angle.cos() * 5.0, angle.sin() * 5.0]
+            })
+        };
+        let outline = cap(100.0, -std::f64::consts::FRAC_PI_2)
+            .chain(cap(0.0, std::f64::consts::FRAC_PI_2))
+            .collect::<Vec<na::Vector2<f64>>>();
+
+        let (path, max_width) = PenPath::from_closed_outline(&outline).unwrap();
+        assert!((max_width - 10.0).abs() < 0.1);
+
+        let elements = path.into_elements();
+        assert!(elements.len() > 2);
+        for element in elements.iter() {
+            assert!(element.pos[1].abs() < 0.1);
+        }
+        let (first_x, last_x) = (
+            elements.first().unwrap().pos[0],
+            elements.last().unwrap().pos[0],
+        );
+        assert!((first_x.min(last_x) + 5.0).abs() < 0.1);
+        assert!((first_x.max(last_x) - 105.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn no_centerline_of_degenerate_outlines() {
+        assert!(
+            PenPath::from_closed_outline(&[na::vector![0.0, 0.0], na::vector![1.0, 0.0]]).is_none()
+        );
+        assert!(PenPath::from_closed_outline(&[na::vector![2.0, 2.0]; 4]).is_none());
+    }
+
+    // A path of lines along the x axis through the given x coordinates
+    fn line_path(xs: &[f64]) -> PenPath {
+        xs.windows(2)
+            .map(|xs| Segment::Line {
+                start: Element::new(na::vector![xs[0], 0.0], 0.5),
+                end: Element::new(na::vector![xs[1], 0.0], 0.5),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn decimating_empty_path() {
+        assert!(PenPath::default().decimated(2.0).is_empty());
+    }
+
+    #[test]
+    fn short_path_is_decimated_to_dot() {
+        let decimated = line_path(&[0.0, 0.5, 1.0, 1.5]).decimated(2.0);
+
+        assert_eq!(decimated.len(), 1);
+        assert!(
+            matches!(decimated[0], Segment::Dot { element } if element.pos == na::vector![0.0, 0.0])
+        );
+    }
+
+    #[test]
+    fn decimating_keeps_first_and_last_element() {
+        let decimated = line_path(&[0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 7.5]).decimated(3.0);
+        let elements = decimated.into_elements();
+
+        assert_eq!(elements.first().unwrap().pos, na::vector![0.0, 0.0]);
+        assert_eq!(elements.last().unwrap().pos, na::vector![7.5, 0.0]);
+    }
+
+    #[test]
+    fn decimated_elements_keep_min_distance() {
+        let xs = (0..100).map(|i| f64::from(i) * 0.3).collect::<Vec<f64>>();
+        let path = line_path(&xs);
+        let min_distance = 2.0;
+
+        let decimated = path.decimated(min_distance);
+        assert!(decimated.len() < path.len());
+
+        for segment in decimated.iter().take(decimated.len() - 1) {
+            match segment {
+                Segment::Line { start, end } => {
+                    assert!((end.pos - start.pos).magnitude() >= min_distance)
+                }
+                _ => panic!("decimated path contains {:?}", segment),
+            }
+        }
+    }
+}
@@ -267,6 +267,16 @@ impl Segment {
             Segment::CubBez { end, .. } => *end,
         }
     }
+
+    /// The velocity of the pen while drawing the segment. None for dots or when the elements have no timestamps
+    pub fn velocity(&self) -> Option<f64> {
+        match self {
+            Segment::Dot { .. } => None,
+            Segment::Line { start, end }
+            | Segment::QuadBez { start, end, .. }
+            | Segment::CubBez { start, end, .. } => end.velocity(start),
+        }
+    }
 }
 
 /// Calculates the number hitbox elems for the given length capped with a maximum no of hitbox elements
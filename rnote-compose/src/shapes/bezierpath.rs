@@ -0,0 +1,407 @@
+use p2d::bounding_volume::AABB;
+use serde::{Deserialize, Serialize};
+
+use crate::helpers::{KurboHelpers, Vector2Helpers};
+use crate::shapes::ShapeBehaviour;
+use crate::transform::TransformBehaviour;
+
+use super::{CubicBezier, Line, QuadraticBezier};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename = "path_segment")]
+/// A segment of a bezier path. It starts at the end of the previous segment
+pub enum PathSegment {
+    #[serde(rename = "line")]
+    /// A line segment
+    Line {
+        #[serde(rename = "end")]
+        /// The line end
+        end: na::Vector2<f64>,
+    },
+    #[serde(rename = "quadbez")]
+    /// A quadratic bezier segment
+    QuadBez {
+        #[serde(rename = "cp")]
+        /// The quadratic curve control point
+        cp: na::Vector2<f64>,
+        #[serde(rename = "end")]
+        /// The quadratic curve end
+        end: na::Vector2<f64>,
+    },
+    #[serde(rename = "cubbez")]
+    /// A cubic bezier segment
+    CubBez {
+        #[serde(rename = "cp1")]
+        /// The cubic curve first control point
+        cp1: na::Vector2<f64>,
+        #[serde(rename = "cp2")]
+        /// The cubic curve second control point
+        cp2: na::Vector2<f64>,
+        #[serde(rename = "end")]
+        /// The cubic curve end
+        end: na::Vector2<f64>,
+    },
+}
+
+impl PathSegment {
+    /// The end of the segment
+    pub fn end(&self) -> na::Vector2<f64> {
+        match self {
+            PathSegment::Line { end } => *end,
+            PathSegment::QuadBez { end, .. } => *end,
+            PathSegment::CubBez { end, .. } => *end,
+        }
+    }
+
+    /// All points of the segment ( control points and the end ) as mutable references
+    pub fn points_mut(&mut self) -> Vec<&mut na::Vector2<f64>> {
+        match self {
+            PathSegment::Line { end } => vec![end],
+            PathSegment::QuadBez { cp, end } => vec![cp, end],
+            PathSegment::CubBez { cp1, cp2, end } => vec![cp1, cp2, end],
+        }
+    }
+
+    /// The hitboxes of the segment, starting at start
+    fn hitboxes(&self, start: na::Vector2<f64>) -> Vec<AABB> {
+        match *self {
+            PathSegment::Line { end } => Line { start, end }.hitboxes(),
+            PathSegment::QuadBez { cp, end } => QuadraticBezier { start, cp, end }.hitboxes(),
+            PathSegment::CubBez { cp1, cp2, end } => CubicBezier {
+                start,
+                cp1,
+                cp2,
+                end,
+            }
+            .hitboxes(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// A editable node of a bezier path
+pub enum PathNode {
+    /// A point on the path, where segments start and end
+    Anchor {
+        /// The position
+        pos: na::Vector2<f64>,
+    },
+    /// A control point of a curve segment
+    Control {
+        /// The position
+        pos: na::Vector2<f64>,
+        /// The position of the anchor the control point is attached to
+        anchor: na::Vector2<f64>,
+    },
+}
+
+impl PathNode {
+    /// The position of the node
+    pub fn pos(&self) -> na::Vector2<f64> {
+        match self {
+            PathNode::Anchor { pos } => *pos,
+            PathNode::Control { pos, .. } => *pos,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "bezier_subpath")]
+/// A subpath of a bezier path, consisting of connected segments
+pub struct BezierSubpath {
+    #[serde(rename = "start")]
+    /// The start of the subpath
+    pub start: na::Vector2<f64>,
+    #[serde(rename = "segments")]
+    /// The segments
+    pub segments: Vec<PathSegment>,
+    #[serde(rename = "closed")]
+    /// Whether the subpath is closed with a line from the end back to the start
+    pub closed: bool,
+}
+
+impl Default for BezierSubpath {
+    fn default() -> Self {
+        Self {
+            start: na::Vector2::zeros(),
+            segments: vec![],
+            closed: false,
+        }
+    }
+}
+
+impl BezierSubpath {
+    /// The end of the last segment, or the start if the subpath has no segments
+    pub fn end(&self) -> na::Vector2<f64> {
+        self.segments
+            .last()
+            .map(|segment| segment.end())
+            .unwrap_or(self.start)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename = "bezier_path")]
+/// A general path made up of line, quadratic and cubic bezier segments.
+///
+/// It can consist of multiple subpaths and is filled with the even-odd rule, so subpaths inside others become holes.
+pub struct BezierPath {
+    #[serde(rename = "subpaths")]
+    /// The subpaths
+    pub subpaths: Vec<BezierSubpath>,
+}
+
+impl TransformBehaviour for BezierPath {
+    fn translate(&mut self, offset: nalgebra::Vector2<f64>) {
+        self.for_each_point_mut(|point| *point += offset);
+    }
+
+    fn rotate(&mut self, angle: f64, center: nalgebra::Point2<f64>) {
+        let mut isometry = na::Isometry2::identity();
+        isometry.append_rotation_wrt_point_mut(&na::UnitComplex::new(angle), &center);
+
+        self.for_each_point_mut(|point| *point = (isometry * na::Point2::from(*point)).coords);
+    }
+
+    fn scale(&mut self, scale: nalgebra::Vector2<f64>) {
+        self.for_each_point_mut(|point| *point = point.component_mul(&scale));
+    }
+}
+
+impl ShapeBehaviour for BezierPath {
+    fn bounds(&self) -> AABB {
+        self.to_kurbo().bounds_as_p2d_aabb()
+    }
+
+    fn hitboxes(&self) -> Vec<AABB> {
+        self.subpaths
+            .iter()
+            .flat_map(|subpath| {
+                let mut hitboxes = vec![];
+                let mut prev = subpath.start;
+
+                for segment in subpath.segments.iter() {
+                    hitboxes.append(&mut segment.hitboxes(prev));
+                    prev = segment.end();
+                }
+
+                if subpath.closed {
+                    hitboxes.append(
+                        &mut Line {
+                            start: prev,
+                            end: subpath.start,
+                        }
+                        .hitboxes(),
+                    );
+                }
+
+                hitboxes
+            })
+            .collect()
+    }
+}
+
+impl BezierPath {
+    /// The tolerance when flattening the path to polygons
+    pub const FLATTEN_TOLERANCE: f64 = 0.1;
+
+    /// New from a kurbo bezier path
+    pub fn from_kurbo(bez_path: &kurbo::BezPath) -> Self {
+        let mut subpaths: Vec<BezierSubpath> = vec![];
+
+        for el in bez_path.elements() {
+            match *el {
+                kurbo::PathEl::MoveTo(p) => subpaths.push(BezierSubpath {
+                    start: na::Vector2::from_kurbo_point(p),
+                    ..BezierSubpath::default()
+                }),
+                kurbo::PathEl::ClosePath => {
+                    if let Some(subpath) = subpaths.last_mut() {
+                        subpath.closed = true;
+                    }
+                }
+                el => {
+                    let segment = match el {
+                        kurbo::PathEl::LineTo(end) => PathSegment::Line {
+                            end: na::Vector2::from_kurbo_point(end),
+                        },
+                        kurbo::PathEl::QuadTo(cp, end) => PathSegment::QuadBez {
+                            cp: na::Vector2::from_kurbo_point(cp),
+                            end: na::Vector2::from_kurbo_point(end),
+                        },
+                        kurbo::PathEl::CurveTo(cp1, cp2, end) => PathSegment::CubBez {
+                            cp1: na::Vector2::from_kurbo_point(cp1),
+                            cp2: na::Vector2::from_kurbo_point(cp2),
+                            end: na::Vector2::from_kurbo_point(end),
+                        },
+                        _ => unreachable!(),
+                    };
+
+                    // Segments without a preceding move start a new subpath at the origin
+                    if subpaths.is_empty() {
+                        subpaths.push(BezierSubpath::default());
+                    }
+                    subpaths.last_mut().unwrap().segments.push(segment);
+                }
+            }
+        }
+
+        Self { subpaths }
+    }
+
+    /// New from closed polygons, where each polygon becomes a subpath made out of lines
+    pub fn from_polygons(polygons: Vec<Vec<na::Vector2<f64>>>) -> Self {
+        let subpaths = polygons
+            .into_iter()
+            .filter_map(|polygon| {
+                let mut points = polygon.into_iter();
+                let start = points.next()?;
+
+                Some(BezierSubpath {
+                    start,
+                    segments: points.map(|end| PathSegment::Line { end }).collect(),
+                    closed: true,
+                })
+            })
+            .collect();
+
+        Self { subpaths }
+    }
+
+    /// Converts the path to a kurbo bezier path
+    pub fn to_kurbo(&self) -> kurbo::BezPath {
+        let mut bez_path = kurbo::BezPath::new();
+
+        for subpath in self.subpaths.iter() {
+            bez_path.move_to(subpath.start.to_kurbo_point());
+
+            for segment in subpath.segments.iter() {
+                match segment {
+                    PathSegment::Line { end } => bez_path.line_to(end.to_kurbo_point()),
+                    PathSegment::QuadBez { cp, end } => {
+                        bez_path.quad_to(cp.to_kurbo_point(), end.to_kurbo_point())
+                    }
+                    PathSegment::CubBez { cp1, cp2, end } => bez_path.curve_to(
+                        cp1.to_kurbo_point(),
+                        cp2.to_kurbo_point(),
+                        end.to_kurbo_point(),
+                    ),
+                }
+            }
+
+            if subpath.closed {
+                bez_path.close_path();
+            }
+        }
+
+        bez_path
+    }
+
+    /// Flattens the subpaths to polygons. Every subpath is treated as closed.
+    pub fn to_polygons(&self, tolerance: f64) -> Vec<Vec<na::Vector2<f64>>> {
+        let mut polygons: Vec<Vec<na::Vector2<f64>>> = vec![];
+
+        kurbo::flatten(self.to_kurbo(), tolerance, |el| match el {
+            kurbo::PathEl::MoveTo(p) => polygons.push(vec![na::Vector2::from_kurbo_point(p)]),
+            kurbo::PathEl::LineTo(p) => {
+                if let Some(polygon) = polygons.last_mut() {
+                    polygon.push(na::Vector2::from_kurbo_point(p));
+                }
+            }
+            _ => {}
+        });
+
+        polygons.retain(|polygon| polygon.len() > 2);
+        polygons
+    }
+
+    /// The editable nodes of the path, in the same order as they are indexed in `move_node()`
+    pub fn nodes(&self) -> Vec<PathNode> {
+        let mut nodes = vec![];
+
+        for subpath in self.subpaths.iter() {
+            let mut prev = subpath.start;
+            nodes.push(PathNode::Anchor { pos: prev });
+
+            for segment in subpath.segments.iter() {
+                match *segment {
+                    PathSegment::Line { end } => {
+                        nodes.push(PathNode::Anchor { pos: end });
+                    }
+                    PathSegment::QuadBez { cp, end } => {
+                        nodes.push(PathNode::Control {
+                            pos: cp,
+                            anchor: prev,
+                        });
+                        nodes.push(PathNode::Anchor { pos: end });
+                    }
+                    PathSegment::CubBez { cp1, cp2, end } => {
+                        nodes.push(PathNode::Control {
+                            pos: cp1,
+                            anchor: prev,
+                        });
+                        nodes.push(PathNode::Control {
+                            pos: cp2,
+                            anchor: end,
+                        });
+                        nodes.push(PathNode::Anchor { pos: end });
+                    }
+                }
+
+                prev = segment.end();
+            }
+        }
+
+        nodes
+    }
+
+    /// Moves the node with the given index to a new position.
+    ///
+    /// When the last segment of a closed subpath ends at its start, the start and the end are moved together so that the subpath stays closed.
+    pub fn move_node(&mut self, index: usize, pos: na::Vector2<f64>) {
+        let mut i = 0;
+
+        for subpath in self.subpaths.iter_mut() {
+            let ends_at_start =
+                subpath.closed && !subpath.segments.is_empty() && subpath.end() == subpath.start;
+
+            let mut points = vec![&mut subpath.start];
+            points.extend(
+                subpath
+                    .segments
+                    .iter_mut()
+                    .flat_map(|segment| segment.points_mut()),
+            );
+            let n_points = points.len();
+
+            if (i..i + n_points).contains(&index) {
+                let j = index - i;
+
+                if ends_at_start && (j == 0 || j == n_points - 1) {
+                    *points[0] = pos;
+                    *points[n_points - 1] = pos;
+                } else {
+                    *points[j] = pos;
+                }
+                return;
+            }
+
+            i += n_points;
+        }
+    }
+
+    fn for_each_point_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&mut na::Vector2<f64>),
+    {
+        for subpath in self.subpaths.iter_mut() {
+            f(&mut subpath.start);
+
+            for segment in subpath.segments.iter_mut() {
+                for point in segment.points_mut() {
+                    f(point);
+                }
+            }
+        }
+    }
+}
@@ -1,7 +1,10 @@
 use p2d::bounding_volume::AABB;
 use serde::{Deserialize, Serialize};
 
-use super::{CubicBezier, Ellipse, Line, QuadraticBezier, Rectangle, ShapeBehaviour};
+use super::{
+    BezierPath, BezierSubpath, CubicBezier, Ellipse, Line, PathSegment, QuadraticBezier, Rectangle,
+    ShapeBehaviour,
+};
 use crate::penpath::Segment;
 use crate::transform::TransformBehaviour;
 
@@ -28,6 +31,9 @@ pub enum Shape {
     #[serde(rename = "segment")]
     /// A segment
     Segment(Segment),
+    #[serde(rename = "path")]
+    /// A bezier path
+    Path(BezierPath),
 }
 
 impl Default for Shape {
@@ -57,6 +63,9 @@ impl TransformBehaviour for Shape {
             Self::Segment(segment) => {
                 segment.translate(offset);
             }
+            Self::Path(path) => {
+                path.translate(offset);
+            }
         }
     }
 
@@ -80,6 +89,9 @@ impl TransformBehaviour for Shape {
             Self::Segment(segment) => {
                 segment.rotate(angle, center);
             }
+            Self::Path(path) => {
+                path.rotate(angle, center);
+            }
         }
     }
 
@@ -103,6 +115,9 @@ impl TransformBehaviour for Shape {
             Self::Segment(segment) => {
                 segment.scale(scale);
             }
+            Self::Path(path) => {
+                path.scale(scale);
+            }
         }
     }
 }
@@ -116,6 +131,7 @@ impl ShapeBehaviour for Shape {
             Self::QuadraticBezier(quadbez) => quadbez.bounds(),
             Self::CubicBezier(cubbez) => cubbez.bounds(),
             Self::Segment(segment) => segment.bounds(),
+            Self::Path(path) => path.bounds(),
         }
     }
     fn hitboxes(&self) -> Vec<AABB> {
@@ -126,6 +142,55 @@ impl ShapeBehaviour for Shape {
             Self::QuadraticBezier(quadbez) => quadbez.hitboxes(),
             Self::CubicBezier(cubbez) => cubbez.hitboxes(),
             Self::Segment(segment) => segment.hitboxes(),
+            Self::Path(path) => path.hitboxes(),
+        }
+    }
+}
+
+impl Shape {
+    /// Converts the shape into a bezier path, which has editable nodes
+    pub fn to_bezier_path(&self) -> BezierPath {
+        match self {
+            Self::Line(line) => BezierPath::from_kurbo(&kurbo::Shape::into_path(
+                line.to_kurbo(),
+                BezierPath::FLATTEN_TOLERANCE,
+            )),
+            Self::Rectangle(rectangle) => BezierPath::from_kurbo(&rectangle.to_kurbo()),
+            Self::Ellipse(ellipse) => BezierPath::from_kurbo(&kurbo::Shape::into_path(
+                ellipse.to_kurbo(),
+                BezierPath::FLATTEN_TOLERANCE,
+            )),
+            Self::QuadraticBezier(quadbez) => BezierPath::from_kurbo(&kurbo::Shape::into_path(
+                quadbez.to_kurbo(),
+                BezierPath::FLATTEN_TOLERANCE,
+            )),
+            Self::CubicBezier(cubbez) => BezierPath::from_kurbo(&kurbo::Shape::into_path(
+                cubbez.to_kurbo(),
+                BezierPath::FLATTEN_TOLERANCE,
+            )),
+            Self::Segment(segment) => {
+                let path_segment = match *segment {
+                    Segment::Dot { .. } => None,
+                    Segment::Line { end, .. } => Some(PathSegment::Line { end: end.pos }),
+                    Segment::QuadBez { cp, end, .. } => {
+                        Some(PathSegment::QuadBez { cp, end: end.pos })
+                    }
+                    Segment::CubBez { cp1, cp2, end, .. } => Some(PathSegment::CubBez {
+                        cp1,
+                        cp2,
+                        end: end.pos,
+                    }),
+                };
+
+                BezierPath {
+                    subpaths: vec![BezierSubpath {
+                        start: segment.start().pos,
+                        segments: path_segment.into_iter().collect(),
+                        closed: false,
+                    }],
+                }
+            }
+            Self::Path(path) => path.clone(),
         }
     }
 }
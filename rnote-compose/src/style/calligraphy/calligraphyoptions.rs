@@ -0,0 +1,50 @@
+use crate::style::{PressureCurve, StrokeDynamics};
+use crate::Color;
+
+use serde::{Deserialize, Serialize};
+
+/// Options for strokes drawn with a flat calligraphy nib
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "calligraphy_options")]
+pub struct CalligraphyOptions {
+    /// The stroke width, which is the width of the nib
+    #[serde(rename = "stroke_width")]
+    pub stroke_width: f64,
+    /// The stroke color
+    #[serde(rename = "stroke_color")]
+    pub stroke_color: Option<Color>,
+    /// The angle of the nib in radians, measured from the x-axis
+    #[serde(rename = "nib_angle")]
+    pub nib_angle: f64,
+    /// The thickness of the nib relative to its width. The valid range is [0.0, 1.0]
+    #[serde(rename = "nib_thickness")]
+    pub nib_thickness: f64,
+    /// Pressure curve
+    #[serde(rename = "pressure_curve")]
+    pub pressure_curve: PressureCurve,
+    /// The velocity and tilt dynamics
+    #[serde(rename = "dynamics")]
+    pub dynamics: StrokeDynamics,
+}
+
+impl Default for CalligraphyOptions {
+    fn default() -> Self {
+        Self {
+            stroke_width: Self::WIDTH_DEFAULT,
+            stroke_color: Some(Color::BLACK),
+            nib_angle: Self::NIB_ANGLE_DEFAULT,
+            nib_thickness: Self::NIB_THICKNESS_DEFAULT,
+            pressure_curve: PressureCurve::default(),
+            dynamics: StrokeDynamics::default(),
+        }
+    }
+}
+
+impl CalligraphyOptions {
+    /// The default width
+    pub const WIDTH_DEFAULT: f64 = 1.0;
+    /// The default nib angle ( 45 degrees )
+    pub const NIB_ANGLE_DEFAULT: f64 = std::f64::consts::FRAC_PI_4;
+    /// The default nib thickness
+    pub const NIB_THICKNESS_DEFAULT: f64 = 0.15;
+}
@@ -0,0 +1,304 @@
+mod calligraphyoptions;
+
+// Re-exports
+pub use calligraphyoptions::CalligraphyOptions;
+
+use super::Composer;
+use crate::helpers::Vector2Helpers;
+use crate::penpath::{Element, Segment};
+use crate::shapes::{CubicBezier, Line, QuadraticBezier, ShapeBehaviour};
+use crate::PenPath;
+
+use p2d::bounding_volume::{BoundingVolume, AABB};
+
+// The width of the nib at the element with the pressure curve and the dynamics applied
+fn nib_width(element: &Element, velocity: Option<f64>, options: &CalligraphyOptions) -> f64 {
+    options
+        .pressure_curve
+        .apply(options.stroke_width, element.pressure)
+        * options.dynamics.width_factor(element, velocity)
+}
+
+// The corners of the nib with the given width, centered at the position
+fn nib_corners(
+    pos: na::Vector2<f64>,
+    width: f64,
+    options: &CalligraphyOptions,
+) -> [na::Point2<f64>; 4] {
+    let nib_dir = na::vector![options.nib_angle.cos(), options.nib_angle.sin()];
+    let half_width = nib_dir * width * 0.5;
+    let half_thickness = nib_dir.orth_unit() * width * options.nib_thickness.clamp(0.0, 1.0) * 0.5;
+
+    [
+        na::Point2::from(pos - half_width - half_thickness),
+        na::Point2::from(pos + half_width - half_thickness),
+        na::Point2::from(pos + half_width + half_thickness),
+        na::Point2::from(pos - half_width + half_thickness),
+    ]
+}
+
+// The maximum distance of the nib corners from the nib center, without pressure and dynamics applied
+fn nib_extent(options: &CalligraphyOptions) -> f64 {
+    options.stroke_width * 0.5 * (1.0 + options.nib_thickness.clamp(0.0, 1.0))
+}
+
+// Composes the area that the nib sweeps over when it moves through the lines, while the width changes linearly from start to end.
+// Must be drawn with only a fill
+fn compose_nib_sweep(
+    lines: &[Line],
+    width_start: f64,
+    width_end: f64,
+    options: &CalligraphyOptions,
+) -> kurbo::BezPath {
+    let n_lines = lines.len().max(1) as f64;
+    let mut bez_path = kurbo::BezPath::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        let line_width_start = width_start + (width_end - width_start) * (i as f64 / n_lines);
+        let line_width_end = width_start + (width_end - width_start) * ((i + 1) as f64 / n_lines);
+
+        let corners = nib_corners(line.start, line_width_start, options)
+            .into_iter()
+            .chain(nib_corners(line.end, line_width_end, options))
+            .collect::<Vec<na::Point2<f64>>>();
+
+        // The sweep of the nib along a line is the convex hull of the nib at its start and end.
+        // All hulls have the same orientation, so they don't cancel each other out when filled
+        let hull = p2d::transformation::convex_hull(&corners);
+
+        let mut hull_iter = hull.into_iter();
+        if let Some(first) = hull_iter.next() {
+            bez_path.move_to(first.coords.to_kurbo_point());
+
+            for point in hull_iter {
+                bez_path.line_to(point.coords.to_kurbo_point());
+            }
+            bez_path.close_path();
+        }
+    }
+
+    bez_path
+}
+
+impl Segment {
+    /// The filled outline of the area the calligraphy nib sweeps over, as it is drawn by the calligraphy composer
+    pub fn calligraphy_outline(&self, options: &CalligraphyOptions) -> kurbo::BezPath {
+        let velocity = self.velocity();
+
+        match self {
+            Segment::Dot { element } => {
+                let width = nib_width(element, None, options);
+
+                compose_nib_sweep(
+                    &[Line {
+                        start: element.pos,
+                        end: element.pos,
+                    }],
+                    width,
+                    width,
+                    options,
+                )
+            }
+            Segment::Line { start, end } => compose_nib_sweep(
+                &[Line {
+                    start: start.pos,
+                    end: end.pos,
+                }],
+                nib_width(start, velocity, options),
+                nib_width(end, velocity, options),
+                options,
+            ),
+            Segment::QuadBez { start, cp, end } => {
+                let n_splits = 5;
+
+                let quadbez = QuadraticBezier {
+                    start: start.pos,
+                    cp: *cp,
+                    end: end.pos,
+                };
+
+                compose_nib_sweep(
+                    &quadbez.approx_with_lines(n_splits),
+                    nib_width(start, velocity, options),
+                    nib_width(end, velocity, options),
+                    options,
+                )
+            }
+            Segment::CubBez {
+                start,
+                cp1,
+                cp2,
+                end,
+            } => {
+                let n_splits = 5;
+
+                let cubbez = CubicBezier {
+                    start: start.pos,
+                    cp1: *cp1,
+                    cp2: *cp2,
+                    end: end.pos,
+                };
+
+                compose_nib_sweep(
+                    &cubbez.approx_with_lines(n_splits),
+                    nib_width(start, velocity, options),
+                    nib_width(end, velocity, options),
+                    options,
+                )
+            }
+        }
+    }
+}
+
+impl PenPath {
+    /// The filled outlines of the segments, as they are drawn by the calligraphy composer. Outlines of neighbouring segments overlap
+    pub fn calligraphy_outlines(&self, options: &CalligraphyOptions) -> Vec<kurbo::BezPath> {
+        self.iter()
+            .map(|segment| segment.calligraphy_outline(options))
+            .collect()
+    }
+}
+
+impl Composer<CalligraphyOptions> for Line {
+    fn composed_bounds(&self, options: &CalligraphyOptions) -> AABB {
+        self.bounds().loosened(nib_extent(options))
+    }
+
+    fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &CalligraphyOptions) {
+        cx.save().unwrap();
+
+        let bez_path = compose_nib_sweep(
+            &[*self],
+            options.stroke_width,
+            options.stroke_width,
+            options,
+        );
+
+        if let Some(fill_color) = options.stroke_color {
+            let fill_brush = cx.solid_brush(fill_color.into());
+            cx.fill(bez_path, &fill_brush);
+        }
+
+        cx.restore().unwrap();
+    }
+}
+
+impl Composer<CalligraphyOptions> for Segment {
+    fn composed_bounds(&self, options: &CalligraphyOptions) -> AABB {
+        self.bounds()
+            .loosened(nib_extent(options) * options.dynamics.width_factor_max())
+    }
+
+    fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &CalligraphyOptions) {
+        cx.save().unwrap();
+
+        let bez_path = self.calligraphy_outline(options);
+
+        if let Some(mut fill_color) = options.stroke_color {
+            fill_color.a *= options.dynamics.segment_opacity_factor(self);
+
+            let fill_brush = cx.solid_brush(fill_color.into());
+            cx.fill(bez_path, &fill_brush);
+        }
+
+        cx.restore().unwrap();
+    }
+}
+
+impl Composer<CalligraphyOptions> for PenPath {
+    fn composed_bounds(&self, options: &CalligraphyOptions) -> AABB {
+        self.iter()
+            .map(|segment| segment.composed_bounds(options))
+            .fold(AABB::new_invalid(), |acc, x| acc.merged(&x))
+    }
+
+    fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &CalligraphyOptions) {
+        cx.save().unwrap();
+
+        if let Some(stroke_color) = options.stroke_color {
+            // The hulls of all segments have the same orientation, so consecutive segments with the same opacity are filled together as their union
+            let segment_outlines = self
+                .iter()
+                .map(|segment| (segment, segment.calligraphy_outline(options)));
+
+            for (opacity_factor, bez_path) in
+                options.dynamics.segment_opacity_groups(segment_outlines)
+            {
+                let mut fill_color = stroke_color;
+                fill_color.a *= opacity_factor;
+
+                let fill_brush = cx.solid_brush(fill_color.into());
+                cx.fill(bez_path, &fill_brush);
+            }
+        }
+
+        cx.restore().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::PressureCurve;
+    use kurbo::Shape;
+
+    fn options(nib_angle: f64) -> CalligraphyOptions {
+        CalligraphyOptions {
+            stroke_width: 10.0,
+            nib_angle,
+            nib_thickness: 0.2,
+            pressure_curve: PressureCurve::Const,
+            ..CalligraphyOptions::default()
+        }
+    }
+
+    fn line_segment() -> Segment {
+        Segment::Line {
+            start: Element::new(na::vector![0.0, 0.0], 1.0),
+            end: Element::new(na::vector![100.0, 0.0], 1.0),
+        }
+    }
+
+    #[test]
+    fn nib_along_stroke_direction_is_thin() {
+        let bounds = line_segment()
+            .calligraphy_outline(&options(0.0))
+            .bounding_box();
+
+        assert!((bounds.x0 + 5.0).abs() < 1e-9 && (bounds.x1 - 105.0).abs() < 1e-9);
+        assert!((bounds.height() - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn nib_across_stroke_direction_is_wide() {
+        let outline = line_segment().calligraphy_outline(&options(std::f64::consts::FRAC_PI_2));
+        let bounds = outline.bounding_box();
+
+        assert!((bounds.x0 + 1.0).abs() < 1e-9 && (bounds.x1 - 101.0).abs() < 1e-9);
+        assert!((bounds.height() - 10.0).abs() < 1e-9);
+        assert!((outline.area().abs() - 102.0 * 10.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn dot_is_the_nib() {
+        let dot = Segment::Dot {
+            element: Element::new(na::vector![5.0, 5.0], 1.0),
+        };
+        let outline = dot.calligraphy_outline(&options(std::f64::consts::FRAC_PI_4));
+
+        assert!((outline.area().abs() - 10.0 * 2.0).abs() < 1e-6);
+        assert!((outline.bounding_box().center().x - 5.0).abs() < 1e-9);
+        assert!((outline.bounding_box().center().y - 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn outline_inside_composed_bounds() {
+        let options = options(0.3);
+        let segment = line_segment();
+        let bounds = segment.calligraphy_outline(&options).bounding_box();
+        let composed_bounds = segment.composed_bounds(&options);
+
+        assert!(bounds.x0 >= composed_bounds.mins[0] && bounds.x1 <= composed_bounds.maxs[0]);
+        assert!(bounds.y0 >= composed_bounds.mins[1] && bounds.y1 <= composed_bounds.maxs[1]);
+    }
+}
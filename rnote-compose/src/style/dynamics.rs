@@ -0,0 +1,224 @@
+use serde::{Deserialize, Serialize};
+
+use crate::penpath::{Element, Segment};
+
+/// Maps the pen velocity and tilt to the width and opacity of a stroke.
+///
+/// All amounts are in the range [0.0, 1.0], where 0.0 disables the effect.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename = "stroke_dynamics")]
+pub struct StrokeDynamics {
+    /// How much the width is reduced when the pen moves fast
+    #[serde(rename = "velocity_width")]
+    pub velocity_width: f64,
+    /// How much the opacity is reduced when the pen moves fast
+    #[serde(rename = "velocity_opacity")]
+    pub velocity_opacity: f64,
+    /// How much the width is increased when the pen is tilted
+    #[serde(rename = "tilt_width")]
+    pub tilt_width: f64,
+    /// How much the opacity is reduced when the pen is tilted
+    #[serde(rename = "tilt_opacity")]
+    pub tilt_opacity: f64,
+}
+
+impl StrokeDynamics {
+    /// The velocity ( in coordinate units per second ) at which the velocity effects are fully applied
+    pub const VELOCITY_MAX: f64 = 2000.0;
+    /// The number of steps the opacity factor of segments is quantized to
+    const SEGMENT_OPACITY_STEPS: f64 = 32.0;
+
+    /// The width factor for the element, moving with the given velocity
+    pub fn width_factor(&self, element: &Element, velocity: Option<f64>) -> f64 {
+        (1.0 - self.velocity_width.clamp(0.0, 1.0) * Self::normalized_velocity(velocity))
+            * (1.0 + self.tilt_width.clamp(0.0, 1.0) * Self::normalized_tilt(element))
+    }
+
+    /// The opacity factor for the element, moving with the given velocity
+    pub fn opacity_factor(&self, element: &Element, velocity: Option<f64>) -> f64 {
+        (1.0 - self.velocity_opacity.clamp(0.0, 1.0) * Self::normalized_velocity(velocity))
+            * (1.0 - self.tilt_opacity.clamp(0.0, 1.0) * Self::normalized_tilt(element))
+    }
+
+    /// The opacity factor of the segment, the average of its start and end.
+    /// Quantized to steps, so that consecutive segments with nearly the same opacity can be filled together (see `segment_opacity_groups()`)
+    pub fn segment_opacity_factor(&self, segment: &Segment) -> f64 {
+        f64::from(self.segment_opacity_step(segment)) / Self::SEGMENT_OPACITY_STEPS
+    }
+
+    /// Groups the outlines of consecutive segments with the same opacity factor into single paths. Returns (opacity factor, path) tuples.
+    ///
+    /// The outlines of neighbouring segments overlap, so filling them one by one would render the joints darker when the color is translucent.
+    pub fn segment_opacity_groups<'a>(
+        &self,
+        segment_outlines: impl IntoIterator<Item = (&'a Segment, kurbo::BezPath)>,
+    ) -> Vec<(f64, kurbo::BezPath)> {
+        let mut groups: Vec<(u32, kurbo::BezPath)> = vec![];
+
+        for (segment, outline) in segment_outlines {
+            let opacity_step = self.segment_opacity_step(segment);
+
+            match groups.last_mut() {
+                Some((group_step, bez_path)) if *group_step == opacity_step => {
+                    bez_path.extend(outline);
+                }
+                _ => groups.push((opacity_step, outline)),
+            }
+        }
+
+        groups
+            .into_iter()
+            .map(|(opacity_step, bez_path)| {
+                (
+                    f64::from(opacity_step) / Self::SEGMENT_OPACITY_STEPS,
+                    bez_path,
+                )
+            })
+            .collect()
+    }
+
+    /// The upper bound of the width factor, needed to calculate the bounds of composed shapes
+    pub fn width_factor_max(&self) -> f64 {
+        1.0 + self.tilt_width.clamp(0.0, 1.0)
+    }
+
+    // The step of the quantized opacity factor of the segment
+    fn segment_opacity_step(&self, segment: &Segment) -> u32 {
+        let velocity = segment.velocity();
+        let opacity_factor = (self.opacity_factor(&segment.start(), velocity)
+            + self.opacity_factor(&segment.end(), velocity))
+            * 0.5;
+
+        (opacity_factor.clamp(0.0, 1.0) * Self::SEGMENT_OPACITY_STEPS).round() as u32
+    }
+
+    fn normalized_velocity(velocity: Option<f64>) -> f64 {
+        velocity
+            .map(|velocity| (velocity / Self::VELOCITY_MAX).clamp(0.0, 1.0))
+            .unwrap_or(0.0)
+    }
+
+    fn normalized_tilt(element: &Element) -> f64 {
+        element
+            .tilt
+            .map(|tilt| (tilt / std::f64::consts::FRAC_PI_2).clamp(0.0, 1.0))
+            .unwrap_or(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_effect_by_default() {
+        let dynamics = StrokeDynamics::default();
+        let element = Element::default().with_axes_tilt(1.0, 0.0);
+
+        assert_eq!(dynamics.width_factor(&element, Some(5000.0)), 1.0);
+        assert_eq!(dynamics.opacity_factor(&element, Some(5000.0)), 1.0);
+        assert_eq!(dynamics.width_factor_max(), 1.0);
+    }
+
+    #[test]
+    fn velocity_factors() {
+        let dynamics = StrokeDynamics {
+            velocity_width: 0.5,
+            velocity_opacity: 1.0,
+            ..StrokeDynamics::default()
+        };
+        let element = Element::default();
+
+        assert_eq!(dynamics.width_factor(&element, None), 1.0);
+        assert_eq!(
+            dynamics.width_factor(&element, Some(StrokeDynamics::VELOCITY_MAX * 0.5)),
+            0.75
+        );
+        // the velocity effect is capped
+        assert_eq!(
+            dynamics.width_factor(&element, Some(StrokeDynamics::VELOCITY_MAX * 4.0)),
+            0.5
+        );
+        assert_eq!(
+            dynamics.opacity_factor(&element, Some(StrokeDynamics::VELOCITY_MAX)),
+            0.0
+        );
+    }
+
+    #[test]
+    fn tilt_factors() {
+        let dynamics = StrokeDynamics {
+            tilt_width: 1.0,
+            tilt_opacity: 0.5,
+            ..StrokeDynamics::default()
+        };
+        let upright = Element::default();
+        let flat = Element {
+            tilt: Some(std::f64::consts::FRAC_PI_2),
+            ..Element::default()
+        };
+
+        assert_eq!(dynamics.width_factor(&upright, None), 1.0);
+        assert_eq!(dynamics.width_factor(&flat, None), 2.0);
+        assert_eq!(dynamics.width_factor_max(), 2.0);
+        assert_eq!(dynamics.opacity_factor(&flat, None), 0.5);
+    }
+
+    #[test]
+    fn segments_with_the_same_opacity_are_grouped() {
+        let dynamics = StrokeDynamics {
+            tilt_opacity: 1.0,
+            ..StrokeDynamics::default()
+        };
+        let segment = |x: f64, tilt: f64| Segment::Line {
+            start: Element {
+                pos: na::vector![x, 0.0],
+                tilt: Some(tilt),
+                ..Element::default()
+            },
+            end: Element {
+                pos: na::vector![x + 10.0, 0.0],
+                tilt: Some(tilt),
+                ..Element::default()
+            },
+        };
+        let segments = [
+            segment(0.0, 0.0),
+            segment(10.0, 0.001),
+            segment(20.0, std::f64::consts::FRAC_PI_4),
+        ];
+
+        let groups = dynamics.segment_opacity_groups(
+            segments
+                .iter()
+                .map(|segment| (segment, kurbo::BezPath::new())),
+        );
+
+        assert_eq!(
+            groups
+                .iter()
+                .map(|(opacity_factor, _)| *opacity_factor)
+                .collect::<Vec<f64>>(),
+            vec![1.0, 0.5]
+        );
+    }
+
+    #[test]
+    fn amounts_clamped() {
+        let dynamics = StrokeDynamics {
+            velocity_width: 3.0,
+            tilt_width: 2.0,
+            ..StrokeDynamics::default()
+        };
+        let flat = Element {
+            tilt: Some(std::f64::consts::FRAC_PI_2),
+            ..Element::default()
+        };
+
+        assert_eq!(dynamics.width_factor(&flat, Some(0.0)), 2.0);
+        assert_eq!(
+            dynamics.width_factor(&Element::default(), Some(StrokeDynamics::VELOCITY_MAX)),
+            0.0
+        );
+    }
+}
@@ -1,6 +1,12 @@
+/// The calligraphy module for calligraphy styles
+pub mod calligraphy;
 mod composer;
 /// Draw helpers
 pub mod drawhelpers;
+mod dynamics;
+/// The pencil module for pencil styles
+pub mod pencil;
+mod pressurespline;
 /// The rough module for rough styles
 pub mod rough;
 /// The smooth module for smooth styles
@@ -9,14 +15,18 @@ pub mod smooth;
 pub mod textured;
 
 // Re exports
+use self::calligraphy::CalligraphyOptions;
+use self::pencil::PencilOptions;
 use self::rough::RoughOptions;
 use self::smooth::SmoothOptions;
 use self::textured::TexturedOptions;
 pub use composer::Composer;
+pub use dynamics::StrokeDynamics;
+pub use pressurespline::PressureSpline;
 
 use crate::penpath::Segment;
-use crate::shapes::{CubicBezier, Ellipse, Line, QuadraticBezier, Rectangle};
-use crate::{PenPath, Shape};
+use crate::shapes::{BezierPath, CubicBezier, Ellipse, Line, QuadraticBezier, Rectangle};
+use crate::{Color, PenPath, Shape};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,6 +42,12 @@ pub enum Style {
     /// A textured style
     #[serde(rename = "textured")]
     Textured(TexturedOptions),
+    /// A calligraphy style
+    #[serde(rename = "calligraphy")]
+    Calligraphy(CalligraphyOptions),
+    /// A pencil style
+    #[serde(rename = "pencil")]
+    Pencil(PencilOptions),
 }
 
 impl Default for Style {
@@ -47,6 +63,32 @@ impl Style {
             Style::Smooth(options) => options.stroke_width,
             Style::Rough(options) => options.stroke_width,
             Style::Textured(options) => options.stroke_width,
+            Style::Calligraphy(options) => options.stroke_width,
+            Style::Pencil(options) => options.stroke_width,
+        }
+    }
+
+    /// returns the stroke color. available on all styles
+    pub fn stroke_color(&self) -> Option<Color> {
+        match self {
+            Style::Smooth(options) => options.stroke_color,
+            Style::Rough(options) => options.stroke_color,
+            Style::Textured(options) => options.stroke_color,
+            Style::Calligraphy(options) => options.stroke_color,
+            Style::Pencil(options) => options.stroke_color,
+        }
+    }
+
+    /// returns smooth options with the stroke width and color of the style.
+    /// Used as fallback for shapes that can't be composed with the style
+    pub fn smooth_fallback_options(&self) -> SmoothOptions {
+        match self {
+            Style::Smooth(options) => options.clone(),
+            _ => SmoothOptions {
+                stroke_width: self.stroke_width(),
+                stroke_color: self.stroke_color(),
+                ..SmoothOptions::default()
+            },
         }
     }
 }
@@ -57,6 +99,8 @@ impl Composer<Style> for Line {
             Style::Smooth(options) => self.composed_bounds(options),
             Style::Rough(options) => self.composed_bounds(options),
             Style::Textured(options) => self.composed_bounds(options),
+            Style::Calligraphy(options) => self.composed_bounds(options),
+            Style::Pencil(options) => self.composed_bounds(options),
         }
     }
 
@@ -65,6 +109,8 @@ impl Composer<Style> for Line {
             Style::Smooth(options) => self.draw_composed(cx, options),
             Style::Rough(options) => self.draw_composed(cx, options),
             Style::Textured(options) => self.draw_composed(cx, options),
+            Style::Calligraphy(options) => self.draw_composed(cx, options),
+            Style::Pencil(options) => self.draw_composed(cx, options),
         }
     }
 }
@@ -75,6 +121,8 @@ impl Composer<Style> for Rectangle {
             Style::Smooth(options) => self.composed_bounds(options),
             Style::Rough(options) => self.composed_bounds(options),
             Style::Textured(_options) => unimplemented!(),
+            Style::Calligraphy(_) => self.composed_bounds(&options.smooth_fallback_options()),
+            Style::Pencil(_) => self.composed_bounds(&options.smooth_fallback_options()),
         }
     }
 
@@ -83,6 +131,8 @@ impl Composer<Style> for Rectangle {
             Style::Smooth(options) => self.draw_composed(cx, options),
             Style::Rough(options) => self.draw_composed(cx, options),
             Style::Textured(_options) => unimplemented!(),
+            Style::Calligraphy(_) => self.draw_composed(cx, &options.smooth_fallback_options()),
+            Style::Pencil(_) => self.draw_composed(cx, &options.smooth_fallback_options()),
         }
     }
 }
@@ -93,6 +143,8 @@ impl Composer<Style> for Ellipse {
             Style::Smooth(options) => self.composed_bounds(options),
             Style::Rough(options) => self.composed_bounds(options),
             Style::Textured(_options) => unimplemented!(),
+            Style::Calligraphy(_) => self.composed_bounds(&options.smooth_fallback_options()),
+            Style::Pencil(_) => self.composed_bounds(&options.smooth_fallback_options()),
         }
     }
 
@@ -101,6 +153,8 @@ impl Composer<Style> for Ellipse {
             Style::Smooth(options) => self.draw_composed(cx, options),
             Style::Rough(options) => self.draw_composed(cx, options),
             Style::Textured(_options) => unimplemented!(),
+            Style::Calligraphy(_) => self.draw_composed(cx, &options.smooth_fallback_options()),
+            Style::Pencil(_) => self.draw_composed(cx, &options.smooth_fallback_options()),
         }
     }
 }
@@ -111,6 +165,8 @@ impl Composer<Style> for QuadraticBezier {
             Style::Smooth(options) => self.composed_bounds(options),
             Style::Rough(options) => self.composed_bounds(options),
             Style::Textured(_options) => unimplemented!(),
+            Style::Calligraphy(_) => self.composed_bounds(&options.smooth_fallback_options()),
+            Style::Pencil(_) => self.composed_bounds(&options.smooth_fallback_options()),
         }
     }
 
@@ -119,6 +175,8 @@ impl Composer<Style> for QuadraticBezier {
             Style::Smooth(options) => self.draw_composed(cx, options),
             Style::Rough(options) => self.draw_composed(cx, options),
             Style::Textured(_options) => unimplemented!(),
+            Style::Calligraphy(_) => self.draw_composed(cx, &options.smooth_fallback_options()),
+            Style::Pencil(_) => self.draw_composed(cx, &options.smooth_fallback_options()),
         }
     }
 }
@@ -129,6 +187,8 @@ impl Composer<Style> for CubicBezier {
             Style::Smooth(options) => self.composed_bounds(options),
             Style::Rough(options) => self.composed_bounds(options),
             Style::Textured(_options) => unimplemented!(),
+            Style::Calligraphy(_) => self.composed_bounds(&options.smooth_fallback_options()),
+            Style::Pencil(_) => self.composed_bounds(&options.smooth_fallback_options()),
         }
     }
 
@@ -137,6 +197,8 @@ impl Composer<Style> for CubicBezier {
             Style::Smooth(options) => self.draw_composed(cx, options),
             Style::Rough(options) => self.draw_composed(cx, options),
             Style::Textured(_options) => unimplemented!(),
+            Style::Calligraphy(_) => self.draw_composed(cx, &options.smooth_fallback_options()),
+            Style::Pencil(_) => self.draw_composed(cx, &options.smooth_fallback_options()),
         }
     }
 }
@@ -147,6 +209,8 @@ impl Composer<Style> for Segment {
             Style::Smooth(options) => self.composed_bounds(options),
             Style::Rough(options) => self.composed_bounds(options),
             Style::Textured(options) => self.composed_bounds(options),
+            Style::Calligraphy(options) => self.composed_bounds(options),
+            Style::Pencil(options) => self.composed_bounds(options),
         }
     }
 
@@ -155,6 +219,8 @@ impl Composer<Style> for Segment {
             Style::Smooth(options) => self.draw_composed(cx, options),
             Style::Rough(options) => self.draw_composed(cx, options),
             Style::Textured(options) => self.draw_composed(cx, options),
+            Style::Calligraphy(options) => self.draw_composed(cx, options),
+            Style::Pencil(options) => self.draw_composed(cx, options),
         }
     }
 }
@@ -165,6 +231,8 @@ impl Composer<Style> for PenPath {
             Style::Smooth(options) => self.composed_bounds(options),
             Style::Rough(options) => self.composed_bounds(options),
             Style::Textured(options) => self.composed_bounds(options),
+            Style::Calligraphy(options) => self.composed_bounds(options),
+            Style::Pencil(options) => self.composed_bounds(options),
         }
     }
 
@@ -173,6 +241,30 @@ impl Composer<Style> for PenPath {
             Style::Smooth(options) => self.draw_composed(cx, options),
             Style::Rough(options) => self.draw_composed(cx, options),
             Style::Textured(options) => self.draw_composed(cx, options),
+            Style::Calligraphy(options) => self.draw_composed(cx, options),
+            Style::Pencil(options) => self.draw_composed(cx, options),
+        }
+    }
+}
+
+impl Composer<Style> for BezierPath {
+    fn composed_bounds(&self, options: &Style) -> p2d::bounding_volume::AABB {
+        match options {
+            Style::Smooth(options) => self.composed_bounds(options),
+            Style::Rough(options) => self.composed_bounds(options),
+            Style::Textured(_) => self.composed_bounds(&options.smooth_fallback_options()),
+            Style::Calligraphy(_) => self.composed_bounds(&options.smooth_fallback_options()),
+            Style::Pencil(_) => self.composed_bounds(&options.smooth_fallback_options()),
+        }
+    }
+
+    fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &Style) {
+        match options {
+            Style::Smooth(options) => self.draw_composed(cx, options),
+            Style::Rough(options) => self.draw_composed(cx, options),
+            Style::Textured(_) => self.draw_composed(cx, &options.smooth_fallback_options()),
+            Style::Calligraphy(_) => self.draw_composed(cx, &options.smooth_fallback_options()),
+            Style::Pencil(_) => self.draw_composed(cx, &options.smooth_fallback_options()),
         }
     }
 }
@@ -186,6 +278,7 @@ impl Composer<Style> for Shape {
             Shape::QuadraticBezier(quadratic_bezier) => quadratic_bezier.composed_bounds(options),
             Shape::CubicBezier(cubic_bezier) => cubic_bezier.composed_bounds(options),
             Shape::Segment(segment) => segment.composed_bounds(options),
+            Shape::Path(path) => path.composed_bounds(options),
         }
     }
 
@@ -197,19 +290,18 @@ impl Composer<Style> for Shape {
             Shape::QuadraticBezier(quadratic_bezier) => quadratic_bezier.draw_composed(cx, options),
             Shape::CubicBezier(cubic_bezier) => cubic_bezier.draw_composed(cx, options),
             Shape::Segment(segment) => segment.draw_composed(cx, options),
+            Shape::Path(path) => path.draw_composed(cx, options),
         }
     }
 }
 
 /// The pressure curve used by some styles
-#[derive(
-    Debug, Clone, Copy, Serialize, Deserialize, num_derive::FromPrimitive, num_derive::ToPrimitive,
-)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename = "pressure_curve")]
 pub enum PressureCurve {
     /// Constant
     #[serde(rename = "const")]
-    Const = 0,
+    Const,
     /// linear
     #[serde(rename = "linear")]
     Linear,
@@ -225,6 +317,9 @@ pub enum PressureCurve {
     /// cubic polynomial
     #[serde(rename = "pow3")]
     Pow3,
+    /// a user defined curve
+    #[serde(rename = "custom")]
+    Custom(PressureSpline),
 }
 
 impl Default for PressureCurve {
@@ -243,6 +338,7 @@ impl PressureCurve {
             Self::Cbrt => width * pressure.cbrt(),
             Self::Pow2 => width * pressure.powi(2),
             Self::Pow3 => width * pressure.powi(3),
+            Self::Custom(spline) => width * spline.eval(pressure),
         }
     }
 }
@@ -250,12 +346,38 @@ impl PressureCurve {
 impl TryFrom<u32> for PressureCurve {
     type Error = anyhow::Error;
 
+    /// A custom curve is created with the default spline
     fn try_from(value: u32) -> Result<Self, Self::Error> {
-        num_traits::FromPrimitive::from_u32(value).ok_or_else(|| {
-            anyhow::anyhow!(
+        match value {
+            0 => Ok(Self::Const),
+            1 => Ok(Self::Linear),
+            2 => Ok(Self::Sqrt),
+            3 => Ok(Self::Cbrt),
+            4 => Ok(Self::Pow2),
+            5 => Ok(Self::Pow3),
+            6 => Ok(Self::Custom(PressureSpline::default())),
+            _ => Err(anyhow::anyhow!(
                 "PressureProfile try_from::<u32>() for value {} failed",
                 value
-            )
+            )),
+        }
+    }
+}
+
+impl num_traits::ToPrimitive for PressureCurve {
+    fn to_i64(&self) -> Option<i64> {
+        Some(match self {
+            Self::Const => 0,
+            Self::Linear => 1,
+            Self::Sqrt => 2,
+            Self::Cbrt => 3,
+            Self::Pow2 => 4,
+            Self::Pow3 => 5,
+            Self::Custom(_) => 6,
         })
     }
+
+    fn to_u64(&self) -> Option<u64> {
+        self.to_i64().map(|value| value as u64)
+    }
 }
@@ -0,0 +1,311 @@
+mod pencilgrain;
+mod penciloptions;
+
+// Re-exports
+pub use pencilgrain::PencilGrain;
+pub use penciloptions::PencilOptions;
+
+use super::smooth::SmoothOptions;
+use super::Composer;
+use crate::penpath::{Element, Segment};
+use crate::shapes::{Line, ShapeBehaviour};
+use crate::PenPath;
+use kurbo::Shape;
+use p2d::bounding_volume::{BoundingVolume, AABB};
+use std::collections::BTreeMap;
+
+/// The amount of opacity levels the grain coverage is quantized to. Every level is drawn with a single fill
+const COVERAGE_LEVELS: usize = 8;
+/// The maximum amount of grain cells across the stroke width. The cell size is doubled until the stroke fits into it
+const CELLS_ACROSS_MAX: f64 = 32.0;
+
+// The options for the smooth outline that is filled with the grain
+fn outline_options(options: &PencilOptions) -> SmoothOptions {
+    SmoothOptions {
+        stroke_width: options.stroke_width,
+        stroke_color: options.stroke_color,
+        pressure_curve: options.pressure_curve.clone(),
+        dynamics: options.dynamics,
+        ..SmoothOptions::default()
+    }
+}
+
+// The size of the grain cells for the stroke width.
+// Only grows in powers of two, so the grid of bigger cells stays aligned with the grid of the smaller ones
+fn grain_cell_size(options: &PencilOptions) -> f64 {
+    // clamped, so the loop terminates quickly for huge or infinite widths
+    let stroke_width = options.stroke_width.clamp(0.0, PencilOptions::WIDTH_MAX);
+    let mut cell_size = options.grain.cell_size();
+
+    while stroke_width / cell_size > CELLS_ACROSS_MAX {
+        cell_size *= 2.0;
+    }
+
+    cell_size
+}
+
+// The grain of the pencil inside the union of the segment outlines, as paths for every coverage level.
+// Cells that are covered by multiple segments get the grain of the segment with the highest pressure
+fn pencil_grain<'a>(
+    segments: impl IntoIterator<Item = &'a Segment>,
+    options: &PencilOptions,
+) -> Vec<(f64, kurbo::BezPath)> {
+    let outline_options = outline_options(options);
+    let cell_size = grain_cell_size(options);
+    let grain_strength = options.grain_strength.clamp(0.0, 1.0);
+    let seed = options.seed.unwrap_or_default();
+
+    // The pressure and the opacity factor for every covered cell, ordered by the cell indices so the paths are identical across renders
+    let mut covered_cells = BTreeMap::<(i64, i64), (f64, f64)>::new();
+
+    for segment in segments {
+        let outline = segment.smooth_outline(&outline_options);
+        let outline_bounds = outline.bounding_box();
+
+        let velocity = segment.velocity();
+        let pressure = (segment.start().pressure + segment.end().pressure) * 0.5;
+        let opacity_factor = (options.dynamics.opacity_factor(&segment.start(), velocity)
+            + options.dynamics.opacity_factor(&segment.end(), velocity))
+            * 0.5;
+
+        let cells_min = na::vector![
+            (outline_bounds.x0 / cell_size).floor() as i64,
+            (outline_bounds.y0 / cell_size).floor() as i64
+        ];
+        let cells_max = na::vector![
+            (outline_bounds.x1 / cell_size).ceil() as i64,
+            (outline_bounds.y1 / cell_size).ceil() as i64
+        ];
+
+        for iy in cells_min[1]..cells_max[1] {
+            for ix in cells_min[0]..cells_max[0] {
+                let cell_center = (na::vector![ix as f64, iy as f64]
+                    + na::Vector2::from_element(0.5))
+                    * cell_size;
+
+                if !outline.contains(kurbo::Point::new(cell_center[0], cell_center[1])) {
+                    continue;
+                }
+
+                let covered = covered_cells
+                    .entry((iy, ix))
+                    .or_insert((pressure, opacity_factor));
+                if pressure > covered.0 {
+                    *covered = (pressure, opacity_factor);
+                }
+            }
+        }
+    }
+
+    let mut level_paths = vec![kurbo::BezPath::new(); COVERAGE_LEVELS];
+
+    for ((iy, ix), (pressure, opacity_factor)) in covered_cells {
+        let cell = na::vector![ix, iy];
+
+        // Pressing harder deposits graphite in the valleys of the paper as well
+        let paper_height = options.grain.paper_height(cell);
+        // The jitter only depends on the cell, so the grain stays the same when the stroke is rendered again
+        let jitter = 0.85 + 0.15 * crate::utils::hash_grid_cell(cell, seed);
+        let coverage = (1.0 - grain_strength * (1.0 - paper_height) * (1.0 - pressure * 0.5))
+            * jitter
+            * opacity_factor;
+
+        let level = (coverage * COVERAGE_LEVELS as f64).round() as usize;
+        if level == 0 {
+            continue;
+        }
+
+        let cell_pos = na::vector![ix as f64, iy as f64] * cell_size;
+        level_paths[level.min(COVERAGE_LEVELS) - 1].extend(
+            kurbo::Rect::new(
+                cell_pos[0],
+                cell_pos[1],
+                cell_pos[0] + cell_size,
+                cell_pos[1] + cell_size,
+            )
+            .path_elements(0.1),
+        );
+    }
+
+    level_paths
+        .into_iter()
+        .enumerate()
+        .filter(|(_, path)| !path.elements().is_empty())
+        .map(|(i, path)| ((i + 1) as f64 / COVERAGE_LEVELS as f64, path))
+        .collect()
+}
+
+// Fills the grain levels with the stroke color
+fn draw_pencil_grain(
+    cx: &mut impl piet::RenderContext,
+    grain: Vec<(f64, kurbo::BezPath)>,
+    options: &PencilOptions,
+) {
+    if let Some(stroke_color) = options.stroke_color {
+        for (coverage, path) in grain {
+            let mut fill_color = stroke_color;
+            fill_color.a *= coverage;
+
+            let fill_brush = cx.solid_brush(fill_color.into());
+            cx.fill(path, &fill_brush);
+        }
+    }
+}
+
+impl Segment {
+    /// The grain of the pencil inside the segment outline, as paths for every coverage level.
+    ///
+    /// The grain cells are aligned to a grid in document coordinates, so the texture does not move with the stroke.
+    /// Returns (coverage, path) tuples, where the coverage is in the range (0.0, 1.0] and includes the opacity dynamics
+    pub fn pencil_grain(&self, options: &PencilOptions) -> Vec<(f64, kurbo::BezPath)> {
+        pencil_grain(std::iter::once(self), options)
+    }
+}
+
+impl PenPath {
+    /// The grain of the pencil inside the union of the segment outlines, as paths for every coverage level.
+    /// The overlapping outlines of neighbouring segments share their grain cells, so the joints are not drawn twice.
+    ///
+    /// Returns (coverage, path) tuples, where the coverage is in the range (0.0, 1.0] and includes the opacity dynamics
+    pub fn pencil_grain(&self, options: &PencilOptions) -> Vec<(f64, kurbo::BezPath)> {
+        pencil_grain(self.iter(), options)
+    }
+}
+
+impl Composer<PencilOptions> for Line {
+    fn composed_bounds(&self, options: &PencilOptions) -> AABB {
+        self.bounds().loosened(options.stroke_width * 0.5)
+    }
+
+    fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &PencilOptions) {
+        let segment = Segment::Line {
+            start: Element::new(self.start, Element::PRESSURE_DEFAULT),
+            end: Element::new(self.end, Element::PRESSURE_DEFAULT),
+        };
+
+        segment.draw_composed(cx, options);
+    }
+}
+
+impl Composer<PencilOptions> for Segment {
+    fn composed_bounds(&self, options: &PencilOptions) -> AABB {
+        self.bounds()
+            .loosened(options.stroke_width * 0.5 * options.dynamics.width_factor_max())
+    }
+
+    fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &PencilOptions) {
+        cx.save().unwrap();
+        draw_pencil_grain(cx, self.pencil_grain(options), options);
+        cx.restore().unwrap();
+    }
+}
+
+impl Composer<PencilOptions> for PenPath {
+    fn composed_bounds(&self, options: &PencilOptions) -> AABB {
+        self.iter()
+            .map(|segment| segment.composed_bounds(options))
+            .fold(AABB::new_invalid(), |acc, x| acc.merged(&x))
+    }
+
+    fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &PencilOptions) {
+        cx.save().unwrap();
+        draw_pencil_grain(cx, self.pencil_grain(options), options);
+        cx.restore().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line_segment() -> Segment {
+        Segment::Line {
+            start: Element::new(na::vector![0.0, 0.0], 0.6),
+            end: Element::new(na::vector![40.0, 25.0], 0.8),
+        }
+    }
+
+    #[test]
+    fn grain_identical_across_renders() {
+        let options = PencilOptions {
+            seed: Some(42),
+            stroke_width: 8.0,
+            ..PencilOptions::default()
+        };
+        let segment = line_segment();
+
+        let first = segment.pencil_grain(&options);
+        let second = segment.pencil_grain(&options);
+
+        assert!(!first.is_empty());
+        assert_eq!(first.len(), second.len());
+        for ((first_coverage, first_path), (second_coverage, second_path)) in
+            first.iter().zip(second.iter())
+        {
+            assert_eq!(first_coverage, second_coverage);
+            assert_eq!(first_path.elements(), second_path.elements());
+        }
+    }
+
+    #[test]
+    fn grain_coverage_in_range() {
+        let options = PencilOptions {
+            seed: Some(7),
+            stroke_width: 8.0,
+            grain: PencilGrain::Charcoal,
+            ..PencilOptions::default()
+        };
+
+        for (coverage, _) in line_segment().pencil_grain(&options) {
+            assert!(coverage > 0.0 && coverage <= 1.0);
+        }
+    }
+
+    #[test]
+    fn path_grain_covers_joints_once() {
+        let options = PencilOptions {
+            seed: Some(42),
+            stroke_width: 8.0,
+            ..PencilOptions::default()
+        };
+        let first = line_segment();
+        let second = Segment::Line {
+            start: Element::new(na::vector![40.0, 25.0], 0.8),
+            end: Element::new(na::vector![80.0, 10.0], 0.8),
+        };
+        let mut path = PenPath::new_w_segment(first.clone());
+        path.push_back(second.clone());
+
+        let n_cells = |grain: Vec<(f64, kurbo::BezPath)>| {
+            grain
+                .iter()
+                .flat_map(|(_, path)| path.elements())
+                .filter(|el| matches!(el, kurbo::PathEl::MoveTo(_)))
+                .count()
+        };
+
+        assert!(
+            n_cells(path.pencil_grain(&options))
+                < n_cells(first.pencil_grain(&options)) + n_cells(second.pencil_grain(&options))
+        );
+    }
+
+    #[test]
+    fn cell_size_for_widths() {
+        let cell_size = |stroke_width| {
+            grain_cell_size(&PencilOptions {
+                stroke_width,
+                ..PencilOptions::default()
+            })
+        };
+        let base = PencilGrain::Pencil.cell_size();
+
+        assert_eq!(cell_size(1.0), base);
+        assert_eq!(cell_size(base * CELLS_ACROSS_MAX * 2.0), base * 2.0);
+        assert_eq!(
+            cell_size(f64::INFINITY),
+            cell_size(PencilOptions::WIDTH_MAX)
+        );
+        assert_eq!(cell_size(f64::NAN), base);
+    }
+}
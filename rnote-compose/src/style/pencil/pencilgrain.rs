@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    Debug,
+    Eq,
+    PartialEq,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    num_derive::FromPrimitive,
+    num_derive::ToPrimitive,
+)]
+#[serde(rename = "pencil_grain")]
+/// The grain of the paper texture that is revealed by a pencil stroke
+pub enum PencilGrain {
+    #[serde(rename = "pencil")]
+    /// A fine grain, like a graphite pencil on paper
+    Pencil = 0,
+    #[serde(rename = "charcoal")]
+    /// A coarse grain, like charcoal on rough paper
+    Charcoal,
+}
+
+impl Default for PencilGrain {
+    fn default() -> Self {
+        Self::Pencil
+    }
+}
+
+impl TryFrom<u32> for PencilGrain {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        num_traits::FromPrimitive::from_u32(value).ok_or_else(|| {
+            anyhow::anyhow!("PencilGrain try_from::<u32>() for value {} failed", value)
+        })
+    }
+}
+
+impl PencilGrain {
+    /// The amount of grain cells that make up one cell of the coarse paper structure
+    const COARSE_CELLS: i64 = 4;
+
+    /// The size of the grain cells in document coordinates
+    pub fn cell_size(&self) -> f64 {
+        match self {
+            Self::Pencil => 0.8,
+            Self::Charcoal => 2.0,
+        }
+    }
+
+    /// The weight of the coarse paper structure compared to the fine grain, in the range [0.0, 1.0]
+    pub fn coarse_weight(&self) -> f64 {
+        match self {
+            Self::Pencil => 0.3,
+            Self::Charcoal => 0.6,
+        }
+    }
+
+    /// The height of the paper at the grain cell, in the range [0.0, 1.0].
+    ///
+    /// Only depends on the cell indices, so the texture is fixed in document space.
+    pub fn paper_height(&self, cell: na::Vector2<i64>) -> f64 {
+        let coarse_cell = cell.map(|v| v.div_euclid(Self::COARSE_CELLS));
+        let coarse_weight = self.coarse_weight();
+
+        (1.0 - coarse_weight) * crate::utils::hash_grid_cell(cell, 0)
+            + coarse_weight * crate::utils::hash_grid_cell(coarse_cell, 1)
+    }
+}
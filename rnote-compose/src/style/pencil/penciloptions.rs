@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+use crate::style::{PressureCurve, StrokeDynamics};
+use crate::Color;
+
+use super::pencilgrain::PencilGrain;
+
+/// The options for a pencil shape, which reveals the grain of a paper texture
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "pencil_options")]
+pub struct PencilOptions {
+    /// An optional seed to generate reproducable strokes
+    #[serde(rename = "seed")]
+    pub seed: Option<u64>,
+    /// The width
+    #[serde(rename = "stroke_width")]
+    pub stroke_width: f64,
+    /// The color of the stroke
+    #[serde(rename = "stroke_color")]
+    pub stroke_color: Option<Color>,
+    /// The paper grain
+    #[serde(rename = "grain")]
+    pub grain: PencilGrain,
+    /// How much the grain reduces the opacity. The valid range is [0.0, 1.0]
+    #[serde(rename = "grain_strength")]
+    pub grain_strength: f64,
+    /// Pressure curve
+    #[serde(rename = "pressure_curve")]
+    pub pressure_curve: PressureCurve,
+    /// The velocity and tilt dynamics
+    #[serde(rename = "dynamics")]
+    pub dynamics: StrokeDynamics,
+}
+
+impl Default for PencilOptions {
+    fn default() -> Self {
+        Self {
+            seed: None,
+            stroke_width: Self::WIDTH_DEFAULT,
+            stroke_color: Some(Color::BLACK),
+            grain: PencilGrain::default(),
+            grain_strength: Self::GRAIN_STRENGTH_DEFAULT,
+            pressure_curve: PressureCurve::default(),
+            dynamics: StrokeDynamics::default(),
+        }
+    }
+}
+
+impl PencilOptions {
+    /// The default width
+    pub const WIDTH_DEFAULT: f64 = 1.0;
+    /// The max width
+    pub const WIDTH_MAX: f64 = 1000.0;
+    /// The default grain strength
+    pub const GRAIN_STRENGTH_DEFAULT: f64 = 0.7;
+}
@@ -0,0 +1,331 @@
+use serde::{Deserialize, Serialize};
+
+/// A user defined pressure curve. It is a monotone cubic spline through the control points,
+/// which maps the pen pressure ( x-axis ) to a width factor ( y-axis ), both in the range [0.0, 1.0].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "pressure_spline", from = "PressureSplinePoints")]
+pub struct PressureSpline {
+    /// The control points, sorted by their x coordinate. The first and last point always sit at x = 0.0 and x = 1.0
+    #[serde(rename = "points")]
+    points: Vec<na::Vector2<f64>>,
+    /// The tangents of the spline at the control points. Updated whenever the points change
+    #[serde(skip)]
+    tangents: Vec<f64>,
+}
+
+impl Default for PressureSpline {
+    fn default() -> Self {
+        Self::from(PressureSplinePoints::default())
+    }
+}
+
+/// The serialized form of the spline, from which the tangents are recomputed when loading
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename = "pressure_spline")]
+struct PressureSplinePoints {
+    #[serde(rename = "points")]
+    points: Vec<na::Vector2<f64>>,
+}
+
+impl Default for PressureSplinePoints {
+    fn default() -> Self {
+        Self {
+            points: vec![
+                na::vector![0.0, 0.0],
+                na::vector![0.5, 0.5],
+                na::vector![1.0, 1.0],
+            ],
+        }
+    }
+}
+
+impl From<PressureSplinePoints> for PressureSpline {
+    fn from(spline_points: PressureSplinePoints) -> Self {
+        Self::new(spline_points.points)
+    }
+}
+
+impl PressureSpline {
+    /// The minimum distance between the x coordinates of the control points
+    pub const POINTS_MIN_DIST: f64 = 0.01;
+
+    /// A new spline through the given points. They get clamped to [0.0, 1.0] and sorted,
+    /// and the ends are added if they are missing.
+    pub fn new(points: Vec<na::Vector2<f64>>) -> Self {
+        let mut points = points
+            .into_iter()
+            .map(|point| point.map(|v| v.clamp(0.0, 1.0)))
+            .collect::<Vec<na::Vector2<f64>>>();
+        points.sort_by(|first, second| first[0].total_cmp(&second[0]));
+        points.dedup_by(|second, first| second[0] - first[0] < Self::POINTS_MIN_DIST);
+
+        match points.first_mut() {
+            Some(first) if first[0] < Self::POINTS_MIN_DIST => first[0] = 0.0,
+            _ => points.insert(0, na::vector![0.0, 0.0]),
+        }
+        match points.last_mut() {
+            Some(last) if last[0] > 1.0 - Self::POINTS_MIN_DIST => last[0] = 1.0,
+            _ => points.push(na::vector![1.0, 1.0]),
+        }
+
+        let mut spline = Self {
+            points,
+            tangents: vec![],
+        };
+        spline.update_tangents();
+        spline
+    }
+
+    /// The control points
+    pub fn points(&self) -> &[na::Vector2<f64>] {
+        &self.points
+    }
+
+    /// Inserts a new control point. Returns its index, or None if it is too close to an existing point
+    pub fn insert_point(&mut self, point: na::Vector2<f64>) -> Option<usize> {
+        let point = point.map(|v| v.clamp(0.0, 1.0));
+
+        if self
+            .points
+            .iter()
+            .any(|existing| (existing[0] - point[0]).abs() < Self::POINTS_MIN_DIST)
+        {
+            return None;
+        }
+
+        let index = self
+            .points
+            .iter()
+            .position(|existing| existing[0] > point[0])
+            .unwrap_or(self.points.len());
+        self.points.insert(index, point);
+        self.update_tangents();
+
+        Some(index)
+    }
+
+    /// Removes the control point at the index. The points at the ends can't be removed
+    pub fn remove_point(&mut self, index: usize) {
+        if index > 0 && index + 1 < self.points.len() {
+            self.points.remove(index);
+            self.update_tangents();
+        }
+    }
+
+    /// Moves the control point at the index. It can't be moved past its neighbours,
+    /// and the points at the ends can only be moved vertically
+    pub fn move_point(&mut self, index: usize, point: na::Vector2<f64>) {
+        let n_points = self.points.len();
+        if index >= n_points {
+            return;
+        }
+
+        let x = if index == 0 {
+            0.0
+        } else if index == n_points - 1 {
+            1.0
+        } else {
+            point[0].clamp(
+                self.points[index - 1][0] + Self::POINTS_MIN_DIST,
+                self.points[index + 1][0] - Self::POINTS_MIN_DIST,
+            )
+        };
+
+        self.points[index] = na::vector![x, point[1].clamp(0.0, 1.0)];
+        self.update_tangents();
+    }
+
+    /// Computes the tangents at the control points with the Fritsch-Carlson method, so the curve does not overshoot between them.
+    fn update_tangents(&mut self) {
+        let points = &self.points;
+        let n_points = points.len();
+        if n_points < 2 {
+            self.tangents = vec![0.0; n_points];
+            return;
+        }
+
+        // the slopes of the secants between the points
+        let secants = points
+            .windows(2)
+            .map(|w| {
+                let dx = w[1][0] - w[0][0];
+                if dx > 0.0 {
+                    (w[1][1] - w[0][1]) / dx
+                } else {
+                    0.0
+                }
+            })
+            .collect::<Vec<f64>>();
+
+        // the tangents at the points
+        let mut tangents = (0..n_points)
+            .map(|i| {
+                if i == 0 {
+                    secants[0]
+                } else if i == n_points - 1 {
+                    secants[n_points - 2]
+                } else if secants[i - 1] * secants[i] <= 0.0 {
+                    0.0
+                } else {
+                    (secants[i - 1] + secants[i]) * 0.5
+                }
+            })
+            .collect::<Vec<f64>>();
+
+        // restrict the tangents to keep the spline monotone
+        for (i, secant) in secants.iter().enumerate() {
+            if *secant == 0.0 {
+                tangents[i] = 0.0;
+                tangents[i + 1] = 0.0;
+            } else {
+                let a = tangents[i] / secant;
+                let b = tangents[i + 1] / secant;
+                let s = a.powi(2) + b.powi(2);
+
+                if s > 9.0 {
+                    let t = 3.0 / s.sqrt();
+                    tangents[i] = t * a * secant;
+                    tangents[i + 1] = t * b * secant;
+                }
+            }
+        }
+
+        self.tangents = tangents;
+    }
+
+    /// Evaluates the spline at the pressure. Expects the pressure to be between 0.0 and 1.0.
+    ///
+    /// The curve does not overshoot between the control points.
+    pub fn eval(&self, pressure: f64) -> f64 {
+        let points = &self.points;
+        let n_points = points.len();
+
+        match n_points {
+            0 => return pressure,
+            1 => return points[0][1],
+            _ => {}
+        }
+        if pressure <= points[0][0] {
+            return points[0][1];
+        }
+        if pressure >= points[n_points - 1][0] {
+            return points[n_points - 1][1];
+        }
+
+        let i = points
+            .windows(2)
+            .position(|w| pressure <= w[1][0])
+            .unwrap_or(n_points - 2);
+        let (start, end) = (points[i], points[i + 1]);
+        let h = end[0] - start[0];
+        if h <= 0.0 {
+            return start[1];
+        }
+        let t = (pressure - start[0]) / h;
+
+        // cubic hermite basis functions
+        let h00 = 2.0 * t.powi(3) - 3.0 * t.powi(2) + 1.0;
+        let h10 = t.powi(3) - 2.0 * t.powi(2) + t;
+        let h01 = -2.0 * t.powi(3) + 3.0 * t.powi(2);
+        let h11 = t.powi(3) - t.powi(2);
+
+        (h00 * start[1]
+            + h10 * h * self.tangents[i]
+            + h01 * end[1]
+            + h11 * h * self.tangents[i + 1])
+            .clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples(spline: &PressureSpline) -> Vec<f64> {
+        (0..=200)
+            .map(|i| spline.eval(f64::from(i) / 200.0))
+            .collect()
+    }
+
+    #[test]
+    fn monotone_between_points() {
+        let spline = PressureSpline::new(vec![
+            na::vector![0.0, 0.0],
+            na::vector![0.1, 0.8],
+            na::vector![0.2, 0.85],
+            na::vector![0.8, 0.9],
+            na::vector![1.0, 1.0],
+        ]);
+
+        assert!(samples(&spline).windows(2).all(|w| w[1] >= w[0]));
+    }
+
+    #[test]
+    fn no_overshoot_at_plateaus() {
+        let spline = PressureSpline::new(vec![
+            na::vector![0.0, 0.2],
+            na::vector![0.3, 0.6],
+            na::vector![0.6, 0.6],
+            na::vector![1.0, 0.1],
+        ]);
+
+        for (i, value) in samples(&spline).into_iter().enumerate() {
+            let pressure = i as f64 / 200.0;
+            if (0.3..=0.6).contains(&pressure) {
+                assert!((value - 0.6).abs() < 1e-9);
+            }
+            assert!((0.1..=0.6).contains(&value));
+        }
+    }
+
+    #[test]
+    fn end_points_clamped() {
+        let mut spline = PressureSpline::new(vec![
+            na::vector![-0.5, 0.3],
+            na::vector![0.5, 0.5],
+            na::vector![1.5, 2.0],
+        ]);
+        assert_eq!(
+            spline.points(),
+            &[
+                na::vector![0.0, 0.3],
+                na::vector![0.5, 0.5],
+                na::vector![1.0, 1.0]
+            ]
+        );
+        assert_eq!(spline.eval(-1.0), 0.3);
+        assert_eq!(spline.eval(0.0), 0.3);
+        assert_eq!(spline.eval(1.0), 1.0);
+        assert_eq!(spline.eval(2.0), 1.0);
+
+        // the ends only move vertically and can't be removed
+        spline.move_point(0, na::vector![0.4, 0.1]);
+        spline.remove_point(0);
+        spline.remove_point(2);
+        assert_eq!(spline.points()[0], na::vector![0.0, 0.1]);
+        assert_eq!(spline.points().len(), 3);
+        assert_eq!(spline.eval(0.0), 0.1);
+    }
+
+    #[test]
+    fn missing_ends_inserted() {
+        let spline = PressureSpline::new(vec![]);
+        assert_eq!(
+            spline.points(),
+            &[na::vector![0.0, 0.0], na::vector![1.0, 1.0]]
+        );
+        assert!((spline.eval(0.25) - 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn tangents_restored_after_deserializing() {
+        let mut spline = PressureSpline::default();
+        spline.insert_point(na::vector![0.2, 0.7]).unwrap();
+
+        let loaded: PressureSpline =
+            serde_json::from_str(&serde_json::to_string(&spline).unwrap()).unwrap();
+
+        assert_eq!(loaded, spline);
+        assert_eq!(samples(&loaded), samples(&spline));
+    }
+}
@@ -11,7 +11,7 @@ use crate::helpers::{Affine2Helpers, Vector2Helpers};
 use crate::penpath::Segment;
 use crate::shapes::Line;
 use crate::shapes::Rectangle;
-use crate::shapes::{CubicBezier, ShapeBehaviour};
+use crate::shapes::{BezierPath, CubicBezier, PathSegment, ShapeBehaviour};
 use crate::shapes::{Ellipse, QuadraticBezier};
 use crate::PenPath;
 
@@ -257,6 +257,80 @@ impl Composer<RoughOptions> for CubicBezier {
     }
 }
 
+impl Composer<RoughOptions> for BezierPath {
+    fn composed_bounds(&self, options: &RoughOptions) -> AABB {
+        self.bounds()
+            .loosened(options.stroke_width * 0.5 + RoughOptions::ROUGH_BOUNDS_MARGIN)
+    }
+
+    fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &RoughOptions) {
+        cx.save().unwrap();
+        let mut rng = crate::utils::new_rng_default_pcg64(options.seed);
+
+        let mut bez_path = kurbo::BezPath::new();
+
+        for subpath in self.subpaths.iter() {
+            let mut prev = subpath.start;
+
+            for segment in subpath.segments.iter() {
+                match *segment {
+                    PathSegment::Line { end } => {
+                        if !options.disable_multistroke {
+                            bez_path
+                                .extend(roughgenerator::doubleline(prev, end, options, &mut rng));
+                        } else {
+                            bez_path.extend(roughgenerator::line(
+                                prev, end, true, false, options, &mut rng,
+                            ));
+                        }
+                    }
+                    PathSegment::QuadBez { cp, end } => {
+                        bez_path.extend(roughgenerator::quadratic_bezier(
+                            prev, cp, end, options, &mut rng,
+                        ));
+                    }
+                    PathSegment::CubBez { cp1, cp2, end } => {
+                        bez_path.extend(roughgenerator::cubic_bezier(
+                            prev, cp1, cp2, end, options, &mut rng,
+                        ));
+                    }
+                }
+                prev = segment.end();
+            }
+
+            if subpath.closed {
+                bez_path.extend(roughgenerator::line(
+                    prev,
+                    subpath.start,
+                    true,
+                    false,
+                    options,
+                    &mut rng,
+                ));
+            }
+        }
+
+        if let Some(fill_color) = options.fill_color {
+            let mut fill_path = kurbo::BezPath::new();
+
+            for polygon in self.to_polygons(BezierPath::FLATTEN_TOLERANCE) {
+                fill_path.extend(fill_polygon(polygon, options));
+            }
+
+            let fill_brush = cx.solid_brush(fill_color.into());
+            cx.fill_even_odd(fill_path, &fill_brush);
+        }
+
+        if let Some(stroke_color) = options.stroke_color {
+            let stroke_brush = cx.solid_brush(stroke_color.into());
+
+            cx.stroke(bez_path, &stroke_brush, options.stroke_width)
+        }
+
+        cx.restore().unwrap();
+    }
+}
+
 impl Composer<RoughOptions> for Segment {
     fn composed_bounds(&self, options: &RoughOptions) -> AABB {
         self.bounds().loosened(options.stroke_width * 0.5)
@@ -356,6 +430,7 @@ impl Composer<RoughOptions> for crate::Shape {
             crate::Shape::QuadraticBezier(quadbez) => quadbez.composed_bounds(options),
             crate::Shape::CubicBezier(cubbez) => cubbez.composed_bounds(options),
             crate::Shape::Segment(segment) => segment.composed_bounds(options),
+            crate::Shape::Path(path) => path.composed_bounds(options),
         }
     }
 
@@ -367,6 +442,7 @@ impl Composer<RoughOptions> for crate::Shape {
             crate::Shape::QuadraticBezier(quadbez) => quadbez.draw_composed(cx, options),
             crate::Shape::CubicBezier(cubbez) => cubbez.draw_composed(cx, options),
             crate::Shape::Segment(segment) => segment.draw_composed(cx, options),
+            crate::Shape::Path(path) => path.draw_composed(cx, options),
         }
     }
 }
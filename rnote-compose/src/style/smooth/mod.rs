@@ -5,7 +5,8 @@ pub use smoothoptions::SmoothOptions;
 
 use super::Composer;
 use crate::helpers::Vector2Helpers;
-use crate::penpath::Segment;
+use crate::penpath::{Element, Segment};
+use crate::shapes::BezierPath;
 use crate::shapes::CubicBezier;
 use crate::shapes::Ellipse;
 use crate::shapes::Line;
@@ -285,99 +286,133 @@ impl Composer<SmoothOptions> for CubicBezier {
     }
 }
 
-impl Composer<SmoothOptions> for Segment {
+impl Composer<SmoothOptions> for BezierPath {
     fn composed_bounds(&self, options: &SmoothOptions) -> AABB {
         self.bounds().loosened(options.stroke_width * 0.5)
     }
 
     fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &SmoothOptions) {
         cx.save().unwrap();
+        let bez_path = self.to_kurbo();
 
-        let bez_path = {
-            match self {
-                Segment::Dot { element } => {
-                    let radii = na::Vector2::from_element(
-                        options
-                            .pressure_curve
-                            .apply(options.stroke_width * 0.5, element.pressure),
-                    );
-
-                    kurbo::Ellipse::new(element.pos.to_kurbo_point(), radii.to_kurbo_vec(), 0.0)
-                        .into_path(0.1)
-                }
-                Segment::Line { start, end } => {
-                    let (width_start, width_end) = (
-                        options
-                            .pressure_curve
-                            .apply(options.stroke_width, start.pressure),
-                        options
-                            .pressure_curve
-                            .apply(options.stroke_width, end.pressure),
-                    );
-
-                    compose_line_variable_width(
-                        Line {
-                            start: start.pos,
-                            end: end.pos,
-                        },
-                        width_start,
-                        width_end,
-                        options,
-                    )
-                }
-                Segment::QuadBez { start, cp, end } => {
-                    let (width_start, width_end) = (
-                        options
-                            .pressure_curve
-                            .apply(options.stroke_width, start.pressure),
-                        options
-                            .pressure_curve
-                            .apply(options.stroke_width, end.pressure),
-                    );
-
-                    let n_splits = 5;
-
-                    let quadbez = QuadraticBezier {
-                        start: start.pos,
-                        cp: *cp,
-                        end: end.pos,
-                    };
-
-                    let lines = quadbez.approx_with_lines(n_splits);
-
-                    compose_lines_variable_width(&lines, width_start, width_end, options)
-                }
-                Segment::CubBez {
-                    start,
-                    cp1,
-                    cp2,
-                    end,
-                } => {
-                    let (width_start, width_end) = (
-                        options
-                            .pressure_curve
-                            .apply(options.stroke_width, start.pressure),
-                        options
-                            .pressure_curve
-                            .apply(options.stroke_width, end.pressure),
-                    );
-
-                    let n_splits = 5;
-
-                    let cubbez = CubicBezier {
+        if let Some(fill_color) = options.fill_color {
+            let fill_brush = cx.solid_brush(fill_color.into());
+            cx.fill_even_odd(bez_path.clone(), &fill_brush);
+        }
+
+        if let Some(stroke_color) = options.stroke_color {
+            let stroke_brush = cx.solid_brush(stroke_color.into());
+            cx.stroke(bez_path, &stroke_brush, options.stroke_width);
+        }
+        cx.restore().unwrap();
+    }
+}
+
+// The width at the element with the pressure curve and the dynamics applied
+fn element_width(element: &Element, velocity: Option<f64>, options: &SmoothOptions) -> f64 {
+    options
+        .pressure_curve
+        .apply(options.stroke_width, element.pressure)
+        * options.dynamics.width_factor(element, velocity)
+}
+
+impl Segment {
+    /// The filled outline of the segment with variable width, as it is drawn by the smooth composer
+    pub fn smooth_outline(&self, options: &SmoothOptions) -> kurbo::BezPath {
+        match self {
+            Segment::Dot { element } => {
+                let radii = na::Vector2::from_element(element_width(element, None, options) * 0.5);
+
+                kurbo::Ellipse::new(element.pos.to_kurbo_point(), radii.to_kurbo_vec(), 0.0)
+                    .into_path(0.1)
+            }
+            Segment::Line { start, end } => {
+                let velocity = self.velocity();
+                let (width_start, width_end) = (
+                    element_width(start, velocity, options),
+                    element_width(end, velocity, options),
+                );
+
+                compose_line_variable_width(
+                    Line {
                         start: start.pos,
-                        cp1: *cp1,
-                        cp2: *cp2,
                         end: end.pos,
-                    };
-                    let lines = cubbez.approx_with_lines(n_splits);
+                    },
+                    width_start,
+                    width_end,
+                    options,
+                )
+            }
+            Segment::QuadBez { start, cp, end } => {
+                let velocity = self.velocity();
+                let (width_start, width_end) = (
+                    element_width(start, velocity, options),
+                    element_width(end, velocity, options),
+                );
+
+                let n_splits = 5;
+
+                let quadbez = QuadraticBezier {
+                    start: start.pos,
+                    cp: *cp,
+                    end: end.pos,
+                };
 
-                    compose_lines_variable_width(&lines, width_start, width_end, options)
-                }
+                let lines = quadbez.approx_with_lines(n_splits);
+
+                compose_lines_variable_width(&lines, width_start, width_end, options)
+            }
+            Segment::CubBez {
+                start,
+                cp1,
+                cp2,
+                end,
+            } => {
+                let velocity = self.velocity();
+                let (width_start, width_end) = (
+                    element_width(start, velocity, options),
+                    element_width(end, velocity, options),
+                );
+
+                let n_splits = 5;
+
+                let cubbez = CubicBezier {
+                    start: start.pos,
+                    cp1: *cp1,
+                    cp2: *cp2,
+                    end: end.pos,
+                };
+                let lines = cubbez.approx_with_lines(n_splits);
+
+                compose_lines_variable_width(&lines, width_start, width_end, options)
             }
-        };
+        }
+    }
+}
+
+impl PenPath {
+    /// The filled outlines of the segments, as they are drawn by the smooth composer. Outlines of neighbouring segments overlap
+    pub fn smooth_outlines(&self, options: &SmoothOptions) -> Vec<kurbo::BezPath> {
+        self.iter()
+            .map(|segment| segment.smooth_outline(options))
+            .collect()
+    }
+}
+
+impl Composer<SmoothOptions> for Segment {
+    fn composed_bounds(&self, options: &SmoothOptions) -> AABB {
+        self.bounds()
+            .loosened(options.stroke_width * 0.5 * options.dynamics.width_factor_max())
+    }
+
+    fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &SmoothOptions) {
+        cx.save().unwrap();
+
+        let bez_path = self.smooth_outline(options);
+
+        if let Some(mut fill_color) = options.stroke_color {
+            fill_color.a *= options.dynamics.segment_opacity_factor(self);
 
-        if let Some(fill_color) = options.stroke_color {
             // Outlines for debugging
             //let stroke_brush = cx.solid_brush(piet::Color::RED);
             //cx.stroke(bez_path.clone(), &stroke_brush, 0.4);
@@ -399,9 +434,24 @@ impl Composer<SmoothOptions> for PenPath {
 
     fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &SmoothOptions) {
         cx.save().unwrap();
-        for segment in self.iter() {
-            segment.draw_composed(cx, options);
+
+        if let Some(stroke_color) = options.stroke_color {
+            // Consecutive segments with the same opacity are filled as one path, so that their overlapping joints are not darker
+            let segment_outlines = self
+                .iter()
+                .map(|segment| (segment, segment.smooth_outline(options)));
+
+            for (opacity_factor, bez_path) in
+                options.dynamics.segment_opacity_groups(segment_outlines)
+            {
+                let mut fill_color = stroke_color;
+                fill_color.a *= opacity_factor;
+
+                let fill_brush = cx.solid_brush(fill_color.into());
+                cx.fill(bez_path, &fill_brush);
+            }
         }
+
         cx.restore().unwrap();
     }
 }
@@ -415,6 +465,7 @@ impl Composer<SmoothOptions> for crate::Shape {
             crate::Shape::QuadraticBezier(quadbez) => quadbez.composed_bounds(options),
             crate::Shape::CubicBezier(cubbez) => cubbez.composed_bounds(options),
             crate::Shape::Segment(segment) => segment.composed_bounds(options),
+            crate::Shape::Path(path) => path.composed_bounds(options),
         }
     }
 
@@ -426,6 +477,7 @@ impl Composer<SmoothOptions> for crate::Shape {
             crate::Shape::QuadraticBezier(quadbez) => quadbez.draw_composed(cx, options),
             crate::Shape::CubicBezier(cubbez) => cubbez.draw_composed(cx, options),
             crate::Shape::Segment(segment) => segment.draw_composed(cx, options),
+            crate::Shape::Path(path) => path.draw_composed(cx, options),
         }
     }
 }
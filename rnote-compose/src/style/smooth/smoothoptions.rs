@@ -1,4 +1,4 @@
-use crate::style::PressureCurve;
+use crate::style::{PressureCurve, StrokeDynamics};
 use crate::Color;
 
 use serde::{Deserialize, Serialize};
@@ -19,6 +19,9 @@ pub struct SmoothOptions {
     /// Pressure curve
     #[serde(rename = "pressure_curve")]
     pub pressure_curve: PressureCurve,
+    /// The velocity and tilt dynamics
+    #[serde(rename = "dynamics")]
+    pub dynamics: StrokeDynamics,
 }
 
 impl Default for SmoothOptions {
@@ -28,6 +31,7 @@ impl Default for SmoothOptions {
             stroke_color: Some(Color::BLACK),
             fill_color: None,
             pressure_curve: PressureCurve::default(),
+            dynamics: StrokeDynamics::default(),
         }
     }
 }
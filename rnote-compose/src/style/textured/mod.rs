@@ -6,7 +6,7 @@ pub use textureddotsdistribution::TexturedDotsDistribution;
 pub use texturedoptions::TexturedOptions;
 
 use crate::helpers::Vector2Helpers;
-use crate::penpath::Segment;
+use crate::penpath::{Element, Segment};
 use crate::shapes::{Line, ShapeBehaviour};
 use crate::PenPath;
 use kurbo::Shape;
@@ -91,9 +91,34 @@ impl Composer<TexturedOptions> for Line {
     }
 }
 
+// The options for drawing the segment between the elements, with the pressure curve and the dynamics applied
+fn segment_options(
+    start: &Element,
+    end: &Element,
+    velocity: Option<f64>,
+    options: &TexturedOptions,
+) -> TexturedOptions {
+    let mut options = options.clone();
+
+    options.stroke_width = options
+        .pressure_curve
+        .apply(options.stroke_width, (start.pressure + end.pressure) * 0.5)
+        * (options.dynamics.width_factor(start, velocity)
+            + options.dynamics.width_factor(end, velocity))
+        * 0.5;
+    if let Some(stroke_color) = options.stroke_color.as_mut() {
+        stroke_color.a *= (options.dynamics.opacity_factor(start, velocity)
+            + options.dynamics.opacity_factor(end, velocity))
+            * 0.5;
+    }
+
+    options
+}
+
 impl Composer<TexturedOptions> for Segment {
     fn composed_bounds(&self, options: &TexturedOptions) -> AABB {
-        self.bounds().loosened(options.stroke_width * 0.5)
+        self.bounds()
+            .loosened(options.stroke_width * 0.5 * options.dynamics.width_factor_max())
     }
 
     fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &TexturedOptions) {
@@ -108,13 +133,7 @@ impl Composer<TexturedOptions> for Segment {
                     end: end.pos,
                 };
 
-                let mut options = options.clone();
-
-                options.stroke_width = options
-                    .pressure_curve
-                    .apply(options.stroke_width, (start.pressure + end.pressure) * 0.5);
-
-                line.draw_composed(cx, &options);
+                line.draw_composed(cx, &segment_options(start, end, self.velocity(), options));
             }
             Self::QuadBez { start, cp: _, end } => {
                 let line = Line {
@@ -122,13 +141,7 @@ impl Composer<TexturedOptions> for Segment {
                     end: end.pos,
                 };
 
-                let mut options = options.clone();
-
-                options.stroke_width = options
-                    .pressure_curve
-                    .apply(options.stroke_width, (start.pressure + end.pressure) * 0.5);
-
-                line.draw_composed(cx, &options);
+                line.draw_composed(cx, &segment_options(start, end, self.velocity(), options));
             }
             Self::CubBez {
                 start,
@@ -141,13 +154,7 @@ impl Composer<TexturedOptions> for Segment {
                     end: end.pos,
                 };
 
-                let mut options = options.clone();
-
-                options.stroke_width = options
-                    .pressure_curve
-                    .apply(options.stroke_width, (start.pressure + end.pressure) * 0.5);
-
-                line.draw_composed(cx, &options);
+                line.draw_composed(cx, &segment_options(start, end, self.velocity(), options));
             }
         }
         cx.restore().unwrap();
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::style::PressureCurve;
+use crate::style::{PressureCurve, StrokeDynamics};
 use crate::Color;
 
 use super::textureddotsdistribution::TexturedDotsDistribution;
@@ -31,6 +31,9 @@ pub struct TexturedOptions {
     /// Pressure curve
     #[serde(rename = "pressure_curve")]
     pub pressure_curve: PressureCurve,
+    /// The velocity and tilt dynamics
+    #[serde(rename = "dynamics")]
+    pub dynamics: StrokeDynamics,
 }
 
 impl Default for TexturedOptions {
@@ -43,6 +46,7 @@ impl Default for TexturedOptions {
             radii: Self::RADII_DEFAULT,
             distribution: TexturedDotsDistribution::default(),
             pressure_curve: PressureCurve::default(),
+            dynamics: StrokeDynamics::default(),
         }
     }
 }
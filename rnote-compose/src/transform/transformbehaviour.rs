@@ -6,4 +6,13 @@ pub trait TransformBehaviour {
     fn rotate(&mut self, angle: f64, center: na::Point2<f64>);
     /// scales by the desired scale
     fn scale(&mut self, scale: na::Vector2<f64>);
+    /// mirrors across the axis through the center, which has the angle (rad) to the x-axis.
+    /// An angle of 0.0 flips vertically, an angle of PI / 2 flips horizontally
+    fn mirror(&mut self, axis_angle: f64, center: na::Point2<f64>) {
+        self.translate(-center.coords);
+        self.rotate(-axis_angle, na::Point2::origin());
+        self.scale(na::vector![1.0, -1.0]);
+        self.rotate(axis_angle, na::Point2::origin());
+        self.translate(center.coords);
+    }
 }
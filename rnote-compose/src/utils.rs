@@ -114,3 +114,61 @@ pub fn seed_advance(seed: u64) -> u64 {
     let mut rng = rand_pcg::Pcg64::seed_from_u64(seed);
     rng.gen()
 }
+
+/// hashes the integer coordinates of a grid cell together with the seed to a value in the range [0.0, 1.0).
+/// The value only depends on its inputs, making it usable for textures that are fixed in space.
+pub fn hash_grid_cell(cell: na::Vector2<i64>, seed: u64) -> f64 {
+    // splitmix64 finalizer, applied to the combined inputs
+    let mut hash = (cell[0] as u64)
+        .wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        .wrapping_add((cell[1] as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F))
+        .wrapping_add(seed.wrapping_mul(0x1656_67B1_9E37_79F9));
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    hash ^= hash >> 31;
+
+    // use the upper 53 bits for the mantissa
+    (hash >> 11) as f64 / (1_u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_grid_cell_deterministic() {
+        for cell in [
+            na::vector![0, 0],
+            na::vector![-3, 17],
+            na::vector![i64::MAX, i64::MIN],
+        ] {
+            assert_eq!(hash_grid_cell(cell, 5), hash_grid_cell(cell, 5));
+        }
+    }
+
+    #[test]
+    fn hash_grid_cell_in_range() {
+        let values = (-20..20)
+            .flat_map(|x| (-20..20).map(move |y| hash_grid_cell(na::vector![x, y], 0)))
+            .collect::<Vec<f64>>();
+
+        assert!(values.iter().all(|value| (0.0..1.0).contains(value)));
+        // roughly uniformly distributed
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        assert!((mean - 0.5).abs() < 0.05);
+    }
+
+    #[test]
+    fn hash_grid_cell_varies() {
+        let origin = hash_grid_cell(na::vector![0, 0], 0);
+
+        assert_ne!(origin, hash_grid_cell(na::vector![1, 0], 0));
+        assert_ne!(origin, hash_grid_cell(na::vector![0, 1], 0));
+        assert_ne!(origin, hash_grid_cell(na::vector![0, 0], 1));
+        // swapped coordinates hash differently
+        assert_ne!(
+            hash_grid_cell(na::vector![2, 5], 0),
+            hash_grid_cell(na::vector![5, 2], 0)
+        );
+    }
+}
@@ -1,15 +1,16 @@
 use anyhow::Context;
-use gtk4::{gdk, graphene, gsk, prelude::*, Snapshot};
+use gtk4::{gdk, glib, graphene, gsk, prelude::*, Snapshot};
 use p2d::bounding_volume::AABB;
 use rnote_compose::shapes::ShapeBehaviour;
 use serde::{Deserialize, Serialize};
 use svg::node::element;
 use svg::Node;
 
-use crate::utils::{GdkRGBAHelpers, GrapheneRectHelpers};
+use crate::utils::{base64, GdkRGBAHelpers, GrapheneRectHelpers};
 use crate::{render, Camera};
 use rnote_compose::helpers::AABBHelpers;
 use rnote_compose::Color;
+use rnote_fileformats::xoppformat;
 
 #[derive(
     Debug,
@@ -32,6 +33,16 @@ pub enum PatternStyle {
     Grid,
     #[serde(rename = "dots")]
     Dots,
+    #[serde(rename = "isometric_dots")]
+    IsometricDots,
+    #[serde(rename = "isometric_grid")]
+    IsometricGrid,
+    #[serde(rename = "hex_grid")]
+    HexGrid,
+    #[serde(rename = "staves")]
+    Staves,
+    #[serde(rename = "cornell")]
+    Cornell,
 }
 
 impl Default for PatternStyle {
@@ -50,6 +61,23 @@ impl TryFrom<u32> for PatternStyle {
     }
 }
 
+/// Maps to the closest xopp background style
+impl From<PatternStyle> for xoppformat::XoppBackgroundSolidStyle {
+    fn from(pattern: PatternStyle) -> Self {
+        match pattern {
+            PatternStyle::None => Self::Plain,
+            PatternStyle::Lines => Self::Ruled,
+            PatternStyle::Grid | PatternStyle::HexGrid => Self::Graph,
+            PatternStyle::Dots => Self::Dotted,
+            PatternStyle::IsometricDots => Self::IsometricDotted,
+            PatternStyle::IsometricGrid => Self::IsometricGraph,
+            PatternStyle::Staves => Self::Staves,
+            // Lines with a margin line
+            PatternStyle::Cornell => Self::Lined,
+        }
+    }
+}
+
 pub fn gen_hline_pattern(
     bounds: AABB,
     spacing: f64,
@@ -184,6 +212,515 @@ pub fn gen_dots_pattern(
     group.into()
 }
 
+/// Generates a pattern of dots on the corners of equilateral triangles with the side length `spacing`
+pub fn gen_isometric_dots_pattern(
+    bounds: AABB,
+    spacing: f64,
+    color: Color,
+    dots_width: f64,
+) -> svg::node::element::Element {
+    let pattern_id = rnote_compose::utils::random_id_prefix() + "_bg_isometric_dots_pattern";
+
+    let width = spacing;
+    let height = spacing * 3_f64.sqrt();
+
+    let dot = |x: f64, y: f64| {
+        element::Rectangle::new()
+            .set("stroke", "none")
+            .set("fill", color.to_css_color_attr())
+            .set("x", x)
+            .set("y", y)
+            .set("width", dots_width)
+            .set("height", dots_width)
+            .set("rx", dots_width / 3.0)
+            .set("ry", dots_width / 3.0)
+    };
+
+    let pattern = element::Definitions::new().add(
+        element::Pattern::new()
+            .set("id", pattern_id.as_str())
+            .set("x", 0_f64)
+            .set("y", 0_f64)
+            .set("width", width)
+            .set("height", height)
+            .set("patternUnits", "userSpaceOnUse")
+            .set("patternContentUnits", "userSpaceOnUse")
+            .add(dot(0.0, 0.0))
+            .add(dot(width * 0.5, height * 0.5)),
+    );
+
+    let rect = element::Rectangle::new()
+        .set("x", bounds.mins[0])
+        .set("y", bounds.mins[1])
+        .set("width", bounds.extents()[0])
+        .set("height", bounds.extents()[1])
+        .set("fill", format!("url(#{})", pattern_id));
+
+    let group = element::Group::new().add(pattern).add(rect);
+    group.into()
+}
+
+/// Generates a grid of equilateral triangles with the side length `spacing`
+pub fn gen_isometric_grid_pattern(
+    bounds: AABB,
+    spacing: f64,
+    color: Color,
+    line_width: f64,
+) -> svg::node::element::Element {
+    let pattern_id = rnote_compose::utils::random_id_prefix() + "_bg_isometric_grid_pattern";
+
+    let line_offset = line_width * 0.5;
+    let width = spacing;
+    let height = spacing * 3_f64.sqrt();
+
+    let line = |x1: f64, y1: f64, x2: f64, y2: f64| {
+        element::Line::new()
+            .set("stroke-width", line_width)
+            .set("stroke", color.to_css_color_attr())
+            .set("x1", x1)
+            .set("y1", y1)
+            .set("x2", x2)
+            .set("y2", y2)
+    };
+
+    let pattern = element::Definitions::new().add(
+        element::Pattern::new()
+            .set("id", pattern_id.as_str())
+            .set("x", 0_f64)
+            .set("y", 0_f64)
+            .set("width", width)
+            .set("height", height)
+            .set("patternUnits", "userSpaceOnUse")
+            .set("patternContentUnits", "userSpaceOnUse")
+            .add(line(0.0, line_offset, width, line_offset))
+            .add(line(0.0, height * 0.5, width, height * 0.5))
+            .add(line(0.0, 0.0, width, height))
+            .add(line(width, 0.0, 0.0, height)),
+    );
+
+    let rect = element::Rectangle::new()
+        .set("x", bounds.mins[0])
+        .set("y", bounds.mins[1])
+        .set("width", bounds.extents()[0])
+        .set("height", bounds.extents()[1])
+        .set("fill", format!("url(#{})", pattern_id));
+
+    let group = element::Group::new().add(pattern).add(rect);
+    group.into()
+}
+
+/// Generates a grid of flat topped hexagons with the side length `side`
+pub fn gen_hex_grid_pattern(
+    bounds: AABB,
+    side: f64,
+    color: Color,
+    line_width: f64,
+) -> svg::node::element::Element {
+    let pattern_id = rnote_compose::utils::random_id_prefix() + "_bg_hex_grid_pattern";
+
+    let width = side * 3.0;
+    let height = side * 3_f64.sqrt();
+
+    // One hexagon, and the edge to the neighbouring hexagons on the right
+    let path_data = element::path::Data::new()
+        .move_to((0.0, height * 0.5))
+        .line_to((side * 0.5, 0.0))
+        .line_to((side * 1.5, 0.0))
+        .line_to((side * 2.0, height * 0.5))
+        .line_to((side * 1.5, height))
+        .line_to((side * 0.5, height))
+        .close()
+        .move_to((side * 2.0, height * 0.5))
+        .line_to((width, height * 0.5));
+
+    let pattern = element::Definitions::new().add(
+        element::Pattern::new()
+            .set("id", pattern_id.as_str())
+            .set("x", 0_f64)
+            .set("y", 0_f64)
+            .set("width", width)
+            .set("height", height)
+            .set("patternUnits", "userSpaceOnUse")
+            .set("patternContentUnits", "userSpaceOnUse")
+            .add(
+                element::Path::new()
+                    .set("stroke-width", line_width)
+                    .set("stroke", color.to_css_color_attr())
+                    .set("fill", "none")
+                    .set("d", path_data),
+            ),
+    );
+
+    let rect = element::Rectangle::new()
+        .set("x", bounds.mins[0])
+        .set("y", bounds.mins[1])
+        .set("width", bounds.extents()[0])
+        .set("height", bounds.extents()[1])
+        .set("fill", format!("url(#{})", pattern_id));
+
+    let group = element::Group::new().add(pattern).add(rect);
+    group.into()
+}
+
+/// Generates music staves, each with five lines. The staves are separated by the height of a staff
+pub fn gen_staves_pattern(
+    bounds: AABB,
+    line_spacing: f64,
+    color: Color,
+    line_width: f64,
+) -> svg::node::element::Element {
+    let pattern_id = rnote_compose::utils::random_id_prefix() + "_bg_staves_pattern";
+
+    let mut pattern = element::Pattern::new()
+        .set("id", pattern_id.as_str())
+        .set("x", 0_f64)
+        .set("y", 0_f64)
+        .set("width", bounds.extents()[0])
+        .set("height", line_spacing * Background::STAVES_PERIOD_LINES)
+        .set("patternUnits", "userSpaceOnUse")
+        .set("patternContentUnits", "userSpaceOnUse");
+
+    for i in 0..5 {
+        let y = line_spacing * (3.0 + f64::from(i));
+
+        pattern = pattern.add(
+            element::Line::new()
+                .set("stroke-width", line_width)
+                .set("stroke", color.to_css_color_attr())
+                .set("x1", 0_f64)
+                .set("y1", y)
+                .set("x2", bounds.extents()[0])
+                .set("y2", y),
+        );
+    }
+
+    let rect = element::Rectangle::new()
+        .set("x", bounds.mins[0])
+        .set("y", bounds.mins[1])
+        .set("width", bounds.extents()[0])
+        .set("height", bounds.extents()[1])
+        .set("fill", format!("url(#{})", pattern_id));
+
+    let group = element::Group::new()
+        .add(element::Definitions::new().add(pattern))
+        .add(rect);
+    group.into()
+}
+
+/// Generates the Cornell notes layout, repeating with the size of the page it is aligned to.
+///
+/// It has a header, ruled lines with a margin line separating the cue column, and a summary box at the bottom of the page.
+pub fn gen_cornell_pattern(
+    bounds: AABB,
+    page_bounds: AABB,
+    line_spacing: f64,
+    color: Color,
+    line_width: f64,
+) -> svg::node::element::Element {
+    let pattern_id = rnote_compose::utils::random_id_prefix() + "_bg_cornell_pattern";
+
+    let page_width = page_bounds.extents()[0];
+    let page_height = page_bounds.extents()[1];
+    let header_height = line_spacing * 2.0;
+    let summary_top = page_height * (1.0 - Background::CORNELL_SUMMARY_HEIGHT_RATIO);
+    let cue_column_width = page_width * Background::CORNELL_CUE_COLUMN_WIDTH_RATIO;
+    let margin_line_width = line_width * 3.0;
+
+    let mut pattern = element::Pattern::new()
+        .set("id", pattern_id.as_str())
+        .set("x", page_bounds.mins[0])
+        .set("y", page_bounds.mins[1])
+        .set("width", page_width)
+        .set("height", page_height)
+        .set("patternUnits", "userSpaceOnUse")
+        .set("patternContentUnits", "userSpaceOnUse");
+
+    // The ruled lines between the header and the summary
+    let mut y = header_height + line_spacing;
+    while line_spacing > 0.0 && y < summary_top {
+        pattern = pattern.add(
+            element::Line::new()
+                .set("stroke-width", line_width)
+                .set("stroke", color.to_css_color_attr())
+                .set("x1", 0_f64)
+                .set("y1", y)
+                .set("x2", page_width)
+                .set("y2", y),
+        );
+        y += line_spacing;
+    }
+
+    pattern = pattern
+        .add(
+            // The margin line between the cue column and the notes
+            element::Line::new()
+                .set("stroke-width", margin_line_width)
+                .set("stroke", color.to_css_color_attr())
+                .set("x1", cue_column_width)
+                .set("y1", header_height)
+                .set("x2", cue_column_width)
+                .set("y2", summary_top),
+        )
+        .add(
+            // The line below the header
+            element::Line::new()
+                .set("stroke-width", margin_line_width)
+                .set("stroke", color.to_css_color_attr())
+                .set("x1", 0_f64)
+                .set("y1", header_height)
+                .set("x2", page_width)
+                .set("y2", header_height),
+        )
+        .add(
+            // The summary box
+            element::Rectangle::new()
+                .set("stroke-width", margin_line_width)
+                .set("stroke", color.to_css_color_attr())
+                .set("fill", "none")
+                .set("x", margin_line_width * 0.5)
+                .set("y", summary_top)
+                .set("width", page_width - margin_line_width)
+                .set(
+                    "height",
+                    page_height - summary_top - margin_line_width * 0.5,
+                ),
+        );
+
+    let rect = element::Rectangle::new()
+        .set("x", bounds.mins[0])
+        .set("y", bounds.mins[1])
+        .set("width", bounds.extents()[0])
+        .set("height", bounds.extents()[1])
+        .set("fill", format!("url(#{})", pattern_id));
+
+    let group = element::Group::new()
+        .add(element::Definitions::new().add(pattern))
+        .add(rect);
+    group.into()
+}
+
+#[derive(
+    Debug,
+    Eq,
+    PartialEq,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    num_derive::FromPrimitive,
+    num_derive::ToPrimitive,
+)]
+#[serde(rename = "template_fit")]
+pub enum TemplateFit {
+    /// The template is stretched to the size of the page
+    #[serde(rename = "stretch")]
+    Stretch = 0,
+    /// The template is repeated in its intrinsic size, starting at the page origin
+    #[serde(rename = "tile")]
+    Tile,
+}
+
+impl Default for TemplateFit {
+    fn default() -> Self {
+        Self::Stretch
+    }
+}
+
+impl TryFrom<u32> for TemplateFit {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        num_traits::FromPrimitive::from_u32(value).ok_or_else(|| {
+            anyhow::anyhow!("TemplateFit try_from::<u32>() for value {} failed", value)
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename = "template_source")]
+pub enum TemplateSource {
+    /// Svg data. Pdf pages are converted to svg on import
+    #[serde(rename = "svg")]
+    Svg {
+        #[serde(rename = "svg_data")]
+        svg_data: String,
+    },
+    /// A PNG encoded bitmap image. is (de) serialized in base64 encoding
+    #[serde(rename = "bitmap")]
+    Bitmap {
+        #[serde(rename = "data", with = "base64")]
+        data: Vec<u8>,
+    },
+}
+
+/// A template image, which is drawn on every page below the background pattern.
+/// It is part of the background and is embedded in the document, so it can't be selected or erased like strokes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename = "background_template")]
+pub struct BackgroundTemplate {
+    #[serde(rename = "source")]
+    pub source: TemplateSource,
+    #[serde(rename = "intrinsic_size")]
+    pub intrinsic_size: na::Vector2<f64>,
+    #[serde(rename = "fit")]
+    pub fit: TemplateFit,
+}
+
+impl BackgroundTemplate {
+    /// The max size of the template image in pixels, so that stretched templates don't take up huge amounts of memory when zoomed in
+    const IMAGE_MAX_PIXEL_SIZE: f64 = 4096.0;
+
+    pub fn from_svg_data(svg_data: &str) -> anyhow::Result<Self> {
+        let xml_options = usvg::XmlOptions {
+            id_prefix: Some(rnote_compose::utils::random_id_prefix()),
+            writer_opts: xmlwriter::Options {
+                use_single_quote: false,
+                indent: xmlwriter::Indent::None,
+                attributes_indent: xmlwriter::Indent::None,
+            },
+        };
+
+        let rtree = usvg::Tree::from_str(svg_data, &render::USVG_OPTIONS.to_ref())?;
+        let svg_data = rtree.to_string(&xml_options);
+
+        let svg_node = rtree.svg_node();
+        let intrinsic_size = na::vector![svg_node.size.width(), svg_node.size.height()];
+
+        Self::new(TemplateSource::Svg { svg_data }, intrinsic_size)
+    }
+
+    /// Imports a bitmap image in any of the supported formats. It is stored PNG encoded
+    pub fn from_bitmap_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        let image = render::Image::try_from_encoded_bytes(bytes)?;
+        let intrinsic_size =
+            na::vector![f64::from(image.pixel_width), f64::from(image.pixel_height)];
+        let data = image
+            .into_encoded_bytes(image::ImageOutputFormat::Png)
+            .context("into_encoded_bytes() failed in BackgroundTemplate from_bitmap_bytes()")?;
+
+        Self::new(TemplateSource::Bitmap { data }, intrinsic_size)
+    }
+
+    /// Imports the page with the given index from the pdf as svg
+    pub fn from_pdf_page_bytes(bytes: &[u8], page_i: u32) -> anyhow::Result<Self> {
+        let doc = poppler::Document::from_bytes(&glib::Bytes::from(bytes), None)?;
+        let page = doc.page(page_i as i32).ok_or_else(|| {
+            anyhow::anyhow!(
+                "page {} does not exist in pdf with {} pages",
+                page_i,
+                doc.n_pages()
+            )
+        })?;
+        let svg_data = render::Svg::gen_from_pdf_page(&page, None)?.svg_data;
+
+        Self::from_svg_data(&svg_data)
+    }
+
+    fn new(source: TemplateSource, intrinsic_size: na::Vector2<f64>) -> anyhow::Result<Self> {
+        if intrinsic_size[0] <= 0.0 || intrinsic_size[1] <= 0.0 {
+            return Err(anyhow::anyhow!(
+                "background template has invalid intrinsic size {:?}",
+                intrinsic_size
+            ));
+        }
+
+        Ok(Self {
+            source,
+            intrinsic_size,
+            fit: TemplateFit::default(),
+        })
+    }
+
+    /// The size after which the template repeats itself
+    fn period(&self, page_bounds: AABB) -> na::Vector2<f64> {
+        match self.fit {
+            TemplateFit::Stretch => page_bounds.extents(),
+            TemplateFit::Tile => self.intrinsic_size,
+        }
+    }
+
+    /// Generates the template, stretched to the bounds
+    fn gen_svg_element(&self, bounds: AABB) -> svg::node::element::Element {
+        match &self.source {
+            TemplateSource::Svg { svg_data } => element::SVG::new()
+                .set("x", bounds.mins[0])
+                .set("y", bounds.mins[1])
+                .set("width", bounds.extents()[0])
+                .set("height", bounds.extents()[1])
+                .set(
+                    "viewBox",
+                    format!(
+                        "{:.3} {:.3} {:.3} {:.3}",
+                        0.0, 0.0, self.intrinsic_size[0], self.intrinsic_size[1]
+                    ),
+                )
+                .set("preserveAspectRatio", "none")
+                .add(svg::node::Text::new(svg_data.clone()))
+                .into(),
+            TemplateSource::Bitmap { data } => element::Image::new()
+                .set("x", bounds.mins[0])
+                .set("y", bounds.mins[1])
+                .set("width", bounds.extents()[0])
+                .set("height", bounds.extents()[1])
+                .set("preserveAspectRatio", "none")
+                .set(
+                    "href",
+                    format!("data:image/png;base64,{}", ::base64::encode(data)),
+                )
+                .into(),
+        }
+    }
+
+    /// Generates the template repeating with its period inside the bounds, aligned to the page bounds
+    fn gen_pattern_svg_element(
+        &self,
+        bounds: AABB,
+        page_bounds: AABB,
+    ) -> svg::node::element::Element {
+        let pattern_id = rnote_compose::utils::random_id_prefix() + "_bg_template_pattern";
+        let period = self.period(page_bounds);
+
+        let pattern = element::Pattern::new()
+            .set("id", pattern_id.as_str())
+            .set("x", page_bounds.mins[0])
+            .set("y", page_bounds.mins[1])
+            .set("width", period[0])
+            .set("height", period[1])
+            .set("patternUnits", "userSpaceOnUse")
+            .set("patternContentUnits", "userSpaceOnUse")
+            .add(self.gen_svg_element(AABB::new(
+                na::point![0.0, 0.0],
+                na::point![period[0], period[1]],
+            )));
+
+        let rect = element::Rectangle::new()
+            .set("x", bounds.mins[0])
+            .set("y", bounds.mins[1])
+            .set("width", bounds.extents()[0])
+            .set("height", bounds.extents()[1])
+            .set("fill", format!("url(#{})", pattern_id));
+
+        let group = element::Group::new()
+            .add(element::Definitions::new().add(pattern))
+            .add(rect);
+        group.into()
+    }
+
+    /// Generates the image of one template period
+    fn gen_image(&self, page_bounds: AABB, image_scale: f64) -> anyhow::Result<render::Image> {
+        let period = self.period(page_bounds);
+        let bounds = AABB::new(na::point![0.0, 0.0], na::point![period[0], period[1]]);
+        let image_scale = image_scale.min(Self::IMAGE_MAX_PIXEL_SIZE / period.max());
+
+        let svg_data = rnote_compose::utils::svg_node_to_string(&self.gen_svg_element(bounds))
+            .map_err(|e| {
+                anyhow::anyhow!("node_to_string() failed for background template, {}", e)
+            })?;
+
+        render::Image::gen_image_from_svg(render::Svg { svg_data, bounds }, bounds, image_scale)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename = "background")]
 pub struct Background {
@@ -195,9 +732,13 @@ pub struct Background {
     pub pattern_size: na::Vector2<f64>,
     #[serde(rename = "pattern_color")]
     pub pattern_color: Color,
+    #[serde(rename = "template")]
+    pub template: Option<BackgroundTemplate>,
     #[serde(skip)]
     pub image: Option<render::Image>,
     #[serde(skip)]
+    template_image: Option<render::Image>,
+    #[serde(skip)]
     rendernodes: Vec<gsk::RenderNode>,
 }
 
@@ -208,7 +749,9 @@ impl Default for Background {
             pattern: PatternStyle::default(),
             pattern_size: Self::PATTERN_SIZE_DEFAULT,
             pattern_color: Self::PATTERN_COLOR_DEFAULT,
+            template: None,
             image: None,
+            template_image: None,
             rendernodes: vec![],
         }
     }
@@ -216,6 +759,9 @@ impl Default for Background {
 
 impl Background {
     const TILE_MAX_SIZE: f64 = 128.0;
+    /// The max size of the pattern image in pixels. Patterns that are aligned to the page (e.g. cornell notes) have the page as tile,
+    /// which would take up huge amounts of memory when zoomed in
+    const IMAGE_MAX_PIXEL_SIZE: f64 = 4096.0;
     const COLOR_DEFAULT: Color = Color::WHITE;
     const PATTERN_SIZE_DEFAULT: na::Vector2<f64> = na::vector![32.0, 32.0];
     const PATTERN_COLOR_DEFAULT: Color = Color {
@@ -224,27 +770,96 @@ impl Background {
         b: 1.0,
         a: 1.0,
     };
+    /// The height of the staves pattern period, in multiples of the line spacing
+    const STAVES_PERIOD_LINES: f64 = 10.0;
+    /// The width of the cue column of the cornell pattern, relative to the page width
+    const CORNELL_CUE_COLUMN_WIDTH_RATIO: f64 = 0.3;
+    /// The height of the summary box of the cornell pattern, relative to the page height
+    const CORNELL_SUMMARY_HEIGHT_RATIO: f64 = 0.2;
 
-    /// Calculates the tile size as multiple of pattern_size with max size TITLE_MAX_SIZE
-    fn tile_size(&self) -> na::Vector2<f64> {
-        let tile_factor =
-            na::Vector2::from_element(Self::TILE_MAX_SIZE).component_div(&self.pattern_size);
+    /// The size after which the pattern repeats itself
+    fn pattern_period(&self, page_bounds: AABB) -> na::Vector2<f64> {
+        match self.pattern {
+            PatternStyle::None | PatternStyle::Lines | PatternStyle::Grid | PatternStyle::Dots => {
+                self.pattern_size
+            }
+            PatternStyle::IsometricDots | PatternStyle::IsometricGrid => {
+                na::vector![self.pattern_size[0], self.pattern_size[0] * 3_f64.sqrt()]
+            }
+            PatternStyle::HexGrid => na::vector![
+                self.pattern_size[0] * 3.0,
+                self.pattern_size[0] * 3_f64.sqrt()
+            ],
+            PatternStyle::Staves => na::vector![
+                self.pattern_size[0],
+                self.pattern_size[1] * Self::STAVES_PERIOD_LINES
+            ],
+            PatternStyle::Cornell => page_bounds.extents(),
+        }
+    }
+
+    /// The origin the pattern is aligned to. Only the cornell pattern is aligned to the page
+    fn pattern_origin(&self, page_bounds: AABB) -> na::Point2<f64> {
+        match self.pattern {
+            PatternStyle::Cornell => page_bounds.mins,
+            _ => na::point![0.0, 0.0],
+        }
+    }
+
+    /// Whether the pattern or the template is aligned to the size of the page, so that it needs to be drawn with the bounds of each page
+    pub(crate) fn aligned_to_page_size(&self) -> bool {
+        self.pattern == PatternStyle::Cornell
+            || self
+                .template
+                .as_ref()
+                .map_or(false, |template| template.fit == TemplateFit::Stretch)
+    }
+
+    /// The origin and the spacing of the pattern lines or dots that positions can be snapped to, with the bounds of the page it is drawn on.
+    /// The spacing is None on the axes where the pattern has no regular spacing
+    pub(crate) fn snap_grid(&self, page_bounds: AABB) -> (na::Point2<f64>, [Option<f64>; 2]) {
+        let spacing = match self.pattern {
+            PatternStyle::None => [None, None],
+            PatternStyle::Lines | PatternStyle::Cornell => [None, Some(self.pattern_size[1])],
+            PatternStyle::Grid | PatternStyle::Dots => {
+                [Some(self.pattern_size[0]), Some(self.pattern_size[1])]
+            }
+            // Not aligned to a rectangular grid of the pattern size
+            PatternStyle::IsometricDots
+            | PatternStyle::IsometricGrid
+            | PatternStyle::HexGrid
+            | PatternStyle::Staves => [None, None],
+        };
+
+        (
+            self.pattern_origin(page_bounds),
+            spacing.map(|spacing| spacing.filter(|&spacing| spacing > 0.0)),
+        )
+    }
+
+    /// Calculates the tile size as multiple of the pattern period with max size TITLE_MAX_SIZE.
+    /// When zoomed out the max size grows, so that the number of tiles in the viewport stays bounded.
+    /// The tile is at least one pattern period, which can be larger. Its image is then clamped to IMAGE_MAX_PIXEL_SIZE
+    fn tile_size(&self, page_bounds: AABB, image_scale: f64) -> na::Vector2<f64> {
+        let pattern_period = self.pattern_period(page_bounds);
+        let tile_max_size = Self::TILE_MAX_SIZE / image_scale.min(1.0);
+        let tile_factor = na::Vector2::from_element(tile_max_size).component_div(&pattern_period);
 
         let tile_width = if tile_factor[0] > 1.0 {
-            tile_factor[0].floor() * self.pattern_size[0]
+            tile_factor[0].floor() * pattern_period[0]
         } else {
-            self.pattern_size[0]
+            pattern_period[0]
         };
         let tile_height = if tile_factor[1] > 1.0 {
-            tile_factor[1].floor() * self.pattern_size[1]
+            tile_factor[1].floor() * pattern_period[1]
         } else {
-            self.pattern_size[1]
+            pattern_period[1]
         };
 
         na::vector![tile_width, tile_height]
     }
 
-    fn gen_svg_element(&self, bounds: AABB) -> svg::node::element::Element {
+    fn gen_svg_element(&self, bounds: AABB, page_bounds: AABB) -> svg::node::element::Element {
         let mut group = element::Group::new();
 
         // background color
@@ -256,6 +871,23 @@ impl Background {
             .set("fill", self.color.to_css_color_attr());
         group = group.add(color_rect);
 
+        if let Some(template) = &self.template {
+            group = group.add(template.gen_pattern_svg_element(bounds, page_bounds));
+        }
+
+        group = group.add(self.gen_pattern_svg_element(bounds, page_bounds));
+
+        group.into()
+    }
+
+    /// Generates the pattern without the background color and the template
+    fn gen_pattern_svg_element(
+        &self,
+        bounds: AABB,
+        page_bounds: AABB,
+    ) -> svg::node::element::Element {
+        let mut group = element::Group::new();
+
         match self.pattern {
             PatternStyle::None => {}
             PatternStyle::Lines => {
@@ -284,23 +916,67 @@ impl Background {
                     1.5,
                 ));
             }
+            PatternStyle::IsometricDots => {
+                group = group.add(gen_isometric_dots_pattern(
+                    bounds,
+                    self.pattern_size[0],
+                    self.pattern_color,
+                    1.5,
+                ));
+            }
+            PatternStyle::IsometricGrid => {
+                group = group.add(gen_isometric_grid_pattern(
+                    bounds,
+                    self.pattern_size[0],
+                    self.pattern_color,
+                    0.5,
+                ));
+            }
+            PatternStyle::HexGrid => {
+                group = group.add(gen_hex_grid_pattern(
+                    bounds,
+                    self.pattern_size[0],
+                    self.pattern_color,
+                    0.5,
+                ));
+            }
+            PatternStyle::Staves => {
+                group = group.add(gen_staves_pattern(
+                    bounds,
+                    self.pattern_size[1],
+                    self.pattern_color,
+                    0.5,
+                ));
+            }
+            PatternStyle::Cornell => {
+                group = group.add(gen_cornell_pattern(
+                    bounds,
+                    page_bounds,
+                    self.pattern_size[1],
+                    self.pattern_color,
+                    0.5,
+                ));
+            }
         }
 
         group.into()
     }
 
+    /// Draws the background. The pattern is aligned to the page bounds, when it depends on the page (e.g. cornell notes)
     pub fn draw_to_piet_svg(
         &self,
         piet_svg_cx: &mut piet_svg::RenderContext,
         bounds: AABB,
+        page_bounds: AABB,
     ) -> anyhow::Result<()> {
-        piet_svg_cx.append_svg_node(self.gen_svg_element(bounds));
+        piet_svg_cx.append_svg_node(self.gen_svg_element(bounds, page_bounds));
         Ok(())
     }
 
-    /// Generates the background svg, without xml header or svg root
-    pub fn gen_svg(&self, bounds: AABB) -> Result<render::Svg, anyhow::Error> {
-        let svg_element = self.gen_svg_element(bounds);
+    /// Generates the background svg, without xml header or svg root.
+    /// The pattern is aligned to the page bounds, when it depends on the page (e.g. cornell notes)
+    pub fn gen_svg(&self, bounds: AABB, page_bounds: AABB) -> Result<render::Svg, anyhow::Error> {
+        let svg_element = self.gen_svg_element(bounds, page_bounds);
 
         let svg_data = rnote_compose::utils::svg_node_to_string(&svg_element)
             .map_err(|e| anyhow::anyhow!("node_to_string() failed for background, {}", e))?;
@@ -308,21 +984,56 @@ impl Background {
         Ok(render::Svg { svg_data, bounds })
     }
 
-    fn gen_image(
+    /// Generates the image of the pattern. It is transparent, so that the background color and the template stay visible below it
+    fn gen_pattern_image(
         &self,
         bounds: AABB,
+        page_bounds: AABB,
         image_scale: f64,
     ) -> Result<Option<render::Image>, anyhow::Error> {
-        let svg = self.gen_svg(bounds)?;
+        let svg_data = rnote_compose::utils::svg_node_to_string(
+            &self.gen_pattern_svg_element(bounds, page_bounds),
+        )
+        .map_err(|e| anyhow::anyhow!("node_to_string() failed for background pattern, {}", e))?;
+        let image_scale = image_scale.min(Self::IMAGE_MAX_PIXEL_SIZE / bounds.extents().max());
+
         Ok(Some(render::Image::gen_image_from_svg(
-            svg,
+            render::Svg { svg_data, bounds },
             bounds,
             image_scale,
         )?))
     }
 
-    fn gen_rendernodes(&mut self, viewport: AABB) -> Result<Vec<gsk::RenderNode>, anyhow::Error> {
+    fn gen_rendernodes(
+        &mut self,
+        viewport: AABB,
+        page_bounds: AABB,
+    ) -> Result<Vec<gsk::RenderNode>, anyhow::Error> {
         let mut rendernodes: Vec<gsk::RenderNode> = vec![];
+        let pattern_offset = self.pattern_origin(page_bounds).coords;
+
+        // The template is below the pattern and always aligned to the page
+        if let (Some(template), Some(template_image)) = (&self.template, &self.template_image) {
+            let template_texture = template_image.to_memtexture().context(
+                "template image to_memtexture() failed in gen_rendernode() of background.",
+            )?;
+            let template_offset = page_bounds.mins.coords;
+
+            for splitted_bounds in viewport
+                .translate(-template_offset)
+                .split_extended_origin_aligned(template.period(page_bounds))
+            {
+                let splitted_bounds = splitted_bounds.translate(template_offset);
+
+                rendernodes.push(
+                    gsk::TextureNode::new(
+                        &template_texture,
+                        &graphene::Rect::from_p2d_aabb(splitted_bounds),
+                    )
+                    .upcast(),
+                );
+            }
+        }
 
         if let Some(image) = &self.image {
             // Only create the texture once, it is expensive
@@ -330,9 +1041,12 @@ impl Background {
                 .to_memtexture()
                 .context("image to_memtexture() failed in gen_rendernode() of background.")?;
 
-            for splitted_bounds in
-                viewport.split_extended_origin_aligned(image.rect.bounds().extents())
+            for splitted_bounds in viewport
+                .translate(-pattern_offset)
+                .split_extended_origin_aligned(image.rect.bounds().extents())
             {
+                let splitted_bounds = splitted_bounds.translate(pattern_offset);
+
                 rendernodes.push(
                     gsk::TextureNode::new(
                         &new_texture,
@@ -346,8 +1060,8 @@ impl Background {
         Ok(rendernodes)
     }
 
-    pub fn update_rendernodes(&mut self, viewport: AABB) -> anyhow::Result<()> {
-        match self.gen_rendernodes(viewport) {
+    pub fn update_rendernodes(&mut self, viewport: AABB, page_bounds: AABB) -> anyhow::Result<()> {
+        match self.gen_rendernodes(viewport, page_bounds) {
             Ok(rendernodes) => {
                 self.rendernodes = rendernodes;
             }
@@ -362,13 +1076,38 @@ impl Background {
         Ok(())
     }
 
-    pub fn regenerate_pattern(&mut self, viewport: AABB, image_scale: f64) -> anyhow::Result<()> {
-        let tile_size = self.tile_size();
+    /// Whether the pattern tile was generated and not cleared since
+    pub(crate) fn pattern_generated(&self) -> bool {
+        self.image.is_some()
+    }
+
+    /// Clears the pattern tile, the template image and the rendernodes. The pattern then needs to be regenerated before it is drawn
+    pub(crate) fn clear_pattern(&mut self) {
+        self.image = None;
+        self.template_image = None;
+        self.rendernodes = vec![];
+    }
+
+    /// Regenerates the pattern tile and the template image. The pattern is aligned to the page bounds, when it depends on the page (e.g. cornell notes)
+    pub fn regenerate_pattern(
+        &mut self,
+        viewport: AABB,
+        page_bounds: AABB,
+        image_scale: f64,
+    ) -> anyhow::Result<()> {
+        let tile_size = self.tile_size(page_bounds, image_scale);
         let tile_bounds = AABB::new(na::point![0.0, 0.0], na::point![tile_size[0], tile_size[1]]);
+        // The tile is generated at the origin
+        let tile_page_bounds = page_bounds.translate(-page_bounds.mins.coords);
 
-        self.image = self.gen_image(tile_bounds, image_scale)?;
+        self.image = self.gen_pattern_image(tile_bounds, tile_page_bounds, image_scale)?;
+        self.template_image = self
+            .template
+            .as_ref()
+            .map(|template| template.gen_image(tile_page_bounds, image_scale))
+            .transpose()?;
 
-        self.update_rendernodes(viewport)?;
+        self.update_rendernodes(viewport, page_bounds)?;
         Ok(())
     }
 
@@ -397,3 +1136,211 @@ impl Background {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bounds() -> AABB {
+        AABB::new(na::point![0.0, 0.0], na::point![400.0, 400.0])
+    }
+
+    fn attr(name: &str, value: f64) -> String {
+        format!("{}=\"{}\"", name, value)
+    }
+
+    #[test]
+    fn isometric_patterns_repeat_with_triangle_height() {
+        let height = 10.0 * 3_f64.sqrt();
+
+        let dots = gen_isometric_dots_pattern(bounds(), 10.0, Color::BLACK, 1.5).to_string();
+        assert!(dots.contains(&attr("width", 10.0)));
+        assert!(dots.contains(&attr("height", height)));
+        // Two dots and the rectangle filled with the pattern
+        assert_eq!(dots.matches("<rect").count(), 3);
+
+        let grid = gen_isometric_grid_pattern(bounds(), 10.0, Color::BLACK, 0.5).to_string();
+        assert!(grid.contains(&attr("width", 10.0)));
+        assert!(grid.contains(&attr("height", height)));
+        assert_eq!(grid.matches("<line").count(), 4);
+    }
+
+    #[test]
+    fn hex_grid_pattern_repeats_with_two_hexagons() {
+        let hex_grid = gen_hex_grid_pattern(bounds(), 10.0, Color::BLACK, 0.5).to_string();
+
+        assert!(hex_grid.contains(&attr("width", 30.0)));
+        assert!(hex_grid.contains(&attr("height", 10.0 * 3_f64.sqrt())));
+        assert_eq!(hex_grid.matches("<path").count(), 1);
+    }
+
+    #[test]
+    fn staves_have_five_lines() {
+        let staves = gen_staves_pattern(bounds(), 10.0, Color::BLACK, 0.5).to_string();
+
+        assert!(staves.contains(&attr("height", 100.0)));
+        assert_eq!(staves.matches("<line").count(), 5);
+        for y in [30.0, 40.0, 50.0, 60.0, 70.0] {
+            assert!(staves.contains(&attr("y1", y)));
+        }
+    }
+
+    #[test]
+    fn cornell_pattern_is_aligned_to_the_page() {
+        let page_bounds = AABB::new(na::point![50.0, 100.0], na::point![250.0, 400.0]);
+        let cornell =
+            gen_cornell_pattern(bounds(), page_bounds, 10.0, Color::BLACK, 0.5).to_string();
+
+        assert!(cornell.contains(&attr("x", 50.0)));
+        assert!(cornell.contains(&attr("y", 100.0)));
+        assert!(cornell.contains(&attr("width", 200.0)));
+        assert!(cornell.contains(&attr("height", 300.0)));
+        // The ruled lines from below the header to the summary box, the margin line and the line below the header
+        assert_eq!(cornell.matches("<line").count(), 21 + 2);
+        // The summary box and the rectangle filled with the pattern
+        assert_eq!(cornell.matches("<rect").count(), 2);
+    }
+
+    #[test]
+    fn pattern_image_size_is_clamped() {
+        let page_bounds = AABB::new(na::point![0.0, 0.0], na::point![300.0, 400.0]);
+        let background = Background {
+            pattern: PatternStyle::Cornell,
+            ..Background::default()
+        };
+        let tile_size = background.tile_size(page_bounds, 20.0);
+        let tile_bounds = AABB::new(na::point![0.0, 0.0], na::Point2::from(tile_size));
+
+        let image = background
+            .gen_pattern_image(tile_bounds, page_bounds, 20.0)
+            .unwrap()
+            .unwrap();
+        // The image bounds are loosened by a small margin
+        assert!(f64::from(image.pixel_height) < Background::IMAGE_MAX_PIXEL_SIZE * 1.01);
+        assert!(f64::from(image.pixel_width) < Background::IMAGE_MAX_PIXEL_SIZE * 1.01);
+    }
+
+    #[test]
+    fn cornell_background_follows_the_page() {
+        let page_bounds = AABB::new(na::point![0.0, 500.0], na::point![300.0, 900.0]);
+        let background = Background {
+            pattern: PatternStyle::Cornell,
+            pattern_size: na::vector![20.0, 30.0],
+            ..Background::default()
+        };
+
+        assert!(background.aligned_to_page_size());
+        assert_eq!(
+            background.pattern_period(page_bounds),
+            page_bounds.extents()
+        );
+        assert_eq!(background.pattern_origin(page_bounds), page_bounds.mins);
+
+        let (origin, spacing) = background.snap_grid(page_bounds);
+        assert_eq!(origin, page_bounds.mins);
+        assert_eq!(spacing, [None, Some(30.0)]);
+    }
+
+    #[test]
+    fn grid_backgrounds_follow_the_origin() {
+        let page_bounds = AABB::new(na::point![0.0, 500.0], na::point![300.0, 900.0]);
+
+        for pattern in [
+            PatternStyle::IsometricDots,
+            PatternStyle::IsometricGrid,
+            PatternStyle::HexGrid,
+            PatternStyle::Staves,
+        ] {
+            let background = Background {
+                pattern,
+                ..Background::default()
+            };
+
+            assert!(!background.aligned_to_page_size());
+            assert_eq!(background.pattern_origin(page_bounds), na::point![0.0, 0.0]);
+            assert_eq!(background.snap_grid(page_bounds).1, [None, None]);
+        }
+    }
+
+    #[test]
+    fn patterns_map_to_xopp_styles() {
+        use xoppformat::XoppBackgroundSolidStyle;
+
+        assert!(matches!(
+            XoppBackgroundSolidStyle::from(PatternStyle::IsometricDots),
+            XoppBackgroundSolidStyle::IsometricDotted
+        ));
+        assert!(matches!(
+            XoppBackgroundSolidStyle::from(PatternStyle::IsometricGrid),
+            XoppBackgroundSolidStyle::IsometricGraph
+        ));
+        assert!(matches!(
+            XoppBackgroundSolidStyle::from(PatternStyle::HexGrid),
+            XoppBackgroundSolidStyle::Graph
+        ));
+        assert!(matches!(
+            XoppBackgroundSolidStyle::from(PatternStyle::Staves),
+            XoppBackgroundSolidStyle::Staves
+        ));
+        assert!(matches!(
+            XoppBackgroundSolidStyle::from(PatternStyle::Cornell),
+            XoppBackgroundSolidStyle::Lined
+        ));
+    }
+
+    #[test]
+    fn templates_survive_rnote_file_round_trip() {
+        use rnote_fileformats::rnoteformat::RnotefileMaj0Min5;
+        use rnote_fileformats::{FileFormatLoader, FileFormatSaver};
+
+        let mut svg_template = BackgroundTemplate::from_svg_data(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="30" height="20"><rect width="10" height="10"/></svg>"#,
+        )
+        .unwrap();
+        svg_template.fit = TemplateFit::Tile;
+
+        let mut png_bytes = vec![];
+        image::DynamicImage::new_rgba8(3, 2)
+            .write_to(
+                &mut std::io::Cursor::new(&mut png_bytes),
+                image::ImageOutputFormat::Png,
+            )
+            .unwrap();
+        let bitmap_template = BackgroundTemplate::from_bitmap_bytes(&png_bytes).unwrap();
+
+        let mut doc = super::super::Document::default();
+        doc.background.template = Some(svg_template);
+        doc.page_mut(1).background = Some(Background {
+            template: Some(bitmap_template.clone()),
+            ..Background::default()
+        });
+
+        let rnote_file = RnotefileMaj0Min5 {
+            document: serde_json::to_value(&doc).unwrap(),
+            store_snapshot: serde_json::Value::Null,
+        };
+        let bytes = rnote_file.save_as_bytes("template.rnote").unwrap();
+        let loaded_doc: super::super::Document =
+            serde_json::from_value(RnotefileMaj0Min5::load_from_bytes(&bytes).unwrap().document)
+                .unwrap();
+
+        let svg_template = loaded_doc.background.template.as_ref().unwrap();
+        assert_eq!(svg_template.fit, TemplateFit::Tile);
+        assert_eq!(svg_template.intrinsic_size, na::vector![30.0, 20.0]);
+        assert!(
+            matches!(&svg_template.source, TemplateSource::Svg { svg_data } if svg_data.contains("<path"))
+        );
+
+        let loaded_bitmap_template = loaded_doc
+            .page(1)
+            .and_then(|page| page.background.as_ref())
+            .and_then(|background| background.template.as_ref())
+            .unwrap();
+        assert_eq!(loaded_bitmap_template.fit, TemplateFit::default());
+        assert_eq!(loaded_bitmap_template.intrinsic_size, na::vector![3.0, 2.0]);
+        assert!(matches!(
+            (&loaded_bitmap_template.source, &bitmap_template.source),
+            (TemplateSource::Bitmap { data: loaded_data }, TemplateSource::Bitmap { data }) if loaded_data == data
+        ));
+    }
+}
@@ -3,7 +3,6 @@ use p2d::bounding_volume::{BoundingVolume, AABB};
 use piet::RenderContext;
 use serde::{Deserialize, Serialize};
 
-use rnote_compose::helpers::AABBHelpers;
 use rnote_compose::{color, Color};
 
 use crate::utils::{GdkRGBAHelpers, GrapheneRectHelpers};
@@ -139,6 +138,63 @@ impl Default for Orientation {
     }
 }
 
+/// The margins of the pages. New content is placed inside of them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename = "page_margins")]
+pub struct PageMargins {
+    #[serde(rename = "top")]
+    pub top: f64,
+    #[serde(rename = "bottom")]
+    pub bottom: f64,
+    #[serde(rename = "left")]
+    pub left: f64,
+    #[serde(rename = "right")]
+    pub right: f64,
+}
+
+impl PageMargins {
+    pub fn is_zero(&self) -> bool {
+        self.top == 0.0 && self.bottom == 0.0 && self.left == 0.0 && self.right == 0.0
+    }
+}
+
+/// The values for the placeholders in the header and footer templates
+#[derive(Debug, Clone)]
+pub struct PageTextContext {
+    /// The number of the page, starting with 1
+    pub page_number: usize,
+    pub n_pages: usize,
+    pub title: String,
+    pub date: String,
+}
+
+impl PageTextContext {
+    /// Creates the context for the first page with the current date. Created once for all pages, with the page number updated for each page
+    pub fn new(n_pages: usize, title: String) -> Self {
+        let date = glib::DateTime::now_local()
+            .and_then(|datetime| datetime.format("%x"))
+            .map(|date| date.to_string())
+            .unwrap_or_default();
+
+        Self {
+            page_number: 1,
+            n_pages,
+            title,
+            date,
+        }
+    }
+
+    /// Replaces the placeholders `{page}`, `{pages}`, `{date}` and `{title}` in the template
+    pub fn expand(&self, template: &str) -> String {
+        // the title is replaced last, so that placeholders in it are kept as they are
+        template
+            .replace("{page}", &self.page_number.to_string())
+            .replace("{pages}", &self.n_pages.to_string())
+            .replace("{date}", &self.date)
+            .replace("{title}", &self.title)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename = "format")]
 pub struct Format {
@@ -154,6 +210,14 @@ pub struct Format {
     pub border_color: Color,
     #[serde(rename = "show_borders")]
     pub show_borders: bool,
+    #[serde(rename = "margins")]
+    pub margins: PageMargins,
+    /// The template of the text in the top margin of the pages
+    #[serde(rename = "header")]
+    pub header: Option<String>,
+    /// The template of the text in the bottom margin of the pages
+    #[serde(rename = "footer")]
+    pub footer: Option<String>,
 }
 
 impl Default for Format {
@@ -165,6 +229,9 @@ impl Default for Format {
             orientation: Orientation::default(),
             border_color: Color::from(Self::BORDER_COLOR_DEFAULT),
             show_borders: true,
+            margins: PageMargins::default(),
+            header: None,
+            footer: None,
         }
     }
 }
@@ -184,6 +251,82 @@ impl Format {
 
     pub const BORDER_COLOR_DEFAULT: piet::Color = color::GNOME_BRIGHTS[2];
 
+    pub const MARGIN_MAX: f64 = 5000.0;
+    /// The header and footer are drawn in at least this height, even when the margins are smaller
+    const PAGE_TEXT_AREA_MIN_HEIGHT: f64 = 32.0;
+    const PAGE_TEXT_FONT_SIZE: f64 = 12.0;
+    const PAGE_TEXT_COLOR: piet::Color = color::GNOME_DARKS[0];
+
+    /// The bounds of the page inside the margins
+    pub fn content_bounds(&self, page_bounds: AABB) -> AABB {
+        let mins = page_bounds.mins + na::vector![self.margins.left, self.margins.top];
+        let maxs = page_bounds.maxs - na::vector![self.margins.right, self.margins.bottom];
+
+        // Margins larger than the page leave an empty content area
+        AABB::new(mins, maxs.sup(&mins))
+    }
+
+    /// The area of the header in the top margin, and of the footer in the bottom margin
+    fn page_text_areas(&self, page_bounds: AABB) -> (AABB, AABB) {
+        let content_bounds = self.content_bounds(page_bounds);
+        let header_height = self.margins.top.max(Self::PAGE_TEXT_AREA_MIN_HEIGHT);
+        let footer_height = self.margins.bottom.max(Self::PAGE_TEXT_AREA_MIN_HEIGHT);
+
+        (
+            AABB::new(
+                na::point![content_bounds.mins[0], page_bounds.mins[1]],
+                na::point![content_bounds.maxs[0], page_bounds.mins[1] + header_height],
+            ),
+            AABB::new(
+                na::point![content_bounds.mins[0], page_bounds.maxs[1] - footer_height],
+                na::point![content_bounds.maxs[0], page_bounds.maxs[1]],
+            ),
+        )
+    }
+
+    /// Whether there is a header or footer to draw
+    pub fn has_page_texts(&self) -> bool {
+        self.header.is_some() || self.footer.is_some()
+    }
+
+    /// Draws the header and footer of the page, centered in the top and bottom margin
+    pub fn draw_page_texts(
+        &self,
+        piet_cx: &mut impl piet::RenderContext,
+        page_bounds: AABB,
+        page_text_context: &PageTextContext,
+    ) -> anyhow::Result<()> {
+        use piet::{Text, TextLayout, TextLayoutBuilder};
+
+        let (header_area, footer_area) = self.page_text_areas(page_bounds);
+
+        for (template, area) in [(&self.header, header_area), (&self.footer, footer_area)] {
+            let template = match template {
+                Some(template) => template,
+                None => continue,
+            };
+
+            let text_layout = piet_cx
+                .text()
+                .new_text_layout(page_text_context.expand(template))
+                .font(piet::FontFamily::SANS_SERIF, Self::PAGE_TEXT_FONT_SIZE)
+                .text_color(Self::PAGE_TEXT_COLOR)
+                .max_width(area.extents()[0])
+                .alignment(piet::TextAlignment::Center)
+                .build()
+                .map_err(|e| anyhow::anyhow!("{}", e))?;
+
+            let pos = na::point![
+                area.mins[0],
+                area.center()[1] - text_layout.size().height * 0.5
+            ];
+
+            piet_cx.draw_text(&text_layout, kurbo::Point::new(pos[0], pos[1]));
+        }
+
+        Ok(())
+    }
+
     fn draw_origin_indicator(camera: &Camera) -> anyhow::Result<gsk::RenderNode> {
         const PATH_COLOR: piet::Color = color::GNOME_GREENS[4];
         let path_width: f64 = 1.0 / camera.total_zoom();
@@ -226,6 +369,7 @@ impl Format {
         &self,
         snapshot: &Snapshot,
         doc_bounds: AABB,
+        pages_bounds: &[AABB],
         camera: &Camera,
     ) -> anyhow::Result<()> {
         if self.show_borders {
@@ -235,9 +379,7 @@ impl Format {
 
             snapshot.push_clip(&graphene::Rect::from_p2d_aabb(doc_bounds.loosened(2.0)));
 
-            for page_bounds in
-                doc_bounds.split_extended_origin_aligned(na::vector![self.width, self.height])
-            {
+            for &page_bounds in pages_bounds {
                 if !page_bounds.intersects(&viewport) {
                     continue;
                 }
@@ -275,4 +417,64 @@ impl Format {
 
         Ok(())
     }
+
+    /// Draws the margin guides and the header and footer of the pages in the viewport
+    pub fn draw_page_decorations(
+        &self,
+        snapshot: &Snapshot,
+        pages_bounds: &[AABB],
+        title: &str,
+        camera: &Camera,
+    ) -> anyhow::Result<()> {
+        let border_width = 1.0 / camera.total_zoom();
+        let viewport = camera.viewport();
+        let mut page_text_context = PageTextContext::new(pages_bounds.len(), title.to_string());
+        let mut guide_color = self.border_color;
+        guide_color.a *= 0.5;
+
+        for (i, &page_bounds) in pages_bounds.iter().enumerate() {
+            if !page_bounds.intersects(&viewport) {
+                continue;
+            }
+
+            if self.show_borders && !self.margins.is_zero() {
+                let rounded_rect = gsk::RoundedRect::new(
+                    graphene::Rect::from_p2d_aabb(self.content_bounds(page_bounds)),
+                    graphene::Size::zero(),
+                    graphene::Size::zero(),
+                    graphene::Size::zero(),
+                    graphene::Size::zero(),
+                );
+
+                snapshot.append_border(
+                    &rounded_rect,
+                    &[
+                        border_width as f32,
+                        border_width as f32,
+                        border_width as f32,
+                        border_width as f32,
+                    ],
+                    &[
+                        gdk::RGBA::from_compose_color(guide_color),
+                        gdk::RGBA::from_compose_color(guide_color),
+                        gdk::RGBA::from_compose_color(guide_color),
+                        gdk::RGBA::from_compose_color(guide_color),
+                    ],
+                )
+            }
+
+            if self.has_page_texts() {
+                page_text_context.page_number = i + 1;
+
+                let cairo_cx = snapshot.append_cairo(&graphene::Rect::from_p2d_aabb(page_bounds));
+                let mut piet_cx = piet_cairo::CairoRenderContext::new(&cairo_cx);
+
+                self.draw_page_texts(&mut piet_cx, page_bounds, &page_text_context)?;
+
+                piet_cx.finish().map_err(|e| anyhow::anyhow!("{}", e))?;
+            }
+        }
+
+        Ok(())
+    }
 }
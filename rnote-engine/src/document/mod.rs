@@ -1,17 +1,22 @@
 pub mod background;
 pub mod format;
+pub mod page;
+pub mod snap;
 
 // Re-exports
 pub use background::Background;
 pub use format::Format;
+pub use page::{Page, PagesSnapshot};
 use rnote_compose::Color;
+pub use snap::SnapOptions;
 
 use crate::utils::{GdkRGBAHelpers, GrapheneRectHelpers};
-use crate::{Camera, StrokeStore};
+use crate::{render, Camera, StrokeStore};
 use rnote_compose::helpers::AABBHelpers;
 
 use gtk4::{gdk, graphene, gsk, Snapshot};
 use p2d::bounding_volume::{BoundingVolume, AABB};
+use p2d::query::PointQuery;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -21,6 +26,11 @@ pub enum Layout {
     FixedSize,
     #[serde(rename = "continuous_vertical", alias = "endless_vertical")]
     ContinuousVertical,
+    #[serde(rename = "continuous_horizontal")]
+    ContinuousHorizontal,
+    /// Two pages side by side, the spreads continue downwards
+    #[serde(rename = "book_spread")]
+    BookSpread,
     #[serde(rename = "infinite")]
     Infinite,
 }
@@ -31,6 +41,34 @@ impl Default for Layout {
     }
 }
 
+impl Layout {
+    /// The direction in which the pages follow each other and the document expands
+    pub fn flow_direction(&self) -> FlowDirection {
+        match self {
+            Self::ContinuousHorizontal => FlowDirection::Horizontal,
+            Self::FixedSize | Self::ContinuousVertical | Self::BookSpread | Self::Infinite => {
+                FlowDirection::Vertical
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlowDirection {
+    Vertical,
+    Horizontal,
+}
+
+impl FlowDirection {
+    /// The index of the coordinate in the flow direction
+    pub fn axis(&self) -> usize {
+        match self {
+            Self::Vertical => 1,
+            Self::Horizontal => 0,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename = "document")]
 pub struct Document {
@@ -46,8 +84,27 @@ pub struct Document {
     pub format: Format,
     #[serde(rename = "background")]
     pub background: Background,
+    #[serde(rename = "snap")]
+    pub snap: SnapOptions,
     #[serde(rename = "layout", alias = "expand_mode")]
     layout: Layout,
+    /// The per-page overrides of the format and background, indexed by the page number.
+    /// Pages without an entry use the document settings
+    #[serde(rename = "pages")]
+    pages: Vec<Page>,
+    /// The number of pages the continuous layouts keep, even when they are empty. Raised by explicitly inserted pages
+    #[serde(rename = "n_pages_min")]
+    n_pages_min: usize,
+    /// Copies of the document background for the pages that are not aligned to its pattern, with their bounds.
+    /// Only needed when the pattern is aligned to the page size. Updated when the background patterns are regenerated
+    #[serde(skip)]
+    unaligned_page_backgrounds: Vec<(AABB, Background)>,
+    /// Incremented every time the backgrounds were changed, to know when renderings of them are outdated
+    #[serde(skip)]
+    background_generation: u64,
+    /// The title for the `{title}` placeholder of the header and footer. Not saved, it is set from the file name
+    #[serde(skip)]
+    pub title: String,
 }
 
 impl Default for Document {
@@ -59,7 +116,13 @@ impl Default for Document {
             height: Format::default().height,
             format: Format::default(),
             background: Background::default(),
+            snap: SnapOptions::default(),
             layout: Layout::default(),
+            pages: vec![],
+            n_pages_min: 0,
+            unaligned_page_backgrounds: vec![],
+            background_generation: 0,
+            title: String::new(),
         }
     }
 }
@@ -73,6 +136,8 @@ impl Document {
         b: 0.1,
         a: 0.3,
     };
+    /// The tolerance when checking if pages are aligned to the document background pattern
+    const ALIGNMENT_TOLERANCE: f64 = 1e-3;
 
     pub(crate) fn layout(&self) -> Layout {
         self.layout
@@ -84,6 +149,23 @@ impl Document {
         self.resize_to_fit_strokes(store, camera);
     }
 
+    /// A document with only the settings that are saved in the app settings and used as defaults for new documents.
+    /// The size, the pages and the embedded template are specific to the document and are left out
+    pub(crate) fn settings_defaults(&self) -> Self {
+        let mut background = self.background.clone();
+        background.template = None;
+
+        Self {
+            width: self.format.width,
+            height: self.format.height,
+            format: self.format.clone(),
+            background,
+            snap: self.snap,
+            layout: self.layout,
+            ..Self::default()
+        }
+    }
+
     pub fn bounds(&self) -> AABB {
         AABB::new(
             na::point![self.x, self.y],
@@ -91,26 +173,525 @@ impl Document {
         )
     }
 
-    // Generates bounds for each page for the doc bounds, extended to fit the format. May contain many empty pages (in infinite mode)
-    pub fn pages_bounds(&self) -> Vec<AABB> {
-        let doc_bounds = self.bounds();
+    /// The overrides of the page with the given index
+    pub fn page(&self, i: usize) -> Option<&Page> {
+        self.pages.get(i)
+    }
+
+    /// The overrides of the page with the given index. Pages up to the index are created if they do not exist yet
+    pub fn page_mut(&mut self, i: usize) -> &mut Page {
+        self.set_backgrounds_changed();
+
+        if self.pages.len() <= i {
+            self.pages.resize(i + 1, Page::default());
+        }
+        &mut self.pages[i]
+    }
+
+    /// Sets the overrides of the page with the given index. Trailing pages without overrides are removed
+    pub fn set_page(&mut self, i: usize, page: Page) {
+        *self.page_mut(i) = page;
+        self.trim_pages();
+    }
+
+    // Removes the trailing pages without overrides
+    fn trim_pages(&mut self) {
+        while self.pages.last().map_or(false, |page| page.is_default()) {
+            self.pages.pop();
+        }
+    }
+
+    /// Whether pages can be inserted, removed and moved. Only possible when the pages follow each other in the page layouts
+    pub fn pages_manageable(&self) -> bool {
+        self.layout != Layout::Infinite
+    }
+
+    /// Moves the position inside the margins of the nearest page, to place new content there.
+    /// Returns the position unchanged in infinite layout
+    pub fn clamp_to_page_content(&self, pos: na::Vector2<f64>) -> na::Vector2<f64> {
+        if !self.pages_manageable() {
+            return pos;
+        }
+
+        let pages_bounds = self.pages_bounds();
+
+        match Self::nearest_page(&pages_bounds, na::Point2::from(pos)) {
+            Some(i) => {
+                let content_bounds = self.format.content_bounds(pages_bounds[i]);
+                pos.sup(&content_bounds.mins.coords)
+                    .inf(&content_bounds.maxs.coords)
+            }
+            None => pos,
+        }
+    }
+
+    /// The background of the page at the position, with the bounds of the page. Positions outside of the pages are on the nearest page.
+    /// In infinite layout this is the document background, with the page of the format grid at the position
+    pub(crate) fn background_at(&self, pos: na::Point2<f64>) -> (&Background, AABB) {
+        if self.pages_manageable() {
+            let pages_bounds = self.pages_bounds();
+
+            if let Some(i) = Self::nearest_page(&pages_bounds, pos) {
+                return (self.page_background(i), pages_bounds[i]);
+            }
+        }
 
-        if self.format.height > 0.0 && self.format.width > 0.0 {
-            doc_bounds
-                .split_extended_origin_aligned(na::vector![self.format.width, self.format.height])
+        let format_size = self.format_page_bounds().extents();
+        let mins = if format_size[0] > 0.0 && format_size[1] > 0.0 {
+            pos.coords
+                .component_div(&format_size)
+                .map(f64::floor)
+                .component_mul(&format_size)
         } else {
-            vec![]
+            na::Vector2::zeros()
+        };
+
+        (
+            &self.background,
+            AABB::new(na::Point2::from(mins), na::Point2::from(mins + format_size)),
+        )
+    }
+
+    /// The index of the page at the position. Positions outside of the pages are on the nearest page.
+    /// Returns None in infinite layout
+    pub fn page_index_at(&self, pos: na::Point2<f64>) -> Option<usize> {
+        if !self.pages_manageable() {
+            return None;
         }
+
+        Self::nearest_page(&self.pages_bounds(), pos)
     }
 
-    pub fn calc_n_pages(&self) -> u32 {
-        // Avoid div by 0
-        if self.format.height > 0.0 && self.format.width > 0.0 {
-            (self.width / self.format.width).round() as u32
-                * (self.height / self.format.height).round() as u32
+    /// The index of the page that contains the position, or else the page nearest to it
+    pub(crate) fn nearest_page(pages_bounds: &[AABB], pos: na::Point2<f64>) -> Option<usize> {
+        pages_bounds
+            .iter()
+            .map(|page_bounds| page_bounds.distance_to_local_point(&pos, true))
+            .enumerate()
+            .min_by(|(_, first), (_, second)| first.total_cmp(second))
+            .map(|(i, _)| i)
+    }
+
+    /// Inserts a page with the given overrides at the index, and extends the document by it
+    pub(crate) fn insert_page(&mut self, i: usize, page: Page) {
+        let n_pages = self.pages_bounds().len();
+
+        if i < self.pages.len() {
+            self.pages.insert(i, page);
+        } else {
+            *self.page_mut(i) = page;
+        }
+        self.trim_pages();
+        self.n_pages_min = self.n_pages_min.max(n_pages + 1);
+
+        self.resize_to_n_pages(n_pages + 1);
+    }
+
+    /// Removes the page at the index, and shrinks the document by it. The document keeps at least one page
+    pub(crate) fn remove_page(&mut self, i: usize) -> Page {
+        let n_pages = self.pages_bounds().len();
+
+        self.set_backgrounds_changed();
+        let page = if i < self.pages.len() {
+            self.pages.remove(i)
         } else {
-            0
+            Page::default()
+        };
+        self.trim_pages();
+        self.n_pages_min = self.n_pages_min.min(n_pages.saturating_sub(1));
+
+        self.resize_to_n_pages(n_pages.saturating_sub(1));
+
+        page
+    }
+
+    /// Moves the page at the index `from` to the index `to`. The pages in between shift by one page
+    pub(crate) fn move_page(&mut self, from: usize, to: usize) {
+        let n_pages = self.pages_bounds().len();
+
+        // Ensure both pages exist
+        self.page_mut(from.max(to));
+        let page = self.pages.remove(from);
+        self.pages.insert(to, page);
+        self.trim_pages();
+
+        // The pages can have different sizes, so the document extent might change
+        self.resize_to_n_pages(n_pages);
+    }
+
+    /// Takes a snapshot of the pages, to be recorded in the history
+    pub(crate) fn pages_snapshot(&self) -> PagesSnapshot {
+        PagesSnapshot {
+            pages: self.pages.clone(),
+            n_pages_min: self.n_pages_min,
+            layout: self.layout,
+            size: na::vector![self.width, self.height],
+        }
+    }
+
+    /// Imports a pages snapshot from the history. The doc then needs to be resized
+    pub(crate) fn import_pages_snapshot(&mut self, snapshot: &PagesSnapshot) {
+        self.pages = snapshot.pages.clone();
+        self.set_backgrounds_changed();
+        self.n_pages_min = snapshot.n_pages_min;
+        self.layout = snapshot.layout;
+
+        if self.pages_manageable() {
+            self.x = 0.0;
+            self.y = 0.0;
+            self.width = snapshot.size[0];
+            self.height = snapshot.size[1];
+        }
+    }
+
+    /// Marks the document and page backgrounds as changed. Needs to be called after the backgrounds were changed, so that renderings of them are regenerated
+    pub fn set_backgrounds_changed(&mut self) {
+        self.background_generation += 1;
+    }
+
+    /// The generation of the backgrounds. Changes every time the backgrounds were changed
+    pub fn background_generation(&self) -> u64 {
+        self.background_generation
+    }
+
+    /// Whether any page overrides the document settings
+    pub fn has_page_overrides(&self) -> bool {
+        self.pages.iter().any(|page| !page.is_default())
+    }
+
+    /// The size of the page with the given index. In infinite layout all pages have the size of the format
+    pub fn page_size(&self, i: usize) -> na::Vector2<f64> {
+        match (self.layout, self.pages.get(i)) {
+            (Layout::Infinite, _) | (_, None) => na::vector![self.format.width, self.format.height],
+            (_, Some(page)) => page.size(&self.format),
+        }
+    }
+
+    /// The background of the page with the given index
+    pub fn page_background(&self, i: usize) -> &Background {
+        match self.pages.get(i) {
+            Some(page) => page.background(&self.background),
+            None => &self.background,
+        }
+    }
+
+    // The bounds of the page following the given pages, in the flow of the layout
+    fn next_page_bounds(&self, pages_bounds: &[AABB]) -> AABB {
+        let i = pages_bounds.len();
+        let page_size = self.page_size(i);
+
+        let origin = match self.layout {
+            Layout::ContinuousHorizontal => {
+                na::point![pages_bounds.last().map_or(0.0, |last| last.maxs[0]), 0.0]
+            }
+            Layout::BookSpread => {
+                if i % 2 == 1 {
+                    // The right page is next to the left page of the spread
+                    let left = pages_bounds[i - 1];
+                    na::point![left.maxs[0], left.mins[1]]
+                } else {
+                    // A new spread starts below the taller page of the previous spread
+                    let y = pages_bounds
+                        .iter()
+                        .rev()
+                        .take(2)
+                        .map(|page_bounds| page_bounds.maxs[1])
+                        .fold(0.0, f64::max);
+                    na::point![0.0, y]
+                }
+            }
+            Layout::FixedSize | Layout::ContinuousVertical | Layout::Infinite => {
+                na::point![0.0, pages_bounds.last().map_or(0.0, |last| last.maxs[1])]
+            }
+        };
+
+        AABB::new(origin, origin + page_size)
+    }
+
+    // The bounds of the pages following each other from the origin, until they cover the given extent in the flow direction.
+    // Contains at least one page, unless the size of the first page is not positive
+    fn flowed_pages_bounds(&self, extent: f64) -> Vec<AABB> {
+        let axis = self.layout.flow_direction().axis();
+        let mut pages_bounds = vec![];
+
+        loop {
+            let page_bounds = self.next_page_bounds(&pages_bounds);
+            // Avoid endless loops
+            if page_bounds.extents()[0] <= 0.0 || page_bounds.extents()[1] <= 0.0 {
+                break;
+            }
+            // Round to avoid an additional page because of floating point errors
+            if !pages_bounds.is_empty() && page_bounds.mins[axis].round() >= extent.round() {
+                break;
+            }
+
+            pages_bounds.push(page_bounds);
         }
+
+        pages_bounds
+    }
+
+    // Resizes the document to fit the pages, and at least the format across the flow direction.
+    // The extent in the flow direction is the given one, or else the end of the pages.
+    fn resize_to_pages_bounds(&mut self, pages_bounds: &[AABB], flow_extent: Option<f64>) {
+        let axis = self.layout.flow_direction().axis();
+        let cross_axis = 1 - axis;
+        let mut new_size = na::vector![self.format.width, self.format.height];
+
+        new_size[cross_axis] = pages_bounds
+            .iter()
+            .map(|page_bounds| page_bounds.maxs[cross_axis])
+            .fold(new_size[cross_axis], f64::max);
+        new_size[axis] = flow_extent.unwrap_or_else(|| {
+            pages_bounds
+                .iter()
+                .map(|page_bounds| page_bounds.maxs[axis])
+                .reduce(f64::max)
+                .unwrap_or(new_size[axis])
+        });
+
+        self.x = 0.0;
+        self.y = 0.0;
+        self.width = new_size[0];
+        self.height = new_size[1];
+    }
+
+    /// Resizes the document to fit the given number of pages. Has at least one page
+    pub(crate) fn resize_to_n_pages(&mut self, n_pages: usize) {
+        let mut pages_bounds = vec![];
+
+        for _ in 0..n_pages.max(1) {
+            pages_bounds.push(self.next_page_bounds(&pages_bounds));
+        }
+
+        self.resize_to_pages_bounds(&pages_bounds, None);
+    }
+
+    /// Generates bounds for each page for the doc bounds, extended to fit the format. May contain many empty pages (in infinite mode).
+    ///
+    /// In the page layouts each page has its own size and they follow each other in the page order, in infinite layout they are aligned to a grid of the format size.
+    pub fn pages_bounds(&self) -> Vec<AABB> {
+        match self.layout {
+            Layout::FixedSize
+            | Layout::ContinuousVertical
+            | Layout::ContinuousHorizontal
+            | Layout::BookSpread => match self.layout.flow_direction() {
+                FlowDirection::Vertical => self.flowed_pages_bounds(self.y + self.height),
+                FlowDirection::Horizontal => self.flowed_pages_bounds(self.x + self.width),
+            },
+            Layout::Infinite => {
+                if self.format.height > 0.0 && self.format.width > 0.0 {
+                    self.bounds().split_extended_origin_aligned(na::vector![
+                        self.format.width,
+                        self.format.height
+                    ])
+                } else {
+                    vec![]
+                }
+            }
+        }
+    }
+
+    pub fn calc_n_pages(&self) -> u32 {
+        match self.layout {
+            Layout::FixedSize
+            | Layout::ContinuousVertical
+            | Layout::ContinuousHorizontal
+            | Layout::BookSpread => self.pages_bounds().len() as u32,
+            Layout::Infinite => {
+                // Avoid div by 0
+                if self.format.height > 0.0 && self.format.width > 0.0 {
+                    (self.width / self.format.width).round() as u32
+                        * (self.height / self.format.height).round() as u32
+                } else {
+                    0
+                }
+            }
+        }
+    }
+
+    /// The pages that have their own background, with their bounds
+    fn page_background_overrides(&self) -> Vec<(AABB, &Background)> {
+        if self.layout == Layout::Infinite {
+            return vec![];
+        }
+
+        self.pages_bounds()
+            .into_iter()
+            .zip(self.pages.iter())
+            .filter_map(|(page_bounds, page)| {
+                page.background
+                    .as_ref()
+                    .map(|background| (page_bounds, background))
+            })
+            .collect()
+    }
+
+    // The pages that have their own background, with their bounds
+    fn page_background_overrides_mut(&mut self) -> Vec<(AABB, &mut Background)> {
+        if self.layout == Layout::Infinite {
+            return vec![];
+        }
+
+        self.pages_bounds()
+            .into_iter()
+            .zip(self.pages.iter_mut())
+            .filter_map(|(page_bounds, page)| {
+                page.background
+                    .as_mut()
+                    .map(|background| (page_bounds, background))
+            })
+            .collect()
+    }
+
+    // The bounds of a page with the format size at the origin. The document background pattern is aligned to it
+    fn format_page_bounds(&self) -> AABB {
+        AABB::new(
+            na::point![0.0, 0.0],
+            na::point![self.format.width, self.format.height],
+        )
+    }
+
+    // The bounds of the pages that use the document background, but are not aligned to its pattern repeating with the format size.
+    // Only the case when the pattern is aligned to the page size, then the document background needs to be drawn with the bounds of these pages
+    fn unaligned_pages_bounds(&self) -> Vec<AABB> {
+        if self.layout == Layout::Infinite || !self.background.aligned_to_page_size() {
+            return vec![];
+        }
+        let format_size = self.format_page_bounds().extents();
+
+        self.pages_bounds()
+            .into_iter()
+            .enumerate()
+            .filter(|&(i, page_bounds)| {
+                let uses_doc_background =
+                    self.page(i).map_or(true, |page| page.background.is_none());
+                let format_sized =
+                    (page_bounds.extents() - format_size).amax() < Self::ALIGNMENT_TOLERANCE;
+                let on_format_grid = (0..2).all(|axis| {
+                    let steps = (page_bounds.mins[axis] / format_size[axis]).round();
+                    (page_bounds.mins[axis] - steps * format_size[axis]).abs()
+                        < Self::ALIGNMENT_TOLERANCE
+                });
+
+                uses_doc_background && !(format_sized && on_format_grid)
+            })
+            .map(|(_, page_bounds)| page_bounds)
+            .collect()
+    }
+
+    /// Regenerates the background patterns of the document and the pages.
+    /// The patterns of the page backgrounds are only regenerated for the pages in the viewport, the others when they are scrolled into it
+    pub fn regenerate_background_patterns(
+        &mut self,
+        viewport: AABB,
+        image_scale: f64,
+    ) -> anyhow::Result<()> {
+        let format_page_bounds = self.format_page_bounds();
+        self.background
+            .regenerate_pattern(viewport, format_page_bounds, image_scale)?;
+
+        for (_, background) in self.page_background_overrides_mut() {
+            background.clear_pattern();
+        }
+        self.unaligned_page_backgrounds = self
+            .unaligned_pages_bounds()
+            .into_iter()
+            .map(|page_bounds| {
+                let mut background = self.background.clone();
+                background.clear_pattern();
+                (page_bounds, background)
+            })
+            .collect();
+
+        self.update_page_background_rendernodes(viewport, image_scale)
+    }
+
+    /// Updates the background rendernodes of the document and the pages for the viewport.
+    /// Generates the patterns of the page backgrounds that were scrolled into the viewport
+    pub fn update_background_rendernodes(
+        &mut self,
+        viewport: AABB,
+        image_scale: f64,
+    ) -> anyhow::Result<()> {
+        let format_page_bounds = self.format_page_bounds();
+        self.background
+            .update_rendernodes(viewport, format_page_bounds)?;
+
+        self.update_page_background_rendernodes(viewport, image_scale)
+    }
+
+    // Updates the rendernodes of the page backgrounds in the viewport, and generates their pattern if it is not generated yet
+    fn update_page_background_rendernodes(
+        &mut self,
+        viewport: AABB,
+        image_scale: f64,
+    ) -> anyhow::Result<()> {
+        let update_page_background = |page_bounds: AABB, background: &mut Background| {
+            if !page_bounds.intersects(&viewport) {
+                return Ok(());
+            }
+
+            if background.pattern_generated() {
+                background.update_rendernodes(viewport, page_bounds)
+            } else {
+                background.regenerate_pattern(viewport, page_bounds, image_scale)
+            }
+        };
+
+        for (page_bounds, background) in self.page_background_overrides_mut() {
+            update_page_background(page_bounds, background)?;
+        }
+        for (page_bounds, background) in self.unaligned_page_backgrounds.iter_mut() {
+            update_page_background(*page_bounds, background)?;
+        }
+
+        Ok(())
+    }
+
+    /// Draws the document background, and the page backgrounds on top of it
+    pub fn draw_backgrounds(&self, snapshot: &Snapshot, camera: &Camera) -> anyhow::Result<()> {
+        self.background.draw(snapshot, self.bounds(), camera)?;
+
+        let viewport = camera.viewport();
+
+        for (page_bounds, background) in self.page_background_overrides().into_iter().chain(
+            self.unaligned_page_backgrounds
+                .iter()
+                .map(|(page_bounds, background)| (*page_bounds, background)),
+        ) {
+            if !page_bounds.intersects(&viewport) {
+                continue;
+            }
+
+            background.draw(snapshot, page_bounds, camera)?;
+        }
+
+        Ok(())
+    }
+
+    /// Generates the svg of the document background and the page backgrounds inside the given bounds
+    pub fn gen_background_svg(&self, bounds: AABB) -> anyhow::Result<render::Svg> {
+        let mut background_svg = self.background.gen_svg(bounds, self.format_page_bounds())?;
+
+        let page_svgs = self
+            .page_background_overrides()
+            .into_iter()
+            .chain(
+                self.unaligned_pages_bounds()
+                    .into_iter()
+                    .map(|page_bounds| (page_bounds, &self.background)),
+            )
+            .filter_map(|(page_bounds, background)| {
+                let visible_bounds = page_bounds.intersection(&bounds)?;
+                Some(background.gen_svg(visible_bounds, page_bounds))
+            })
+            .collect::<anyhow::Result<Vec<render::Svg>>>()?;
+
+        // The bounds of the page backgrounds are inside the document background bounds
+        background_svg.merge(page_svgs);
+
+        Ok(background_svg)
     }
 
     pub(crate) fn resize_to_fit_strokes(&mut self, store: &StrokeStore, camera: &Camera) {
@@ -118,8 +699,8 @@ impl Document {
             Layout::FixedSize => {
                 self.resize_doc_fixed_size_layout(store);
             }
-            Layout::ContinuousVertical => {
-                self.resize_doc_continuous_vertical_layout(store);
+            Layout::ContinuousVertical | Layout::ContinuousHorizontal | Layout::BookSpread => {
+                self.resize_doc_continuous_layout(store);
             }
             Layout::Infinite => {
                 self.resize_doc_infinite_layout_to_fit_strokes(store);
@@ -133,8 +714,8 @@ impl Document {
             Layout::FixedSize => {
                 // Does not resize in fixed size mode, if wanted use resize_doc_to_fit_strokes() for it.
             }
-            Layout::ContinuousVertical => {
-                self.resize_doc_continuous_vertical_layout(store);
+            Layout::ContinuousVertical | Layout::ContinuousHorizontal | Layout::BookSpread => {
+                self.resize_doc_continuous_layout(store);
             }
             Layout::Infinite => {
                 self.resize_doc_infinite_layout_to_fit_strokes(store);
@@ -144,27 +725,32 @@ impl Document {
     }
 
     pub(crate) fn resize_doc_fixed_size_layout(&mut self, store: &StrokeStore) {
-        let format_height = self.format.height;
-
-        let new_width = self.format.width;
-        // +1.0 because then 'fraction'.ceil() is at least 1
-        let new_height = ((store.calc_height() + 1.0) / format_height).ceil() * format_height;
+        // +1.0 because then there is at least one page
+        let pages_bounds = self.flowed_pages_bounds(store.calc_height() + 1.0);
 
-        self.x = 0.0;
-        self.y = 0.0;
-        self.width = new_width;
-        self.height = new_height;
+        self.resize_to_pages_bounds(&pages_bounds, None);
     }
 
-    pub(crate) fn resize_doc_continuous_vertical_layout(&mut self, store: &StrokeStore) {
-        let padding_bottom = self.format.height;
-        let new_height = store.calc_height() + padding_bottom;
-        let new_width = self.format.width;
+    /// Resizes the document in the continuous layouts, so that it has one format of padding after the strokes in the flow direction.
+    /// Explicitly inserted pages are kept, even when they are empty
+    pub(crate) fn resize_doc_continuous_layout(&mut self, store: &StrokeStore) {
+        let axis = self.layout.flow_direction().axis();
+        let mut min_pages_bounds = vec![];
+        for _ in 0..self.n_pages_min {
+            min_pages_bounds.push(self.next_page_bounds(&min_pages_bounds));
+        }
+        let min_extent = min_pages_bounds
+            .last()
+            .map_or(0.0, |page_bounds| page_bounds.maxs[axis]);
 
-        self.x = 0.0;
-        self.y = 0.0;
-        self.width = new_width;
-        self.height = new_height;
+        let new_extent = match self.layout.flow_direction() {
+            FlowDirection::Vertical => store.calc_height() + self.format.height,
+            FlowDirection::Horizontal => store.calc_width() + self.format.width,
+        }
+        .max(min_extent);
+        let pages_bounds = self.flowed_pages_bounds(new_extent);
+
+        self.resize_to_pages_bounds(&pages_bounds, Some(new_extent));
     }
 
     pub(crate) fn expand_doc_infinite_layout(&mut self, viewport: AABB) {
@@ -229,3 +815,143 @@ impl Document {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use background::PatternStyle;
+
+    fn continuous_doc(store: &StrokeStore) -> Document {
+        let mut doc = Document::default();
+        doc.set_layout(Layout::ContinuousVertical, store, &Camera::default());
+        doc
+    }
+
+    #[test]
+    fn inserted_trailing_page_is_kept() {
+        let store = StrokeStore::default();
+        let mut doc = continuous_doc(&store);
+        let n_pages = doc.pages_bounds().len();
+
+        doc.insert_page(n_pages, Page::default());
+        doc.resize_doc_continuous_layout(&store);
+
+        assert_eq!(doc.pages_bounds().len(), n_pages + 1);
+    }
+
+    #[test]
+    fn removed_page_shrinks_kept_pages() {
+        let store = StrokeStore::default();
+        let mut doc = continuous_doc(&store);
+        let n_pages = doc.pages_bounds().len();
+
+        doc.insert_page(n_pages, Page::default());
+        doc.insert_page(n_pages, Page::default());
+        doc.remove_page(n_pages);
+        doc.resize_doc_continuous_layout(&store);
+
+        assert_eq!(doc.pages_bounds().len(), n_pages + 1);
+    }
+
+    #[test]
+    fn kept_pages_survive_snapshots() {
+        let store = StrokeStore::default();
+        let mut doc = continuous_doc(&store);
+        let n_pages = doc.pages_bounds().len();
+        let snapshot = doc.pages_snapshot();
+
+        doc.insert_page(n_pages, Page::default());
+        let inserted_snapshot = doc.pages_snapshot();
+
+        doc.import_pages_snapshot(&snapshot);
+        doc.resize_doc_continuous_layout(&store);
+        assert_eq!(doc.pages_bounds().len(), n_pages);
+
+        doc.import_pages_snapshot(&inserted_snapshot);
+        doc.resize_doc_continuous_layout(&store);
+        assert_eq!(doc.pages_bounds().len(), n_pages + 1);
+    }
+
+    // A document with a Cornell notes background, where the first page is shorter than the format
+    fn cornell_doc(store: &StrokeStore) -> Document {
+        let mut doc = continuous_doc(store);
+        doc.background.pattern = PatternStyle::Cornell;
+        doc.background.pattern_size = na::vector![32.0, 32.0];
+        doc.snap.to_grid = true;
+
+        doc.set_page(
+            0,
+            Page {
+                size: Some(na::vector![doc.format.width, 500.0]),
+                ..Page::default()
+            },
+        );
+        doc.resize_to_n_pages(3);
+        doc
+    }
+
+    #[test]
+    fn page_aligned_background_is_drawn_for_unaligned_pages() {
+        let store = StrokeStore::default();
+        let mut doc = cornell_doc(&store);
+        let pages_bounds = doc.pages_bounds();
+
+        // The first page has another size, the following ones are shifted against the format grid
+        assert_eq!(doc.unaligned_pages_bounds(), pages_bounds);
+
+        doc.background.pattern = PatternStyle::Lines;
+        assert!(doc.unaligned_pages_bounds().is_empty());
+    }
+
+    #[test]
+    fn aligned_pages_use_the_document_background() {
+        let store = StrokeStore::default();
+        let mut doc = continuous_doc(&store);
+        doc.background.pattern = PatternStyle::Cornell;
+        doc.resize_to_n_pages(3);
+
+        assert!(doc.unaligned_pages_bounds().is_empty());
+    }
+
+    #[test]
+    fn snap_to_cornell_lines_of_the_page() {
+        let store = StrokeStore::default();
+        let doc = cornell_doc(&store);
+        let second_page_top = doc.pages_bounds()[1].mins[1];
+        assert_eq!(second_page_top, 500.0);
+
+        let (snapped, guides) = doc.snap_pos(na::vector![10.0, 601.0], &store, &Camera::default());
+
+        assert_eq!(snapped, na::vector![10.0, second_page_top + 96.0]);
+        assert_eq!(guides.x, None);
+        assert_eq!(guides.y, Some(second_page_top + 96.0));
+    }
+
+    #[test]
+    fn settings_defaults_leave_out_the_document_pages() {
+        let store = StrokeStore::default();
+        let mut doc = continuous_doc(&store);
+        doc.background.pattern = PatternStyle::Grid;
+        doc.background.template = background::BackgroundTemplate::from_svg_data(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10"/>"#,
+        )
+        .ok();
+        doc.insert_page(1, Page::default());
+        doc.set_page(
+            0,
+            Page {
+                background: Some(Background::default()),
+                ..Page::default()
+            },
+        );
+
+        let defaults = doc.settings_defaults();
+
+        assert_eq!(defaults.layout(), Layout::ContinuousVertical);
+        assert_eq!(defaults.background.pattern, PatternStyle::Grid);
+        assert!(defaults.background.template.is_none());
+        assert!(defaults.page(0).is_none());
+        assert_eq!(defaults.n_pages_min, 0);
+        assert_eq!(defaults.height, defaults.format.height);
+    }
+}
@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+
+use super::format::{Format, Orientation};
+use super::{Background, Layout};
+
+/// The settings of a single page, overriding the format and background of the document.
+///
+/// Pages only have their own size in the page layouts, where they follow each other from the origin.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename = "page")]
+pub struct Page {
+    /// Overrides the size ( width, height ) of the document format
+    #[serde(rename = "size")]
+    pub size: Option<na::Vector2<f64>>,
+    /// Overrides the orientation. Swaps width and height of the size if it does not match
+    #[serde(rename = "orientation")]
+    pub orientation: Option<Orientation>,
+    /// Overrides the document background
+    #[serde(rename = "background")]
+    pub background: Option<Background>,
+}
+
+impl Page {
+    /// Whether the page overrides nothing and is equal to a page with the document settings
+    pub fn is_default(&self) -> bool {
+        self.size.is_none() && self.orientation.is_none() && self.background.is_none()
+    }
+
+    /// The size of the page, with the overrides applied to the document format
+    pub fn size(&self, format: &Format) -> na::Vector2<f64> {
+        let size = self
+            .size
+            .unwrap_or_else(|| na::vector![format.width, format.height]);
+
+        match self.orientation {
+            Some(Orientation::Portrait) if size[0] > size[1] => na::vector![size[1], size[0]],
+            Some(Orientation::Landscape) if size[1] > size[0] => na::vector![size[1], size[0]],
+            _ => size,
+        }
+    }
+
+    /// The background of the page, falling back to the document background
+    pub fn background<'a>(&'a self, doc_background: &'a Background) -> &'a Background {
+        self.background.as_ref().unwrap_or(doc_background)
+    }
+}
+
+/// The pages of the document, as they are recorded in the history of the store
+#[derive(Debug, Clone, Default)]
+pub struct PagesSnapshot {
+    pub(crate) pages: Vec<Page>,
+    pub(crate) n_pages_min: usize,
+    pub(crate) layout: Layout,
+    pub(crate) size: na::Vector2<f64>,
+}
@@ -0,0 +1,189 @@
+use p2d::bounding_volume::AABB;
+use piet::RenderContext;
+use rnote_compose::color;
+use serde::{Deserialize, Serialize};
+
+use super::Document;
+use crate::{Camera, StrokeStore};
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default, rename = "snap_options")]
+pub struct SnapOptions {
+    /// Snap to the spacing of the background pattern
+    #[serde(rename = "to_grid")]
+    pub to_grid: bool,
+    /// Snap to the edges and centers of the bounds of nearby strokes
+    #[serde(rename = "to_strokes")]
+    pub to_strokes: bool,
+}
+
+/// The guide lines of an active snap, in document coords
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SnapGuides {
+    /// The x position of the vertical guide line
+    pub x: Option<f64>,
+    /// The y position of the horizontal guide line
+    pub y: Option<f64>,
+}
+
+impl SnapGuides {
+    /// The guide line width, in surface coords
+    const LINE_WIDTH: f64 = 1.0;
+    const LINE_COLOR: piet::Color = color::GNOME_ORANGES[3].with_a8(0xd0);
+    const DASH_PATTERN: [f64; 2] = [6.0, 4.0];
+
+    pub fn is_empty(&self) -> bool {
+        self.x.is_none() && self.y.is_none()
+    }
+
+    /// Draws the guide lines across the viewport
+    pub fn draw(&self, cx: &mut impl RenderContext, camera: &Camera) {
+        let total_zoom = camera.total_zoom();
+        let viewport = camera.viewport();
+
+        let mut stroke_style = piet::StrokeStyle::new();
+        stroke_style.set_dash_pattern(
+            Self::DASH_PATTERN
+                .into_iter()
+                .map(|x| x / total_zoom)
+                .collect::<Vec<f64>>(),
+        );
+
+        if let Some(x) = self.x {
+            cx.stroke_styled(
+                kurbo::Line::new((x, viewport.mins[1]), (x, viewport.maxs[1])),
+                &Self::LINE_COLOR,
+                Self::LINE_WIDTH / total_zoom,
+                &stroke_style,
+            );
+        }
+        if let Some(y) = self.y {
+            cx.stroke_styled(
+                kurbo::Line::new((viewport.mins[0], y), (viewport.maxs[0], y)),
+                &Self::LINE_COLOR,
+                Self::LINE_WIDTH / total_zoom,
+                &stroke_style,
+            );
+        }
+    }
+}
+
+impl Document {
+    /// The distance where positions get snapped to a target, in surface coords
+    const SNAP_DISTANCE: f64 = 8.0;
+
+    /// Snaps the position to the enabled targets. Returns the snapped position and the guides of the active snap.
+    pub(crate) fn snap_pos(
+        &self,
+        pos: na::Vector2<f64>,
+        store: &StrokeStore,
+        camera: &Camera,
+    ) -> (na::Vector2<f64>, SnapGuides) {
+        let (offset, guides) = self.snap_offset(&[pos[0]], &[pos[1]], store, camera);
+
+        (pos + offset, guides)
+    }
+
+    /// Calculates the offset which snaps the edges or the center of the bounds to the enabled targets.
+    /// Returns the offset and the guides of the active snap.
+    pub(crate) fn snap_bounds_offset(
+        &self,
+        bounds: AABB,
+        store: &StrokeStore,
+        camera: &Camera,
+    ) -> (na::Vector2<f64>, SnapGuides) {
+        let center = bounds.center();
+
+        self.snap_offset(
+            &[bounds.mins[0], center[0], bounds.maxs[0]],
+            &[bounds.mins[1], center[1], bounds.maxs[1]],
+            store,
+            camera,
+        )
+    }
+
+    fn snap_offset(
+        &self,
+        x_candidates: &[f64],
+        y_candidates: &[f64],
+        store: &StrokeStore,
+        camera: &Camera,
+    ) -> (na::Vector2<f64>, SnapGuides) {
+        let snap_distance = Self::SNAP_DISTANCE / camera.total_zoom();
+        let mut x_targets = vec![];
+        let mut y_targets = vec![];
+
+        if self.snap.to_grid {
+            // The pattern of the page the candidates are on. It is aligned to the origin, or to the page
+            let pos = na::point![
+                x_candidates[x_candidates.len() / 2],
+                y_candidates[y_candidates.len() / 2]
+            ];
+            let (background, page_bounds) = self.background_at(pos);
+            let (origin, spacing) = background.snap_grid(page_bounds);
+
+            if let Some(spacing) = spacing[0] {
+                x_targets.extend(
+                    x_candidates
+                        .iter()
+                        .map(|x| origin[0] + ((x - origin[0]) / spacing).round() * spacing),
+                );
+            }
+            if let Some(spacing) = spacing[1] {
+                y_targets.extend(
+                    y_candidates
+                        .iter()
+                        .map(|y| origin[1] + ((y - origin[1]) / spacing).round() * spacing),
+                );
+            }
+        }
+
+        if self.snap.to_strokes {
+            // Selected strokes are excluded, so a selection never snaps to itself
+            for bounds in store.strokes_bounds(
+                &store.stroke_keys_as_rendered_intersecting_bounds(camera.viewport()),
+            ) {
+                let center = bounds.center();
+
+                x_targets.extend([bounds.mins[0], center[0], bounds.maxs[0]]);
+                y_targets.extend([bounds.mins[1], center[1], bounds.maxs[1]]);
+            }
+        }
+
+        let (x_offset, x_guide) = Self::nearest_snap(x_candidates, &x_targets, snap_distance);
+        let (y_offset, y_guide) = Self::nearest_snap(y_candidates, &y_targets, snap_distance);
+
+        (
+            na::vector![x_offset, y_offset],
+            SnapGuides {
+                x: x_guide,
+                y: y_guide,
+            },
+        )
+    }
+
+    /// Finds the candidate and target pair with the smallest distance below the snap distance.
+    /// Returns the offset to the target and the target itself.
+    fn nearest_snap(candidates: &[f64], targets: &[f64], snap_distance: f64) -> (f64, Option<f64>) {
+        let mut nearest: Option<(f64, f64)> = None;
+
+        for candidate in candidates {
+            for target in targets {
+                let offset = target - candidate;
+
+                if offset.abs() <= snap_distance
+                    && nearest.map_or(true, |(nearest_offset, _)| {
+                        offset.abs() < nearest_offset.abs()
+                    })
+                {
+                    nearest = Some((offset, *target));
+                }
+            }
+        }
+
+        match nearest {
+            Some((offset, target)) => (offset, Some(target)),
+            None => (0.0, None),
+        }
+    }
+}
@@ -1,18 +1,22 @@
+use std::cmp::Ordering;
 use std::path::PathBuf;
 use std::sync::Arc;
 
-use crate::document::Layout;
+use crate::document::format::PageTextContext;
+use crate::document::{Layout, Page, PagesSnapshot};
 use crate::import::PdfImportPrefs;
 use crate::pens::penholder::PenStyle;
 use crate::pens::PenMode;
-use crate::store::StrokeKey;
+use crate::store::{Alignment, Axis, BooleanOp, Reordering, StrokeKey, TileKey};
 use crate::strokes::strokebehaviour::GeneratedStrokeImages;
+use crate::thumbnails::{PageSignature, PageThumbnail, PageThumbnails};
 use crate::{render, AudioPlayer, DrawBehaviour, DrawOnDocBehaviour, WidgetFlags};
 use crate::{Camera, Document, PenHolder, StrokeStore};
 use gtk4::Snapshot;
 use piet::RenderContext;
 use rnote_compose::helpers::{AABBHelpers, Vector2Helpers};
 use rnote_compose::penhelpers::{PenEvent, ShortcutKey};
+use rnote_compose::shapes::ShapeBehaviour;
 use rnote_compose::transform::TransformBehaviour;
 use rnote_fileformats::rnoteformat::RnotefileMaj0Min5;
 use rnote_fileformats::{xoppformat, FileFormatSaver};
@@ -72,6 +76,20 @@ pub enum EngineTask {
         key: StrokeKey,
         images: GeneratedStrokeImages,
     },
+    /// Inserts the rendered image of the tile into the tile cache.
+    /// The tile is only updated if it is still requested with the given id, outdated requests are discarded.
+    UpdateTileWithImage {
+        tile_key: TileKey,
+        request_id: u64,
+        image: render::Image,
+    },
+    /// Inserts the generated thumbnail of the page.
+    /// The thumbnail is only updated if it is still requested with the given id, outdated requests are discarded.
+    UpdatePageThumbnail {
+        page: usize,
+        request_id: u64,
+        image: render::Image,
+    },
     /// indicates that the application is quitting. Usually handled to quit the async loop which receives the tasks
     Quit,
 }
@@ -88,6 +106,8 @@ struct EngineConfig {
     pdf_import_prefs: serde_json::Value,
     #[serde(rename = "pen_sounds")]
     pen_sounds: serde_json::Value,
+    #[serde(rename = "tile_rendering")]
+    tile_rendering: serde_json::Value,
 }
 
 impl Default for EngineConfig {
@@ -95,11 +115,12 @@ impl Default for EngineConfig {
         let engine = RnoteEngine::new(None);
 
         Self {
-            document: serde_json::to_value(&engine.document).unwrap(),
+            document: serde_json::to_value(&engine.document.settings_defaults()).unwrap(),
             penholder: serde_json::to_value(&engine.penholder).unwrap(),
 
             pdf_import_prefs: serde_json::to_value(&engine.pdf_import_prefs).unwrap(),
             pen_sounds: serde_json::to_value(&engine.pen_sounds).unwrap(),
+            tile_rendering: serde_json::to_value(&engine.tile_rendering).unwrap(),
         }
     }
 }
@@ -125,16 +146,23 @@ pub struct RnoteEngine {
     pub pdf_import_prefs: PdfImportPrefs,
     #[serde(rename = "pen_sounds")]
     pub pen_sounds: bool,
+    #[serde(rename = "tile_rendering")]
+    pub tile_rendering: bool,
 
     #[serde(skip)]
     pub audioplayer: Option<AudioPlayer>,
     #[serde(skip)]
     pub visual_debug: bool,
+    /// Skips creating the rendernodes and textures when updating the rendering, so that the engine can be used without a display
+    #[serde(skip)]
+    pub headless: bool,
     #[serde(skip)]
     pub tasks_tx: EngineTaskSender,
     /// To be taken out into a loop which processes the receiver stream. The received tasks should be processed with process_received_task()
     #[serde(skip)]
     pub tasks_rx: Option<EngineTaskReceiver>,
+    #[serde(skip)]
+    page_thumbnails: PageThumbnails,
 }
 
 impl Default for RnoteEngine {
@@ -176,11 +204,14 @@ impl RnoteEngine {
 
             pdf_import_prefs: PdfImportPrefs::default(),
             pen_sounds,
+            tile_rendering: false,
 
             audioplayer,
             visual_debug: false,
+            headless: false,
             tasks_tx,
             tasks_rx: Some(tasks_rx),
+            page_thumbnails: PageThumbnails::default(),
         }
     }
 
@@ -224,6 +255,21 @@ impl RnoteEngine {
         }
     }
 
+    /// wether the strokes are rendered into cached tiles of the document
+    pub fn tile_rendering(&self) -> bool {
+        self.tile_rendering
+    }
+
+    /// enables / disables rendering the strokes into cached tiles of the document. Regenerates the rendering
+    pub fn set_tile_rendering(&mut self, tile_rendering: bool) {
+        self.tile_rendering = tile_rendering;
+
+        if self.store.tile_rendering() != tile_rendering {
+            self.store.set_tile_rendering(tile_rendering);
+            self.update_rendering_current_viewport();
+        }
+    }
+
     /// records the current store state and saves it as a history entry.
     pub fn record(&mut self) -> WidgetFlags {
         self.store.record()
@@ -238,7 +284,9 @@ impl RnoteEngine {
             widget_flags.merge_with_other(self.handle_pen_event(PenEvent::Cancel, None));
         }
 
+        let prev_doc_pages = self.store.doc_pages();
         widget_flags.merge_with_other(self.store.undo());
+        widget_flags.merge_with_other(self.import_doc_pages_from_store(prev_doc_pages));
 
         if !self.store.selection_keys_unordered().is_empty() {
             widget_flags.merge_with_other(
@@ -269,7 +317,9 @@ impl RnoteEngine {
             widget_flags.merge_with_other(self.handle_pen_event(PenEvent::Cancel, None));
         }
 
+        let prev_doc_pages = self.store.doc_pages();
         widget_flags.merge_with_other(self.store.redo());
+        widget_flags.merge_with_other(self.import_doc_pages_from_store(prev_doc_pages));
 
         if !self.store.selection_keys_unordered().is_empty() {
             widget_flags.merge_with_other(
@@ -291,9 +341,271 @@ impl RnoteEngine {
         widget_flags
     }
 
+    /// Aligns the selected strokes to the edges or the centers of the selection bounds
+    pub fn align_selection(&mut self, alignment: Alignment) -> WidgetFlags {
+        let selection_keys = self.store.selection_keys_as_rendered();
+        if self.store.keys_as_group_units(&selection_keys).len() < 2 {
+            return WidgetFlags::default();
+        }
+
+        let mut widget_flags = self.store.record();
+        self.store.align_strokes(&selection_keys, alignment);
+        widget_flags.merge_with_other(self.update_after_transforming_strokes(&selection_keys));
+
+        widget_flags
+    }
+
+    /// Distributes the selected strokes along the axis with equal gaps between them
+    pub fn distribute_selection(&mut self, axis: Axis) -> WidgetFlags {
+        let selection_keys = self.store.selection_keys_as_rendered();
+        if self.store.keys_as_group_units(&selection_keys).len() < 3 {
+            return WidgetFlags::default();
+        }
+
+        let mut widget_flags = self.store.record();
+        self.store.distribute_strokes(&selection_keys, axis);
+        widget_flags.merge_with_other(self.update_after_transforming_strokes(&selection_keys));
+
+        widget_flags
+    }
+
+    /// Matches the width ( horizontal axis ) or height ( vertical axis ) of the selected strokes to the largest one
+    pub fn match_selection_extents(&mut self, axis: Axis) -> WidgetFlags {
+        let selection_keys = self.store.selection_keys_as_rendered();
+        if self.store.keys_as_group_units(&selection_keys).len() < 2 {
+            return WidgetFlags::default();
+        }
+
+        let mut widget_flags = self.store.record();
+        self.store.match_strokes_extents(&selection_keys, axis);
+        widget_flags.merge_with_other(self.update_after_transforming_strokes(&selection_keys));
+
+        widget_flags
+    }
+
+    /// The bounds of the current selection
+    pub fn selection_bounds(&self) -> Option<AABB> {
+        self.store.gen_selection_bounds()
+    }
+
+    /// Transforms the selection to the new bounds and then rotates it by the angle ( in radians ) around its new center.
+    ///
+    /// If the aspectratio of the selector is locked, the extent that changed relatively the most determines the scale for both axes.
+    pub fn transform_selection(&mut self, new_bounds: AABB, angle: f64) -> WidgetFlags {
+        let selection_keys = self.store.selection_keys_as_rendered();
+        let old_bounds = match self.store.gen_selection_bounds() {
+            Some(old_bounds) => old_bounds,
+            None => return WidgetFlags::default(),
+        };
+        let old_extents = old_bounds.extents();
+        // A selection without an extent on one axis ( e.g. a straight line ) can only be scaled on the other axis
+        let scalable = old_extents.map(|extent| extent > 0.0);
+        let mut new_extents = new_bounds.extents();
+        new_extents
+            .iter_mut()
+            .zip(scalable.iter())
+            .filter(|(_, scalable)| !**scalable)
+            .for_each(|(extent, _)| *extent = 0.0);
+
+        if (scalable[0] && new_extents[0] <= 0.0) || (scalable[1] && new_extents[1] <= 0.0) {
+            return WidgetFlags::default();
+        }
+
+        if self.penholder.selector.resize_lock_aspectratio {
+            let uniform_scale = match (scalable[0], scalable[1]) {
+                (true, true) => {
+                    let scale = new_extents.component_div(&old_extents);
+
+                    if (scale[0] - 1.0).abs() >= (scale[1] - 1.0).abs() {
+                        scale[0]
+                    } else {
+                        scale[1]
+                    }
+                }
+                (true, false) => new_extents[0] / old_extents[0],
+                (false, true) => new_extents[1] / old_extents[1],
+                (false, false) => 1.0,
+            };
+            new_extents = old_extents * uniform_scale;
+        }
+
+        let new_bounds = AABB::new(
+            new_bounds.mins,
+            na::Point2::from(new_bounds.mins.coords + new_extents),
+        );
+
+        if new_bounds == old_bounds && angle == 0.0 {
+            return WidgetFlags::default();
+        }
+
+        let mut widget_flags = self.store.record();
+
+        if new_bounds != old_bounds {
+            self.store.resize_strokes(&selection_keys, new_bounds);
+        }
+        if angle != 0.0 {
+            self.store
+                .rotate_strokes(&selection_keys, angle, new_bounds.center());
+        }
+
+        widget_flags.merge_with_other(self.update_after_transforming_strokes(&selection_keys));
+
+        widget_flags
+    }
+
+    /// Mirrors the selection across the axis, which has the angle (rad) to the x-axis.
+    /// The axis goes through the axis point, or through the center of the selection if it is None.
+    ///
+    /// An angle of 0.0 flips the selection vertically, an angle of PI / 2 flips it horizontally.
+    pub fn mirror_selection(
+        &mut self,
+        axis_angle: f64,
+        axis_point: Option<na::Point2<f64>>,
+    ) -> WidgetFlags {
+        let selection_keys = self.store.selection_keys_as_rendered();
+        let center = match axis_point.or_else(|| {
+            self.store
+                .gen_selection_bounds()
+                .map(|bounds| bounds.center())
+        }) {
+            Some(center) => center,
+            None => return WidgetFlags::default(),
+        };
+
+        let mut widget_flags = self.store.record();
+        self.store
+            .mirror_strokes(&selection_keys, axis_angle, center);
+        widget_flags.merge_with_other(self.update_after_transforming_strokes(&selection_keys));
+
+        widget_flags
+    }
+
+    /// Combines the closed strokes of the selection with the boolean operation into a new path shape stroke, which replaces them.
+    /// The first operand is the lowest stroke in the stacking order.
+    pub fn boolean_op_selection(&mut self, op: BooleanOp) -> WidgetFlags {
+        let selection_keys = self.store.selection_keys_as_rendered();
+        let (new_stroke, operand_keys) = match self.store.gen_boolean_op_stroke(&selection_keys, op)
+        {
+            Some(result) => result,
+            None => return WidgetFlags::default(),
+        };
+
+        let mut widget_flags = self.store.record();
+        self.store.set_trashed_keys(&operand_keys, true);
+
+        let new_key = self.store.insert_stroke(new_stroke);
+        self.store.set_selected(new_key, true);
+        widget_flags.merge_with_other(self.update_after_transforming_strokes(&[new_key]));
+
+        widget_flags
+    }
+
+    /// Converts the selected brush strokes into path shapes of their filled outlines
+    pub fn convert_selection_to_outlines(&mut self) -> WidgetFlags {
+        let selection_keys = self.store.selection_keys_as_rendered();
+        if selection_keys.is_empty() {
+            return WidgetFlags::default();
+        }
+
+        let mut widget_flags = self.store.record();
+        self.store.convert_brushstrokes_to_outlines(&selection_keys);
+        widget_flags.merge_with_other(self.update_after_transforming_strokes(&selection_keys));
+
+        widget_flags
+    }
+
+    /// Converts the selected closed shapes into brush strokes along their estimated centerlines
+    pub fn convert_selection_to_centerlines(&mut self) -> WidgetFlags {
+        let selection_keys = self.store.selection_keys_as_rendered();
+        if selection_keys.is_empty() {
+            return WidgetFlags::default();
+        }
+
+        let mut widget_flags = self.store.record();
+        self.store.convert_outlines_to_brushstrokes(&selection_keys);
+        widget_flags.merge_with_other(self.update_after_transforming_strokes(&selection_keys));
+
+        widget_flags
+    }
+
+    /// Starts editing the nodes of the selected shape stroke with the selector
+    pub fn edit_selection_nodes(&mut self) -> WidgetFlags {
+        self.penholder
+            .selector
+            .start_editing_nodes(&mut EngineViewMut {
+                tasks_tx: self.tasks_tx(),
+                doc: &mut self.document,
+                store: &mut self.store,
+                camera: &mut self.camera,
+                audioplayer: &mut self.audioplayer,
+            })
+    }
+
+    /// Changes the stacking order of the selected strokes
+    pub fn reorder_selection(&mut self, reordering: Reordering) -> WidgetFlags {
+        let selection_keys = self.store.selection_keys_as_rendered();
+        if selection_keys.is_empty() {
+            return WidgetFlags::default();
+        }
+
+        let mut widget_flags = self.store.record();
+        self.store.reorder_strokes(&selection_keys, reordering);
+
+        widget_flags.redraw = true;
+        widget_flags.indicate_changed_store = true;
+
+        widget_flags
+    }
+
+    /// Groups the selected strokes. Already existing groups in the selection get nested
+    pub fn group_selection(&mut self) -> WidgetFlags {
+        let selection_keys = self.store.selection_keys_as_rendered();
+        if selection_keys.len() < 2 {
+            return WidgetFlags::default();
+        }
+
+        let mut widget_flags = self.store.record();
+        self.store.group_strokes(&selection_keys);
+
+        widget_flags.indicate_changed_store = true;
+
+        widget_flags
+    }
+
+    /// Dissolves the outermost groups of the selected strokes
+    pub fn ungroup_selection(&mut self) -> WidgetFlags {
+        let selection_keys = self.store.selection_keys_as_rendered();
+        if selection_keys.is_empty() {
+            return WidgetFlags::default();
+        }
+
+        let mut widget_flags = self.store.record();
+        self.store.ungroup_strokes(&selection_keys);
+
+        widget_flags.indicate_changed_store = true;
+
+        widget_flags
+    }
+
+    fn update_after_transforming_strokes(&mut self, keys: &[StrokeKey]) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+
+        self.store.update_geometry_for_strokes(keys);
+        self.resize_autoexpand();
+        self.update_pens_states();
+        self.update_rendering_current_viewport();
+
+        widget_flags.redraw = true;
+        widget_flags.resize = true;
+        widget_flags.indicate_changed_store = true;
+
+        widget_flags
+    }
+
     // Clears the store
     pub fn clear(&mut self) {
         self.store.clear();
+        self.page_thumbnails.clear();
         self.update_pens_states();
     }
 
@@ -340,6 +652,35 @@ impl RnoteEngine {
                 widget_flags.redraw = true;
                 widget_flags.indicate_changed_store = true;
             }
+            EngineTask::UpdateTileWithImage {
+                tile_key,
+                request_id,
+                image,
+            } => {
+                if let Err(e) = self
+                    .store
+                    .replace_tile_with_image(tile_key, request_id, image)
+                {
+                    log::error!(
+                        "replace_tile_with_image() in process_received_task() failed with Err {}",
+                        e
+                    );
+                }
+
+                widget_flags.redraw = true;
+            }
+            EngineTask::UpdatePageThumbnail {
+                page,
+                request_id,
+                image,
+            } => {
+                if self
+                    .page_thumbnails
+                    .insert_thumbnail(page, request_id, image)
+                {
+                    widget_flags.update_page_thumbnails = true;
+                }
+            }
             EngineTask::Quit => {
                 widget_flags.quit = true;
             }
@@ -391,6 +732,20 @@ impl RnoteEngine {
         )
     }
 
+    /// applies the pen preset with the given name and changes to its pen
+    pub fn apply_pen_preset(&mut self, name: &str) -> anyhow::Result<WidgetFlags> {
+        self.penholder.apply_preset(
+            name,
+            &mut EngineViewMut {
+                tasks_tx: self.tasks_tx(),
+                doc: &mut self.document,
+                store: &mut self.store,
+                camera: &mut self.camera,
+                audioplayer: &mut self.audioplayer,
+            },
+        )
+    }
+
     /// change the pen style override
     pub fn change_pen_style_override(
         &mut self,
@@ -423,12 +778,19 @@ impl RnoteEngine {
     }
 
     /// updates the background rendering for the current viewport.
-    /// if the background pattern or zoom has changed, background.regenerate_pattern() needs to be called first.
+    /// if the background pattern or zoom has changed, document.regenerate_background_patterns() needs to be called first.
     pub fn update_background_rendering_current_viewport(&mut self) {
+        if self.headless {
+            return;
+        }
         let viewport = self.camera.viewport();
+        let image_scale = self.camera.image_scale();
 
         // Update background and strokes for the new viewport
-        if let Err(e) = self.document.background.update_rendernodes(viewport) {
+        if let Err(e) = self
+            .document
+            .update_background_rendernodes(viewport, image_scale)
+        {
             log::error!(
                 "failed to update background rendernodes on canvas resize with Err {}",
                 e
@@ -438,6 +800,9 @@ impl RnoteEngine {
 
     /// updates the content rendering for the current viewport. including the background rendering.
     pub fn update_rendering_current_viewport(&mut self) {
+        if self.headless {
+            return;
+        }
         let viewport = self.camera.viewport();
         let image_scale = self.camera.image_scale();
 
@@ -451,39 +816,175 @@ impl RnoteEngine {
         );
     }
 
-    // Generates bounds for each page on the document which contains content
-    pub fn pages_bounds_w_content(&self) -> Vec<AABB> {
-        let doc_bounds = self.document.bounds();
+    /// updates the tiles for the current viewport, if tile rendering is enabled.
+    /// Needs to be called after strokes have changed, so that the tiles covering them get rerendered.
+    pub fn update_tiles_current_viewport(&mut self) {
+        if !self.tile_rendering {
+            return;
+        }
+        let viewport = self.camera.viewport();
+        let image_scale = self.camera.image_scale();
+
+        self.store
+            .regenerate_tiles_in_viewport_threaded(self.tasks_tx(), viewport, image_scale);
+    }
+
+    /// Generates the index and the bounds for each page on the document which contains content
+    pub fn pages_w_content(&self) -> Vec<(usize, AABB)> {
         let mut keys = self.store.stroke_keys_as_rendered();
         keys.extend(self.store.selection_keys_as_rendered());
 
         let strokes_bounds = self.store.strokes_bounds(&keys);
 
-        let pages_bounds = doc_bounds
-            .split_extended_origin_aligned(na::vector![
-                self.document.format.width,
-                self.document.format.height
-            ])
+        let pages = self
+            .document
+            .pages_bounds()
             .into_iter()
-            .filter(|page_bounds| {
+            .enumerate()
+            .filter(|(_, page_bounds)| {
                 // Filter the pages out that doesn't intersect with any stroke
                 strokes_bounds
                     .iter()
                     .any(|stroke_bounds| stroke_bounds.intersects(page_bounds))
             })
-            .collect::<Vec<AABB>>();
+            .collect::<Vec<(usize, AABB)>>();
 
-        if pages_bounds.is_empty() {
+        if pages.is_empty() {
             // If no page has content, return the origin page
-            vec![AABB::new(
-                na::point![0.0, 0.0],
-                na::point![self.document.format.width, self.document.format.height],
+            let page_size = self.document.page_size(0);
+
+            vec![(
+                0,
+                AABB::new(na::point![0.0, 0.0], na::point![page_size[0], page_size[1]]),
             )]
         } else {
-            pages_bounds
+            pages
         }
     }
 
+    /// The index and the bounds of the pages that are exported. In the page layouts all pages are exported, so that they keep their page numbers.
+    /// In infinite layout only the pages which contain content
+    pub fn pages_for_export(&self) -> Vec<(usize, AABB)> {
+        if self.document.pages_manageable() {
+            self.document
+                .pages_bounds()
+                .into_iter()
+                .enumerate()
+                .collect()
+        } else {
+            self.pages_w_content()
+        }
+    }
+
+    // Generates bounds for each page on the document which contains content
+    pub fn pages_bounds_w_content(&self) -> Vec<AABB> {
+        self.pages_w_content()
+            .into_iter()
+            .map(|(_, page_bounds)| page_bounds)
+            .collect()
+    }
+
+    /// Regenerates the outdated thumbnails for each page of `Document::pages_bounds()` in separate threads, with the given width in pixels.
+    /// The generated thumbnails are sent as `EngineTask::UpdatePageThumbnail` through the tasks channel.
+    ///
+    /// The thumbnails are cached and only regenerated when the content on their page changed.
+    pub fn update_page_thumbnails_threaded(&mut self, width: u32) {
+        let pages_bounds = self.document.pages_bounds();
+        self.page_thumbnails.retain_pages(pages_bounds.len());
+
+        for (i, page_bounds) in pages_bounds.into_iter().enumerate() {
+            let mut keys = self
+                .store
+                .stroke_keys_as_rendered_intersecting_bounds(page_bounds);
+            keys.extend(
+                self.store
+                    .selection_keys_as_rendered_intersecting_bounds(page_bounds),
+            );
+
+            let signature = PageSignature::new(
+                page_bounds,
+                self.document.background_generation(),
+                keys.iter()
+                    .filter_map(|&key| Some((key, self.store.stroke_generation(key)?))),
+            );
+            let request_id = match self.page_thumbnails.request_thumbnail(i, signature, width) {
+                Some(request_id) => request_id,
+                None => continue,
+            };
+
+            let background_svg = match self.document.gen_background_svg(page_bounds) {
+                Ok(background_svg) => background_svg,
+                Err(e) => {
+                    log::error!("gen_background_svg() failed in update_page_thumbnails_threaded() for page {}, Err {}", i, e);
+                    continue;
+                }
+            };
+            let strokes = self.store.get_strokes_arc(&keys);
+            let image_scale = f64::from(width) / page_bounds.extents()[0];
+            let tasks_tx = self.tasks_tx();
+
+            // Spawn a new thread for the thumbnail rendering
+            rayon::spawn(move || {
+                let gen_thumbnail = || -> anyhow::Result<render::Image> {
+                    let svg_bounds = AABB::new(
+                        na::point![0.0, 0.0],
+                        na::Point2::from(page_bounds.extents()),
+                    );
+
+                    let mut page_svg = background_svg;
+                    page_svg.wrap_svg_root(Some(svg_bounds), Some(page_bounds), true);
+                    page_svg.merge([render::Svg::gen_with_piet_cairo_backend(
+                        |piet_cx| {
+                            piet_cx.transform(kurbo::Affine::translate(
+                                -page_bounds.mins.coords.to_kurbo_vec(),
+                            ));
+
+                            for stroke in strokes.iter() {
+                                stroke.draw(piet_cx, image_scale)?;
+                            }
+                            Ok(())
+                        },
+                        svg_bounds,
+                    )?]);
+
+                    render::Image::gen_image_from_svg(page_svg, svg_bounds, image_scale)
+                };
+
+                match gen_thumbnail() {
+                    Ok(image) => {
+                        tasks_tx
+                            .unbounded_send(EngineTask::UpdatePageThumbnail {
+                                page: i,
+                                request_id,
+                                image,
+                            })
+                            .unwrap_or_else(|e| {
+                                log::error!("tasks_tx.send() UpdatePageThumbnail failed in update_page_thumbnails_threaded() for page {}, with Err, {}", i, e);
+                            });
+                    }
+                    Err(e) => {
+                        log::error!(
+                            "generating thumbnail for page {} failed in update_page_thumbnails_threaded(), Err {}",
+                            i,
+                            e
+                        );
+                    }
+                }
+            });
+        }
+    }
+
+    /// The bounds of each page of `Document::pages_bounds()`, with the latest generated thumbnail of the page.
+    /// The thumbnails might be outdated or missing while they are regenerated with `update_page_thumbnails_threaded()`.
+    pub fn page_thumbnails(&self) -> Vec<(AABB, Option<&PageThumbnail>)> {
+        self.document
+            .pages_bounds()
+            .into_iter()
+            .enumerate()
+            .map(|(i, page_bounds)| (page_bounds, self.page_thumbnails.thumbnail(i)))
+            .collect()
+    }
+
     /// Generates bounds which contain all pages on the doc with content extended to fit the format.
     pub fn bounds_w_content_extended(&self) -> Option<AABB> {
         let pages_bounds = self.pages_bounds_w_content();
@@ -504,8 +1005,30 @@ impl RnoteEngine {
         self.document.layout()
     }
 
-    pub fn set_doc_layout(&mut self, layout: Layout) {
+    /// Sets the document layout. When switching between page layouts, the strokes are moved with their pages to the new position of the pages.
+    pub fn set_doc_layout(&mut self, layout: Layout) -> WidgetFlags {
+        let prev_layout = self.document.layout();
+        if layout == prev_layout {
+            self.document.set_layout(layout, &self.store, &self.camera);
+            return WidgetFlags::default();
+        }
+        let pages_bounds = self.document.pages_bounds();
+        let keys_on_pages = self.keys_on_pages(&pages_bounds);
+
+        let mut widget_flags = self.record_w_doc_pages();
+
         self.document.set_layout(layout, &self.store, &self.camera);
+        let mut moved_keys = vec![];
+        if prev_layout != Layout::Infinite && layout != Layout::Infinite {
+            // The pages keep their order and follow each other in the new layout
+            self.document.resize_to_n_pages(pages_bounds.len());
+            moved_keys = self.move_strokes_with_pages(&pages_bounds, keys_on_pages, Some);
+            self.document
+                .resize_to_fit_strokes(&self.store, &self.camera);
+        }
+
+        widget_flags.merge_with_other(self.update_after_changing_pages(&moved_keys));
+        widget_flags
     }
 
     /// resizes the doc to the format and to fit all strokes
@@ -521,6 +1044,275 @@ impl RnoteEngine {
         self.document.resize_autoexpand(&self.store, &self.camera);
     }
 
+    /// The index of the page in the center of the viewport. None in infinite layout
+    pub fn current_page(&self) -> Option<usize> {
+        self.document.page_index_at(self.camera.viewport().center())
+    }
+
+    /// Inserts a new page with the document format and background at the index. The strokes on the following pages are moved with their pages.
+    pub fn insert_page(&mut self, i: usize) -> WidgetFlags {
+        let pages_bounds = self.document.pages_bounds();
+        if !self.document.pages_manageable() || i > pages_bounds.len() {
+            return WidgetFlags::default();
+        }
+        let keys_on_pages = self.keys_on_pages(&pages_bounds);
+
+        let mut widget_flags = self.record_w_doc_pages();
+
+        self.document.insert_page(i, Page::default());
+        let moved_keys = self.move_strokes_with_pages(&pages_bounds, keys_on_pages, |j| {
+            Some(if j < i { j } else { j + 1 })
+        });
+
+        widget_flags.merge_with_other(self.update_after_changing_pages(&moved_keys));
+        widget_flags
+    }
+
+    /// Removes the page at the index. The strokes on it are trashed, the strokes on the following pages are moved with their pages.
+    pub fn remove_page(&mut self, i: usize) -> WidgetFlags {
+        let pages_bounds = self.document.pages_bounds();
+        if !self.document.pages_manageable() || i >= pages_bounds.len() {
+            return WidgetFlags::default();
+        }
+        let keys_on_pages = self.keys_on_pages(&pages_bounds);
+
+        let mut widget_flags = self.record_w_doc_pages();
+
+        self.document.remove_page(i);
+        let moved_keys =
+            self.move_strokes_with_pages(&pages_bounds, keys_on_pages, |j| match j.cmp(&i) {
+                Ordering::Less => Some(j),
+                Ordering::Equal => None,
+                Ordering::Greater => Some(j - 1),
+            });
+
+        widget_flags.merge_with_other(self.update_after_changing_pages(&moved_keys));
+        widget_flags
+    }
+
+    /// Moves the page at the index one page up, together with its strokes
+    pub fn move_page_up(&mut self, i: usize) -> WidgetFlags {
+        if i == 0 {
+            return WidgetFlags::default();
+        }
+        self.move_page(i, i - 1)
+    }
+
+    /// Moves the page at the index one page down, together with its strokes
+    pub fn move_page_down(&mut self, i: usize) -> WidgetFlags {
+        self.move_page(i, i + 1)
+    }
+
+    /// Moves the page at the index `from` to the index `to` together with its strokes. The pages in between shift by one page.
+    pub fn move_page(&mut self, from: usize, to: usize) -> WidgetFlags {
+        let pages_bounds = self.document.pages_bounds();
+        if !self.document.pages_manageable()
+            || from == to
+            || from >= pages_bounds.len()
+            || to >= pages_bounds.len()
+        {
+            return WidgetFlags::default();
+        }
+        let keys_on_pages = self.keys_on_pages(&pages_bounds);
+
+        let mut widget_flags = self.record_w_doc_pages();
+
+        self.document.move_page(from, to);
+        let moved_keys = self.move_strokes_with_pages(&pages_bounds, keys_on_pages, |j| {
+            if j == from {
+                Some(to)
+            } else if from < to && (from..=to).contains(&j) {
+                Some(j - 1)
+            } else if to < from && (to..=from).contains(&j) {
+                Some(j + 1)
+            } else {
+                Some(j)
+            }
+        });
+
+        widget_flags.merge_with_other(self.update_after_changing_pages(&moved_keys));
+        widget_flags
+    }
+
+    /// Duplicates the page at the index with its overrides and strokes. The duplicate is inserted after it.
+    pub fn duplicate_page(&mut self, i: usize) -> WidgetFlags {
+        let pages_bounds = self.document.pages_bounds();
+        let page_bounds = match pages_bounds.get(i) {
+            Some(&page_bounds) if self.document.pages_manageable() => page_bounds,
+            _ => return WidgetFlags::default(),
+        };
+        let keys_on_pages = self.keys_on_pages(&pages_bounds);
+        let page_keys = keys_on_pages[i].clone();
+
+        let mut widget_flags = self.record_w_doc_pages();
+
+        let page = self.document.page(i).cloned().unwrap_or_default();
+        self.document.insert_page(i + 1, page);
+        let mut moved_keys = self.move_strokes_with_pages(&pages_bounds, keys_on_pages, |j| {
+            Some(if j <= i { j } else { j + 1 })
+        });
+
+        if let Some(duplicate_bounds) = self.document.pages_bounds().get(i + 1) {
+            let offset = duplicate_bounds.mins - page_bounds.mins;
+
+            let duplicated_keys = self
+                .store
+                .clone_strokes(&page_keys)
+                .into_iter()
+                .map(|stroke| self.store.insert_stroke(stroke))
+                .collect::<Vec<StrokeKey>>();
+            self.store
+                .copy_groups_w_new_ids(&page_keys, &duplicated_keys);
+            self.store.translate_strokes(&duplicated_keys, offset);
+
+            moved_keys.extend(duplicated_keys);
+        }
+
+        widget_flags.merge_with_other(self.update_after_changing_pages(&moved_keys));
+        widget_flags
+    }
+
+    /// Sets the overrides of the format, orientation and background of the page at the index.
+    /// The page can change its size, so the strokes on the following pages are moved with their pages.
+    pub fn set_page_overrides(&mut self, i: usize, page: Page) -> WidgetFlags {
+        let pages_bounds = self.document.pages_bounds();
+        if !self.document.pages_manageable() || i >= pages_bounds.len() {
+            return WidgetFlags::default();
+        }
+        let keys_on_pages = self.keys_on_pages(&pages_bounds);
+
+        let mut widget_flags = self.record_w_doc_pages();
+
+        self.document.set_page(i, page);
+        self.document.resize_to_n_pages(pages_bounds.len());
+        let moved_keys = self.move_strokes_with_pages(&pages_bounds, keys_on_pages, Some);
+
+        widget_flags.merge_with_other(self.update_after_changing_pages(&moved_keys));
+        widget_flags
+    }
+
+    // The keys of the rendered strokes on each of the pages. Strokes belong to the page nearest to the center of their bounds
+    fn keys_on_pages(&self, pages_bounds: &[AABB]) -> Vec<Vec<StrokeKey>> {
+        let mut keys_on_pages = vec![vec![]; pages_bounds.len()];
+        let mut keys = self.store.stroke_keys_as_rendered();
+        keys.extend(self.store.selection_keys_as_rendered());
+
+        for key in keys {
+            if let Some(i) = self
+                .store
+                .get_stroke_ref(key)
+                .and_then(|stroke| Document::nearest_page(pages_bounds, stroke.bounds().center()))
+            {
+                keys_on_pages[i].push(key);
+            }
+        }
+
+        keys_on_pages
+    }
+
+    // Moves the strokes to the new position of their page, after the pages were changed.
+    // `new_index` maps the index of a page before the change to its index after it, the strokes of pages that map to None are trashed.
+    // Returns the keys of the moved strokes
+    fn move_strokes_with_pages<F>(
+        &mut self,
+        old_pages_bounds: &[AABB],
+        keys_on_pages: Vec<Vec<StrokeKey>>,
+        new_index: F,
+    ) -> Vec<StrokeKey>
+    where
+        F: Fn(usize) -> Option<usize>,
+    {
+        let new_pages_bounds = self.document.pages_bounds();
+        let mut moved_keys = vec![];
+
+        for (i, (old_page_bounds, keys)) in old_pages_bounds.iter().zip(keys_on_pages).enumerate() {
+            let new_page_bounds = match new_index(i) {
+                Some(new_i) => match new_pages_bounds.get(new_i) {
+                    Some(new_page_bounds) => new_page_bounds,
+                    None => continue,
+                },
+                None => {
+                    self.store.set_trashed_keys(&keys, true);
+                    continue;
+                }
+            };
+            let offset = new_page_bounds.mins - old_page_bounds.mins;
+            if offset == na::Vector2::zeros() {
+                continue;
+            }
+
+            self.store.translate_strokes(&keys, offset);
+            self.store.translate_strokes_images(&keys, offset);
+            moved_keys.extend(keys);
+        }
+
+        moved_keys
+    }
+
+    // Records the current state together with the document pages, to be called before the pages are changed
+    fn record_w_doc_pages(&mut self) -> WidgetFlags {
+        self.store.set_doc_pages(self.document.pages_snapshot());
+        self.store.record()
+    }
+
+    // Applies the document pages from the current state of the store, after undo or redo.
+    // Only when they differ from the pages before, else undoing a change of the strokes would reset the size of the document to when the pages were last changed
+    fn import_doc_pages_from_store(
+        &mut self,
+        prev_doc_pages: Option<Arc<PagesSnapshot>>,
+    ) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+
+        if let Some(doc_pages) = self.store.doc_pages() {
+            if prev_doc_pages.map_or(false, |prev_doc_pages| {
+                Arc::ptr_eq(&prev_doc_pages, &doc_pages)
+            }) {
+                return widget_flags;
+            }
+            let prev_layout = self.document.layout();
+            self.document.import_pages_snapshot(&doc_pages);
+            self.regenerate_background_patterns();
+
+            // The layout is displayed in the UI
+            widget_flags.refresh_ui = self.document.layout() != prev_layout;
+        }
+
+        widget_flags
+    }
+
+    fn regenerate_background_patterns(&mut self) {
+        if self.headless {
+            return;
+        }
+        if let Err(e) = self
+            .document
+            .regenerate_background_patterns(self.camera.viewport(), self.camera.image_scale())
+        {
+            log::error!(
+                "regenerate_background_patterns() failed after changing the pages, Err {}",
+                e
+            );
+        }
+    }
+
+    fn update_after_changing_pages(&mut self, keys: &[StrokeKey]) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+
+        self.store.update_geometry_for_strokes(keys);
+        self.resize_autoexpand();
+        // The page backgrounds are aligned to their pages, which might have moved
+        self.regenerate_background_patterns();
+        self.store.set_doc_pages(self.document.pages_snapshot());
+        self.update_pens_states();
+        self.update_rendering_current_viewport();
+
+        widget_flags.redraw = true;
+        widget_flags.resize = true;
+        widget_flags.indicate_changed_store = true;
+
+        widget_flags
+    }
+
     /// Updates the camera and expands doc dimensions with offset
     /// Document background rendering then needs to be updated.
     pub fn update_camera_offset(&mut self, new_offset: na::Vector2<f64>) {
@@ -530,9 +1322,8 @@ impl RnoteEngine {
             Layout::FixedSize => {
                 // Does not resize in fixed size mode, use resize_doc_to_fit_strokes() for it.
             }
-            Layout::ContinuousVertical => {
-                self.document
-                    .resize_doc_continuous_vertical_layout(&self.store);
+            Layout::ContinuousVertical | Layout::ContinuousHorizontal | Layout::BookSpread => {
+                self.document.resize_doc_continuous_layout(&self.store);
             }
             Layout::Infinite => {
                 // only expand, don't resize to fit strokes
@@ -598,13 +1389,25 @@ impl RnoteEngine {
     pub fn load_engine_config(&mut self, serialized_config: &str) -> anyhow::Result<()> {
         let engine_config = serde_json::from_str::<EngineConfig>(serialized_config)?;
 
-        self.document = serde_json::from_value(engine_config.document)?;
+        // Configs of older versions can contain the pages of the last document, which must not end up in new documents
+        self.document =
+            serde_json::from_value::<Document>(engine_config.document)?.settings_defaults();
         self.penholder = serde_json::from_value(engine_config.penholder)?;
         self.pdf_import_prefs = serde_json::from_value(engine_config.pdf_import_prefs)?;
         self.pen_sounds = serde_json::from_value(engine_config.pen_sounds)?;
+        let tile_rendering = serde_json::from_value(engine_config.tile_rendering)?;
 
         // Set the pen sounds to update the audioplayer
         self.set_pen_sounds(self.pen_sounds);
+        self.set_tile_rendering(tile_rendering);
+
+        // The spell checker dictionary is not part of the config, so it needs to be reloaded
+        if let Err(e) = self.penholder.typewriter.spellchecker.reload_dictionary() {
+            log::error!(
+                "reloading the spell checker dictionary failed in load_engine_config() with Err {}",
+                e
+            );
+        }
 
         Ok(())
     }
@@ -612,10 +1415,11 @@ impl RnoteEngine {
     /// Exports the current engine config as JSON string
     pub fn save_engine_config(&self) -> anyhow::Result<String> {
         let engine_config = EngineConfig {
-            document: serde_json::to_value(&self.document)?,
+            document: serde_json::to_value(&self.document.settings_defaults())?,
             penholder: serde_json::to_value(&self.penholder)?,
             pdf_import_prefs: serde_json::to_value(&self.pdf_import_prefs)?,
             pen_sounds: serde_json::to_value(&self.pen_sounds)?,
+            tile_rendering: serde_json::to_value(&self.tile_rendering)?,
         };
 
         Ok(serde_json::to_string(&engine_config)?)
@@ -668,7 +1472,7 @@ impl RnoteEngine {
         strokes.extend(self.store.selection_keys_as_rendered());
 
         let mut doc_svg = if with_background {
-            let mut background_svg = self.document.background.gen_svg(doc_bounds)?;
+            let mut background_svg = self.document.gen_background_svg(doc_bounds)?;
 
             background_svg.wrap_svg_root(
                 Some(AABB::new(
@@ -716,7 +1520,7 @@ impl RnoteEngine {
     ) -> Result<render::Svg, anyhow::Error> {
         // Background bounds are still doc bounds, for correct alignment of the background pattern
         let mut doc_svg = if with_background {
-            let mut background_svg = self.document.background.gen_svg(viewport)?;
+            let mut background_svg = self.document.gen_background_svg(viewport)?;
 
             background_svg.wrap_svg_root(
                 Some(AABB::new(
@@ -783,7 +1587,7 @@ impl RnoteEngine {
             };
 
         let mut selection_svg = if with_background {
-            let mut background_svg = self.document.background.gen_svg(selection_bounds)?;
+            let mut background_svg = self.document.gen_background_svg(selection_bounds)?;
 
             background_svg.wrap_svg_root(
                 Some(AABB::new(
@@ -901,20 +1705,21 @@ impl RnoteEngine {
     pub fn export_doc_as_xopp_bytes(&self, filename: &str) -> Result<Vec<u8>, anyhow::Error> {
         let current_dpi = self.document.format.dpi;
 
-        // Only one background for all pages
-        let background = xoppformat::XoppBackground {
-            name: None,
-            bg_type: xoppformat::XoppBackgroundType::Solid {
-                color: self.document.background.color.into(),
-                style: xoppformat::XoppBackgroundSolidStyle::Plain,
-            },
-        };
-
-        // xopp spec needs at least one page in vec, but its fine because pages_bounds_w_content() always produces at least one
+        // xopp spec needs at least one page in vec, but its fine because pages_for_export() always produces at least one
         let pages = self
-            .pages_bounds_w_content()
-            .iter()
-            .map(|&page_bounds| {
+            .pages_for_export()
+            .into_iter()
+            .map(|(page_i, page_bounds)| {
+                // The background patterns are mapped to the closest xopp background styles
+                let page_background = self.document.page_background(page_i);
+                let background = xoppformat::XoppBackground {
+                    name: None,
+                    bg_type: xoppformat::XoppBackgroundType::Solid {
+                        color: page_background.color.into(),
+                        style: page_background.pattern.into(),
+                    },
+                };
+
                 let mut page_keys = self
                     .store
                     .stroke_keys_as_rendered_intersecting_bounds(page_bounds);
@@ -987,7 +1792,7 @@ impl RnoteEngine {
                 xoppformat::XoppPage {
                     width: page_dimensions[0],
                     height: page_dimensions[1],
-                    background: background.clone(),
+                    background,
                     layers: vec![layer],
                 }
             })
@@ -1015,29 +1820,22 @@ impl RnoteEngine {
         with_background: bool,
     ) -> oneshot::Receiver<anyhow::Result<Vec<u8>>> {
         let (oneshot_sender, oneshot_receiver) = oneshot::channel::<anyhow::Result<Vec<u8>>>();
-        let doc_bounds = self.document.bounds();
-        let format_size = na::vector![self.document.format.width, self.document.format.height];
         let store_snapshot = self.store.take_store_snapshot();
-
-        let background_svg = if with_background {
-            self.document
-                .background
-                .gen_svg(doc_bounds)
-                .map_err(|e| {
-                    log::error!(
-                        "background.gen_svg() failed in export_doc_as_pdf_bytes() with Err {}",
-                        e
-                    )
-                })
-                .ok()
+        // The header and footer are only drawn in the page layouts, like on the canvas
+        let format = self.document.format.clone();
+        let with_page_texts = self.document.pages_manageable() && format.has_page_texts();
+        let page_texts_title = if self.document.title.is_empty() {
+            title.clone()
         } else {
-            None
+            self.document.title.clone()
         };
 
-        let pages_strokes = self
-            .pages_bounds_w_content()
+        let n_pages = self.document.pages_bounds().len();
+
+        let pages = self
+            .pages_for_export()
             .into_iter()
-            .map(|page_bounds| {
+            .map(|(page_i, page_bounds)| {
                 let mut strokes_in_viewport = self
                     .store
                     .stroke_keys_as_rendered_intersecting_bounds(page_bounds);
@@ -1046,16 +1844,40 @@ impl RnoteEngine {
                         .selection_keys_as_rendered_intersecting_bounds(page_bounds),
                 );
 
-                (page_bounds, strokes_in_viewport)
+                // Every page gets its own background, as pages can override the document background
+                let background_svg = if with_background {
+                    self.document
+                        .gen_background_svg(page_bounds)
+                        .map_err(|e| {
+                            log::error!(
+                                "gen_background_svg() failed in export_doc_as_pdf_bytes() with Err {}",
+                                e
+                            )
+                        })
+                        .ok()
+                } else {
+                    None
+                };
+
+                (page_i, page_bounds, background_svg, strokes_in_viewport)
             })
-            .collect::<Vec<(AABB, Vec<StrokeKey>)>>();
+            .collect::<Vec<(usize, AABB, Option<render::Svg>, Vec<StrokeKey>)>>();
 
         // Fill the pdf surface on a new thread to avoid blocking
         rayon::spawn(move || {
             let result = || -> anyhow::Result<Vec<u8>> {
-                let surface =
-                    cairo::PdfSurface::for_stream(format_size[0], format_size[1], Vec::<u8>::new())
-                        .context("pdfsurface creation failed")?;
+                // The size of the first page, set for every page below
+                let first_page_size = pages
+                    .first()
+                    .map(|(_, page_bounds, _, _)| page_bounds.extents())
+                    .unwrap_or_else(|| na::vector![1.0, 1.0]);
+
+                let surface = cairo::PdfSurface::for_stream(
+                    first_page_size[0],
+                    first_page_size[1],
+                    Vec::<u8>::new(),
+                )
+                .context("pdfsurface creation failed")?;
 
                 surface
                     .set_metadata(cairo::PdfMetadata::Title, title.as_str())
@@ -1071,13 +1893,21 @@ impl RnoteEngine {
                 {
                     let cairo_cx =
                         cairo::Context::new(&surface).context("cario cx new() failed")?;
+                    let mut page_text_context = PageTextContext::new(n_pages, page_texts_title);
+
+                    for (i, (page_i, page_bounds, background_svg, page_strokes)) in
+                        pages.into_iter().enumerate()
+                    {
+                        // Pages can have different sizes, must be set before anything is drawn on the page
+                        surface
+                            .set_size(page_bounds.extents()[0], page_bounds.extents()[1])
+                            .context("set pdf surface size failed")?;
 
-                    for (i, (page_bounds, page_strokes)) in pages_strokes.into_iter().enumerate() {
                         // We can't render the background svg with piet, so we have to do it with cairo.
                         cairo_cx.save()?;
                         cairo_cx.translate(-page_bounds.mins[0], -page_bounds.mins[1]);
 
-                        if let Some(background_svg) = background_svg.clone() {
+                        if let Some(background_svg) = background_svg {
                             render::Svg::draw_svgs_to_cairo_context(&[background_svg], &cairo_cx)?;
                         }
                         cairo_cx.restore()?;
@@ -1095,6 +1925,16 @@ impl RnoteEngine {
                             }
                         }
 
+                        if with_page_texts {
+                            page_text_context.page_number = page_i + 1;
+
+                            format.draw_page_texts(
+                                &mut piet_cx,
+                                page_bounds,
+                                &page_text_context,
+                            )?;
+                        }
+
                         cairo_cx.show_page().map_err(|e| {
                             anyhow::anyhow!(
                                 "show_page() failed when exporting page {} as pdf, Err {}",
@@ -1147,13 +1987,22 @@ impl RnoteEngine {
 
         self.document.draw_shadow(snapshot);
 
-        self.document
-            .background
-            .draw(snapshot, doc_bounds, &self.camera)?;
+        self.document.draw_backgrounds(snapshot, &self.camera)?;
+
+        let pages_bounds = self.document.pages_bounds();
 
         self.document
             .format
-            .draw(snapshot, doc_bounds, &self.camera)?;
+            .draw(snapshot, doc_bounds, &pages_bounds, &self.camera)?;
+
+        if self.document.pages_manageable() {
+            self.document.format.draw_page_decorations(
+                snapshot,
+                &pages_bounds,
+                &self.document.title,
+                &self.camera,
+            )?;
+        }
 
         self.store
             .draw_strokes_snapshot(snapshot, doc_bounds, viewport);
@@ -1452,3 +2301,140 @@ pub mod visual_debug {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strokes::{ShapeStroke, Stroke};
+    use rnote_compose::shapes::{Line, Shape};
+    use rnote_compose::Style;
+
+    // A line in the middle of the page at the index, in the vertical page layouts
+    fn stroke_on_page(engine: &RnoteEngine, i: usize) -> Stroke {
+        let y = (i as f64 + 0.5) * engine.document.format.height;
+
+        Stroke::ShapeStroke(ShapeStroke::new(
+            Shape::Line(Line {
+                start: na::vector![10.0, y],
+                end: na::vector![100.0, y],
+            }),
+            Style::default(),
+        ))
+    }
+
+    fn page_of(engine: &RnoteEngine, key: StrokeKey) -> Option<usize> {
+        let stroke = engine.store.get_stroke_ref(key)?;
+        engine.document.page_index_at(stroke.bounds().center())
+    }
+
+    // An engine in the continuous vertical layout with a stroke on the first and on the second page
+    fn engine_w_strokes_on_pages() -> (RnoteEngine, StrokeKey, StrokeKey) {
+        let mut engine = RnoteEngine::new(None);
+        engine.headless = true;
+        let _ = engine.set_doc_layout(Layout::ContinuousVertical);
+
+        let first = stroke_on_page(&engine, 0);
+        let first = engine.store.insert_stroke(first);
+        let second = stroke_on_page(&engine, 1);
+        let second = engine.store.insert_stroke(second);
+        engine.resize_autoexpand();
+
+        (engine, first, second)
+    }
+
+    #[test]
+    fn insert_page_moves_following_strokes() {
+        let (mut engine, first, second) = engine_w_strokes_on_pages();
+        let n_pages = engine.document.pages_bounds().len();
+
+        let _ = engine.insert_page(1);
+        assert_eq!(engine.document.pages_bounds().len(), n_pages + 1);
+        assert_eq!(page_of(&engine, first), Some(0));
+        assert_eq!(page_of(&engine, second), Some(2));
+
+        let _ = engine.undo();
+        assert_eq!(engine.document.pages_bounds().len(), n_pages);
+        assert_eq!(page_of(&engine, first), Some(0));
+        assert_eq!(page_of(&engine, second), Some(1));
+    }
+
+    #[test]
+    fn remove_page_trashes_its_strokes() {
+        let (mut engine, first, second) = engine_w_strokes_on_pages();
+
+        let _ = engine.remove_page(0);
+        assert_eq!(engine.store.trashed(first), Some(true));
+        assert_eq!(page_of(&engine, second), Some(0));
+
+        let _ = engine.undo();
+        assert_eq!(engine.store.trashed(first), Some(false));
+        assert_eq!(page_of(&engine, first), Some(0));
+        assert_eq!(page_of(&engine, second), Some(1));
+    }
+
+    #[test]
+    fn move_page_moves_its_strokes() {
+        let (mut engine, first, second) = engine_w_strokes_on_pages();
+
+        let _ = engine.move_page(0, 1);
+        assert_eq!(page_of(&engine, first), Some(1));
+        assert_eq!(page_of(&engine, second), Some(0));
+
+        let _ = engine.undo();
+        assert_eq!(page_of(&engine, first), Some(0));
+        assert_eq!(page_of(&engine, second), Some(1));
+    }
+
+    #[test]
+    fn duplicate_page_duplicates_its_strokes() {
+        let (mut engine, first, second) = engine_w_strokes_on_pages();
+
+        let _ = engine.duplicate_page(0);
+        let keys = engine.store.stroke_keys_as_rendered();
+        assert_eq!(keys.len(), 3);
+        assert_eq!(page_of(&engine, first), Some(0));
+        assert_eq!(page_of(&engine, second), Some(2));
+        assert!(keys
+            .into_iter()
+            .any(|key| key != first && key != second && page_of(&engine, key) == Some(1)));
+
+        let _ = engine.undo();
+        assert_eq!(engine.store.stroke_keys_as_rendered().len(), 2);
+        assert_eq!(page_of(&engine, second), Some(1));
+    }
+
+    #[test]
+    fn empty_pages_are_exported_in_page_layouts() {
+        let (mut engine, first, _) = engine_w_strokes_on_pages();
+        engine.store.set_trashed_keys(&[first], true);
+        let n_pages = engine.document.pages_bounds().len();
+
+        let pages = engine.pages_for_export();
+        assert_eq!(pages.len(), n_pages);
+        assert_eq!(pages[0].0, 0);
+        assert_eq!(engine.pages_w_content().len(), 1);
+    }
+
+    #[test]
+    fn undoing_stroke_changes_keeps_the_pages() {
+        let mut engine = RnoteEngine::new(None);
+        engine.headless = true;
+        let _ = engine.set_doc_layout(Layout::FixedSize);
+        let _ = engine.insert_page(1);
+
+        let _ = engine.record();
+        let stroke = stroke_on_page(&engine, 4);
+        let key = engine.store.insert_stroke(stroke);
+        engine.resize_to_fit_strokes();
+        let height = engine.document.height;
+
+        let _ = engine.record();
+        engine
+            .store
+            .translate_strokes(&[key], na::vector![10.0, 0.0]);
+
+        let _ = engine.undo();
+        assert_eq!(engine.document.height, height);
+        assert_eq!(engine.document.pages_bounds().len(), 5);
+    }
+}
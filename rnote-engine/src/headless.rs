@@ -0,0 +1,186 @@
+use crate::document::Layout;
+use crate::strokes::Stroke;
+use crate::RnoteEngine;
+
+/// Information about the document that is currently loaded into the engine
+#[derive(Debug, Clone)]
+pub struct DocInfo {
+    pub title: String,
+    pub layout: Layout,
+    pub width: f64,
+    pub height: f64,
+    pub n_pages: usize,
+    pub n_pages_w_content: usize,
+    pub n_brushstrokes: usize,
+    pub n_shapestrokes: usize,
+    pub n_textstrokes: usize,
+    pub n_vectorimages: usize,
+    pub n_bitmapimages: usize,
+}
+
+impl DocInfo {
+    /// The total number of strokes
+    pub fn n_strokes(&self) -> usize {
+        self.n_brushstrokes
+            + self.n_shapestrokes
+            + self.n_textstrokes
+            + self.n_vectorimages
+            + self.n_bitmapimages
+    }
+}
+
+/// Methods to open, inspect and export documents without a display.
+///
+/// They block until the spawned tasks are finished and never create GTK snapshots, textures or rendernodes,
+/// so they can be used without a running GTK main loop (e.g. in batch conversions on servers).
+impl RnoteEngine {
+    /// Opens a .rnote file and replaces the current state with it. Blocks until it is loaded
+    pub fn open_from_rnote_bytes_headless(&mut self, bytes: Vec<u8>) -> anyhow::Result<()> {
+        let store_snapshot_receiver = self.open_from_rnote_bytes_p1(bytes)?;
+        let store_snapshot = futures::executor::block_on(store_snapshot_receiver)??;
+
+        self.open_from_store_snapshot_p2(&store_snapshot)?;
+        self.resize_autoexpand();
+
+        Ok(())
+    }
+
+    /// Opens a Xournal++ .xopp file and replaces the current state with it.
+    pub fn open_from_xopp_bytes_headless(&mut self, bytes: Vec<u8>) -> anyhow::Result<()> {
+        self.open_from_xopp_bytes(bytes)?;
+        self.resize_autoexpand();
+
+        Ok(())
+    }
+
+    /// Saves the current state as .rnote file bytes. Blocks until it is saved
+    pub fn save_as_rnote_bytes_headless(&self, file_name: String) -> anyhow::Result<Vec<u8>> {
+        futures::executor::block_on(self.save_as_rnote_bytes(file_name)?)?
+    }
+
+    /// Exports the doc with the strokes as PDF bytes. Blocks until it is exported
+    pub fn export_doc_as_pdf_bytes_headless(
+        &self,
+        title: String,
+        with_background: bool,
+    ) -> anyhow::Result<Vec<u8>> {
+        futures::executor::block_on(self.export_doc_as_pdf_bytes(title, with_background))?
+    }
+
+    /// Exports the doc with the strokes as PNG bytes
+    pub fn export_doc_as_png_bytes(&self, with_background: bool) -> anyhow::Result<Vec<u8>> {
+        self.export_doc_as_bitmapimage_bytes(image::ImageOutputFormat::Png, with_background)
+    }
+
+    /// Generates information about the current document. Trashed strokes are not counted
+    pub fn doc_info(&self) -> DocInfo {
+        let mut keys = self.store.stroke_keys_as_rendered();
+        keys.extend(self.store.selection_keys_as_rendered());
+
+        let mut doc_info = DocInfo {
+            title: self.document.title.clone(),
+            layout: self.doc_layout(),
+            width: self.document.width,
+            height: self.document.height,
+            n_pages: self.document.pages_bounds().len(),
+            n_pages_w_content: self.pages_w_content().len(),
+            n_brushstrokes: 0,
+            n_shapestrokes: 0,
+            n_textstrokes: 0,
+            n_vectorimages: 0,
+            n_bitmapimages: 0,
+        };
+
+        for stroke in self.store.get_strokes_ref(&keys) {
+            match stroke {
+                Stroke::BrushStroke(_) => doc_info.n_brushstrokes += 1,
+                Stroke::ShapeStroke(_) => doc_info.n_shapestrokes += 1,
+                Stroke::TextStroke(_) => doc_info.n_textstrokes += 1,
+                Stroke::VectorImage(_) => doc_info.n_vectorimages += 1,
+                Stroke::BitmapImage(_) => doc_info.n_bitmapimages += 1,
+            }
+        }
+
+        doc_info
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strokes::{BrushStroke, ShapeStroke};
+    use rnote_compose::penpath::{Element, Segment};
+    use rnote_compose::shapes::{Line, Shape};
+    use rnote_compose::Style;
+
+    // An engine with a brushstroke and a shapestroke on the first page
+    fn engine_w_strokes() -> RnoteEngine {
+        let mut engine = RnoteEngine::new(None);
+
+        engine
+            .store
+            .insert_stroke(Stroke::BrushStroke(BrushStroke::new(
+                Segment::Line {
+                    start: Element::new(na::vector![10.0, 10.0], 0.5),
+                    end: Element::new(na::vector![100.0, 50.0], 0.5),
+                },
+                Style::default(),
+            )));
+        engine
+            .store
+            .insert_stroke(Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Line(Line {
+                    start: na::vector![10.0, 100.0],
+                    end: na::vector![100.0, 150.0],
+                }),
+                Style::default(),
+            )));
+        engine.resize_autoexpand();
+
+        engine
+    }
+
+    #[test]
+    fn doc_info_of_rnote_file() {
+        let bytes = engine_w_strokes()
+            .save_as_rnote_bytes_headless(String::from("fixture.rnote"))
+            .unwrap();
+        let mut engine = RnoteEngine::new(None);
+        engine.open_from_rnote_bytes_headless(bytes).unwrap();
+
+        let doc_info = engine.doc_info();
+
+        assert_eq!(doc_info.n_strokes(), 2);
+        assert_eq!(doc_info.n_brushstrokes, 1);
+        assert_eq!(doc_info.n_shapestrokes, 1);
+        assert_eq!(doc_info.n_pages_w_content, 1);
+        assert_eq!(doc_info.n_pages, engine.document.pages_bounds().len());
+        assert_eq!(doc_info.width, engine.document.width);
+    }
+
+    #[test]
+    fn doc_info_of_xopp_file() {
+        let bytes = engine_w_strokes()
+            .export_doc_as_xopp_bytes("fixture.xopp")
+            .unwrap();
+        let mut engine = RnoteEngine::new(None);
+        engine.open_from_xopp_bytes_headless(bytes).unwrap();
+
+        let doc_info = engine.doc_info();
+
+        // Shapestrokes are exported as images to Xournal++
+        assert_eq!(doc_info.n_strokes(), 2);
+        assert_eq!(doc_info.n_brushstrokes, 1);
+        assert_eq!(doc_info.n_bitmapimages, 1);
+        assert_eq!(doc_info.n_pages_w_content, 1);
+    }
+
+    #[test]
+    fn doc_info_excludes_trashed_strokes() {
+        let mut engine = engine_w_strokes();
+        let keys = engine.store.stroke_keys_unordered();
+        engine.store.set_trashed_keys(&keys[..1], true);
+
+        assert_eq!(engine.doc_info().n_strokes(), 1);
+    }
+}
@@ -4,7 +4,7 @@ use futures::channel::oneshot;
 use rnote_fileformats::{rnoteformat, xoppformat, FileFormatLoader};
 use serde::{Deserialize, Serialize};
 
-use crate::document::{background, Background, Format};
+use crate::document::{background, Background, Format, Layout};
 use crate::pens::penholder::PenStyle;
 use crate::store::{StoreSnapshot, StrokeKey};
 use crate::strokes::{BitmapImage, Stroke, VectorImage};
@@ -164,6 +164,27 @@ impl RnoteEngine {
         format.width = doc_width;
         format.height = doc_height / f64::from(no_pages);
 
+        // Pages with differing sizes get their own size, which needs the pages to be stacked
+        let mut stacked_pages = false;
+        if let Some(first_page) = xopp_file.xopp_root.pages.get(0) {
+            if xopp_file
+                .xopp_root
+                .pages
+                .iter()
+                .any(|page| page.width != first_page.width || page.height != first_page.height)
+            {
+                format.width = first_page.width;
+                format.height = first_page.height;
+                stacked_pages = true;
+
+                for (i, page) in xopp_file.xopp_root.pages.iter().enumerate() {
+                    if page.width != first_page.width || page.height != first_page.height {
+                        doc.page_mut(i).size = Some(na::vector![page.width, page.height]);
+                    }
+                }
+            }
+        }
+
         if let Some(first_page) = xopp_file.xopp_root.pages.get(0) {
             if let xoppformat::XoppBackgroundType::Solid {
                 color: _color,
@@ -222,6 +243,11 @@ impl RnoteEngine {
         self.document = doc;
         self.store.import_snapshot(&*store.take_store_snapshot());
 
+        if stacked_pages {
+            self.document
+                .set_layout(Layout::FixedSize, &self.store, &self.camera);
+        }
+
         self.update_pens_states();
 
         Ok(())
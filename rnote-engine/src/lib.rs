@@ -11,12 +11,18 @@ pub mod camera;
 pub mod document;
 mod drawbehaviour;
 pub mod engine;
+/// module for opening, inspecting and exporting documents without a display
+pub mod headless;
 /// module concerned with importing data into the engine
 pub mod import;
 pub mod pens;
 pub mod render;
+/// module for spell checking text with local dictionaries
+pub mod spellcheck;
 pub mod store;
 pub mod strokes;
+/// module for the cached thumbnails of the pages
+pub mod thumbnails;
 pub mod utils;
 pub mod widgetflags;
 
@@ -10,6 +10,8 @@ use rnote_compose::builders::Constraints;
 use rnote_compose::builders::{PenPathBuilder, ShapeBuilderBehaviour};
 use rnote_compose::penhelpers::PenEvent;
 use rnote_compose::penpath::Segment;
+use rnote_compose::style::calligraphy::CalligraphyOptions;
+use rnote_compose::style::pencil::PencilOptions;
 use rnote_compose::style::textured::TexturedOptions;
 use rnote_compose::style::PressureCurve;
 use rnote_compose::{Shape, Style};
@@ -39,6 +41,10 @@ pub enum BrushStyle {
     Solid,
     #[serde(rename = "textured")]
     Textured,
+    #[serde(rename = "calligraphy")]
+    Calligraphy,
+    #[serde(rename = "pencil")]
+    Pencil,
 }
 
 impl Default for BrushStyle {
@@ -75,6 +81,10 @@ pub struct Brush {
     pub smooth_options: SmoothOptions,
     #[serde(rename = "textured_options")]
     pub textured_options: TexturedOptions,
+    #[serde(rename = "calligraphy_options")]
+    pub calligraphy_options: CalligraphyOptions,
+    #[serde(rename = "pencil_options")]
+    pub pencil_options: PencilOptions,
 
     #[serde(skip)]
     state: BrushState,
@@ -84,13 +94,19 @@ impl Default for Brush {
     fn default() -> Self {
         let mut smooth_options = SmoothOptions::default();
         let mut textured_options = TexturedOptions::default();
+        let mut calligraphy_options = CalligraphyOptions::default();
+        let mut pencil_options = PencilOptions::default();
         smooth_options.stroke_width = Self::STROKE_WIDTH_DEFAULT;
         textured_options.stroke_width = Self::STROKE_WIDTH_DEFAULT;
+        calligraphy_options.stroke_width = Self::CALLIGRAPHY_STROKE_WIDTH_DEFAULT;
+        pencil_options.stroke_width = Self::PENCIL_STROKE_WIDTH_DEFAULT;
 
         Self {
             style: BrushStyle::default(),
             smooth_options,
             textured_options,
+            calligraphy_options,
+            pencil_options,
             state: BrushState::Idle,
         }
     }
@@ -123,6 +139,7 @@ impl PenBehaviour for Brush {
                     // A new seed for a new brush stroke
                     let seed = Some(rand_pcg::Pcg64::from_entropy().gen());
                     self.textured_options.seed = seed;
+                    self.pencil_options.seed = seed;
 
                     let brushstroke = Stroke::BrushStroke(BrushStroke::new(
                         Segment::Dot { element },
@@ -319,6 +336,22 @@ impl Brush {
     pub const STROKE_WIDTH_MIN: f64 = 1.0;
     pub const STROKE_WIDTH_MAX: f64 = 500.0;
     pub const STROKE_WIDTH_DEFAULT: f64 = 2.0;
+    /// Calligraphy strokes need a wider nib to show the varying width
+    pub const CALLIGRAPHY_STROKE_WIDTH_DEFAULT: f64 = 8.0;
+    /// Pencil strokes need to be a few grain cells wide to show the paper grain
+    pub const PENCIL_STROKE_WIDTH_DEFAULT: f64 = 4.0;
+
+    /// A copy of the configuration, without the current state
+    pub fn clone_config(&self) -> Self {
+        Self {
+            style: self.style,
+            smooth_options: self.smooth_options.clone(),
+            textured_options: self.textured_options.clone(),
+            calligraphy_options: self.calligraphy_options.clone(),
+            pencil_options: self.pencil_options.clone(),
+            state: BrushState::Idle,
+        }
+    }
 
     fn start_audio(style: BrushStyle, audioplayer: &mut Option<AudioPlayer>) {
         if let Some(audioplayer) = audioplayer {
@@ -326,7 +359,10 @@ impl Brush {
                 BrushStyle::Marker => {
                     audioplayer.play_random_marker_sound();
                 }
-                BrushStyle::Solid | BrushStyle::Textured => {
+                BrushStyle::Solid
+                | BrushStyle::Textured
+                | BrushStyle::Calligraphy
+                | BrushStyle::Pencil => {
                     audioplayer.start_random_brush_sound();
                 }
             }
@@ -357,6 +393,16 @@ impl Brush {
 
                 Style::Textured(options)
             }
+            BrushStyle::Calligraphy => {
+                let options = self.calligraphy_options.clone();
+
+                Style::Calligraphy(options)
+            }
+            BrushStyle::Pencil => {
+                let options = self.pencil_options.clone();
+
+                Style::Pencil(options)
+            }
         }
     }
 }
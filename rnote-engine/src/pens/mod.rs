@@ -3,6 +3,7 @@ pub mod eraser;
 pub mod penbehaviour;
 pub mod penholder;
 pub mod penmode;
+pub mod presets;
 pub mod selector;
 pub mod shaper;
 pub mod shortcuts;
@@ -15,6 +16,7 @@ pub use eraser::Eraser;
 pub use penbehaviour::PenBehaviour;
 pub use penholder::PenHolder;
 pub use penmode::PenMode;
+pub use presets::Presets;
 pub use selector::Selector;
 pub use shaper::Shaper;
 pub use shortcuts::Shortcuts;
@@ -1,4 +1,5 @@
 use crate::engine::{EngineView, EngineViewMut};
+use crate::pens::presets::{Preset, PresetConfig};
 use crate::pens::shortcuts::ShortcutAction;
 use crate::pens::Tools;
 
@@ -13,7 +14,9 @@ use serde::{Deserialize, Serialize};
 
 use super::penbehaviour::PenProgress;
 use super::penmode::PenModeState;
-use super::{Brush, Eraser, PenBehaviour, PenMode, Selector, Shaper, Shortcuts, Typewriter};
+use super::{
+    Brush, Eraser, PenBehaviour, PenMode, Presets, Selector, Shaper, Shortcuts, Typewriter,
+};
 
 #[derive(
     Eq,
@@ -118,6 +121,8 @@ pub struct PenHolder {
     pen_mode_state: PenModeState,
     #[serde(rename = "shortcuts")]
     shortcuts: Shortcuts,
+    #[serde(rename = "presets")]
+    presets: Presets,
 
     #[serde(skip)]
     pen_progress: PenProgress,
@@ -134,6 +139,7 @@ impl Default for PenHolder {
             tools: Tools::default(),
             pen_mode_state: PenModeState::default(),
             shortcuts: Shortcuts::default(),
+            presets: Presets::default(),
 
             pen_progress: PenProgress::Idle,
         }
@@ -160,10 +166,82 @@ impl PenHolder {
     pub fn list_current_shortcuts(&self) -> Vec<(ShortcutKey, ShortcutAction)> {
         self.shortcuts
             .iter()
-            .map(|(key, action)| (*key, *action))
+            .map(|(key, action)| (*key, action.clone()))
             .collect()
     }
 
+    /// Saves the current brush configuration as preset with the given name. Replaces an existing preset with the same name
+    pub fn save_brush_as_preset(&mut self, name: String) {
+        let config = PresetConfig::from_brush(&self.brush);
+        self.presets.insert(Preset { name, config });
+    }
+
+    /// Saves the current shaper configuration as preset with the given name. Replaces an existing preset with the same name
+    pub fn save_shaper_as_preset(&mut self, name: String) {
+        let config = PresetConfig::from_shaper(&self.shaper);
+        self.presets.insert(Preset { name, config });
+    }
+
+    /// Removes the preset with the given name and the shortcuts that apply it. Returns false if it did not exist
+    pub fn remove_preset(&mut self, name: &str) -> bool {
+        self.shortcuts.retain(|_, action| {
+            !matches!(action, ShortcutAction::ApplyPreset { name: preset_name } if preset_name == name)
+        });
+
+        self.presets.remove(name).is_some()
+    }
+
+    /// Lists the names of the saved presets and the pen style they belong to
+    pub fn list_presets(&self) -> Vec<(String, PenStyle)> {
+        self.presets
+            .iter()
+            .map(|preset| (preset.name.clone(), preset.config.pen_style()))
+            .collect()
+    }
+
+    /// Exports all presets as JSON string
+    pub fn export_presets_as_json(&self) -> anyhow::Result<String> {
+        self.presets.export_as_json()
+    }
+
+    /// Imports presets from a JSON string. Returns the number of imported presets
+    pub fn import_presets_from_json(&mut self, json: &str) -> anyhow::Result<usize> {
+        self.presets.import_from_json(json)
+    }
+
+    /// Applies the preset with the given name to its pen and changes to it
+    pub fn apply_preset(
+        &mut self,
+        name: &str,
+        engine_view: &mut EngineViewMut,
+    ) -> anyhow::Result<WidgetFlags> {
+        let config = self
+            .presets
+            .get(name)
+            .ok_or_else(|| anyhow::anyhow!("there is no preset with name `{}`", name))?
+            .config
+            .clone();
+        let mut widget_flags = WidgetFlags::default();
+
+        // Cancel the current pen, as its state is reset by loading the config
+        widget_flags.merge_with_other(self.handle_pen_event(PenEvent::Cancel, None, engine_view));
+
+        let pen_style = config.pen_style();
+        match config {
+            PresetConfig::Brush(brush) => {
+                self.brush = brush;
+            }
+            PresetConfig::Shaper(shaper) => {
+                self.shaper = shaper;
+            }
+        }
+
+        widget_flags.merge_with_other(self.change_style(pen_style, engine_view));
+        widget_flags.refresh_ui = true;
+
+        Ok(widget_flags)
+    }
+
     /// Gets the current style, or the override if it is set.
     pub fn current_style_w_override(&self) -> PenStyle {
         self.pen_mode_state.current_style_w_override()
@@ -381,6 +459,21 @@ impl PenHolder {
                         );
                     }
                 }
+                ShortcutAction::ApplyPreset { name } => {
+                    match self.apply_preset(&name, engine_view) {
+                        Ok(other_widget_flags) => {
+                            widget_flags.merge_with_other(other_widget_flags);
+                        }
+                        Err(e) => {
+                            log::error!(
+                                "applying preset `{}` for shortcut key {:?} failed, Err {}",
+                                name,
+                                shortcut_key,
+                                e
+                            );
+                        }
+                    }
+                }
             }
         }
 
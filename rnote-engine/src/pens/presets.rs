@@ -0,0 +1,218 @@
+use super::penholder::PenStyle;
+use super::{Brush, Shaper};
+use std::ops::{Deref, DerefMut};
+
+use serde::{Deserialize, Serialize};
+
+/// The captured configuration of a pen
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename = "preset_config")]
+pub enum PresetConfig {
+    #[serde(rename = "brush")]
+    Brush(Brush),
+    #[serde(rename = "shaper")]
+    Shaper(Shaper),
+}
+
+impl Clone for PresetConfig {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Brush(brush) => Self::Brush(brush.clone_config()),
+            Self::Shaper(shaper) => Self::Shaper(shaper.clone_config()),
+        }
+    }
+}
+
+impl PresetConfig {
+    /// Captures the configuration of the brush
+    pub fn from_brush(brush: &Brush) -> Self {
+        Self::Brush(brush.clone_config())
+    }
+
+    /// Captures the configuration of the shaper
+    pub fn from_shaper(shaper: &Shaper) -> Self {
+        Self::Shaper(shaper.clone_config())
+    }
+
+    /// The pen style the configuration belongs to
+    pub fn pen_style(&self) -> PenStyle {
+        match self {
+            Self::Brush(_) => PenStyle::Brush,
+            Self::Shaper(_) => PenStyle::Shaper,
+        }
+    }
+}
+
+/// A named pen configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename = "preset")]
+pub struct Preset {
+    #[serde(rename = "name")]
+    pub name: String,
+    #[serde(rename = "config")]
+    pub config: PresetConfig,
+}
+
+/// holds the saved presets, in the order they were added
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename = "presets")]
+pub struct Presets(Vec<Preset>);
+
+impl Presets {
+    /// Inserts the preset. Replaces the preset with the same name, if there is one
+    pub fn insert(&mut self, preset: Preset) {
+        if let Some(existing) = self.0.iter_mut().find(|p| p.name == preset.name) {
+            *existing = preset;
+        } else {
+            self.0.push(preset);
+        }
+    }
+
+    /// Removes the preset with the given name, if it exists
+    pub fn remove(&mut self, name: &str) -> Option<Preset> {
+        let index = self.0.iter().position(|p| p.name == name)?;
+        Some(self.0.remove(index))
+    }
+
+    /// Gets the preset with the given name
+    pub fn get(&self, name: &str) -> Option<&Preset> {
+        self.0.iter().find(|p| p.name == name)
+    }
+
+    /// Exports the presets as JSON string, to be shared between installations
+    pub fn export_as_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Imports presets from a JSON string that was exported with `export_as_json()`.
+    /// Imported presets replace existing presets with the same name. Returns the number of imported presets
+    pub fn import_from_json(&mut self, json: &str) -> anyhow::Result<usize> {
+        let imported = serde_json::from_str::<Presets>(json)?;
+        let n_imported = imported.len();
+
+        for preset in imported.0 {
+            self.insert(preset);
+        }
+
+        Ok(n_imported)
+    }
+}
+
+impl Deref for Presets {
+    type Target = Vec<Preset>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Presets {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pens::brush::BrushStyle;
+    use crate::pens::shaper::ShaperStyle;
+    use rnote_compose::builders::ShapeBuilderType;
+    use rnote_compose::Color;
+
+    fn presets() -> Presets {
+        let mut brush = Brush::default();
+        brush.style = BrushStyle::Textured;
+        brush.textured_options.stroke_width = 12.0;
+        brush.textured_options.stroke_color = Some(Color::RED);
+
+        let mut shaper = Shaper::default();
+        shaper.style = ShaperStyle::Rough;
+        shaper.builder_type = ShapeBuilderType::Ellipse;
+        shaper.rough_options.stroke_width = 5.0;
+
+        let mut presets = Presets::default();
+        presets.insert(Preset {
+            name: String::from("textured red"),
+            config: PresetConfig::from_brush(&brush),
+        });
+        presets.insert(Preset {
+            name: String::from("rough ellipse"),
+            config: PresetConfig::from_shaper(&shaper),
+        });
+        presets
+    }
+
+    #[test]
+    fn export_import_round_trip() {
+        let exported = presets().export_as_json().unwrap();
+
+        let mut imported = Presets::default();
+        assert_eq!(imported.import_from_json(&exported).unwrap(), 2);
+        assert_eq!(
+            imported
+                .iter()
+                .map(|preset| preset.name.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["textured red", "rough ellipse"]
+        );
+
+        match &imported.get("textured red").unwrap().config {
+            PresetConfig::Brush(brush) => {
+                assert!(matches!(brush.style, BrushStyle::Textured));
+                assert_eq!(brush.textured_options.stroke_width, 12.0);
+                assert_eq!(
+                    brush
+                        .textured_options
+                        .stroke_color
+                        .map(|color| (color.r, color.g, color.b, color.a)),
+                    Some((Color::RED.r, Color::RED.g, Color::RED.b, Color::RED.a))
+                );
+            }
+            PresetConfig::Shaper(_) => panic!("imported brush preset is a shaper preset"),
+        }
+        match &imported.get("rough ellipse").unwrap().config {
+            PresetConfig::Shaper(shaper) => {
+                assert!(matches!(shaper.style, ShaperStyle::Rough));
+                assert!(matches!(shaper.builder_type, ShapeBuilderType::Ellipse));
+                assert_eq!(shaper.rough_options.stroke_width, 5.0);
+            }
+            PresetConfig::Brush(_) => panic!("imported shaper preset is a brush preset"),
+        }
+
+        // exporting again gives the same JSON
+        assert_eq!(imported.export_as_json().unwrap(), exported);
+    }
+
+    #[test]
+    fn import_replaces_presets_with_same_name() {
+        let mut presets = presets();
+        let mut other = Presets::default();
+        other.insert(Preset {
+            name: String::from("rough ellipse"),
+            config: PresetConfig::from_brush(&Brush::default()),
+        });
+
+        presets
+            .import_from_json(&other.export_as_json().unwrap())
+            .unwrap();
+
+        assert_eq!(presets.len(), 2);
+        assert_eq!(
+            presets.get("rough ellipse").unwrap().config.pen_style(),
+            PenStyle::Brush
+        );
+    }
+
+    #[test]
+    fn import_invalid_json_keeps_presets() {
+        let mut presets = presets();
+
+        assert!(presets
+            .import_from_json(r#"[{"name": "broken", "config": {"brush": 3}}]"#)
+            .is_err());
+        assert!(presets.import_from_json("not json").is_err());
+        assert_eq!(presets.len(), 2);
+        assert!(presets.get("broken").is_none());
+    }
+}
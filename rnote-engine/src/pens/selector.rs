@@ -1,7 +1,9 @@
 use super::penbehaviour::{PenBehaviour, PenProgress};
+use crate::document::snap::SnapGuides;
 use crate::engine::{EngineView, EngineViewMut};
 use crate::store::StrokeKey;
-use crate::{Camera, DrawOnDocBehaviour, WidgetFlags};
+use crate::strokes::Stroke;
+use crate::{Camera, DrawOnDocBehaviour, StrokeStore, WidgetFlags};
 use kurbo::Shape;
 use p2d::query::PointQuery;
 use piet::RenderContext;
@@ -9,9 +11,9 @@ use rnote_compose::helpers::{AABBHelpers, Vector2Helpers};
 use rnote_compose::penhelpers::{KeyboardKey, PenState};
 use rnote_compose::penhelpers::{PenEvent, ShortcutKey};
 use rnote_compose::penpath::Element;
-use rnote_compose::shapes::ShapeBehaviour;
+use rnote_compose::shapes::{BezierPath, PathNode, Shape, ShapeBehaviour};
 use rnote_compose::style::drawhelpers;
-use rnote_compose::{color, Color};
+use rnote_compose::{color, Color, Style};
 
 use p2d::bounding_volume::{BoundingSphere, BoundingVolume, AABB};
 use serde::{Deserialize, Serialize};
@@ -29,7 +31,8 @@ pub(super) enum ModifyState {
     Up,
     Translate {
         start_pos: na::Vector2<f64>,
-        current_pos: na::Vector2<f64>,
+        start_bounds: AABB,
+        snap_guides: SnapGuides,
     },
     Rotate {
         rotation_center: na::Point2<f64>,
@@ -40,6 +43,7 @@ pub(super) enum ModifyState {
         from_corner: ResizeCorner,
         start_bounds: AABB,
         start_pos: na::Vector2<f64>,
+        snap_guides: SnapGuides,
     },
 }
 
@@ -60,6 +64,12 @@ pub(super) enum SelectorState {
         selection: Vec<StrokeKey>,
         selection_bounds: AABB,
     },
+    /// Editing the nodes of a path shape stroke
+    EditNodes {
+        key: StrokeKey,
+        /// the index of the node which is currently dragged
+        dragged_node: Option<usize>,
+    },
 }
 
 impl Default for SelectorState {
@@ -223,7 +233,6 @@ impl PenBehaviour for Selector {
                                     engine_view.camera.viewport(),
                                 );
                             if !new_keys.is_empty() {
-                                engine_view.store.set_selected_keys(&new_keys, true);
                                 Some(new_keys)
                             } else {
                                 None
@@ -241,7 +250,6 @@ impl PenBehaviour for Selector {
                                 engine_view.camera.viewport(),
                             );
                             if !new_keys.is_empty() {
-                                engine_view.store.set_selected_keys(&new_keys, true);
                                 Some(new_keys)
                             } else {
                                 None
@@ -258,10 +266,7 @@ impl PenBehaviour for Selector {
                                     engine_view.camera.viewport(),
                                     last.pos,
                                 )
-                                .map(|stroke_key| {
-                                    engine_view.store.set_selected(stroke_key, true);
-                                    vec![stroke_key]
-                                })
+                                .map(|stroke_key| vec![stroke_key])
                         } else {
                             None
                         }
@@ -276,9 +281,6 @@ impl PenBehaviour for Selector {
                                     engine_view.camera.viewport(),
                                 );
                             if !intersecting_keys.is_empty() {
-                                engine_view
-                                    .store
-                                    .set_selected_keys(&intersecting_keys, true);
                                 Some(intersecting_keys)
                             } else {
                                 None
@@ -286,6 +288,10 @@ impl PenBehaviour for Selector {
                         }
                     }
                 } {
+                    // Selecting a member of a group selects the entire group
+                    let selection = engine_view.store.keys_with_group_members(&selection);
+                    engine_view.store.set_selected_keys(&selection, true);
+
                     if let Some(selection_bounds) = engine_view.store.bounds_for_strokes(&selection)
                     {
                         // Change to the modifiy state
@@ -396,10 +402,12 @@ impl PenBehaviour for Selector {
                                 }
                             })
                         {
-                            // If we click on another stroke while in apiece style or while pressing Shift, we add it to the selection
-                            engine_view.store.set_selected(key_to_add, true);
+                            // If we click on another stroke while in apiece style or while pressing Shift, we add it ( and its group members ) to the selection
+                            let keys_to_add =
+                                engine_view.store.keys_with_group_members(&[key_to_add]);
+                            engine_view.store.set_selected_keys(&keys_to_add, true);
 
-                            selection.push(key_to_add);
+                            selection.extend(keys_to_add);
                             engine_view
                                 .store
                                 .bounds_for_strokes(selection)
@@ -430,6 +438,7 @@ impl PenBehaviour for Selector {
                                 from_corner: ResizeCorner::TopLeft,
                                 start_bounds: *selection_bounds,
                                 start_pos: element.pos,
+                                snap_guides: SnapGuides::default(),
                             }
                         } else if Self::resize_node_bounds(
                             ResizeCorner::TopRight,
@@ -442,6 +451,7 @@ impl PenBehaviour for Selector {
                                 from_corner: ResizeCorner::TopRight,
                                 start_bounds: *selection_bounds,
                                 start_pos: element.pos,
+                                snap_guides: SnapGuides::default(),
                             }
                         } else if Self::resize_node_bounds(
                             ResizeCorner::BottomLeft,
@@ -454,6 +464,7 @@ impl PenBehaviour for Selector {
                                 from_corner: ResizeCorner::BottomLeft,
                                 start_bounds: *selection_bounds,
                                 start_pos: element.pos,
+                                snap_guides: SnapGuides::default(),
                             }
                         } else if Self::resize_node_bounds(
                             ResizeCorner::BottomRight,
@@ -466,6 +477,7 @@ impl PenBehaviour for Selector {
                                 from_corner: ResizeCorner::BottomRight,
                                 start_bounds: *selection_bounds,
                                 start_pos: element.pos,
+                                snap_guides: SnapGuides::default(),
                             }
                         } else if selection_bounds
                             .contains_local_point(&na::Point2::from(element.pos))
@@ -473,7 +485,8 @@ impl PenBehaviour for Selector {
                             // clicking inside the selection bounds, triggering translation
                             *modify_state = ModifyState::Translate {
                                 start_pos: element.pos,
-                                current_pos: element.pos,
+                                start_bounds: *selection_bounds,
+                                snap_guides: SnapGuides::default(),
                             };
                         } else {
                             // If clicking outside the selection bounds, reset
@@ -484,10 +497,21 @@ impl PenBehaviour for Selector {
                         }
                     }
                     ModifyState::Translate {
-                        start_pos: _,
-                        current_pos,
+                        start_pos,
+                        start_bounds,
+                        snap_guides,
                     } => {
-                        let offset = element.pos - *current_pos;
+                        let unsnapped_offset = element.pos - *start_pos;
+                        let (snap_offset, new_snap_guides) = engine_view.doc.snap_bounds_offset(
+                            start_bounds.translate(unsnapped_offset),
+                            engine_view.store,
+                            engine_view.camera,
+                        );
+                        *snap_guides = new_snap_guides;
+
+                        // the offset relative to the already applied translation
+                        let offset = unsnapped_offset + snap_offset
+                            - (selection_bounds.mins.coords - start_bounds.mins.coords);
 
                         if offset.magnitude()
                             > Self::TRANSLATE_MAGNITUDE_THRESHOLD / engine_view.camera.total_zoom()
@@ -505,8 +529,6 @@ impl PenBehaviour for Selector {
                                 engine_view.camera.viewport(),
                                 engine_view.camera.image_scale(),
                             );
-
-                            *current_pos = element.pos;
                         }
                     }
                     ModifyState::Rotate {
@@ -543,9 +565,39 @@ impl PenBehaviour for Selector {
                         from_corner,
                         start_bounds,
                         start_pos,
+                        snap_guides,
                     } => {
+                        let lock_aspectratio = self.resize_lock_aspectratio
+                            || shortcut_keys.contains(&ShortcutKey::KeyboardCtrl);
+
                         let (pos_offset, pivot) = {
-                            let pos_offset = element.pos - *start_pos;
+                            let start_corner_pos = match from_corner {
+                                ResizeCorner::TopLeft => start_bounds.mins.coords,
+                                ResizeCorner::TopRight => na::vector![
+                                    start_bounds.maxs.coords[0],
+                                    start_bounds.mins.coords[1]
+                                ],
+                                ResizeCorner::BottomLeft => na::vector![
+                                    start_bounds.mins.coords[0],
+                                    start_bounds.maxs.coords[1]
+                                ],
+                                ResizeCorner::BottomRight => start_bounds.maxs.coords,
+                            };
+                            let mut pos_offset = element.pos - *start_pos;
+
+                            // Snapping the dragged corner would be overridden when the aspectratio is locked
+                            if lock_aspectratio {
+                                *snap_guides = SnapGuides::default();
+                            } else {
+                                let (snapped_corner_pos, new_snap_guides) =
+                                    engine_view.doc.snap_pos(
+                                        start_corner_pos + pos_offset,
+                                        engine_view.store,
+                                        engine_view.camera,
+                                    );
+                                *snap_guides = new_snap_guides;
+                                pos_offset = snapped_corner_pos - start_corner_pos;
+                            }
 
                             match from_corner {
                                 ResizeCorner::TopLeft => (-pos_offset, start_bounds.maxs.coords),
@@ -567,9 +619,7 @@ impl PenBehaviour for Selector {
                             }
                         };
 
-                        let new_extents = if self.resize_lock_aspectratio
-                            || shortcut_keys.contains(&ShortcutKey::KeyboardCtrl)
-                        {
+                        let new_extents = if lock_aspectratio {
                             // Lock aspectratio
                             rnote_compose::helpers::scale_w_locked_aspectratio(
                                 start_bounds.extents(),
@@ -723,6 +773,91 @@ impl PenBehaviour for Selector {
                 widget_flags.resize = true;
                 widget_flags.indicate_changed_store = true;
 
+                PenProgress::Finished
+            }
+            (SelectorState::EditNodes { key, dragged_node }, PenEvent::Down { element, .. }) => {
+                let key = *key;
+                let total_zoom = engine_view.camera.total_zoom();
+
+                match *dragged_node {
+                    Some(index) => {
+                        if let Some(path) = Self::edited_path_mut(engine_view.store, key) {
+                            path.move_node(index, element.pos);
+                        }
+
+                        if let Err(e) = engine_view.store.regenerate_rendering_for_stroke(
+                            key,
+                            engine_view.camera.viewport(),
+                            engine_view.camera.image_scale(),
+                        ) {
+                            log::error!("regenerate_rendering_for_stroke() failed while dragging a path node, Err {}", e);
+                        }
+                    }
+                    None => {
+                        let hit_node = Self::edited_path(engine_view.store, key).and_then(|path| {
+                            // the last node is drawn on top, so it is hit first
+                            path.nodes().iter().rposition(|node| {
+                                (node.pos() - element.pos).magnitude()
+                                    < Self::PATH_NODE_SIZE * 0.5 / total_zoom
+                            })
+                        });
+
+                        if let Some(index) = hit_node {
+                            widget_flags.merge_with_other(engine_view.store.record());
+                            *dragged_node = Some(index);
+                        } else {
+                            // Clicking beside the nodes finishes editing, the stroke stays selected
+                            self.update_internal_state(&engine_view.as_im());
+                        }
+                    }
+                }
+
+                widget_flags.redraw = true;
+                widget_flags.indicate_changed_store = true;
+
+                PenProgress::InProgress
+            }
+            (SelectorState::EditNodes { key, dragged_node }, PenEvent::Up { .. }) => {
+                if dragged_node.take().is_some() {
+                    engine_view.store.update_geometry_for_stroke(*key);
+                    engine_view.store.regenerate_rendering_in_viewport_threaded(
+                        engine_view.tasks_tx.clone(),
+                        false,
+                        engine_view.camera.viewport(),
+                        engine_view.camera.image_scale(),
+                    );
+
+                    engine_view
+                        .doc
+                        .resize_autoexpand(engine_view.store, engine_view.camera);
+
+                    widget_flags.redraw = true;
+                    widget_flags.resize = true;
+                    widget_flags.indicate_changed_store = true;
+                }
+
+                PenProgress::InProgress
+            }
+            (SelectorState::EditNodes { .. }, PenEvent::Proximity { .. }) => {
+                PenProgress::InProgress
+            }
+            (SelectorState::EditNodes { .. }, PenEvent::KeyPressed { keyboard_key, .. }) => {
+                // Escape finishes editing, the stroke stays selected
+                if keyboard_key == KeyboardKey::Escape {
+                    self.update_internal_state(&engine_view.as_im());
+
+                    widget_flags.redraw = true;
+                }
+
+                PenProgress::InProgress
+            }
+            (SelectorState::EditNodes { key, .. }, PenEvent::Cancel) => {
+                engine_view.store.set_selected(*key, false);
+                self.state = SelectorState::Idle;
+
+                widget_flags.redraw = true;
+                widget_flags.indicate_changed_store = true;
+
                 PenProgress::Finished
             }
         };
@@ -774,8 +909,34 @@ impl DrawOnDocBehaviour for Selector {
                 }
             }
             SelectorState::ModifySelection {
-                selection_bounds, ..
-            } => Some(selection_bounds.extend_by(Self::RESIZE_NODE_SIZE / total_zoom)),
+                modify_state,
+                selection_bounds,
+                ..
+            } => {
+                let bounds = selection_bounds.extend_by(Self::RESIZE_NODE_SIZE / total_zoom);
+
+                match modify_state {
+                    ModifyState::Translate { snap_guides, .. }
+                    | ModifyState::Resize { snap_guides, .. }
+                        if !snap_guides.is_empty() =>
+                    {
+                        // The guides span the entire viewport
+                        Some(bounds.merged(&engine_view.camera.viewport()))
+                    }
+                    _ => Some(bounds),
+                }
+            }
+            SelectorState::EditNodes { key, .. } => {
+                let path = Self::edited_path(engine_view.store, *key)?;
+
+                // control points can lie outside of the path bounds
+                let mut bounds = path.bounds();
+                for node in path.nodes() {
+                    bounds.take_point(na::Point2::from(node.pos()));
+                }
+
+                Some(bounds.loosened(Self::PATH_NODE_SIZE / total_zoom))
+            }
         }
     }
 
@@ -922,9 +1083,18 @@ impl DrawOnDocBehaviour for Selector {
                             engine_view.camera,
                         )?;
                     }
+                    ModifyState::Translate { snap_guides, .. }
+                    | ModifyState::Resize { snap_guides, .. } => {
+                        snap_guides.draw(cx, engine_view.camera);
+                    }
                     _ => {}
                 }
             }
+            SelectorState::EditNodes { key, dragged_node } => {
+                if let Some(path) = Self::edited_path(engine_view.store, *key) {
+                    Self::draw_path_nodes(cx, path, *dragged_node, engine_view.camera);
+                }
+            }
         }
 
         cx.restore().map_err(|e| anyhow::anyhow!("{}", e))?;
@@ -949,6 +1119,76 @@ impl Selector {
     const RESIZE_NODE_SIZE: na::Vector2<f64> = na::vector![18.0, 18.0];
     /// rotate node size, in surface coords
     const ROTATE_NODE_SIZE: f64 = 18.0;
+    /// path node size when editing nodes, in surface coords
+    const PATH_NODE_SIZE: f64 = 12.0;
+
+    /// Starts editing the nodes of the selected shape stroke. Shapes which are not a path are converted to one.
+    ///
+    /// Only possible when exactly one shape stroke is selected and its style is not textured, as paths can't be drawn textured.
+    pub fn start_editing_nodes(&mut self, engine_view: &mut EngineViewMut) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+
+        let key = match engine_view.store.selection_keys_as_rendered()[..] {
+            [key] => key,
+            _ => return widget_flags,
+        };
+        let needs_conversion = match engine_view.store.get_stroke_ref(key) {
+            Some(Stroke::ShapeStroke(shapestroke))
+                if !matches!(
+                    shapestroke.style,
+                    Style::Textured(_) | Style::Calligraphy(_) | Style::Pencil(_)
+                ) =>
+            {
+                !matches!(shapestroke.shape, Shape::Path(_))
+            }
+            _ => return widget_flags,
+        };
+
+        if needs_conversion {
+            widget_flags.merge_with_other(engine_view.store.record());
+
+            if let Some(Stroke::ShapeStroke(shapestroke)) = engine_view.store.get_stroke_mut(key) {
+                shapestroke.shape = Shape::Path(shapestroke.shape.to_bezier_path());
+            }
+            engine_view.store.update_geometry_for_stroke(key);
+            engine_view.store.regenerate_rendering_in_viewport_threaded(
+                engine_view.tasks_tx.clone(),
+                false,
+                engine_view.camera.viewport(),
+                engine_view.camera.image_scale(),
+            );
+
+            widget_flags.indicate_changed_store = true;
+        }
+
+        self.state = SelectorState::EditNodes {
+            key,
+            dragged_node: None,
+        };
+        widget_flags.redraw = true;
+
+        widget_flags
+    }
+
+    fn edited_path(store: &StrokeStore, key: StrokeKey) -> Option<&BezierPath> {
+        match store.get_stroke_ref(key) {
+            Some(Stroke::ShapeStroke(shapestroke)) => match &shapestroke.shape {
+                Shape::Path(path) => Some(path),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn edited_path_mut(store: &mut StrokeStore, key: StrokeKey) -> Option<&mut BezierPath> {
+        match store.get_stroke_mut(key) {
+            Some(Stroke::ShapeStroke(shapestroke)) => match &mut shapestroke.shape {
+                Shape::Path(path) => Some(path),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
 
     fn add_to_select_path(style: SelectorStyle, path: &mut Vec<Element>, element: Element) {
         match style {
@@ -1151,6 +1391,53 @@ impl Selector {
         Ok(())
     }
 
+    fn draw_path_nodes(
+        piet_cx: &mut impl RenderContext,
+        path: &BezierPath,
+        dragged_node: Option<usize>,
+        camera: &Camera,
+    ) {
+        let total_zoom = camera.total_zoom();
+        let nodes = path.nodes();
+        let node_state = |index: usize| {
+            if dragged_node == Some(index) {
+                PenState::Down
+            } else {
+                PenState::Up
+            }
+        };
+
+        // the handles from the control points to their anchors
+        for (i, node) in nodes.iter().enumerate() {
+            if let PathNode::Control { pos, anchor } = *node {
+                drawhelpers::draw_vec_indicator(piet_cx, node_state(i), anchor, pos, total_zoom);
+            }
+        }
+
+        for (i, node) in nodes.iter().enumerate() {
+            match *node {
+                PathNode::Anchor { pos } => drawhelpers::draw_rectangular_node(
+                    piet_cx,
+                    node_state(i),
+                    AABB::from_half_extents(
+                        na::Point2::from(pos),
+                        na::Vector2::repeat(Self::PATH_NODE_SIZE * 0.5 / total_zoom),
+                    ),
+                    total_zoom,
+                ),
+                PathNode::Control { pos, .. } => drawhelpers::draw_circular_node(
+                    piet_cx,
+                    node_state(i),
+                    BoundingSphere::new(
+                        na::Point2::from(pos),
+                        Self::PATH_NODE_SIZE * 0.5 / total_zoom,
+                    ),
+                    total_zoom,
+                ),
+            }
+        }
+    }
+
     fn draw_rotation_indicator(
         piet_cx: &mut impl RenderContext,
         rotation_center: na::Point2<f64>,
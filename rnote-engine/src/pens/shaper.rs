@@ -1,10 +1,11 @@
 use super::penbehaviour::{PenBehaviour, PenProgress};
+use crate::document::snap::SnapGuides;
 use crate::engine::{EngineView, EngineViewMut};
 use crate::strokes::ShapeStroke;
 use crate::strokes::Stroke;
 use crate::{DrawOnDocBehaviour, WidgetFlags};
 
-use p2d::bounding_volume::AABB;
+use p2d::bounding_volume::{BoundingVolume, AABB};
 use piet::RenderContext;
 use rand::{Rng, SeedableRng};
 use rnote_compose::builders::shapebuilderbehaviour::{BuilderProgress, ShapeBuilderCreator};
@@ -68,6 +69,8 @@ pub struct Shaper {
     pub constraints: Constraints,
     #[serde(skip)]
     state: ShaperState,
+    #[serde(skip)]
+    snap_guides: SnapGuides,
 }
 
 impl Default for Shaper {
@@ -84,6 +87,7 @@ impl Default for Shaper {
             rough_options,
             constraints: Constraints::default(),
             state: ShaperState::Idle,
+            snap_guides: SnapGuides::default(),
         }
     }
 }
@@ -96,6 +100,8 @@ impl PenBehaviour for Shaper {
     ) -> (PenProgress, WidgetFlags) {
         let mut widget_flags = WidgetFlags::default();
 
+        let event = self.snap_event(event, &engine_view.as_im());
+
         let pen_progress = match (&mut self.state, event) {
             (ShaperState::Idle, PenEvent::Down { element, .. }) => {
                 // A new seed for a new shape
@@ -142,6 +148,7 @@ impl PenBehaviour for Shaper {
             (ShaperState::Idle, _) => PenProgress::Idle,
             (ShaperState::BuildShape { .. }, PenEvent::Cancel) => {
                 self.state = ShaperState::Idle;
+                self.snap_guides = SnapGuides::default();
 
                 widget_flags.redraw = true;
                 PenProgress::Finished
@@ -228,6 +235,7 @@ impl PenBehaviour for Shaper {
                         }
 
                         self.state = ShaperState::Idle;
+                        self.snap_guides = SnapGuides::default();
 
                         widget_flags.redraw = true;
 
@@ -248,7 +256,15 @@ impl DrawOnDocBehaviour for Shaper {
         match &self.state {
             ShaperState::Idle => None,
             ShaperState::BuildShape { builder } => {
-                builder.bounds(&style, engine_view.camera.total_zoom())
+                let bounds = builder.bounds(&style, engine_view.camera.total_zoom());
+
+                if self.snap_guides.is_empty() {
+                    bounds
+                } else {
+                    // The guides span the entire viewport
+                    let viewport = engine_view.camera.viewport();
+                    Some(bounds.map_or(viewport, |bounds| bounds.merged(&viewport)))
+                }
             }
         }
     }
@@ -264,7 +280,8 @@ impl DrawOnDocBehaviour for Shaper {
         match &self.state {
             ShaperState::Idle => {}
             ShaperState::BuildShape { builder } => {
-                builder.draw_styled(cx, &style, engine_view.camera.total_zoom())
+                builder.draw_styled(cx, &style, engine_view.camera.total_zoom());
+                self.snap_guides.draw(cx, engine_view.camera);
             }
         }
 
@@ -280,6 +297,48 @@ impl Shaper {
     pub const STROKE_WIDTH_MAX: f64 = 500.0;
     pub const STROKE_WIDTH_DEFAULT: f64 = 2.0;
 
+    /// A copy of the configuration, without the current state
+    pub fn clone_config(&self) -> Self {
+        Self {
+            builder_type: self.builder_type,
+            style: self.style,
+            smooth_options: self.smooth_options.clone(),
+            rough_options: self.rough_options.clone(),
+            constraints: self.constraints.clone(),
+            state: ShaperState::Idle,
+            snap_guides: SnapGuides::default(),
+        }
+    }
+
+    /// Snaps the element position of the event to the enabled snap targets of the document.
+    /// Proximity events are only snapped while a shape is built, hovering over the document does not need the snap query.
+    fn snap_event(&mut self, mut event: PenEvent, engine_view: &EngineView) -> PenEvent {
+        let building = matches!(self.state, ShaperState::BuildShape { .. });
+
+        match &mut event {
+            PenEvent::Proximity { .. } if !building => {
+                self.snap_guides = SnapGuides::default();
+            }
+            PenEvent::Down { element, .. }
+            | PenEvent::Up { element, .. }
+            | PenEvent::Proximity { element, .. } => {
+                let (snapped_pos, snap_guides) =
+                    engine_view
+                        .doc
+                        .snap_pos(element.pos, engine_view.store, engine_view.camera);
+
+                element.pos = snapped_pos;
+                self.snap_guides = snap_guides;
+            }
+            PenEvent::KeyPressed { .. } => {}
+            PenEvent::Cancel => {
+                self.snap_guides = SnapGuides::default();
+            }
+        }
+
+        event
+    }
+
     pub fn gen_style_for_current_options(&self) -> Style {
         match &self.style {
             ShaperStyle::Smooth => {
@@ -6,7 +6,7 @@ use rnote_compose::penhelpers::ShortcutKey;
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 #[serde(rename = "shortcut_action")]
 pub enum ShortcutAction {
     #[serde(rename = "change_pen_style")]
@@ -16,6 +16,12 @@ pub enum ShortcutAction {
         #[serde(rename = "permanent")]
         permanent: bool,
     },
+    /// Applies the preset with the given name and changes to its pen
+    #[serde(rename = "apply_preset")]
+    ApplyPreset {
+        #[serde(rename = "name")]
+        name: String,
+    },
 }
 
 /// holds the registered shortcut actions for the given shortcut keys
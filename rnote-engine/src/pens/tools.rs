@@ -1,3 +1,4 @@
+use crate::document::FlowDirection;
 use crate::engine::{EngineView, EngineViewMut};
 use crate::store::StrokeKey;
 use crate::{DrawOnDocBehaviour, WidgetFlags};
@@ -11,29 +12,36 @@ use serde::{Deserialize, Serialize};
 
 use super::penbehaviour::{PenBehaviour, PenProgress};
 
+/// Inserts or removes space between the strokes. The space is vertical, or horizontal when the document layout flows horizontally
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(default, rename = "verticalspace_tool")]
-pub struct VerticalSpaceTool {
+pub struct SpaceTool {
     #[serde(skip)]
-    start_pos_y: f64,
+    flow_direction: FlowDirection,
+    /// The start position on the axis of the flow direction
     #[serde(skip)]
-    current_pos_y: f64,
+    start_pos: f64,
+    /// The current position on the axis of the flow direction
     #[serde(skip)]
-    strokes_below: Vec<StrokeKey>,
+    current_pos: f64,
+    /// The strokes below, or right of the start position
+    #[serde(skip)]
+    strokes_after: Vec<StrokeKey>,
 }
 
-impl Default for VerticalSpaceTool {
+impl Default for SpaceTool {
     fn default() -> Self {
         Self {
-            start_pos_y: 0.0,
-            current_pos_y: 0.0,
-            strokes_below: vec![],
+            flow_direction: FlowDirection::Vertical,
+            start_pos: 0.0,
+            current_pos: 0.0,
+            strokes_after: vec![],
         }
     }
 }
 
-impl VerticalSpaceTool {
-    const Y_OFFSET_THRESHOLD: f64 = 0.1;
+impl SpaceTool {
+    const OFFSET_THRESHOLD: f64 = 0.1;
 
     const FILL_COLOR: piet::Color = color::GNOME_BRIGHTS[2].with_a8(0x17);
     const THRESHOLD_LINE_COLOR: piet::Color = color::GNOME_GREENS[4].with_a8(0xf0);
@@ -41,19 +49,33 @@ impl VerticalSpaceTool {
 
     const THRESHOLD_LINE_WIDTH: f64 = 4.0;
     const OFFSET_LINE_WIDTH: f64 = 2.0;
+
+    // The line across the viewport at the position on the axis of the flow direction
+    fn line_across_viewport(&self, viewport: AABB, pos: f64) -> kurbo::Line {
+        match self.flow_direction {
+            FlowDirection::Vertical => kurbo::Line::new(
+                kurbo::Point::new(viewport.mins[0], pos),
+                kurbo::Point::new(viewport.maxs[0], pos),
+            ),
+            FlowDirection::Horizontal => kurbo::Line::new(
+                kurbo::Point::new(pos, viewport.mins[1]),
+                kurbo::Point::new(pos, viewport.maxs[1]),
+            ),
+        }
+    }
 }
 
-impl DrawOnDocBehaviour for VerticalSpaceTool {
+impl DrawOnDocBehaviour for SpaceTool {
     fn bounds_on_doc(&self, engine_view: &EngineView) -> Option<AABB> {
         let viewport = engine_view.camera.viewport();
+        let axis = self.flow_direction.axis();
 
-        let x = viewport.mins[0];
-        let y = self.start_pos_y;
-        let width = viewport.extents()[0];
-        let height = self.current_pos_y - self.start_pos_y;
-        let tool_bounds = AABB::new_positive(na::point![x, y], na::point![x + width, y + height]);
+        let mut mins = viewport.mins;
+        let mut maxs = viewport.maxs;
+        mins[axis] = self.start_pos;
+        maxs[axis] = self.current_pos;
 
-        Some(tool_bounds)
+        Some(AABB::new_positive(mins, maxs))
     }
 
     fn draw_on_doc(
@@ -64,20 +86,16 @@ impl DrawOnDocBehaviour for VerticalSpaceTool {
         cx.save().map_err(|e| anyhow::anyhow!("{}", e))?;
 
         let viewport = engine_view.camera.viewport();
-        let x = viewport.mins[0];
-        let y = self.start_pos_y;
-        let width = viewport.extents()[0];
-        let height = self.current_pos_y - self.start_pos_y;
-        let tool_bounds = AABB::new_positive(na::point![x, y], na::point![x + width, y + height]);
-
-        let tool_bounds_rect = kurbo::Rect::from_points(
-            tool_bounds.mins.coords.to_kurbo_point(),
-            tool_bounds.maxs.coords.to_kurbo_point(),
-        );
-        cx.fill(tool_bounds_rect, &Self::FILL_COLOR);
 
-        let threshold_line =
-            kurbo::Line::new(kurbo::Point::new(x, y), kurbo::Point::new(x + width, y));
+        if let Some(tool_bounds) = self.bounds_on_doc(engine_view) {
+            let tool_bounds_rect = kurbo::Rect::from_points(
+                tool_bounds.mins.coords.to_kurbo_point(),
+                tool_bounds.maxs.coords.to_kurbo_point(),
+            );
+            cx.fill(tool_bounds_rect, &Self::FILL_COLOR);
+        }
+
+        let threshold_line = self.line_across_viewport(viewport, self.start_pos);
 
         cx.stroke_styled(
             threshold_line,
@@ -86,10 +104,7 @@ impl DrawOnDocBehaviour for VerticalSpaceTool {
             &piet::StrokeStyle::new().dash_pattern(&[12.0, 6.0]),
         );
 
-        let offset_line = kurbo::Line::new(
-            kurbo::Point::new(x, y + height),
-            kurbo::Point::new(x + width, y + height),
-        );
+        let offset_line = self.line_across_viewport(viewport, self.current_pos);
         cx.stroke(
             offset_line,
             &Self::OFFSET_LINE_COLOR,
@@ -234,7 +249,7 @@ impl DrawOnDocBehaviour for OffsetCameraTool {
 #[serde(rename = "tools_style")]
 pub enum ToolsStyle {
     #[serde(rename = "verticalspace")]
-    VerticalSpace,
+    Space,
     #[serde(rename = "dragproximity")]
     DragProximity,
     #[serde(rename = "offsetcamera")]
@@ -243,7 +258,7 @@ pub enum ToolsStyle {
 
 impl Default for ToolsStyle {
     fn default() -> Self {
-        Self::VerticalSpace
+        Self::Space
     }
 }
 
@@ -274,8 +289,9 @@ impl Default for ToolsState {
 pub struct Tools {
     #[serde(rename = "style")]
     pub style: ToolsStyle,
+    // Keeps the name of the former vertical space tool, so that existing settings are loaded
     #[serde(rename = "verticalspace_tool")]
-    pub verticalspace_tool: VerticalSpaceTool,
+    pub space_tool: SpaceTool,
     #[serde(rename = "dragproximity_tool")]
     pub dragproximity_tool: DragProximityTool,
     #[serde(rename = "offsetcamera_tool")]
@@ -304,13 +320,18 @@ impl PenBehaviour for Tools {
                 widget_flags.merge_with_other(engine_view.store.record());
 
                 match self.style {
-                    ToolsStyle::VerticalSpace => {
-                        self.verticalspace_tool.start_pos_y = element.pos[1];
-                        self.verticalspace_tool.current_pos_y = element.pos[1];
-
-                        self.verticalspace_tool.strokes_below = engine_view
-                            .store
-                            .keys_below_y_pos(self.verticalspace_tool.current_pos_y);
+                    ToolsStyle::Space => {
+                        let flow_direction = engine_view.doc.layout().flow_direction();
+                        let pos = element.pos[flow_direction.axis()];
+
+                        self.space_tool.flow_direction = flow_direction;
+                        self.space_tool.start_pos = pos;
+                        self.space_tool.current_pos = pos;
+
+                        self.space_tool.strokes_after = match flow_direction {
+                            FlowDirection::Vertical => engine_view.store.keys_below_y_pos(pos),
+                            FlowDirection::Horizontal => engine_view.store.keys_right_of_x_pos(pos),
+                        };
                     }
                     ToolsStyle::DragProximity => {
                         self.dragproximity_tool.pos = element.pos;
@@ -343,20 +364,20 @@ impl PenBehaviour for Tools {
                 },
             ) => {
                 let pen_progress = match self.style {
-                    ToolsStyle::VerticalSpace => {
-                        let y_offset = element.pos[1] - self.verticalspace_tool.current_pos_y;
+                    ToolsStyle::Space => {
+                        let axis = self.space_tool.flow_direction.axis();
+                        let mut offset = na::Vector2::<f64>::zeros();
+                        offset[axis] = element.pos[axis] - self.space_tool.current_pos;
 
-                        if y_offset.abs() > VerticalSpaceTool::Y_OFFSET_THRESHOLD {
-                            engine_view.store.translate_strokes(
-                                &self.verticalspace_tool.strokes_below,
-                                na::vector![0.0, y_offset],
-                            );
-                            engine_view.store.translate_strokes_images(
-                                &self.verticalspace_tool.strokes_below,
-                                na::vector![0.0, y_offset],
-                            );
+                        if offset[axis].abs() > SpaceTool::OFFSET_THRESHOLD {
+                            engine_view
+                                .store
+                                .translate_strokes(&self.space_tool.strokes_after, offset);
+                            engine_view
+                                .store
+                                .translate_strokes_images(&self.space_tool.strokes_after, offset);
 
-                            self.verticalspace_tool.current_pos_y = element.pos[1];
+                            self.space_tool.current_pos = element.pos[axis];
                         }
 
                         PenProgress::InProgress
@@ -418,10 +439,10 @@ impl PenBehaviour for Tools {
             }
             (ToolsState::Active, PenEvent::Up { .. }) => {
                 match self.style {
-                    ToolsStyle::VerticalSpace => {
+                    ToolsStyle::Space => {
                         engine_view
                             .store
-                            .update_geometry_for_strokes(&self.verticalspace_tool.strokes_below);
+                            .update_geometry_for_strokes(&self.space_tool.strokes_after);
                     }
                     ToolsStyle::DragProximity => {}
                     ToolsStyle::OffsetCamera => {}
@@ -474,7 +495,7 @@ impl DrawOnDocBehaviour for Tools {
     fn bounds_on_doc(&self, engine_view: &EngineView) -> Option<AABB> {
         match self.state {
             ToolsState::Active => match self.style {
-                ToolsStyle::VerticalSpace => self.verticalspace_tool.bounds_on_doc(engine_view),
+                ToolsStyle::Space => self.space_tool.bounds_on_doc(engine_view),
                 ToolsStyle::DragProximity => self.dragproximity_tool.bounds_on_doc(engine_view),
                 ToolsStyle::OffsetCamera => self.offsetcamera_tool.bounds_on_doc(engine_view),
             },
@@ -490,8 +511,8 @@ impl DrawOnDocBehaviour for Tools {
         cx.save().map_err(|e| anyhow::anyhow!("{}", e))?;
 
         match &self.style {
-            ToolsStyle::VerticalSpace => {
-                self.verticalspace_tool.draw_on_doc(cx, engine_view)?;
+            ToolsStyle::Space => {
+                self.space_tool.draw_on_doc(cx, engine_view)?;
             }
             ToolsStyle::DragProximity => {
                 self.dragproximity_tool.draw_on_doc(cx, engine_view)?;
@@ -511,9 +532,9 @@ impl Tools {
         let current_style = self.style;
 
         match current_style {
-            ToolsStyle::VerticalSpace => {
-                self.verticalspace_tool.start_pos_y = 0.0;
-                self.verticalspace_tool.current_pos_y = 0.0;
+            ToolsStyle::Space => {
+                self.space_tool.start_pos = 0.0;
+                self.space_tool.current_pos = 0.0;
             }
             ToolsStyle::DragProximity => {
                 self.dragproximity_tool.pos = na::Vector2::zeros();
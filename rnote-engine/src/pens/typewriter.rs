@@ -10,6 +10,7 @@ use rnote_compose::{color, Transform};
 use serde::{Deserialize, Serialize};
 
 use crate::engine::{EngineView, EngineViewMut};
+use crate::spellcheck::SpellChecker;
 use crate::store::StrokeKey;
 use crate::strokes::textstroke::{RangedTextAttribute, TextAttribute, TextStyle};
 use crate::strokes::{Stroke, TextStroke};
@@ -64,6 +65,8 @@ pub struct Typewriter {
     pub max_width_enabled: bool,
     #[serde(rename = "text_width")]
     pub text_width: f64,
+    #[serde(rename = "spellchecker")]
+    pub spellchecker: SpellChecker,
 
     #[serde(skip)]
     state: TypewriterState,
@@ -75,6 +78,7 @@ impl Default for Typewriter {
             text_style: TextStyle::default(),
             max_width_enabled: true,
             text_width: 600.0,
+            spellchecker: SpellChecker::default(),
 
             state: TypewriterState::default(),
         }
@@ -165,6 +169,12 @@ impl DrawOnDocBehaviour for Typewriter {
 
                     cx.stroke(text_drawrect, &OUTLINE_COLOR, outline_width);
 
+                    // Draw the spell checking underlines
+                    textstroke.draw_misspelled_underlines(
+                        cx,
+                        &self.spellchecker.check(&textstroke.text),
+                    )?;
+
                     // Draw the cursor
                     textstroke.text_style.draw_cursor(
                         cx,
@@ -216,6 +226,12 @@ impl DrawOnDocBehaviour for Typewriter {
 
                     cx.stroke(text_drawrect, &OUTLINE_COLOR, outline_width);
 
+                    // Draw the spell checking underlines
+                    textstroke.draw_misspelled_underlines(
+                        cx,
+                        &self.spellchecker.check(&textstroke.text),
+                    )?;
+
                     // Draw the text selection
                     textstroke.text_style.draw_text_selection(
                         cx,
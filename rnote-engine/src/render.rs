@@ -626,6 +626,81 @@ impl Svg {
         }
     }
 
+    /// Generates an svg from the pdf page, painted white where the page has no content. The svg data is a complete svg document
+    /// and the bounds have the page size in popplers page units ( points ^= 1 / 72 inch ). Optionally draws an outline with the given color around the page.
+    pub fn gen_from_pdf_page(
+        page: &poppler::Page,
+        outline_color: Option<piet::Color>,
+    ) -> anyhow::Result<Self> {
+        let intrinsic_size = page.size();
+        let svg_stream: Vec<u8> = vec![];
+
+        let mut svg_surface =
+            cairo::SvgSurface::for_stream(intrinsic_size.0, intrinsic_size.1, svg_stream)
+                .map_err(|e| {
+                    anyhow::anyhow!(
+                    "create SvgSurface with dimensions ({}, {}) failed in svg gen_from_pdf_page() with Err {}",
+                    intrinsic_size.0,
+                    intrinsic_size.1,
+                    e
+                )
+                })?;
+
+        // Popplers page units are in points ( ^= 1 / 72 inch )
+        svg_surface.set_document_unit(cairo::SvgUnit::Pt);
+
+        {
+            let cx = cairo::Context::new(&svg_surface).map_err(|e| {
+                anyhow::anyhow!(
+                    "new cairo::Context failed in svg gen_from_pdf_page() with Err {}",
+                    e
+                )
+            })?;
+
+            // Set margin to white
+            cx.set_source_rgba(1.0, 1.0, 1.0, 1.0);
+            cx.paint()?;
+
+            // Render the poppler page
+            page.render(&cx);
+
+            if let Some(outline_color) = outline_color {
+                let (r, g, b, a) = outline_color.as_rgba();
+                cx.set_source_rgba(r, g, b, a);
+
+                let line_width = 1.0;
+                cx.set_line_width(line_width);
+                cx.rectangle(
+                    line_width * 0.5,
+                    line_width * 0.5,
+                    intrinsic_size.0 - line_width,
+                    intrinsic_size.1 - line_width,
+                );
+                cx.stroke()?;
+            }
+        }
+
+        let svg_data = String::from_utf8(
+            *svg_surface
+                .finish_output_stream()
+                .map_err(|e| anyhow::anyhow!("{}", e))?
+                .downcast::<Vec<u8>>()
+                .map_err(|_e| {
+                    anyhow::anyhow!(
+                        "failed to downcast svg surface content in svg gen_from_pdf_page()"
+                    )
+                })?,
+        )?;
+
+        Ok(Self {
+            svg_data,
+            bounds: AABB::new(
+                na::point![0.0, 0.0],
+                na::point![intrinsic_size.0, intrinsic_size.1],
+            ),
+        })
+    }
+
     /// Generates an svg with piet, using the piet_svg backend (context creation might be slow due to font loading).
     pub fn gen_with_piet_svg_backend<F>(draw_func: F, mut bounds: AABB) -> anyhow::Result<Self>
     where
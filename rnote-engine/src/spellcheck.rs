@@ -0,0 +1,677 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::Context;
+use encoding_rs::Encoding;
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The way the affix flags are encoded in the dictionary and affix files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FlagType {
+    /// a single (unicode) char per flag. This is the default
+    Char,
+    /// two chars per flag
+    Long,
+    /// comma separated decimal numbers
+    Num,
+}
+
+impl Default for FlagType {
+    fn default() -> Self {
+        Self::Char
+    }
+}
+
+impl FlagType {
+    fn parse_flags(self, flags: &str) -> Vec<String> {
+        match self {
+            FlagType::Char => flags.chars().map(String::from).collect(),
+            FlagType::Long => flags
+                .chars()
+                .collect::<Vec<char>>()
+                .chunks(2)
+                .map(|chunk| chunk.iter().collect())
+                .collect(),
+            FlagType::Num => flags
+                .split(',')
+                .map(|flag| flag.trim().to_string())
+                .filter(|flag| !flag.is_empty())
+                .collect(),
+        }
+    }
+}
+
+/// The encoding of the dictionary and affix files, declared in the affix file with the SET keyword. Defaults to UTF-8
+fn hunspell_encoding(aff_bytes: &[u8]) -> anyhow::Result<&'static Encoding> {
+    let name = String::from_utf8_lossy(aff_bytes).lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        if fields.next() == Some("SET") {
+            fields.next().map(|name| name.to_lowercase())
+        } else {
+            None
+        }
+    });
+
+    let name = match name {
+        Some(name) => name,
+        None => return Ok(encoding_rs::UTF_8),
+    };
+    // Hunspell uses some names that are not WHATWG labels
+    let label = match name.as_str() {
+        "microsoft-cp1251" => "windows-1251",
+        "tis620-2533" => "tis-620",
+        label => label,
+    };
+
+    Encoding::for_label(label.as_bytes())
+        .ok_or_else(|| anyhow::anyhow!("unsupported hunspell dictionary encoding `{}`", name))
+}
+
+/// A single element of a affix condition, which is a simplified regular expression
+#[derive(Debug, Clone)]
+enum ConditionElement {
+    Any,
+    Char(char),
+    Set { negated: bool, chars: Vec<char> },
+}
+
+impl ConditionElement {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            ConditionElement::Any => true,
+            ConditionElement::Char(cond_char) => *cond_char == c,
+            ConditionElement::Set { negated, chars } => chars.contains(&c) != *negated,
+        }
+    }
+
+    fn parse_condition(condition: &str) -> Vec<Self> {
+        let mut elements = vec![];
+        let mut chars = condition.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '.' => elements.push(ConditionElement::Any),
+                '[' => {
+                    let mut set_chars = vec![];
+                    let mut negated = false;
+
+                    for (i, set_char) in chars.by_ref().enumerate() {
+                        if set_char == ']' {
+                            break;
+                        } else if i == 0 && set_char == '^' {
+                            negated = true;
+                        } else {
+                            set_chars.push(set_char);
+                        }
+                    }
+
+                    elements.push(ConditionElement::Set {
+                        negated,
+                        chars: set_chars,
+                    });
+                }
+                c => elements.push(ConditionElement::Char(c)),
+            }
+        }
+
+        elements
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AffixKind {
+    Prefix,
+    Suffix,
+}
+
+#[derive(Debug, Clone)]
+struct AffixRule {
+    strip: String,
+    add: String,
+    condition: Vec<ConditionElement>,
+}
+
+impl AffixRule {
+    /// Applies the rule on the word for the given affix kind. Returns None if the rule does not match.
+    fn apply(&self, word: &str, kind: AffixKind) -> Option<String> {
+        let word_chars = word.chars().collect::<Vec<char>>();
+
+        if word_chars.len() < self.condition.len() {
+            return None;
+        }
+
+        match kind {
+            AffixKind::Prefix => {
+                if !self
+                    .condition
+                    .iter()
+                    .zip(word_chars.iter())
+                    .all(|(cond, &c)| cond.matches(c))
+                {
+                    return None;
+                }
+
+                let stem = word.strip_prefix(self.strip.as_str())?;
+                Some(format!("{}{}", self.add, stem))
+            }
+            AffixKind::Suffix => {
+                if !self
+                    .condition
+                    .iter()
+                    .rev()
+                    .zip(word_chars.iter().rev())
+                    .all(|(cond, &c)| cond.matches(c))
+                {
+                    return None;
+                }
+
+                let stem = word.strip_suffix(self.strip.as_str())?;
+                Some(format!("{}{}", stem, self.add))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct AffixClass {
+    kind: AffixKind,
+    cross_product: bool,
+    rules: Vec<AffixRule>,
+}
+
+/// A dictionary loaded from a Hunspell-format `.dic` and `.aff` file pair.
+/// Contains all word forms that can be generated from the dictionary stems and the prefix and suffix rules.
+/// Compounding and morphological analysis are not supported.
+#[derive(Debug, Clone, Default)]
+pub struct Dictionary {
+    words: HashSet<String>,
+}
+
+impl Dictionary {
+    /// Loads the dictionary from the contents of a `.dic` and `.aff` file.
+    pub fn from_hunspell_bytes(dic_bytes: &[u8], aff_bytes: &[u8]) -> anyhow::Result<Self> {
+        let encoding = hunspell_encoding(aff_bytes)?;
+        // Malformed sequences are replaced
+        let decode = |bytes: &[u8]| encoding.decode_without_bom_handling(bytes).0.into_owned();
+
+        let aff = decode(aff_bytes);
+        let dic = decode(dic_bytes);
+
+        let mut flag_type = FlagType::default();
+        let mut affix_classes = HashMap::<String, AffixClass>::new();
+        let mut forbidden_flag = None;
+        let mut needaffix_flag = None;
+
+        for line in aff.lines() {
+            let fields = line.split_whitespace().collect::<Vec<&str>>();
+
+            match fields.as_slice() {
+                ["FLAG", flag_type_str, ..] => {
+                    flag_type = match *flag_type_str {
+                        "long" => FlagType::Long,
+                        "num" => FlagType::Num,
+                        _ => FlagType::Char,
+                    };
+                }
+                ["FORBIDDENWORD", flag, ..] => forbidden_flag = Some(flag.to_string()),
+                ["NEEDAFFIX", flag, ..] => needaffix_flag = Some(flag.to_string()),
+                [kind @ ("PFX" | "SFX"), flag, cross_product, count]
+                    if count.parse::<usize>().is_ok() =>
+                {
+                    let kind = if *kind == "PFX" {
+                        AffixKind::Prefix
+                    } else {
+                        AffixKind::Suffix
+                    };
+
+                    affix_classes.insert(
+                        flag.to_string(),
+                        AffixClass {
+                            kind,
+                            cross_product: *cross_product == "Y",
+                            rules: vec![],
+                        },
+                    );
+                }
+                ["PFX" | "SFX", flag, strip, add, rest @ ..] => {
+                    if let Some(affix_class) = affix_classes.get_mut(*flag) {
+                        // continuation classes after the add string are not supported, so they are cut off
+                        let add = add.split('/').next().unwrap_or_default();
+                        let condition = rest.first().copied().unwrap_or(".");
+
+                        affix_class.rules.push(AffixRule {
+                            strip: if *strip == "0" {
+                                String::new()
+                            } else {
+                                strip.to_string()
+                            },
+                            add: if add == "0" {
+                                String::new()
+                            } else {
+                                add.to_string()
+                            },
+                            condition: ConditionElement::parse_condition(condition),
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut words = HashSet::new();
+
+        // the first line is the approximate word count
+        for line in dic.lines().skip(1) {
+            // morphological fields are separated by tabs or spaces
+            let entry = match line.split(|c: char| c == '\t' || c == ' ').next() {
+                Some(entry) if !entry.is_empty() => entry,
+                _ => continue,
+            };
+
+            let (stem, flags) = match entry.split_once('/') {
+                Some((stem, flags)) => (stem, flag_type.parse_flags(flags)),
+                None => (entry, vec![]),
+            };
+
+            if forbidden_flag
+                .as_ref()
+                .map(|forbidden| flags.contains(forbidden))
+                .unwrap_or(false)
+            {
+                continue;
+            }
+
+            if !needaffix_flag
+                .as_ref()
+                .map(|needaffix| flags.contains(needaffix))
+                .unwrap_or(false)
+            {
+                words.insert(stem.to_string());
+            }
+
+            let classes = flags
+                .iter()
+                .filter_map(|flag| affix_classes.get(flag))
+                .collect::<Vec<&AffixClass>>();
+
+            // (form, cross_product)
+            let mut suffixed_forms = vec![];
+
+            for class in classes
+                .iter()
+                .filter(|class| class.kind == AffixKind::Suffix)
+            {
+                for rule in class.rules.iter() {
+                    if let Some(form) = rule.apply(stem, AffixKind::Suffix) {
+                        suffixed_forms.push((form, class.cross_product));
+                    }
+                }
+            }
+
+            for class in classes
+                .iter()
+                .filter(|class| class.kind == AffixKind::Prefix)
+            {
+                for rule in class.rules.iter() {
+                    if let Some(form) = rule.apply(stem, AffixKind::Prefix) {
+                        words.insert(form);
+                    }
+
+                    // Combine prefixes and suffixes if both allow cross products
+                    if class.cross_product {
+                        for (suffixed_form, _) in suffixed_forms
+                            .iter()
+                            .filter(|(_, cross_product)| *cross_product)
+                        {
+                            if let Some(form) = rule.apply(suffixed_form, AffixKind::Prefix) {
+                                words.insert(form);
+                            }
+                        }
+                    }
+                }
+            }
+
+            words.extend(suffixed_forms.into_iter().map(|(form, _)| form));
+        }
+
+        if words.is_empty() {
+            return Err(anyhow::anyhow!(
+                "loading hunspell dictionary failed, no words found"
+            ));
+        }
+
+        Ok(Self { words })
+    }
+
+    /// Loads the dictionary `<language>.dic` and `<language>.aff` from the given directory.
+    pub fn load_from_dir(dir: &Path, language: &str) -> anyhow::Result<Self> {
+        let dic_path = dir.join(format!("{}.dic", language));
+        let aff_path = dir.join(format!("{}.aff", language));
+
+        let dic_bytes = std::fs::read(&dic_path)
+            .with_context(|| format!("reading dictionary file {:?} failed", dic_path))?;
+        let aff_bytes = std::fs::read(&aff_path)
+            .with_context(|| format!("reading affix file {:?} failed", aff_path))?;
+
+        Self::from_hunspell_bytes(&dic_bytes, &aff_bytes)
+    }
+
+    /// The number of word forms in the dictionary
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    /// If the dictionary does not contain any word forms
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Checks if the word is spelled correctly. Capitalized and all uppercase words are also accepted when the lowercase form is known.
+    pub fn check_word(&self, word: &str) -> bool {
+        if self.words.contains(word) {
+            return true;
+        }
+
+        let lowercase = word.to_lowercase();
+        if self.words.contains(&lowercase) {
+            return true;
+        }
+
+        // for all uppercase words, also check the capitalized form ( e.g. proper nouns )
+        if word.chars().all(|c| !c.is_lowercase()) {
+            let mut chars = lowercase.chars();
+
+            if let Some(first) = chars.next() {
+                let capitalized = first.to_uppercase().chain(chars).collect::<String>();
+
+                return self.words.contains(&capitalized);
+            }
+        }
+
+        false
+    }
+
+    /// Returns the byte ranges of the misspelled words in the text.
+    pub fn misspelled_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        text.unicode_word_indices()
+            .filter_map(|(start, word)| {
+                // Trim apostrophes, as they might be used as quotation marks
+                let trimmed_start = word.len() - word.trim_start_matches('\'').len();
+                let word = word.trim_matches('\'');
+
+                // skip words that contain numbers, as they are not in the dictionaries
+                if word.is_empty() || word.chars().any(|c| c.is_numeric()) {
+                    return None;
+                }
+
+                if self.check_word(word) {
+                    None
+                } else {
+                    let start = start + trimmed_start;
+                    Some(start..start + word.len())
+                }
+            })
+            .collect()
+    }
+}
+
+/// The spell checker. Loads Hunspell-format dictionaries from a configurable directory and works entirely offline.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "spellchecker")]
+pub struct SpellChecker {
+    #[serde(rename = "enabled")]
+    enabled: bool,
+    #[serde(rename = "dictionaries_dir")]
+    dictionaries_dir: PathBuf,
+    #[serde(rename = "language")]
+    language: String,
+
+    #[serde(skip)]
+    dictionary: Option<Arc<Dictionary>>,
+    /// The last checked text with its misspelled ranges, so that the text is only checked again when it changed
+    #[serde(skip)]
+    checked: RefCell<Option<(String, Vec<Range<usize>>)>>,
+}
+
+impl Default for SpellChecker {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dictionaries_dir: PathBuf::from(Self::DICTIONARIES_DIR_DEFAULT),
+            language: String::from(Self::LANGUAGE_DEFAULT),
+
+            dictionary: None,
+            checked: RefCell::new(None),
+        }
+    }
+}
+
+impl SpellChecker {
+    /// The default directory where the Hunspell dictionaries are searched
+    pub const DICTIONARIES_DIR_DEFAULT: &'static str = "/usr/share/hunspell";
+    /// The default language
+    pub const LANGUAGE_DEFAULT: &'static str = "en_US";
+
+    /// wether the spell checker is enabled
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// enables / disables the spell checker. Loads the dictionary if it is not loaded yet, and stays disabled if loading it failed.
+    pub fn set_enabled(&mut self, enabled: bool) -> anyhow::Result<()> {
+        self.enabled = enabled;
+
+        if enabled && self.dictionary.is_none() {
+            self.checked.replace(None);
+            self.load_dictionary()?;
+        }
+
+        Ok(())
+    }
+
+    /// The directory where the dictionaries are searched
+    pub fn dictionaries_dir(&self) -> &Path {
+        &self.dictionaries_dir
+    }
+
+    /// Sets the directory where the dictionaries are searched and reloads the dictionary.
+    pub fn set_dictionaries_dir(&mut self, dictionaries_dir: PathBuf) -> anyhow::Result<()> {
+        self.dictionaries_dir = dictionaries_dir;
+        self.reload_dictionary()
+    }
+
+    /// The current language, which is the file stem of the dictionary files. (e.g. `en_US` )
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// Sets the language and reloads the dictionary.
+    pub fn set_language(&mut self, language: String) -> anyhow::Result<()> {
+        self.language = language;
+        self.reload_dictionary()
+    }
+
+    /// Lists the languages that have a `.dic` and `.aff` file in the dictionaries directory.
+    pub fn available_languages(&self) -> Vec<String> {
+        let entries = match std::fs::read_dir(&self.dictionaries_dir) {
+            Ok(entries) => entries,
+            Err(_) => return vec![],
+        };
+
+        let mut languages = entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+
+                if path.extension()? != "dic" || !path.with_extension("aff").exists() {
+                    return None;
+                }
+
+                Some(path.file_stem()?.to_string_lossy().to_string())
+            })
+            .collect::<Vec<String>>();
+        languages.sort();
+
+        languages
+    }
+
+    /// (Re)loads the dictionary for the current dictionaries directory and language. Only loads when enabled, and disables the spell checker if loading it failed.
+    pub fn reload_dictionary(&mut self) -> anyhow::Result<()> {
+        self.dictionary = None;
+        self.checked.replace(None);
+
+        if self.enabled {
+            self.load_dictionary()?;
+        }
+
+        Ok(())
+    }
+
+    // Loads the dictionary. The spell checker is disabled if it failed, so it doesn't stay enabled without a dictionary
+    fn load_dictionary(&mut self) -> anyhow::Result<()> {
+        match Dictionary::load_from_dir(&self.dictionaries_dir, &self.language) {
+            Ok(dictionary) => {
+                self.dictionary = Some(Arc::new(dictionary));
+                Ok(())
+            }
+            Err(e) => {
+                self.enabled = false;
+                Err(e)
+            }
+        }
+    }
+
+    /// Returns the byte ranges of the misspelled words in the text.
+    /// Returns no ranges if the spell checker is disabled or no dictionary is loaded.
+    ///
+    /// The result for the last checked text is cached, so this can be called on every redraw and only checks again after the text was edited.
+    pub fn check(&self, text: &str) -> Vec<Range<usize>> {
+        let dictionary = match &self.dictionary {
+            Some(dictionary) if self.enabled => dictionary,
+            _ => return vec![],
+        };
+
+        if let Some((checked_text, ranges)) = self.checked.borrow().as_ref() {
+            if checked_text == text {
+                return ranges.clone();
+            }
+        }
+
+        let ranges = dictionary.misspelled_ranges(text);
+        self.checked
+            .replace(Some((text.to_string(), ranges.clone())));
+
+        ranges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_AFF: &str = "SET UTF-8
+TRY esianrtolcdugmphbyfvkwzESIANRTOLCDUGMPHBYFVKWZ'
+
+PFX A Y 1
+PFX A   0     re         .
+
+SFX S Y 4
+SFX S   y     ies        [^aeiou]y
+SFX S   0     s          [aeiou]y
+SFX S   0     es         [sxzh]
+SFX S   0     s          [^sxzhy]
+";
+
+    const TEST_DIC: &str = "4
+try/S
+box/S
+write/AS
+Paris
+";
+
+    #[test]
+    fn affix_expansion() {
+        let dictionary =
+            Dictionary::from_hunspell_bytes(TEST_DIC.as_bytes(), TEST_AFF.as_bytes()).unwrap();
+
+        for word in [
+            "try", "tries", "box", "boxes", "write", "writes", "rewrite", "rewrites", "Paris",
+        ] {
+            assert!(dictionary.check_word(word), "word `{}` not accepted", word);
+        }
+
+        for word in ["trys", "boxs", "paris", "rebox"] {
+            assert!(!dictionary.check_word(word), "word `{}` accepted", word);
+        }
+    }
+
+    #[test]
+    fn misspelled_ranges() {
+        let dictionary =
+            Dictionary::from_hunspell_bytes(TEST_DIC.as_bytes(), TEST_AFF.as_bytes()).unwrap();
+
+        let text = "Boxes trys 42 'rewrites' PARIS wrte";
+
+        assert_eq!(dictionary.misspelled_ranges(text), vec![6..10, 31..35]);
+    }
+
+    #[test]
+    fn dictionary_encodings() {
+        // "žena" and "šum" in ISO8859-2
+        let dictionary =
+            Dictionary::from_hunspell_bytes(b"2\n\xbeena\n\xb9um\n", b"SET ISO8859-2\n").unwrap();
+        assert!(dictionary.check_word("žena"));
+        assert!(dictionary.check_word("šum"));
+
+        // "мир" in KOI8-R
+        let dictionary =
+            Dictionary::from_hunspell_bytes(b"1\n\xcd\xc9\xd2\n", b"SET KOI8-R\n").unwrap();
+        assert!(dictionary.check_word("мир"));
+
+        assert!(Dictionary::from_hunspell_bytes(b"1\nword\n", b"SET UNKNOWN-1\n").is_err());
+    }
+
+    #[test]
+    fn enabling_fails_without_dictionary() {
+        let mut spellchecker = SpellChecker {
+            dictionaries_dir: PathBuf::from("/nonexistent"),
+            ..SpellChecker::default()
+        };
+
+        assert!(spellchecker.set_enabled(true).is_err());
+        assert!(!spellchecker.enabled());
+    }
+
+    #[test]
+    fn failed_reloads_disable_the_spellchecker() {
+        let enabled_spellchecker = || SpellChecker {
+            enabled: true,
+            dictionary: Some(Arc::new(Dictionary::default())),
+            ..SpellChecker::default()
+        };
+
+        let mut spellchecker = enabled_spellchecker();
+        assert!(spellchecker
+            .set_dictionaries_dir(PathBuf::from("/nonexistent"))
+            .is_err());
+        assert!(!spellchecker.enabled());
+
+        let mut spellchecker = SpellChecker {
+            dictionaries_dir: PathBuf::from("/nonexistent"),
+            ..enabled_spellchecker()
+        };
+        assert!(spellchecker.set_language(String::from("en_US")).is_err());
+        assert!(!spellchecker.enabled());
+
+        let mut spellchecker = SpellChecker {
+            dictionaries_dir: PathBuf::from("/nonexistent"),
+            ..enabled_spellchecker()
+        };
+        assert!(spellchecker.reload_dictionary().is_err());
+        assert!(!spellchecker.enabled());
+        assert!(spellchecker.check("wrte").is_empty());
+    }
+}
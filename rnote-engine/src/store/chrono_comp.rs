@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::sync::Arc;
 
 use p2d::bounding_volume::AABB;
@@ -5,6 +6,7 @@ use rayon::slice::ParallelSliceMut;
 use serde::{Deserialize, Serialize};
 
 use super::{StrokeKey, StrokeStore};
+use rnote_compose::shapes::ShapeBehaviour;
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, Ord, PartialEq, PartialOrd)]
 #[serde(default, rename = "chrono_component")]
@@ -25,12 +27,27 @@ impl ChronoComponent {
     }
 }
 
+/// Changes of the stacking order of strokes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reordering {
+    /// Raises the strokes above the next overlapping stroke
+    BringForward,
+    /// Lowers the strokes below the previous overlapping stroke
+    SendBackward,
+    BringToFront,
+    SendToBack,
+}
+
 /// Systems that are related to their chronological ordering.
 impl StrokeStore {
     pub fn update_chrono_to_last(&mut self, key: StrokeKey) {
         if let Some(chrono_comp) = Arc::make_mut(&mut self.chrono_components).get_mut(key) {
             self.chrono_counter += 1;
             Arc::make_mut(chrono_comp).t = self.chrono_counter;
+
+            if let Some(stroke) = self.stroke_components.get(key) {
+                self.key_tree.record_change(key, stroke.bounds());
+            }
         } else {
             log::debug!(
                 "get chrono_comp in set_chrono_to_last() returned None for stroke with key {:?}",
@@ -75,4 +92,123 @@ impl StrokeStore {
 
         keys
     }
+
+    /// Changes the stacking order of the strokes. The relative order between the strokes is kept.
+    pub fn reorder_strokes(&mut self, keys: &[StrokeKey], reordering: Reordering) {
+        let keys = keys.iter().copied().collect::<HashSet<StrokeKey>>();
+        let mut order = self.keys_sorted_chrono();
+
+        match reordering {
+            Reordering::BringToFront => {
+                let (mut reordered, rest): (Vec<StrokeKey>, Vec<StrokeKey>) =
+                    order.into_iter().partition(|key| keys.contains(key));
+                order = rest;
+                order.append(&mut reordered);
+            }
+            Reordering::SendToBack => {
+                let (reordered, mut rest): (Vec<StrokeKey>, Vec<StrokeKey>) =
+                    order.into_iter().partition(|key| keys.contains(key));
+                order = reordered;
+                order.append(&mut rest);
+            }
+            Reordering::BringForward => {
+                // Starting with the topmost, so that the strokes don't overtake each other
+                let reordered = order
+                    .iter()
+                    .rev()
+                    .filter(|key| keys.contains(key))
+                    .copied()
+                    .collect::<Vec<StrokeKey>>();
+
+                for key in reordered {
+                    let i = Self::position_in_order(&order, key);
+
+                    // Raised above the lowest overlapping stroke above it
+                    if let Some(j) = self
+                        .overlapping_rendered_keys(key, &keys)
+                        .into_iter()
+                        .map(|other| Self::position_in_order(&order, other))
+                        .filter(|&j| j > i)
+                        .min()
+                    {
+                        let key = order.remove(i);
+                        order.insert(j, key);
+                    }
+                }
+            }
+            Reordering::SendBackward => {
+                // Starting with the bottommost, so that the strokes don't overtake each other
+                let reordered = order
+                    .iter()
+                    .filter(|key| keys.contains(key))
+                    .copied()
+                    .collect::<Vec<StrokeKey>>();
+
+                for key in reordered {
+                    let i = Self::position_in_order(&order, key);
+
+                    // Lowered below the topmost overlapping stroke below it
+                    if let Some(j) = self
+                        .overlapping_rendered_keys(key, &keys)
+                        .into_iter()
+                        .map(|other| Self::position_in_order(&order, other))
+                        .filter(|&j| j < i)
+                        .max()
+                    {
+                        let key = order.remove(i);
+                        order.insert(j, key);
+                    }
+                }
+            }
+        }
+
+        self.reload_chrono_with_order(&order);
+    }
+
+    fn position_in_order(order: &[StrokeKey], key: StrokeKey) -> usize {
+        order
+            .iter()
+            .position(|&other| other == key)
+            .unwrap_or(order.len())
+    }
+
+    /// The keys of the strokes that are not trashed and overlap with the stroke, except the excluded ones
+    fn overlapping_rendered_keys(
+        &self,
+        key: StrokeKey,
+        excluded: &HashSet<StrokeKey>,
+    ) -> Vec<StrokeKey> {
+        let bounds = match self.stroke_components.get(key) {
+            Some(stroke) => stroke.bounds(),
+            None => return vec![],
+        };
+
+        self.keys_sorted_chrono_intersecting_bounds(bounds)
+            .into_iter()
+            .filter(|&other| {
+                other != key && !excluded.contains(&other) && !self.trashed(other).unwrap_or(false)
+            })
+            .collect()
+    }
+
+    /// Renumbers the chrono components in the given order, as in first: gets drawn first, last: gets drawn last.
+    /// Records a change for every stroke which changed its position, so that its rendering is updated
+    fn reload_chrono_with_order(&mut self, order: &[StrokeKey]) {
+        let chrono_components = Arc::make_mut(&mut self.chrono_components);
+
+        for (i, &key) in order.iter().enumerate() {
+            let chrono_comp = ChronoComponent::new(i as u32 + 1);
+
+            if chrono_components
+                .insert(key, Arc::new(chrono_comp))
+                .map_or(true, |prev_chrono_comp| *prev_chrono_comp != chrono_comp)
+            {
+                if let Some(stroke) = self.stroke_components.get(key) {
+                    self.key_tree.record_change(key, stroke.bounds());
+                }
+            }
+        }
+
+        self.chrono_counter = order.len() as u32;
+    }
 }
@@ -0,0 +1,147 @@
+use super::{StrokeKey, StrokeStore};
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename = "group_component")]
+pub struct GroupComponent {
+    /// The ids of the groups the stroke is a member of, ordered from the innermost to the outermost group
+    #[serde(rename = "groups")]
+    pub groups: Vec<u32>,
+}
+
+impl GroupComponent {
+    pub fn outermost_group(&self) -> Option<u32> {
+        self.groups.last().copied()
+    }
+}
+
+/// Systems that are related to grouping strokes
+impl StrokeStore {
+    /// The id of the outermost group the stroke is a member of. None if it is not grouped.
+    pub fn outermost_group(&self, key: StrokeKey) -> Option<u32> {
+        self.group_components
+            .get(key)
+            .and_then(|group_comp| group_comp.outermost_group())
+    }
+
+    /// Extends the keys with all members of the outermost groups they are a member of.
+    /// Trashed strokes are excluded.
+    pub fn keys_with_group_members(&self, keys: &[StrokeKey]) -> Vec<StrokeKey> {
+        let groups = keys
+            .iter()
+            .filter_map(|&key| self.outermost_group(key))
+            .collect::<HashSet<u32>>();
+
+        let mut keys = keys.to_vec();
+
+        if groups.is_empty() {
+            return keys;
+        }
+        let contained_keys = keys.iter().copied().collect::<HashSet<StrokeKey>>();
+
+        let members = self
+            .stroke_components
+            .keys()
+            .filter(|key| {
+                !(self.trashed(*key).unwrap_or(false))
+                    && self
+                        .outermost_group(*key)
+                        .map_or(false, |group| groups.contains(&group))
+                    && !contained_keys.contains(key)
+            })
+            .collect::<Vec<StrokeKey>>();
+
+        keys.extend(members);
+        keys
+    }
+
+    /// Partitions the keys into the units which are arranged together. The strokes of an outermost group form one unit,
+    /// every stroke which is not grouped is a unit by itself. The units are ordered by their first key.
+    pub fn keys_as_group_units(&self, keys: &[StrokeKey]) -> Vec<Vec<StrokeKey>> {
+        let mut units = Vec::<Vec<StrokeKey>>::new();
+        let mut group_units = HashMap::<u32, usize>::new();
+
+        for &key in keys {
+            match self.outermost_group(key) {
+                Some(group) => {
+                    let i = *group_units.entry(group).or_insert_with(|| {
+                        units.push(vec![]);
+                        units.len() - 1
+                    });
+                    units[i].push(key);
+                }
+                None => units.push(vec![key]),
+            }
+        }
+
+        units
+    }
+
+    /// Groups the strokes. If some of them already are grouped, the existing groups get nested in the new one.
+    pub fn group_strokes(&mut self, keys: &[StrokeKey]) {
+        if keys.len() < 2 {
+            return;
+        }
+
+        self.group_counter += 1;
+        let new_group = self.group_counter;
+
+        for &key in keys {
+            self.push_group(key, new_group);
+        }
+    }
+
+    /// Removes the strokes from their outermost group. Nested groups are kept.
+    pub fn ungroup_strokes(&mut self, keys: &[StrokeKey]) {
+        for &key in keys {
+            if let Some(group_comp) = Arc::make_mut(&mut self.group_components)
+                .get_mut(key)
+                .map(Arc::make_mut)
+            {
+                group_comp.groups.pop();
+            }
+        }
+    }
+
+    /// Copies the groups of the strokes to the other strokes ( e.g. when duplicating ),
+    /// but with new group ids so that the copies are not joined with the originals.
+    pub fn copy_groups_w_new_ids(&mut self, from_keys: &[StrokeKey], to_keys: &[StrokeKey]) {
+        let mut new_ids = HashMap::<u32, u32>::new();
+
+        for (&from_key, &to_key) in from_keys.iter().zip(to_keys.iter()) {
+            let groups = match self.group_components.get(from_key) {
+                Some(group_comp) => group_comp.groups.clone(),
+                None => continue,
+            };
+
+            for group in groups {
+                let new_group = *new_ids.entry(group).or_insert_with(|| {
+                    self.group_counter += 1;
+                    self.group_counter
+                });
+
+                self.push_group(to_key, new_group);
+            }
+        }
+    }
+
+    fn push_group(&mut self, key: StrokeKey, group: u32) {
+        // Strokes loaded from older files might not have a group component yet
+        if let Some(group_comp) = Arc::make_mut(&mut self.group_components)
+            .get_mut(key)
+            .map(Arc::make_mut)
+        {
+            group_comp.groups.push(group);
+        } else if self.stroke_components.contains_key(key) {
+            Arc::make_mut(&mut self.group_components).insert(
+                key,
+                Arc::new(GroupComponent {
+                    groups: vec![group],
+                }),
+            );
+        }
+    }
+}
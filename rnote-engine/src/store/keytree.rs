@@ -1,5 +1,6 @@
 use p2d::bounding_volume::AABB;
 use rstar::primitives::GeomWithData;
+use slotmap::SecondaryMap;
 
 use super::StrokeKey;
 
@@ -15,21 +16,46 @@ fn new_keytree_object(key: StrokeKey, bounds: AABB) -> KeyTreeObject {
     )
 }
 
+fn keytree_object_bounds(object: &KeyTreeObject) -> AABB {
+    let (lower, upper) = (object.geom().lower(), object.geom().upper());
+
+    AABB::new(
+        na::point![lower[0], lower[1]],
+        na::point![upper[0], upper[1]],
+    )
+}
+
 #[derive(Debug, Default)]
 /// A Rtree with StrokeKeys as associated data. Used for faster spatial queries
-pub(super) struct KeyTree(rstar::RTree<KeyTreeObject, rstar::DefaultParams>);
+pub(super) struct KeyTree {
+    tree: rstar::RTree<KeyTreeObject, rstar::DefaultParams>,
+    /// The keys and the bounds of the changes to the tree, if they are tracked. On geometry updates both the old and the new bounds are recorded
+    changes: Option<Vec<(StrokeKey, AABB)>>,
+    /// Incremented on every change of a stroke
+    generation_counter: u64,
+    /// The generation of the latest change of each stroke, to check if something that depends on a stroke is outdated
+    generations: SecondaryMap<StrokeKey, u64>,
+}
 
 impl KeyTree {
     /// Inserts a new tree object with the given key, bounds
     pub fn insert_with_key(&mut self, key: StrokeKey, bounds: AABB) {
-        self.0.insert(new_keytree_object(key, bounds));
+        self.tree.insert(new_keytree_object(key, bounds));
+        self.record_change(key, bounds);
     }
 
     /// has to iterate through the entire tree in no particular order
     pub fn remove_with_key(&mut self, key: StrokeKey) -> Option<KeyTreeObject> {
-        let object_to_remove = self.0.iter().find(|&object| object.data == key)?.to_owned();
+        let object_to_remove = self
+            .tree
+            .iter()
+            .find(|&object| object.data == key)?
+            .to_owned();
 
-        self.0.remove(&object_to_remove)
+        let removed = self.tree.remove(&object_to_remove)?;
+        self.record_change(key, keytree_object_bounds(&removed));
+
+        Some(removed)
     }
 
     /// has to be called when the geometry of the stroke with the given key has changed.
@@ -40,7 +66,7 @@ impl KeyTree {
 
     /// Returns the keys that intersect with the given bounds
     pub fn keys_intersecting_bounds(&self, bounds: AABB) -> Vec<StrokeKey> {
-        self.0
+        self.tree
             .locate_in_envelope_intersecting(&rstar::AABB::from_corners(
                 [bounds.mins[0], bounds.mins[1]],
                 [bounds.maxs[0], bounds.maxs[1]],
@@ -50,17 +76,130 @@ impl KeyTree {
     }
 
     /// Reloads the entire tree from the given Vec of (key, bounds).
+    /// Not recorded as change, as everything that depends on the tree needs to be reloaded as well.
+    /// The strokes keep their generation, new strokes get a new one
     pub fn reload_with_vec(&mut self, strokes: Vec<(StrokeKey, AABB)>) {
+        self.generation_counter += 1;
+        self.generations = strokes
+            .iter()
+            .map(|&(key, _)| {
+                let generation = self
+                    .generations
+                    .get(key)
+                    .copied()
+                    .unwrap_or(self.generation_counter);
+                (key, generation)
+            })
+            .collect();
+
         let objects = strokes
             .into_iter()
             .map(|(key, bounds)| new_keytree_object(key, bounds))
             .collect();
 
-        self.0 = rstar::RTree::bulk_load(objects);
+        self.tree = rstar::RTree::bulk_load(objects);
     }
 
-    ///  Clears the entire tree
+    ///  Clears the entire tree. Keeps tracking changes if it was enabled
     pub fn clear(&mut self) {
-        *self = Self::default()
+        self.tree = rstar::RTree::default();
+        self.generations.clear();
+    }
+
+    /// Enables / disables tracking the changes to the tree. Already tracked changes are discarded
+    pub fn set_track_changes(&mut self, track_changes: bool) {
+        self.changes = track_changes.then(Vec::new);
+    }
+
+    /// Records a change of a stroke that did not change its geometry, but its appearance (e.g. when it got trashed)
+    pub fn record_change(&mut self, key: StrokeKey, bounds: AABB) {
+        self.generation_counter += 1;
+        self.generations.insert(key, self.generation_counter);
+
+        if let Some(changes) = self.changes.as_mut() {
+            changes.push((key, bounds));
+        }
+    }
+
+    /// The generation of the latest change of the stroke. Changes every time the stroke is changed
+    pub fn generation(&self, key: StrokeKey) -> Option<u64> {
+        self.generations.get(key).copied()
+    }
+
+    /// The tracked changes that were not yet taken
+    pub fn changes(&self) -> &[(StrokeKey, AABB)] {
+        self.changes.as_deref().unwrap_or(&[])
+    }
+
+    /// Takes the tracked changes
+    pub fn take_changes(&mut self) -> Vec<(StrokeKey, AABB)> {
+        self.changes
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use slotmap::SlotMap;
+
+    fn bounds(x: f64) -> AABB {
+        AABB::new(na::point![x, 0.0], na::point![x + 10.0, 10.0])
+    }
+
+    #[test]
+    fn changes_increase_the_generation() {
+        let mut keys = SlotMap::<StrokeKey, ()>::with_key();
+        let (first, second) = (keys.insert(()), keys.insert(()));
+        let mut key_tree = KeyTree::default();
+
+        key_tree.insert_with_key(first, bounds(0.0));
+        key_tree.insert_with_key(second, bounds(20.0));
+        let (first_generation, second_generation) = (
+            key_tree.generation(first).unwrap(),
+            key_tree.generation(second).unwrap(),
+        );
+
+        key_tree.update_with_key(first, bounds(40.0));
+        assert!(key_tree.generation(first).unwrap() > first_generation);
+        assert_eq!(key_tree.generation(second), Some(second_generation));
+
+        key_tree.record_change(second, bounds(20.0));
+        assert!(key_tree.generation(second).unwrap() > second_generation);
+    }
+
+    #[test]
+    fn reload_keeps_generations() {
+        let mut keys = SlotMap::<StrokeKey, ()>::with_key();
+        let (first, second) = (keys.insert(()), keys.insert(()));
+        let mut key_tree = KeyTree::default();
+
+        key_tree.insert_with_key(first, bounds(0.0));
+        let first_generation = key_tree.generation(first).unwrap();
+
+        key_tree.reload_with_vec(vec![(first, bounds(0.0)), (second, bounds(20.0))]);
+        assert_eq!(key_tree.generation(first), Some(first_generation));
+        assert!(key_tree.generation(second).unwrap() > first_generation);
+        assert_eq!(
+            key_tree.keys_intersecting_bounds(bounds(20.0)),
+            vec![second]
+        );
+    }
+
+    #[test]
+    fn clear_forgets_generations() {
+        let mut keys = SlotMap::<StrokeKey, ()>::with_key();
+        let key = keys.insert(());
+        let mut key_tree = KeyTree::default();
+
+        key_tree.insert_with_key(key, bounds(0.0));
+        let generation = key_tree.generation(key).unwrap();
+        key_tree.clear();
+        assert_eq!(key_tree.generation(key), None);
+
+        key_tree.reload_with_vec(vec![(key, bounds(0.0))]);
+        assert!(key_tree.generation(key).unwrap() > generation);
     }
 }
@@ -1,20 +1,26 @@
 pub mod chrono_comp;
+pub mod group_comp;
 pub mod keytree;
 pub mod render_comp;
 pub mod selection_comp;
 pub mod stroke_comp;
+pub mod tilecache;
 pub mod trash_comp;
 
 // Re-exports
-pub use chrono_comp::ChronoComponent;
+pub use chrono_comp::{ChronoComponent, Reordering};
+pub use group_comp::GroupComponent;
 use keytree::KeyTree;
 pub use render_comp::RenderComponent;
 pub use selection_comp::SelectionComponent;
+pub use stroke_comp::{Alignment, Axis, BooleanOp};
+pub use tilecache::{TileCache, TileKey};
 pub use trash_comp::TrashComponent;
 
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
 use std::sync::Arc;
 
+use crate::document::page::PagesSnapshot;
 use crate::strokes::Stroke;
 use crate::WidgetFlags;
 use rnote_compose::shapes::ShapeBehaviour;
@@ -36,9 +42,16 @@ pub struct HistoryEntry {
     pub selection_components: Arc<SecondaryMap<StrokeKey, Arc<SelectionComponent>>>,
     #[serde(rename = "chrono_components")]
     pub chrono_components: Arc<SecondaryMap<StrokeKey, Arc<ChronoComponent>>>,
+    #[serde(rename = "group_components")]
+    pub group_components: Arc<SecondaryMap<StrokeKey, Arc<GroupComponent>>>,
+    /// The pages of the document. Only present once the pages were changed through the engine
+    #[serde(skip)]
+    doc_pages: Option<Arc<PagesSnapshot>>,
 
     #[serde(rename = "chrono_counter")]
     chrono_counter: u32,
+    #[serde(rename = "group_counter")]
+    group_counter: u32,
 }
 
 impl Default for HistoryEntry {
@@ -48,8 +61,11 @@ impl Default for HistoryEntry {
             trash_components: Arc::new(SecondaryMap::new()),
             selection_components: Arc::new(SecondaryMap::new()),
             chrono_components: Arc::new(SecondaryMap::new()),
+            group_components: Arc::new(SecondaryMap::new()),
+            doc_pages: None,
 
             chrono_counter: 0,
+            group_counter: 0,
         }
     }
 }
@@ -72,6 +88,7 @@ impl StoreSnapshot {
             Arc::make_mut(&mut self.trash_components).remove(key);
             Arc::make_mut(&mut self.selection_components).remove(key);
             Arc::make_mut(&mut self.chrono_components).remove(key);
+            Arc::make_mut(&mut self.group_components).remove(key);
         }
     }
 }
@@ -82,8 +99,11 @@ impl StoreSnapshot {
 ///     * 'trash_components': Hold state whether the strokes are trashed
 ///     * 'selection_components': Hold state whether the strokes are selected
 ///     * 'chrono_components': Hold state about the chronological ordering
+///     * 'group_components': Hold the (nested) groups the strokes are a member of
 ///     * 'render_components': Hold state about the current rendering of the strokes.
 ///
+/// Additionally the store holds the pages of the document, so that page changes are part of the history.
+///
 /// The systems are implemented as methods on StrokesStore, loosely categorized to the different components (but often modify others as well).
 /// Most systems take a key or a slice of keys, and iterate with them over the different components.
 /// There also is a different category of methods which return filtered keys, (e.g. `.keys_sorted_chrono` returns the keys in chronological ordering,
@@ -101,8 +121,13 @@ pub struct StrokeStore {
     selection_components: Arc<SecondaryMap<StrokeKey, Arc<SelectionComponent>>>,
     #[serde(rename = "chrono_components")]
     chrono_components: Arc<SecondaryMap<StrokeKey, Arc<ChronoComponent>>>,
+    #[serde(rename = "group_components")]
+    group_components: Arc<SecondaryMap<StrokeKey, Arc<GroupComponent>>>,
     #[serde(skip)]
     render_components: SecondaryMap<StrokeKey, RenderComponent>,
+    // The pages of the document, recorded together with the strokes
+    #[serde(skip)]
+    doc_pages: Option<Arc<PagesSnapshot>>,
 
     // The history
     #[serde(skip)]
@@ -113,11 +138,17 @@ pub struct StrokeStore {
     // A rtree backed by the slotmap, for faster spatial queries. Needs to be updated with update_with_key() when strokes changed their geometry or position!
     #[serde(skip)]
     key_tree: KeyTree,
+    // The cache of rendered document tiles. When present, the strokes are rendered into tiles instead of per stroke
+    #[serde(skip)]
+    tile_cache: Option<TileCache>,
 
     // Other state
     /// incrementing counter for chrono_components. value is equal chrono_component of the newest inserted or modified stroke.
     #[serde(rename = "chrono_counter")]
     chrono_counter: u32,
+    /// incrementing counter for the ids of new groups.
+    #[serde(rename = "group_counter")]
+    group_counter: u32,
 }
 
 impl Default for StrokeStore {
@@ -127,14 +158,18 @@ impl Default for StrokeStore {
             trash_components: Arc::new(SecondaryMap::new()),
             selection_components: Arc::new(SecondaryMap::new()),
             chrono_components: Arc::new(SecondaryMap::new()),
+            group_components: Arc::new(SecondaryMap::new()),
             render_components: SecondaryMap::new(),
+            doc_pages: None,
 
             history: VecDeque::new(),
             history_pos: None,
 
             key_tree: KeyTree::default(),
+            tile_cache: None,
 
             chrono_counter: 0,
+            group_counter: 0,
         }
     }
 }
@@ -155,8 +190,11 @@ impl StrokeStore {
         self.trash_components = Arc::clone(&store_snapshot.trash_components);
         self.selection_components = Arc::clone(&store_snapshot.selection_components);
         self.chrono_components = Arc::clone(&store_snapshot.chrono_components);
+        self.group_components = Arc::clone(&store_snapshot.group_components);
+        self.doc_pages = None;
 
         self.chrono_counter = store_snapshot.chrono_counter;
+        self.group_counter = store_snapshot.group_counter;
 
         self.update_geometry_for_strokes(&self.keys_unordered());
 
@@ -183,6 +221,12 @@ impl StrokeStore {
                 &history_entry.selection_components,
             )
             && Arc::ptr_eq(&self.chrono_components, &history_entry.chrono_components)
+            && Arc::ptr_eq(&self.group_components, &history_entry.group_components)
+            && match (&self.doc_pages, &history_entry.doc_pages) {
+                (Some(doc_pages), Some(other)) => Arc::ptr_eq(doc_pages, other),
+                (None, None) => true,
+                _ => false,
+            }
     }
 
     /// Returns a history entry created from the current state
@@ -192,7 +236,10 @@ impl StrokeStore {
             trash_components: Arc::clone(&self.trash_components),
             selection_components: Arc::clone(&self.selection_components),
             chrono_components: Arc::clone(&self.chrono_components),
+            group_components: Arc::clone(&self.group_components),
+            doc_pages: self.doc_pages.clone(),
             chrono_counter: self.chrono_counter,
+            group_counter: self.group_counter,
         })
     }
 
@@ -203,15 +250,25 @@ impl StrokeStore {
 
     /// Imports a given history entry and replaces the current state with it.
     fn import_history_entry(&mut self, history_entry: &Arc<HistoryEntry>) {
+        let prev_state = self.history_entry_from_current_state();
+
         self.stroke_components = Arc::clone(&history_entry.stroke_components);
         self.trash_components = Arc::clone(&history_entry.trash_components);
         self.selection_components = Arc::clone(&history_entry.selection_components);
         self.chrono_components = Arc::clone(&history_entry.chrono_components);
+        self.group_components = Arc::clone(&history_entry.group_components);
+        // Entries that were recorded before the pages were changed the first time keep the current pages
+        if history_entry.doc_pages.is_some() {
+            self.doc_pages = history_entry.doc_pages.clone();
+        }
 
         self.chrono_counter = history_entry.chrono_counter;
+        self.group_counter = history_entry.group_counter;
 
         // Since we don't store the tree in the history, we need to reload it.
         self.reload_tree();
+        // Reloading the tree is not recorded as change, so the strokes that differ need to be recorded separately
+        self.record_changes_from_state(&prev_state);
         // render_components are also not stored in the history, but for the duration of the running app we don't ever remove it,
         // so we can actually skip rebuilding it when importing a history entry. This avoids flickering where we have already rebuilt the components
         // and can't display anything until the asynchronous rendering is finished
@@ -220,6 +277,64 @@ impl StrokeStore {
         self.set_rendering_dirty_all_keys();
     }
 
+    /// Records the strokes that differ between the current and the given previous state as changed, with their previous and current bounds
+    fn record_changes_from_state(&mut self, prev_state: &HistoryEntry) {
+        fn differs<T>(
+            first: &SecondaryMap<StrokeKey, Arc<T>>,
+            second: &SecondaryMap<StrokeKey, Arc<T>>,
+            key: StrokeKey,
+        ) -> bool {
+            match (first.get(key), second.get(key)) {
+                (Some(first), Some(second)) => !Arc::ptr_eq(first, second),
+                (None, None) => false,
+                _ => true,
+            }
+        }
+
+        let keys = self
+            .stroke_components
+            .keys()
+            .chain(prev_state.stroke_components.keys())
+            .collect::<HashSet<StrokeKey>>();
+
+        for key in keys {
+            let prev_stroke = prev_state.stroke_components.get(key);
+            let stroke = self.stroke_components.get(key);
+
+            let stroke_differs = match (prev_stroke, stroke) {
+                (Some(prev_stroke), Some(stroke)) => !Arc::ptr_eq(prev_stroke, stroke),
+                (None, None) => false,
+                _ => true,
+            };
+
+            if !(stroke_differs
+                || differs(&self.trash_components, &prev_state.trash_components, key)
+                || differs(
+                    &self.selection_components,
+                    &prev_state.selection_components,
+                    key,
+                )
+                || differs(&self.chrono_components, &prev_state.chrono_components, key)
+                || differs(&self.group_components, &prev_state.group_components, key))
+            {
+                continue;
+            }
+
+            for bounds in prev_stroke
+                .into_iter()
+                .chain(stroke)
+                .map(|stroke| stroke.bounds())
+            {
+                self.key_tree.record_change(key, bounds);
+            }
+        }
+    }
+
+    /// The generation of the latest change of the stroke. Changes every time the stroke is changed, so it can be used to check if something that depends on it is outdated
+    pub(crate) fn stroke_generation(&self, key: StrokeKey) -> Option<u64> {
+        self.key_tree.generation(key)
+    }
+
     /// records the current state and saves it in the history
     pub fn record(&mut self) -> WidgetFlags {
         /*
@@ -420,6 +535,17 @@ impl StrokeStore {
         self.history_pos = None;
     }
 
+    /// The pages of the document in the current state, if they were ever set.
+    pub(crate) fn doc_pages(&self) -> Option<Arc<PagesSnapshot>> {
+        self.doc_pages.clone()
+    }
+
+    /// Sets the pages of the document, to be recorded with the next history entry.
+    /// Must be called before recording and after changing the pages, so that undo and redo can restore them
+    pub(crate) fn set_doc_pages(&mut self, doc_pages: PagesSnapshot) {
+        self.doc_pages = Some(Arc::new(doc_pages));
+    }
+
     pub fn clear_history(&mut self) {
         self.history.clear();
         self.history_pos = None;
@@ -439,6 +565,7 @@ impl StrokeStore {
             .insert(key, Arc::new(SelectionComponent::default()));
         Arc::make_mut(&mut self.chrono_components)
             .insert(key, Arc::new(ChronoComponent::new(self.chrono_counter)));
+        Arc::make_mut(&mut self.group_components).insert(key, Arc::new(GroupComponent::default()));
         self.render_components
             .insert(key, RenderComponent::default());
 
@@ -450,6 +577,7 @@ impl StrokeStore {
         Arc::make_mut(&mut self.trash_components).remove(key);
         Arc::make_mut(&mut self.selection_components).remove(key);
         Arc::make_mut(&mut self.chrono_components).remove(key);
+        Arc::make_mut(&mut self.group_components).remove(key);
         self.render_components.remove(key);
 
         self.key_tree.remove_with_key(key);
@@ -464,11 +592,342 @@ impl StrokeStore {
         Arc::make_mut(&mut self.trash_components).clear();
         Arc::make_mut(&mut self.selection_components).clear();
         Arc::make_mut(&mut self.chrono_components).clear();
+        Arc::make_mut(&mut self.group_components).clear();
 
         self.chrono_counter = 0;
+        self.group_counter = 0;
+        self.doc_pages = None;
         self.clear_history();
 
         self.render_components.clear();
         self.key_tree.clear();
+        if let Some(tile_cache) = self.tile_cache.as_mut() {
+            tile_cache.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strokes::ShapeStroke;
+    use p2d::bounding_volume::AABB;
+    use rnote_compose::shapes::{Line, Rectangle, Shape};
+    use rnote_compose::Style;
+
+    fn line_stroke(y: f64) -> Stroke {
+        Stroke::ShapeStroke(ShapeStroke::new(
+            Shape::Line(Line {
+                start: na::vector![0.0, y],
+                end: na::vector![100.0, y],
+            }),
+            Style::default(),
+        ))
+    }
+
+    #[test]
+    fn undo_changes_generations_of_differing_strokes() {
+        let mut store = StrokeStore::default();
+        let moved = store.insert_stroke(line_stroke(0.0));
+        let unchanged = store.insert_stroke(line_stroke(100.0));
+        let _ = store.record();
+
+        store.translate_strokes(&[moved], na::vector![0.0, 50.0]);
+        let moved_generation = store.stroke_generation(moved).unwrap();
+        let unchanged_generation = store.stroke_generation(unchanged).unwrap();
+
+        let _ = store.undo();
+        assert!(store.stroke_generation(moved).unwrap() > moved_generation);
+        assert_eq!(
+            store.stroke_generation(unchanged),
+            Some(unchanged_generation)
+        );
+        assert!(store
+            .keys_unordered_intersecting_bounds(AABB::new(
+                na::point![0.0, 40.0],
+                na::point![100.0, 60.0]
+            ))
+            .is_empty());
+
+        let moved_generation = store.stroke_generation(moved).unwrap();
+        let _ = store.redo();
+        assert!(store.stroke_generation(moved).unwrap() > moved_generation);
+        assert_eq!(
+            store.stroke_generation(unchanged),
+            Some(unchanged_generation)
+        );
+    }
+
+    #[test]
+    fn undo_records_changed_bounds() {
+        let mut store = StrokeStore::default();
+        let key = store.insert_stroke(line_stroke(0.0));
+        let _ = store.record();
+        store.translate_strokes(&[key], na::vector![0.0, 50.0]);
+
+        store.key_tree.set_track_changes(true);
+        let _ = store.undo();
+        let changes = store.key_tree.take_changes();
+
+        // The previous and the restored bounds
+        assert_eq!(changes.len(), 2);
+        assert!(changes.iter().all(|&(changed_key, _)| changed_key == key));
+        assert!(changes
+            .iter()
+            .any(|(_, bounds)| bounds.center()[1] > 40.0 && bounds.center()[1] < 60.0));
+        assert!(changes
+            .iter()
+            .any(|(_, bounds)| bounds.center()[1].abs() < 10.0));
+    }
+
+    #[test]
+    fn bring_to_front_keeps_relative_order() {
+        let mut store = StrokeStore::default();
+        let first = store.insert_stroke(line_stroke(0.0));
+        let second = store.insert_stroke(line_stroke(500.0));
+        let third = store.insert_stroke(line_stroke(0.0));
+
+        store.reorder_strokes(&[first, second], Reordering::BringToFront);
+
+        assert_eq!(store.keys_sorted_chrono(), vec![third, first, second]);
+    }
+
+    #[test]
+    fn send_to_back_keeps_relative_order() {
+        let mut store = StrokeStore::default();
+        let first = store.insert_stroke(line_stroke(0.0));
+        let second = store.insert_stroke(line_stroke(500.0));
+        let third = store.insert_stroke(line_stroke(0.0));
+
+        store.reorder_strokes(&[second, third], Reordering::SendToBack);
+
+        assert_eq!(store.keys_sorted_chrono(), vec![second, third, first]);
+    }
+
+    #[test]
+    fn bring_forward_steps_over_next_overlapping_stroke() {
+        let mut store = StrokeStore::default();
+        let raised = store.insert_stroke(line_stroke(0.0));
+        let apart = store.insert_stroke(line_stroke(500.0));
+        let overlapping = store.insert_stroke(line_stroke(0.0));
+        let top = store.insert_stroke(line_stroke(0.0));
+
+        store.reorder_strokes(&[raised], Reordering::BringForward);
+
+        assert_eq!(
+            store.keys_sorted_chrono(),
+            vec![apart, overlapping, raised, top]
+        );
+    }
+
+    #[test]
+    fn send_backward_steps_under_previous_overlapping_stroke() {
+        let mut store = StrokeStore::default();
+        let bottom = store.insert_stroke(line_stroke(0.0));
+        let overlapping = store.insert_stroke(line_stroke(0.0));
+        let apart = store.insert_stroke(line_stroke(500.0));
+        let lowered = store.insert_stroke(line_stroke(0.0));
+
+        store.reorder_strokes(&[lowered], Reordering::SendBackward);
+
+        assert_eq!(
+            store.keys_sorted_chrono(),
+            vec![bottom, lowered, overlapping, apart]
+        );
+    }
+
+    // Three overlapping strokes, where the trashed one is between the others
+    fn store_w_trashed_between() -> (StrokeStore, [StrokeKey; 3]) {
+        let mut store = StrokeStore::default();
+        let trashed = store.insert_stroke(line_stroke(0.0));
+        let lower = store.insert_stroke(line_stroke(0.0));
+        // Trashing moves the stroke to the top
+        store.set_trashed(trashed, true);
+        let upper = store.insert_stroke(line_stroke(0.0));
+        assert_eq!(store.keys_sorted_chrono(), vec![lower, trashed, upper]);
+
+        (store, [lower, trashed, upper])
+    }
+
+    #[test]
+    fn bring_forward_steps_over_trashed_strokes() {
+        let (mut store, [lower, trashed, upper]) = store_w_trashed_between();
+
+        store.reorder_strokes(&[lower], Reordering::BringForward);
+
+        assert_eq!(store.keys_sorted_chrono(), vec![trashed, upper, lower]);
+    }
+
+    #[test]
+    fn send_backward_steps_over_trashed_strokes() {
+        let (mut store, [lower, trashed, upper]) = store_w_trashed_between();
+
+        store.reorder_strokes(&[upper], Reordering::SendBackward);
+
+        assert_eq!(store.keys_sorted_chrono(), vec![upper, lower, trashed]);
+    }
+
+    #[test]
+    fn reordering_records_changes_of_moved_strokes() {
+        let mut store = StrokeStore::default();
+        let first = store.insert_stroke(line_stroke(0.0));
+        let second = store.insert_stroke(line_stroke(0.0));
+        let third = store.insert_stroke(line_stroke(500.0));
+
+        store.key_tree.set_track_changes(true);
+        store.reorder_strokes(&[first], Reordering::BringForward);
+        let changed_keys = store
+            .key_tree
+            .take_changes()
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect::<HashSet<StrokeKey>>();
+
+        assert_eq!(store.keys_sorted_chrono(), vec![second, first, third]);
+        assert_eq!(changed_keys, HashSet::from([first, second]));
+    }
+
+    fn rectangle_stroke(mins: na::Point2<f64>, maxs: na::Point2<f64>) -> Stroke {
+        Stroke::ShapeStroke(ShapeStroke::new(
+            Shape::Rectangle(Rectangle::from_p2d_aabb(AABB::new(mins, maxs))),
+            Style::default(),
+        ))
+    }
+
+    #[test]
+    fn boolean_op_skips_strokes_which_are_not_closed() {
+        let mut store = StrokeStore::default();
+        let first = store.insert_stroke(rectangle_stroke(
+            na::point![0.0, 0.0],
+            na::point![100.0, 100.0],
+        ));
+        let line = store.insert_stroke(line_stroke(50.0));
+        let second = store.insert_stroke(rectangle_stroke(
+            na::point![50.0, 50.0],
+            na::point![150.0, 150.0],
+        ));
+
+        let (union, operand_keys) = store
+            .gen_boolean_op_stroke(&[first, line, second], BooleanOp::Union)
+            .unwrap();
+        assert_eq!(operand_keys, vec![first, second]);
+        let bounds = union.bounds();
+        assert!((bounds.mins - na::point![0.0, 0.0]).magnitude() < 1.0);
+        assert!((bounds.maxs - na::point![150.0, 150.0]).magnitude() < 1.0);
+
+        let (intersection, _) = store
+            .gen_boolean_op_stroke(&[first, second], BooleanOp::Intersection)
+            .unwrap();
+        let bounds = intersection.bounds();
+        assert!((bounds.mins - na::point![50.0, 50.0]).magnitude() < 1.0);
+        assert!((bounds.maxs - na::point![100.0, 100.0]).magnitude() < 1.0);
+
+        assert!(store
+            .gen_boolean_op_stroke(&[first, line], BooleanOp::Union)
+            .is_none());
+    }
+
+    fn bounds_of(store: &StrokeStore, key: StrokeKey) -> AABB {
+        store.stroke_components.get(key).unwrap().bounds()
+    }
+
+    #[test]
+    fn align_moves_groups_as_a_whole() {
+        let mut store = StrokeStore::default();
+        let single = store.insert_stroke(rectangle_stroke(
+            na::point![0.0, 0.0],
+            na::point![10.0, 10.0],
+        ));
+        let grouped_first = store.insert_stroke(rectangle_stroke(
+            na::point![50.0, 20.0],
+            na::point![60.0, 30.0],
+        ));
+        let grouped_second = store.insert_stroke(rectangle_stroke(
+            na::point![80.0, 40.0],
+            na::point![100.0, 50.0],
+        ));
+        store.group_strokes(&[grouped_first, grouped_second]);
+
+        store.align_strokes(&[single, grouped_first, grouped_second], Alignment::Left);
+
+        let single_bounds = bounds_of(&store, single);
+        let grouped_first_bounds = bounds_of(&store, grouped_first);
+        let grouped_second_bounds = bounds_of(&store, grouped_second);
+        assert!((grouped_first_bounds.mins[0] - single_bounds.mins[0]).abs() < 1e-9);
+        // The group keeps its layout
+        assert!((grouped_second_bounds.mins[0] - grouped_first_bounds.mins[0] - 30.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn distribute_spaces_groups_by_their_combined_bounds() {
+        let mut store = StrokeStore::default();
+        let first = store.insert_stroke(rectangle_stroke(
+            na::point![0.0, 0.0],
+            na::point![10.0, 10.0],
+        ));
+        let grouped_first = store.insert_stroke(rectangle_stroke(
+            na::point![20.0, 0.0],
+            na::point![30.0, 10.0],
+        ));
+        let grouped_second = store.insert_stroke(rectangle_stroke(
+            na::point![40.0, 0.0],
+            na::point![50.0, 10.0],
+        ));
+        let last = store.insert_stroke(rectangle_stroke(
+            na::point![90.0, 0.0],
+            na::point![100.0, 10.0],
+        ));
+        store.group_strokes(&[grouped_first, grouped_second]);
+        let keys = [first, grouped_first, grouped_second, last];
+        let prev_bounds = keys
+            .iter()
+            .map(|&key| bounds_of(&store, key))
+            .collect::<Vec<AABB>>();
+
+        store.distribute_strokes(&keys, Axis::Horizontal);
+
+        // The extents sum up to 50, so the remaining 50 are split into two gaps of 25 and the group moves from 20 to 35
+        for (&key, (prev_bounds, expected_offset)) in keys
+            .iter()
+            .zip(prev_bounds.iter().zip([0.0, 15.0, 15.0, 0.0]))
+        {
+            let bounds = bounds_of(&store, key);
+            assert!((bounds.mins[0] - prev_bounds.mins[0] - expected_offset).abs() < 1e-9);
+            assert!((bounds.mins[1] - prev_bounds.mins[1]).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn match_extents_resizes_groups_as_a_whole() {
+        let mut store = StrokeStore::default();
+        let single = store.insert_stroke(rectangle_stroke(
+            na::point![0.0, 0.0],
+            na::point![10.0, 10.0],
+        ));
+        let grouped_first = store.insert_stroke(rectangle_stroke(
+            na::point![0.0, 20.0],
+            na::point![10.0, 30.0],
+        ));
+        let grouped_second = store.insert_stroke(rectangle_stroke(
+            na::point![30.0, 20.0],
+            na::point![40.0, 30.0],
+        ));
+        store.group_strokes(&[grouped_first, grouped_second]);
+        let grouped_first_bounds = bounds_of(&store, grouped_first);
+        let grouped_second_bounds = bounds_of(&store, grouped_second);
+
+        store.match_strokes_extents(&[single, grouped_first, grouped_second], Axis::Horizontal);
+
+        // The members of the group are not matched to each other
+        for (key, prev_bounds) in [
+            (grouped_first, grouped_first_bounds),
+            (grouped_second, grouped_second_bounds),
+        ] {
+            let bounds = bounds_of(&store, key);
+            assert!((bounds.mins - prev_bounds.mins).magnitude() < 1e-9);
+            assert!((bounds.maxs - prev_bounds.maxs).magnitude() < 1e-9);
+        }
+        // The stroke width is not scaled, so the single stroke only approaches the width of the group
+        assert!(bounds_of(&store, single).extents()[0] > 30.0);
     }
 }
@@ -1,4 +1,4 @@
-use super::{Stroke, StrokeKey, StrokeStore};
+use super::{Stroke, StrokeKey, StrokeStore, TileCache, TileKey};
 use crate::engine::visual_debug;
 use crate::engine::{EngineTask, EngineTaskSender};
 use crate::strokes::strokebehaviour::GeneratedStrokeImages;
@@ -6,6 +6,9 @@ use crate::strokes::StrokeBehaviour;
 use crate::utils::{GdkRGBAHelpers, GrapheneRectHelpers};
 use crate::{render, DrawBehaviour, RnoteEngine};
 
+use std::collections::HashSet;
+use std::sync::Arc;
+
 use anyhow::Context;
 use gtk4::{gdk, graphene, gsk, Snapshot};
 use p2d::bounding_volume::{BoundingVolume, AABB};
@@ -219,7 +222,31 @@ impl StrokeStore {
         viewport: AABB,
         image_scale: f64,
     ) {
-        let keys = self.render_components.keys().collect::<Vec<StrokeKey>>();
+        let keys = if self.tile_cache.is_some() {
+            self.regenerate_tiles_in_viewport_threaded(tasks_tx.clone(), viewport, image_scale);
+
+            // With tile rendering only the selection and the strokes in the overlay are rendered on their own
+            let mut keys = self.selection_keys_unordered();
+            keys.extend(
+                self.tile_cache
+                    .as_ref()
+                    .map(|tile_cache| tile_cache.overlay_keys())
+                    .unwrap_or_default(),
+            );
+            let keys = keys.into_iter().collect::<HashSet<StrokeKey>>();
+
+            for (key, render_comp) in self.render_components.iter_mut() {
+                if !keys.contains(&key) {
+                    render_comp.rendernodes = vec![];
+                    render_comp.images = vec![];
+                    render_comp.state = RenderCompState::Dirty;
+                }
+            }
+
+            keys.into_iter().collect::<Vec<StrokeKey>>()
+        } else {
+            self.render_components.keys().collect::<Vec<StrokeKey>>()
+        };
 
         keys.into_iter().for_each(|key| {
             if let (Some(stroke), Some(render_comp)) =
@@ -289,6 +316,117 @@ impl StrokeStore {
         })
     }
 
+    /// Whether the strokes are rendered into cached tiles of the document instead of per stroke
+    pub fn tile_rendering(&self) -> bool {
+        self.tile_cache.is_some()
+    }
+
+    /// Enables / disables rendering the strokes into cached tiles. The rendering then needs to be regenerated
+    pub fn set_tile_rendering(&mut self, tile_rendering: bool) {
+        if tile_rendering == self.tile_rendering() {
+            return;
+        }
+
+        self.tile_cache = tile_rendering.then(TileCache::default);
+        self.key_tree.set_track_changes(tile_rendering);
+        self.set_rendering_dirty_all_keys();
+    }
+
+    /// Regenerates the tiles for the given viewport that need rerendering. Invalidates the tiles which are covered by changed strokes first.
+    /// Does nothing if tile rendering is disabled
+    pub fn regenerate_tiles_in_viewport_threaded(
+        &mut self,
+        tasks_tx: EngineTaskSender,
+        viewport: AABB,
+        image_scale: f64,
+    ) {
+        // extending the viewport by the factor
+        let viewport_render_margins = viewport.extents() * render::VIEWPORT_EXTENTS_MARGIN_FACTOR;
+        let viewport = viewport.extend_by(viewport_render_margins);
+
+        let changes = self.key_tree.take_changes();
+        let stroke_components = &self.stroke_components;
+
+        let tile_keys = match self.tile_cache.as_mut() {
+            Some(tile_cache) => {
+                tile_cache.record_changes(changes);
+                tile_cache.set_level(TileCache::level_for_image_scale(image_scale));
+                tile_cache.update_overlay(viewport, |key| {
+                    stroke_components.get(key).map(|stroke| stroke.bounds())
+                });
+
+                tile_cache.tiles_to_render(viewport)
+            }
+            None => return,
+        };
+
+        for tile_key in tile_keys {
+            let strokes = self
+                .stroke_keys_as_rendered_intersecting_bounds(tile_key.content_bounds())
+                .into_iter()
+                .filter_map(|key| self.stroke_components.get(key).cloned())
+                .collect::<Vec<Arc<Stroke>>>();
+
+            let tile_cache = match self.tile_cache.as_mut() {
+                Some(tile_cache) => tile_cache,
+                None => return,
+            };
+            let request_id = tile_cache.request_tile(tile_key);
+
+            // Tiles without content don't need to be rendered
+            if strokes.is_empty() {
+                if let Err(e) = tile_cache.insert_tile(tile_key, request_id, None) {
+                    log::error!("insert_tile() failed in regenerate_tiles_in_viewport_threaded() for tile {:?}, with Err {}", tile_key, e);
+                }
+                continue;
+            }
+
+            let tasks_tx = tasks_tx.clone();
+
+            // Spawn a new thread for image rendering
+            rayon::spawn(move || {
+                let tile_image_scale = TileCache::level_image_scale(tile_key.level);
+
+                match render::Image::gen_with_piet(
+                    |piet_cx| {
+                        for stroke in strokes.iter() {
+                            stroke.draw(piet_cx, tile_image_scale)?;
+                        }
+                        Ok(())
+                    },
+                    tile_key.bounds(),
+                    tile_image_scale,
+                ) {
+                    Ok(image) => {
+                        tasks_tx.unbounded_send(EngineTask::UpdateTileWithImage {
+                            tile_key,
+                            request_id,
+                            image,
+                        }).unwrap_or_else(|e| {
+                            log::error!("tasks_tx.send() UpdateTileWithImage failed in regenerate_tiles_in_viewport_threaded() for tile {:?}, with Err, {}", tile_key, e);
+                        });
+                    }
+                    Err(e) => {
+                        log::debug!("Image::gen_with_piet() failed in regenerate_tiles_in_viewport_threaded() for tile {:?}, with Err {}", tile_key, e);
+                    }
+                }
+            });
+        }
+    }
+
+    /// Inserts the rendered image of the tile into the tile cache, if it still was requested with the given id
+    pub fn replace_tile_with_image(
+        &mut self,
+        tile_key: TileKey,
+        request_id: u64,
+        image: render::Image,
+    ) -> anyhow::Result<()> {
+        if let Some(tile_cache) = self.tile_cache.as_mut() {
+            tile_cache.insert_tile(tile_key, request_id, Some(image))?;
+        }
+        Ok(())
+    }
+
     /// generates images and appends them to the render component for the last segments of brushstrokes. For other strokes the rendering is regenerated completely
     pub fn append_rendering_last_segments(
         &mut self,
@@ -385,6 +523,31 @@ impl StrokeStore {
     pub fn draw_strokes_snapshot(&self, snapshot: &Snapshot, doc_bounds: AABB, viewport: AABB) {
         snapshot.push_clip(&graphene::Rect::from_p2d_aabb(doc_bounds));
 
+        if let Some(tile_cache) = self.tile_cache.as_ref() {
+            tile_cache.draw(snapshot, viewport);
+
+            // The strokes that changed since their tiles were rendered are drawn on top
+            let mut overlay_keys = tile_cache
+                .overlay_keys()
+                .into_iter()
+                .collect::<HashSet<StrokeKey>>();
+            overlay_keys.extend(self.key_tree.changes().iter().map(|(key, _)| *key));
+
+            self.stroke_keys_as_rendered_intersecting_bounds(viewport)
+                .into_iter()
+                .filter(|key| overlay_keys.contains(key))
+                .for_each(|key| {
+                    if let Some(render_comp) = self.render_components.get(key) {
+                        for rendernode in render_comp.rendernodes.iter() {
+                            snapshot.append_node(rendernode);
+                        }
+                    }
+                });
+
+            snapshot.pop();
+            return;
+        }
+
         self.stroke_keys_as_rendered_intersecting_bounds(viewport)
             .iter()
             .for_each(|&key| {
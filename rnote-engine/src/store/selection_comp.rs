@@ -1,6 +1,7 @@
 use super::{StrokeKey, StrokeStore};
 
 use p2d::bounding_volume::AABB;
+use rnote_compose::shapes::ShapeBehaviour;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
@@ -43,9 +44,16 @@ impl StrokeStore {
             .get_mut(key)
             .map(Arc::make_mut)
         {
+            if selection_comp.selected == selected {
+                return;
+            }
             selection_comp.selected = selected;
+            // The chrono component is not updated to the last here. It is the z-order of the strokes,
+            // which should only change through `reorder_strokes()` and not by selecting them.
 
-            self.update_chrono_to_last(key);
+            if let Some(stroke) = self.stroke_components.get(key) {
+                self.key_tree.record_change(key, stroke.bounds());
+            }
         }
     }
 
@@ -108,6 +116,7 @@ impl StrokeStore {
                 Some(new_key)
             })
             .collect::<Vec<StrokeKey>>();
+        self.copy_groups_w_new_ids(&old_selected, &new_selected);
 
         // Offsetting the new selected stroke to make the duplication apparent
         self.translate_strokes(
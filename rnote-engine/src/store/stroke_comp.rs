@@ -1,18 +1,69 @@
 use super::render_comp::RenderCompState;
 use super::StrokeKey;
 use crate::pens::tools::DragProximityTool;
-use crate::strokes::Stroke;
-use crate::{render, StrokeStore};
+use crate::strokes::{ShapeStroke, Stroke};
+use crate::{render, utils, StrokeStore};
 use geo::intersects::Intersects;
 use geo::prelude::Contains;
 use rnote_compose::helpers;
 use rnote_compose::penpath::{Element, Segment};
-use rnote_compose::shapes::ShapeBehaviour;
+use rnote_compose::shapes::{Shape, ShapeBehaviour};
 use rnote_compose::transform::TransformBehaviour;
+use rnote_compose::Style;
 
 use p2d::bounding_volume::{BoundingSphere, BoundingVolume, AABB};
 use std::sync::Arc;
 
+/// The alignment of strokes relative to their enclosing bounds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alignment {
+    Left,
+    Right,
+    Top,
+    Bottom,
+    /// aligns the horizontal centers
+    HorizontalCenter,
+    /// aligns the vertical centers
+    VerticalCenter,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+impl Axis {
+    fn index(self) -> usize {
+        match self {
+            Self::Horizontal => 0,
+            Self::Vertical => 1,
+        }
+    }
+}
+
+/// A boolean operation between the closed outlines of strokes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BooleanOp {
+    Union,
+    Intersection,
+    /// subtracts the other operands from the first
+    Difference,
+    /// keeps the areas which are covered by an odd number of operands
+    Xor,
+}
+
+impl BooleanOp {
+    fn op_type(self) -> geo::OpType {
+        match self {
+            Self::Union => geo::OpType::Union,
+            Self::Intersection => geo::OpType::Intersection,
+            Self::Difference => geo::OpType::Difference,
+            Self::Xor => geo::OpType::Xor,
+        }
+    }
+}
+
 /// Systems that are related to the stroke components.
 impl StrokeStore {
     /// Gets a reference to a stroke
@@ -34,6 +85,13 @@ impl StrokeStore {
             .collect::<Vec<&Stroke>>()
     }
 
+    /// Gets shared references to the strokes, e.g. to render them in a separate thread
+    pub(crate) fn get_strokes_arc(&self, keys: &[StrokeKey]) -> Vec<Arc<Stroke>> {
+        keys.iter()
+            .filter_map(|&key| self.stroke_components.get(key).cloned())
+            .collect::<Vec<Arc<Stroke>>>()
+    }
+
     /// Adds a segment to the brush stroke. If the stroke is not a brushstroke this does nothing.
     /// stroke then needs to update its geometry and its rendering
     pub fn add_segment_to_brushstroke(&mut self, key: StrokeKey, segment: Segment) {
@@ -147,6 +205,29 @@ impl StrokeStore {
         strokes_max_y - strokes_min_y
     }
 
+    /// Calculates the width needed to fit all strokes
+    pub fn calc_width(&self) -> f64 {
+        let strokes_iter = self.stroke_keys_unordered().into_iter().filter_map(|key| {
+            let stroke = self.stroke_components.get(key)?;
+            let trash_comp = self.trash_components.get(key)?;
+
+            if !trash_comp.trashed {
+                Some(stroke)
+            } else {
+                None
+            }
+        });
+
+        let strokes_min_x = strokes_iter
+            .clone()
+            .fold(0.0, |acc, stroke| stroke.bounds().mins[0].min(acc));
+        let strokes_max_x = strokes_iter
+            .clone()
+            .fold(0.0, |acc, stroke| stroke.bounds().maxs[0].max(acc));
+
+        strokes_max_x - strokes_min_x
+    }
+
     /// Generates the enclosing bounds for the given stroke keys
     pub fn bounds_for_strokes(&self, keys: &[StrokeKey]) -> Option<AABB> {
         let mut keys_iter = keys.iter();
@@ -228,6 +309,23 @@ impl StrokeStore {
         });
     }
 
+    /// Mirrors the strokes across the axis through the center, which has the angle (rad) to the x-axis.
+    /// strokes then need to update their rendering
+    pub fn mirror_strokes(&mut self, keys: &[StrokeKey], axis_angle: f64, center: na::Point2<f64>) {
+        keys.iter().for_each(|&key| {
+            if let Some(stroke) = Arc::make_mut(&mut self.stroke_components)
+                .get_mut(key)
+                .map(Arc::make_mut)
+            {
+                {
+                    // mirror the stroke geometry
+                    stroke.mirror(axis_angle, center);
+                    self.key_tree.update_with_key(key, stroke.bounds());
+                }
+            }
+        });
+    }
+
     pub fn rotate_strokes_images(
         &mut self,
         keys: &[StrokeKey],
@@ -339,9 +437,8 @@ impl StrokeStore {
                         old_bounds,
                         new_bounds,
                     );
-                    let scale = new_stroke_bounds
-                        .extents()
-                        .component_div(&old_stroke_bounds.extents());
+                    let scale =
+                        resize_scale(old_stroke_bounds.extents(), new_stroke_bounds.extents());
                     let rel_offset = new_stroke_bounds.center() - old_stroke_bounds.center();
 
                     // Translate in relation to the outer bounds
@@ -373,9 +470,8 @@ impl StrokeStore {
                         old_bounds,
                         new_bounds,
                     );
-                    let scale = new_image_bounds
-                        .extents()
-                        .component_div(&old_image_bounds.extents());
+                    let scale =
+                        resize_scale(old_image_bounds.extents(), new_image_bounds.extents());
                     let rel_offset = new_image_bounds.center() - old_image_bounds.center();
 
                     // Translate in relation to the outer bounds
@@ -397,6 +493,228 @@ impl StrokeStore {
         });
     }
 
+    /// Aligns the strokes to the edges or the centers of their enclosing bounds. Groups are aligned as a whole by their combined bounds.
+    /// strokes then need to update their rendering
+    pub fn align_strokes(&mut self, keys: &[StrokeKey], alignment: Alignment) {
+        let all_bounds = match self.bounds_for_strokes(keys) {
+            Some(all_bounds) => all_bounds,
+            None => return,
+        };
+
+        for unit in self.keys_as_group_units(keys) {
+            let bounds = match self.bounds_for_strokes(&unit) {
+                Some(bounds) => bounds,
+                None => continue,
+            };
+
+            let offset = match alignment {
+                Alignment::Left => na::vector![all_bounds.mins[0] - bounds.mins[0], 0.0],
+                Alignment::Right => na::vector![all_bounds.maxs[0] - bounds.maxs[0], 0.0],
+                Alignment::Top => na::vector![0.0, all_bounds.mins[1] - bounds.mins[1]],
+                Alignment::Bottom => na::vector![0.0, all_bounds.maxs[1] - bounds.maxs[1]],
+                Alignment::HorizontalCenter => {
+                    na::vector![all_bounds.center()[0] - bounds.center()[0], 0.0]
+                }
+                Alignment::VerticalCenter => {
+                    na::vector![0.0, all_bounds.center()[1] - bounds.center()[1]]
+                }
+            };
+
+            self.translate_strokes(&unit, offset);
+        }
+    }
+
+    /// Distributes the strokes along the axis, so that the gaps between them are equal. Groups are distributed as a whole by their combined bounds.
+    /// The outermost strokes keep their position.
+    /// strokes then need to update their rendering
+    pub fn distribute_strokes(&mut self, keys: &[StrokeKey], axis: Axis) {
+        let i = axis.index();
+        let mut units_bounds = self
+            .keys_as_group_units(keys)
+            .into_iter()
+            .filter_map(|unit| {
+                let bounds = self.bounds_for_strokes(&unit)?;
+                Some((unit, bounds))
+            })
+            .collect::<Vec<(Vec<StrokeKey>, AABB)>>();
+
+        if units_bounds.len() < 3 {
+            return;
+        }
+
+        units_bounds.sort_unstable_by(|(_, first), (_, second)| {
+            first.center()[i]
+                .partial_cmp(&second.center()[i])
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        let start = units_bounds
+            .iter()
+            .map(|(_, bounds)| bounds.mins[i])
+            .fold(f64::MAX, f64::min);
+        let end = units_bounds
+            .iter()
+            .map(|(_, bounds)| bounds.maxs[i])
+            .fold(f64::MIN, f64::max);
+        let extents_sum = units_bounds
+            .iter()
+            .map(|(_, bounds)| bounds.extents()[i])
+            .sum::<f64>();
+        let gap = (end - start - extents_sum) / (units_bounds.len() - 1) as f64;
+
+        let mut next_pos = start;
+        for (unit, bounds) in units_bounds {
+            let mut offset = na::Vector2::<f64>::zeros();
+            offset[i] = next_pos - bounds.mins[i];
+
+            self.translate_strokes(&unit, offset);
+            next_pos += bounds.extents()[i] + gap;
+        }
+    }
+
+    /// Resizes the strokes so that their extents along the axis match the largest one. The strokes keep their upper left corner.
+    /// Groups are resized as a whole by their combined bounds.
+    /// strokes then need to update their rendering
+    pub fn match_strokes_extents(&mut self, keys: &[StrokeKey], axis: Axis) {
+        let i = axis.index();
+        let units_bounds = self
+            .keys_as_group_units(keys)
+            .into_iter()
+            .filter_map(|unit| {
+                let bounds = self.bounds_for_strokes(&unit)?;
+                Some((unit, bounds))
+            })
+            .collect::<Vec<(Vec<StrokeKey>, AABB)>>();
+
+        let max_extent = match units_bounds
+            .iter()
+            .map(|(_, bounds)| bounds.extents()[i])
+            .reduce(f64::max)
+        {
+            Some(max_extent) => max_extent,
+            None => return,
+        };
+
+        for (unit, bounds) in units_bounds {
+            // Strokes without extent along the axis can't be scaled
+            if bounds.extents()[i] <= 0.0 {
+                continue;
+            }
+
+            let mut new_maxs = bounds.maxs;
+            new_maxs[i] = bounds.mins[i] + max_extent;
+
+            self.resize_strokes(&unit, AABB::new(bounds.mins, new_maxs));
+        }
+    }
+
+    /// Combines the closed outlines of the strokes with the boolean operation, in the order of the keys.
+    /// Strokes which are not closed or whose outline the boolean operation can't handle are skipped.
+    /// The result is a new path shape stroke which is styled like the first operand.
+    ///
+    /// Returns the new stroke together with the keys of the operands,
+    /// or None if there are less than two usable strokes or the result is empty.
+    pub fn gen_boolean_op_stroke(
+        &self,
+        keys: &[StrokeKey],
+        op: BooleanOp,
+    ) -> Option<(Stroke, Vec<StrokeKey>)> {
+        let operands = keys.iter().filter_map(|&key| {
+            let stroke = self.stroke_components.get(key)?;
+            Some((key, stroke.closed_outline()?))
+        });
+
+        let mut operand_keys = vec![];
+        let mut result: Option<geo::MultiPolygon<f64>> = None;
+        for (key, outline) in operands {
+            let next = match utils::bezier_path_to_multipolygon(&outline) {
+                Some(next) => next,
+                None => {
+                    log::warn!("skipping stroke with invalid outline in boolean operation");
+                    continue;
+                }
+            };
+
+            if let Some(acc) = &result {
+                match utils::multipolygon_boolean_op(acc, &next, op.op_type()) {
+                    Some(combined) => result = Some(combined),
+                    None => {
+                        log::warn!("skipping stroke in failed boolean operation");
+                        continue;
+                    }
+                }
+            } else {
+                result = Some(next);
+            }
+            operand_keys.push(key);
+        }
+
+        if operand_keys.len() < 2 {
+            return None;
+        }
+
+        let style = match self.stroke_components.get(operand_keys[0])?.as_ref() {
+            Stroke::BrushStroke(brushstroke) => brushstroke.style.clone(),
+            Stroke::ShapeStroke(shapestroke) => shapestroke.style.clone(),
+            Stroke::TextStroke(_) | Stroke::VectorImage(_) | Stroke::BitmapImage(_) => {
+                Style::default()
+            }
+        };
+
+        let path = utils::multipolygon_to_bezier_path(&result?);
+        if path.subpaths.is_empty() {
+            return None;
+        }
+
+        Some((
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Path(path),
+                boolean_op_result_style(style),
+            )),
+            operand_keys,
+        ))
+    }
+
+    /// Replaces the brush strokes with path shape strokes of their filled outlines. Other strokes are skipped.
+    /// strokes then need to update their rendering
+    pub fn convert_brushstrokes_to_outlines(&mut self, keys: &[StrokeKey]) {
+        for &key in keys {
+            let outline = match self
+                .stroke_components
+                .get(key)
+                .map(|stroke| stroke.as_ref())
+            {
+                Some(Stroke::BrushStroke(brushstroke)) => brushstroke.to_outline(),
+                _ => None,
+            };
+
+            if let (Some(outline), Some(stroke)) = (outline, self.get_stroke_mut(key)) {
+                *stroke = Stroke::ShapeStroke(outline);
+                self.update_geometry_for_stroke(key);
+            }
+        }
+    }
+
+    /// Replaces the closed shape strokes with brush strokes along their estimated centerlines. Other strokes are skipped.
+    /// strokes then need to update their rendering
+    pub fn convert_outlines_to_brushstrokes(&mut self, keys: &[StrokeKey]) {
+        for &key in keys {
+            let centerline = match self
+                .stroke_components
+                .get(key)
+                .map(|stroke| stroke.as_ref())
+            {
+                Some(Stroke::ShapeStroke(shapestroke)) => shapestroke.to_centerline(),
+                _ => None,
+            };
+
+            if let (Some(centerline), Some(stroke)) = (centerline, self.get_stroke_mut(key)) {
+                *stroke = Stroke::BrushStroke(centerline);
+                self.update_geometry_for_stroke(key);
+            }
+        }
+    }
+
     /// returns the strokes whose hitboxes are contained in the given polygon path.
     pub fn strokes_hitboxes_contained_in_path_polygon(
         &mut self,
@@ -561,6 +879,20 @@ impl StrokeStore {
             .collect::<Vec<StrokeKey>>()
     }
 
+    /// Returns all keys right of the x_pos
+    pub fn keys_right_of_x_pos(&self, x_pos: f64) -> Vec<StrokeKey> {
+        self.stroke_components
+            .iter()
+            .filter_map(|(key, stroke)| {
+                if stroke.bounds().mins[0] > x_pos {
+                    Some(key)
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<StrokeKey>>()
+    }
+
     /// Unimplemented!
     /// strokes then need to update their rendering
     pub fn drag_strokes_proximity(&mut self, drag_proximity_tool: &DragProximityTool) {
@@ -582,3 +914,34 @@ impl StrokeStore {
         todo!()
     }
 }
+
+/// The result of a boolean operation is an area, so it should be filled even if the first operand was only stroked
+fn boolean_op_result_style(style: Style) -> Style {
+    match style {
+        Style::Smooth(mut options) => {
+            options.fill_color = options.fill_color.or(options.stroke_color);
+            Style::Smooth(options)
+        }
+        Style::Rough(mut options) => {
+            options.fill_color = options.fill_color.or(options.stroke_color);
+            Style::Rough(options)
+        }
+        // The textured, calligraphy and pencil styles can't be applied to paths
+        Style::Textured(_) | Style::Calligraphy(_) | Style::Pencil(_) => {
+            let mut options = style.smooth_fallback_options();
+            options.fill_color = options.stroke_color;
+            Style::Smooth(options)
+        }
+    }
+}
+
+/// The scale from the old to the new extents. Axes without an old extent (e.g. of a straight line) can't be scaled and keep a scale of 1.0
+fn resize_scale(old_extents: na::Vector2<f64>, new_extents: na::Vector2<f64>) -> na::Vector2<f64> {
+    new_extents.zip_map(&old_extents, |new_extent, old_extent| {
+        if old_extent > 0.0 {
+            new_extent / old_extent
+        } else {
+            1.0
+        }
+    })
+}
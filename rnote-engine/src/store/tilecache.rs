@@ -0,0 +1,637 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+
+use gtk4::{graphene, gsk, Snapshot};
+use p2d::bounding_volume::{BoundingVolume, AABB};
+
+use super::StrokeKey;
+use crate::render;
+use crate::utils::GrapheneRectHelpers;
+
+/// The key of a tile of the document. Consists of the zoom level and the position in the tile grid of the level.
+/// Ordered by level first and then by column, so that the tiles of a level in a range of columns are next to each other
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TileKey {
+    pub level: i32,
+    pub x: i64,
+    pub y: i64,
+}
+
+impl TileKey {
+    /// The bounds of the tile in the coordinate space of the doc
+    pub fn bounds(&self) -> AABB {
+        let size = TileCache::tile_size(self.level);
+
+        AABB::new(
+            na::point![self.x as f64 * size, self.y as f64 * size],
+            na::point![(self.x + 1) as f64 * size, (self.y + 1) as f64 * size],
+        )
+    }
+
+    /// The bounds of the content that is rendered into the tile. Loosened by one pixel, so that the antialiasing of neighbouring strokes is not cut off
+    pub fn content_bounds(&self) -> AABB {
+        self.bounds()
+            .loosened(1.0 / TileCache::level_image_scale(self.level))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CachedTile {
+    /// None for tiles without content
+    rendernode: Option<gsk::RenderNode>,
+    /// the size of the image data in bytes
+    n_bytes: usize,
+    /// the id of the request the tile was rendered for
+    request_id: u64,
+    /// stale tiles are still drawn until their rerendered version replaces them
+    stale: bool,
+    /// the value of the counter when the tile was last drawn. Its key in the index of the least recently used tiles
+    last_used: Cell<u64>,
+}
+
+/// A cache of rendered tiles of the document. The tiles have a fixed size in pixels and are rendered at discrete zoom levels,
+/// which are powers of two of the zoom.
+///
+/// Strokes that changed after the tiles covering them were rendered are drawn with their own rendering on top of the tiles (the overlay),
+/// until the tiles are rerendered. When the cache grows above its memory cap, the least recently used tiles are evicted.
+#[derive(Debug)]
+pub struct TileCache {
+    /// ordered, so that the tiles of other levels can be looked up by their position
+    tiles: BTreeMap<TileKey, CachedTile>,
+    /// the keys of the tiles ordered by when they were last used, for evicting the least recently used tiles.
+    /// Updated when the tiles are drawn, so it needs interior mutability
+    lru: RefCell<BTreeMap<u64, TileKey>>,
+    /// the tiles that are currently rendered in a task, with the id of their request
+    requested: HashMap<TileKey, u64>,
+    /// the strokes in the overlay, with the value of the counter when they changed
+    overlay_keys: HashMap<StrokeKey, u64>,
+    /// the level of the tiles that are drawn
+    level: i32,
+    /// incremented for every request and every drawn tile
+    counter: Cell<u64>,
+    /// the current memory usage of the tiles in bytes
+    memory_usage: usize,
+    /// the memory cap of the tiles in bytes
+    pub memory_cap: usize,
+}
+
+impl Default for TileCache {
+    fn default() -> Self {
+        Self {
+            tiles: BTreeMap::new(),
+            lru: RefCell::new(BTreeMap::new()),
+            requested: HashMap::new(),
+            overlay_keys: HashMap::new(),
+            level: 0,
+            counter: Cell::new(0),
+            memory_usage: 0,
+            memory_cap: Self::MEMORY_CAP_DEFAULT,
+        }
+    }
+}
+
+impl TileCache {
+    /// The size of the tiles in pixels
+    pub const TILE_PIXEL_SIZE: f64 = 512.0;
+    /// The min zoom level
+    pub const LEVEL_MIN: i32 = -6;
+    /// The max zoom level
+    pub const LEVEL_MAX: i32 = 4;
+    /// The default memory cap in bytes
+    pub const MEMORY_CAP_DEFAULT: usize = 256 * 1024 * 1024;
+    /// The max number of tiles, including the ones without content
+    pub const N_TILES_MAX: usize = 8192;
+
+    /// The zoom level for the given image scale
+    pub fn level_for_image_scale(image_scale: f64) -> i32 {
+        (image_scale.log2().round() as i32).clamp(Self::LEVEL_MIN, Self::LEVEL_MAX)
+    }
+
+    /// The image scale the tiles of the level are rendered with
+    pub fn level_image_scale(level: i32) -> f64 {
+        2_f64.powi(level)
+    }
+
+    /// The size of the tiles of the level in the coordinate space of the doc
+    pub fn tile_size(level: i32) -> f64 {
+        Self::TILE_PIXEL_SIZE / Self::level_image_scale(level)
+    }
+
+    /// The level of the tiles that are drawn
+    pub fn level(&self) -> i32 {
+        self.level
+    }
+
+    pub fn set_level(&mut self, level: i32) {
+        self.level = level.clamp(Self::LEVEL_MIN, Self::LEVEL_MAX);
+    }
+
+    /// The current memory usage of the tiles in bytes
+    pub fn memory_usage(&self) -> usize {
+        self.memory_usage
+    }
+
+    /// The ranges of the columns and rows of the tiles of the level that intersect with the bounds
+    fn tile_ranges_for_bounds(bounds: AABB, level: i32) -> (Range<i64>, Range<i64>) {
+        let size = Self::tile_size(level);
+
+        let x_start = (bounds.mins[0] / size).floor() as i64;
+        let y_start = (bounds.mins[1] / size).floor() as i64;
+        let x_end = ((bounds.maxs[0] / size).ceil() as i64).max(x_start + 1);
+        let y_end = ((bounds.maxs[1] / size).ceil() as i64).max(y_start + 1);
+
+        (x_start..x_end, y_start..y_end)
+    }
+
+    /// The keys of the tiles of the level that intersect with the bounds
+    pub fn tile_keys_for_bounds(bounds: AABB, level: i32) -> Vec<TileKey> {
+        let (x_range, y_range) = Self::tile_ranges_for_bounds(bounds, level);
+
+        y_range
+            .flat_map(|y| x_range.clone().map(move |x| TileKey { level, x, y }))
+            .collect()
+    }
+
+    /// The range of the keys of the tiles of the level in the columns that intersect with the bounds, and the range of the rows
+    fn tile_key_range_for_bounds(bounds: AABB, level: i32) -> (Range<TileKey>, Range<i64>) {
+        let (x_range, y_range) = Self::tile_ranges_for_bounds(bounds, level);
+        let start = TileKey {
+            level,
+            x: x_range.start,
+            y: i64::MIN,
+        };
+        let end = TileKey {
+            level,
+            x: x_range.end,
+            y: i64::MIN,
+        };
+
+        (start..end, y_range)
+    }
+
+    /// The cached tiles of the level that intersect with the bounds
+    fn cached_tiles_for_bounds(
+        &self,
+        bounds: AABB,
+        level: i32,
+    ) -> impl Iterator<Item = (&TileKey, &CachedTile)> {
+        let (key_range, y_range) = Self::tile_key_range_for_bounds(bounds, level);
+
+        self.tiles
+            .range(key_range)
+            .filter(move |(tile_key, _)| y_range.contains(&tile_key.y))
+    }
+
+    fn next_count(&self) -> u64 {
+        let count = self.counter.get() + 1;
+        self.counter.set(count);
+        count
+    }
+
+    /// Invalidates the tiles that intersect with the bounds. They are still drawn until they are rerendered, running requests for them are discarded.
+    pub fn invalidate_bounds(&mut self, bounds: AABB) {
+        for level in Self::LEVEL_MIN..=Self::LEVEL_MAX {
+            // The content bounds of the tiles are loosened by one pixel of their level
+            let level_bounds = bounds.loosened(1.0 / Self::level_image_scale(level));
+            let (key_range, y_range) = Self::tile_key_range_for_bounds(level_bounds, level);
+
+            for (_, tile) in self
+                .tiles
+                .range_mut(key_range)
+                .filter(|(tile_key, _)| y_range.contains(&tile_key.y))
+            {
+                tile.stale = true;
+            }
+        }
+
+        self.requested
+            .retain(|tile_key, _| !tile_key.content_bounds().intersects(&bounds));
+    }
+
+    /// Records the changed strokes. Invalidates the tiles covering them and puts them into the overlay
+    pub fn record_changes(&mut self, changes: Vec<(StrokeKey, AABB)>) {
+        if changes.is_empty() {
+            return;
+        }
+        let changed_at = self.next_count();
+
+        for (key, bounds) in changes {
+            self.invalidate_bounds(bounds);
+            self.overlay_keys.insert(key, changed_at);
+        }
+    }
+
+    /// The keys of the strokes in the overlay, in no particular order
+    pub fn overlay_keys(&self) -> Vec<StrokeKey> {
+        self.overlay_keys.keys().copied().collect()
+    }
+
+    /// Removes the strokes from the overlay which are either not in the viewport anymore,
+    /// or for which all tiles of the current level in the viewport were rendered after they changed.
+    /// `stroke_bounds` returns the bounds of the stroke, or None if it does not exist anymore.
+    pub fn update_overlay<F>(&mut self, viewport: AABB, stroke_bounds: F)
+    where
+        F: Fn(StrokeKey) -> Option<AABB>,
+    {
+        let tiles = &self.tiles;
+        let level = self.level;
+
+        self.overlay_keys.retain(|&key, &mut changed_at| {
+            let bounds = match stroke_bounds(key).and_then(|bounds| bounds.intersection(&viewport))
+            {
+                Some(bounds) => bounds,
+                None => return false,
+            };
+
+            Self::tile_keys_for_bounds(bounds, level)
+                .iter()
+                .any(|tile_key| {
+                    tiles
+                        .get(tile_key)
+                        .map_or(true, |tile| tile.stale || tile.request_id < changed_at)
+                })
+        });
+    }
+
+    /// The tiles of the current level in the viewport that need to be (re)rendered and are not requested yet
+    pub fn tiles_to_render(&self, viewport: AABB) -> Vec<TileKey> {
+        Self::tile_keys_for_bounds(viewport, self.level)
+            .into_iter()
+            .filter(|tile_key| {
+                !self.requested.contains_key(tile_key)
+                    && self.tiles.get(tile_key).map_or(true, |tile| tile.stale)
+            })
+            .collect()
+    }
+
+    /// Marks the tile as requested and returns the id of the request
+    pub fn request_tile(&mut self, tile_key: TileKey) -> u64 {
+        let request_id = self.next_count();
+        self.requested.insert(tile_key, request_id);
+
+        request_id
+    }
+
+    /// Inserts the rendered tile, if it is still requested with the given id. A None image is inserted as tile without content.
+    /// Evicts the least recently used tiles when the memory cap is exceeded
+    pub fn insert_tile(
+        &mut self,
+        tile_key: TileKey,
+        request_id: u64,
+        image: Option<render::Image>,
+    ) -> anyhow::Result<()> {
+        if self.requested.get(&tile_key) != Some(&request_id) {
+            // The request is outdated
+            return Ok(());
+        }
+        self.requested.remove(&tile_key);
+
+        let (rendernode, n_bytes) = match image {
+            Some(image) => (Some(image.to_rendernode(None)?), image.data.len()),
+            None => (None, 0),
+        };
+
+        let tile = CachedTile {
+            rendernode,
+            n_bytes,
+            request_id,
+            stale: false,
+            last_used: Cell::new(self.next_count()),
+        };
+        self.insert_cached_tile(tile_key, tile);
+
+        self.evict();
+
+        Ok(())
+    }
+
+    /// Inserts the tile and replaces the previous tile with the key
+    fn insert_cached_tile(&mut self, tile_key: TileKey, tile: CachedTile) {
+        self.memory_usage += tile.n_bytes;
+        self.lru.get_mut().insert(tile.last_used.get(), tile_key);
+
+        if let Some(old_tile) = self.tiles.insert(tile_key, tile) {
+            self.memory_usage -= old_tile.n_bytes;
+            self.lru.get_mut().remove(&old_tile.last_used.get());
+        }
+    }
+
+    /// Marks the tile as the most recently used
+    fn mark_used(&self, tile_key: TileKey, tile: &CachedTile) {
+        let mut lru = self.lru.borrow_mut();
+        lru.remove(&tile.last_used.get());
+
+        let last_used = self.next_count();
+        tile.last_used.set(last_used);
+        lru.insert(last_used, tile_key);
+    }
+
+    /// Evicts the least recently used tiles until the memory usage is below the cap
+    fn evict(&mut self) {
+        while self.memory_usage > self.memory_cap || self.tiles.len() > Self::N_TILES_MAX {
+            let (last_used, lru_key) = match self.lru.get_mut().iter().next() {
+                Some((&last_used, &lru_key)) => (last_used, lru_key),
+                None => break,
+            };
+            self.lru.get_mut().remove(&last_used);
+
+            if let Some(tile) = self.tiles.remove(&lru_key) {
+                self.memory_usage -= tile.n_bytes;
+            }
+        }
+    }
+
+    /// Clears the cache. Running requests are discarded
+    pub fn clear(&mut self) {
+        self.tiles.clear();
+        self.lru.get_mut().clear();
+        self.requested.clear();
+        self.overlay_keys.clear();
+        self.memory_usage = 0;
+    }
+
+    /// Draws the tiles of the current level in the viewport.
+    /// Missing tiles are replaced by the cached tiles of other levels, so that the content does not flicker while zooming
+    pub fn draw(&self, snapshot: &Snapshot, viewport: AABB) {
+        for tile_key in Self::tile_keys_for_bounds(viewport, self.level) {
+            let tile_bounds = tile_key.bounds();
+
+            match self.tiles.get(&tile_key) {
+                Some(tile) => {
+                    self.mark_used(tile_key, tile);
+
+                    if let Some(rendernode) = tile.rendernode.as_ref() {
+                        snapshot.push_clip(&graphene::Rect::from_p2d_aabb(tile_bounds));
+                        snapshot.append_node(rendernode);
+                        snapshot.pop();
+                    }
+                }
+                None => {
+                    // the finer levels are drawn on top
+                    let fallback_tiles = (Self::LEVEL_MIN..=Self::LEVEL_MAX)
+                        .filter(|&level| level != self.level)
+                        .flat_map(|level| self.cached_tiles_for_bounds(tile_bounds, level));
+
+                    snapshot.push_clip(&graphene::Rect::from_p2d_aabb(tile_bounds));
+                    for (other_key, other_tile) in fallback_tiles {
+                        if let Some(rendernode) = other_tile.rendernode.as_ref() {
+                            snapshot.push_clip(&graphene::Rect::from_p2d_aabb(other_key.bounds()));
+                            snapshot.append_node(rendernode);
+                            snapshot.pop();
+                        }
+                    }
+                    snapshot.pop();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use slotmap::SlotMap;
+
+    fn insert_requested_tile(tile_cache: &mut TileCache, tile_key: TileKey) {
+        let request_id = tile_cache.request_tile(tile_key);
+        tile_cache.insert_tile(tile_key, request_id, None).unwrap();
+    }
+
+    // Inserts a tile with the given size, without rendering an image for it
+    fn insert_sized_tile(tile_cache: &mut TileCache, tile_key: TileKey, n_bytes: usize) {
+        let tile = CachedTile {
+            rendernode: None,
+            n_bytes,
+            request_id: tile_cache.next_count(),
+            stale: false,
+            last_used: Cell::new(tile_cache.next_count()),
+        };
+        tile_cache.insert_cached_tile(tile_key, tile);
+    }
+
+    #[test]
+    fn tile_keys_cover_the_bounds() {
+        let size = TileCache::tile_size(0);
+        let bounds = AABB::new(na::point![-1.0, 0.0], na::point![size + 1.0, size]);
+
+        let tile_keys = TileCache::tile_keys_for_bounds(bounds, 0);
+
+        assert_eq!(
+            tile_keys,
+            vec![
+                TileKey {
+                    level: 0,
+                    x: -1,
+                    y: 0
+                },
+                TileKey {
+                    level: 0,
+                    x: 0,
+                    y: 0
+                },
+                TileKey {
+                    level: 0,
+                    x: 1,
+                    y: 0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn tile_keys_for_empty_bounds_contain_one_tile() {
+        let pos = na::point![10.0, 10.0];
+
+        assert_eq!(
+            TileCache::tile_keys_for_bounds(AABB::new(pos, pos), 0),
+            vec![TileKey {
+                level: 0,
+                x: 0,
+                y: 0
+            }]
+        );
+    }
+
+    #[test]
+    fn tile_keys_follow_the_level() {
+        let size = TileCache::tile_size(0);
+        let bounds = AABB::new(na::point![0.0, 0.0], na::point![size, size]);
+
+        assert_eq!(TileCache::tile_keys_for_bounds(bounds, 0).len(), 1);
+        // The tiles of the next level have half the size
+        assert_eq!(TileCache::tile_keys_for_bounds(bounds, 1).len(), 4);
+        assert_eq!(TileCache::tile_keys_for_bounds(bounds, -1).len(), 1);
+    }
+
+    #[test]
+    fn insert_tile_drops_outdated_requests() {
+        let mut tile_cache = TileCache::default();
+        let tile_key = TileKey {
+            level: 0,
+            x: 0,
+            y: 0,
+        };
+
+        let outdated_request_id = tile_cache.request_tile(tile_key);
+        let request_id = tile_cache.request_tile(tile_key);
+
+        tile_cache
+            .insert_tile(tile_key, outdated_request_id, None)
+            .unwrap();
+        assert!(!tile_cache.tiles.contains_key(&tile_key));
+        assert_eq!(tile_cache.tiles_to_render(tile_key.bounds()), vec![]);
+
+        tile_cache.insert_tile(tile_key, request_id, None).unwrap();
+        assert_eq!(tile_cache.tiles[&tile_key].request_id, request_id);
+        assert!(tile_cache.requested.is_empty());
+    }
+
+    #[test]
+    fn invalidated_requests_are_dropped() {
+        let mut tile_cache = TileCache::default();
+        let tile_key = TileKey {
+            level: 0,
+            x: 0,
+            y: 0,
+        };
+
+        let request_id = tile_cache.request_tile(tile_key);
+        let mut strokes = SlotMap::<StrokeKey, ()>::with_key();
+        tile_cache.record_changes(vec![(strokes.insert(()), tile_key.bounds())]);
+        tile_cache.insert_tile(tile_key, request_id, None).unwrap();
+
+        assert!(!tile_cache.tiles.contains_key(&tile_key));
+        assert_eq!(
+            tile_cache.tiles_to_render(tile_key.bounds()),
+            vec![tile_key]
+        );
+    }
+
+    #[test]
+    fn evict_removes_least_recently_used_tiles() {
+        let mut tile_cache = TileCache {
+            memory_cap: 250,
+            ..TileCache::default()
+        };
+        let tile_keys = (0..3)
+            .map(|x| TileKey { level: 0, x, y: 0 })
+            .collect::<Vec<TileKey>>();
+
+        for &tile_key in tile_keys.iter() {
+            insert_sized_tile(&mut tile_cache, tile_key, 100);
+        }
+        // Use the first tile, so that the second one is the least recently used
+        tile_cache.mark_used(tile_keys[0], &tile_cache.tiles[&tile_keys[0]]);
+
+        tile_cache.evict();
+
+        assert_eq!(tile_cache.memory_usage(), 200);
+        assert!(tile_cache.tiles.contains_key(&tile_keys[0]));
+        assert!(!tile_cache.tiles.contains_key(&tile_keys[1]));
+        assert!(tile_cache.tiles.contains_key(&tile_keys[2]));
+    }
+
+    #[test]
+    fn evict_limits_the_number_of_tiles() {
+        let mut tile_cache = TileCache::default();
+
+        for x in 0..=TileCache::N_TILES_MAX as i64 {
+            insert_requested_tile(&mut tile_cache, TileKey { level: 0, x, y: 0 });
+        }
+
+        assert_eq!(tile_cache.tiles.len(), TileCache::N_TILES_MAX);
+        // The first inserted tile is the least recently used
+        assert!(!tile_cache.tiles.contains_key(&TileKey {
+            level: 0,
+            x: 0,
+            y: 0
+        }));
+    }
+
+    #[test]
+    fn invalidate_bounds_marks_the_intersecting_tiles_of_all_levels_stale() {
+        let mut tile_cache = TileCache::default();
+        let tile_keys = [
+            TileKey {
+                level: 0,
+                x: 0,
+                y: 0,
+            },
+            TileKey {
+                level: 1,
+                x: 1,
+                y: 1,
+            },
+            TileKey {
+                level: -1,
+                x: 0,
+                y: 0,
+            },
+            TileKey {
+                level: 0,
+                x: 3,
+                y: 0,
+            },
+            TileKey {
+                level: 0,
+                x: 0,
+                y: 3,
+            },
+        ];
+        for tile_key in tile_keys {
+            insert_sized_tile(&mut tile_cache, tile_key, 0);
+        }
+
+        let size = TileCache::tile_size(1);
+        tile_cache.invalidate_bounds(AABB::new(
+            na::point![size * 1.5, size * 1.5],
+            na::point![size * 1.6, size * 1.6],
+        ));
+
+        let stale = tile_keys.map(|tile_key| tile_cache.tiles[&tile_key].stale);
+        assert_eq!(stale, [true, true, true, false, false]);
+    }
+
+    #[test]
+    fn cached_tiles_are_looked_up_by_level_and_position() {
+        let mut tile_cache = TileCache::default();
+        let size = TileCache::tile_size(1);
+
+        for (level, x, y) in [
+            (1, 0, 0),
+            (1, 1, 1),
+            (1, 2, 0),
+            (1, 0, 2),
+            (0, 0, 0),
+            (2, 0, 0),
+        ] {
+            insert_sized_tile(&mut tile_cache, TileKey { level, x, y }, 0);
+        }
+
+        let mut tile_keys = tile_cache
+            .cached_tiles_for_bounds(
+                AABB::new(na::point![0.0, 0.0], na::point![size * 2.0, size * 2.0]),
+                1,
+            )
+            .map(|(&tile_key, _)| tile_key)
+            .collect::<Vec<TileKey>>();
+        tile_keys.sort();
+
+        assert_eq!(
+            tile_keys,
+            vec![
+                TileKey {
+                    level: 1,
+                    x: 0,
+                    y: 0
+                },
+                TileKey {
+                    level: 1,
+                    x: 1,
+                    y: 1
+                },
+            ]
+        );
+    }
+}
@@ -1,11 +1,13 @@
 use super::strokebehaviour::GeneratedStrokeImages;
-use super::StrokeBehaviour;
+use super::{ShapeStroke, StrokeBehaviour};
 use crate::render::{self};
-use crate::DrawBehaviour;
+use crate::{utils, DrawBehaviour, RnoteEngine};
+use geo::Simplify;
 use piet::RenderContext;
-use rnote_compose::helpers::Vector2Helpers;
+use rnote_compose::helpers::{AABBHelpers, Vector2Helpers};
 use rnote_compose::penpath::{Element, Segment};
-use rnote_compose::shapes::ShapeBehaviour;
+use rnote_compose::shapes::{BezierPath, BezierSubpath, PathSegment, Shape, ShapeBehaviour};
+use rnote_compose::style::smooth::SmoothOptions;
 use rnote_compose::style::Composer;
 use rnote_compose::transform::TransformBehaviour;
 use rnote_compose::{PenPath, Style};
@@ -25,6 +27,17 @@ pub struct BrushStroke {
     hitboxes: Vec<AABB>,
 }
 
+/// The level of detail a brushstroke is rendered with. Reduced when zoomed out far
+#[derive(Debug, Clone)]
+enum LevelOfDetail {
+    /// The full path
+    Full,
+    /// A decimated path
+    Decimated(PenPath),
+    /// A rectangle filling the bounds, for strokes that are only a few pixels in size
+    BoundsApprox,
+}
+
 impl Default for BrushStroke {
     fn default() -> Self {
         Self::new(
@@ -43,7 +56,7 @@ impl StrokeBehaviour for BrushStroke {
         render::Svg::gen_with_piet_svg_backend_no_text(
             |cx| {
                 cx.transform(kurbo::Affine::translate(-bounds.mins.coords.to_kurbo_vec()));
-                self.draw(cx, 1.0)
+                self.draw(cx, RnoteEngine::EXPORT_IMAGE_SCALE)
             },
             bounds,
         )
@@ -61,7 +74,27 @@ impl StrokeBehaviour for BrushStroke {
             (viewport, true)
         };
 
-        let images = if bounds.extents()[0] < Self::IMAGES_SEGMENTS_THRESHOLD / image_scale
+        let level_of_detail = self.level_of_detail(image_scale);
+
+        let images = if !matches!(level_of_detail, LevelOfDetail::Full) {
+            // generate a single image with reduced detail when zoomed out far
+            let image = render::Image::gen_with_piet(
+                |piet_cx| {
+                    self.draw_w_level_of_detail(piet_cx, &level_of_detail);
+                    Ok(())
+                },
+                bounds,
+                image_scale,
+            );
+
+            match image {
+                Ok(image) => vec![image],
+                Err(e) => {
+                    log::error!("gen_images() in brushstroke failed with Err {}", e);
+                    vec![]
+                }
+            }
+        } else if bounds.extents()[0] < Self::IMAGES_SEGMENTS_THRESHOLD / image_scale
             && bounds.extents()[1] < Self::IMAGES_SEGMENTS_THRESHOLD / image_scale
         {
             // generate a single image when bounds are below threshold
@@ -98,6 +131,42 @@ impl StrokeBehaviour for BrushStroke {
                         image_scale,
                     );
 
+                    match image {
+                        Ok(image) => vec![image],
+                        Err(e) => {
+                            log::error!("gen_images() in brushstroke failed with Err {}", e);
+                            vec![]
+                        }
+                    }
+                }
+                Style::Calligraphy(options) => {
+                    let image = render::Image::gen_with_piet(
+                        |piet_cx| {
+                            self.path.draw_composed(piet_cx, options);
+                            Ok(())
+                        },
+                        bounds,
+                        image_scale,
+                    );
+
+                    match image {
+                        Ok(image) => vec![image],
+                        Err(e) => {
+                            log::error!("gen_images() in brushstroke failed with Err {}", e);
+                            vec![]
+                        }
+                    }
+                }
+                Style::Pencil(options) => {
+                    let image = render::Image::gen_with_piet(
+                        |piet_cx| {
+                            self.path.draw_composed(piet_cx, options);
+                            Ok(())
+                        },
+                        bounds,
+                        image_scale,
+                    );
+
                     match image {
                         Ok(image) => vec![image],
                         Err(e) => {
@@ -165,6 +234,51 @@ impl StrokeBehaviour for BrushStroke {
                         })
                         .collect::<Vec<render::Image>>()
                 }
+                Style::Calligraphy(options) => self
+                    .path
+                    .iter()
+                    .filter_map(|segment| {
+                        let image = render::Image::gen_with_piet(
+                            |piet_cx| {
+                                segment.draw_composed(piet_cx, options);
+                                Ok(())
+                            },
+                            segment.composed_bounds(options),
+                            image_scale,
+                        );
+
+                        match image {
+                            Ok(image) => Some(image),
+                            Err(e) => {
+                                log::error!("gen_images() in brushstroke failed with Err {}", e);
+                                None
+                            }
+                        }
+                    })
+                    .collect::<Vec<render::Image>>(),
+                // The grain only depends on the position, so the segments match the grain of the entire stroke
+                Style::Pencil(options) => self
+                    .path
+                    .iter()
+                    .filter_map(|segment| {
+                        let image = render::Image::gen_with_piet(
+                            |piet_cx| {
+                                segment.draw_composed(piet_cx, options);
+                                Ok(())
+                            },
+                            segment.composed_bounds(options),
+                            image_scale,
+                        );
+
+                        match image {
+                            Ok(image) => Some(image),
+                            Err(e) => {
+                                log::error!("gen_images() in brushstroke failed with Err {}", e);
+                                None
+                            }
+                        }
+                    })
+                    .collect::<Vec<render::Image>>(),
             }
         };
 
@@ -177,16 +291,16 @@ impl StrokeBehaviour for BrushStroke {
 }
 
 impl DrawBehaviour for BrushStroke {
-    fn draw(&self, cx: &mut impl piet::RenderContext, _image_scale: f64) -> anyhow::Result<()> {
+    fn draw(&self, cx: &mut impl piet::RenderContext, image_scale: f64) -> anyhow::Result<()> {
         cx.save().map_err(|e| anyhow::anyhow!("{}", e))?;
 
-        match &self.style {
-            Style::Smooth(options) => self.path.draw_composed(cx, options),
-            Style::Rough(_) => {
-                // Rough style currently unsupported for pen paths
-            }
-            Style::Textured(options) => self.path.draw_composed(cx, options),
+        // Exports are always drawn in full detail
+        let level_of_detail = if image_scale >= RnoteEngine::EXPORT_IMAGE_SCALE {
+            LevelOfDetail::Full
+        } else {
+            self.level_of_detail(image_scale)
         };
+        self.draw_w_level_of_detail(cx, &level_of_detail);
 
         cx.restore().map_err(|e| anyhow::anyhow!("{}", e))?;
         Ok(())
@@ -200,6 +314,8 @@ impl ShapeBehaviour for BrushStroke {
             // TODO: Needs fixing
             Style::Rough(_options) => self.path.bounds(),
             Style::Textured(options) => self.path.composed_bounds(options),
+            Style::Calligraphy(options) => self.path.composed_bounds(options),
+            Style::Pencil(options) => self.path.composed_bounds(options),
         }
     }
 
@@ -223,6 +339,16 @@ impl TransformBehaviour for BrushStroke {
 impl BrushStroke {
     /// when one of the extents of the stroke is above this threshold, images are generated seperately for each stroke segment (to avoid very large images)
     pub const IMAGES_SEGMENTS_THRESHOLD: f64 = 1000.0;
+    /// the minimum distance between the start and end of the path under which it is considered closed
+    pub const CLOSED_PATH_THRESHOLD: f64 = 8.0;
+    /// the tolerance when simplifying the outline of the stroke
+    pub const OUTLINE_SIMPLIFY_EPSILON: f64 = 0.2;
+    /// when both extents of the stroke in pixels are below this threshold, only an approximation of its bounds is rendered
+    pub const LOD_BOUNDS_APPROX_THRESHOLD: f64 = 2.0;
+    /// below this image scale the path is decimated for rendering
+    pub const LOD_DECIMATION_IMAGE_SCALE_THRESHOLD: f64 = 0.5;
+    /// the min distance in pixels between the elements of a decimated path
+    pub const LOD_DECIMATION_DISTANCE: f64 = 2.0;
 
     pub fn new(segment: Segment, style: Style) -> Self {
         let path = PenPath::new_w_segment(segment);
@@ -230,6 +356,59 @@ impl BrushStroke {
         Self::from_penpath(path, style).unwrap()
     }
 
+    /// The level of detail the stroke is rendered with for the given image scale
+    fn level_of_detail(&self, image_scale: f64) -> LevelOfDetail {
+        let pixel_extents = self.bounds().extents() * image_scale;
+
+        if pixel_extents[0] < Self::LOD_BOUNDS_APPROX_THRESHOLD
+            && pixel_extents[1] < Self::LOD_BOUNDS_APPROX_THRESHOLD
+        {
+            LevelOfDetail::BoundsApprox
+        } else if image_scale < Self::LOD_DECIMATION_IMAGE_SCALE_THRESHOLD {
+            let decimated_path = self
+                .path
+                .decimated(Self::LOD_DECIMATION_DISTANCE / image_scale);
+
+            if decimated_path.len() < self.path.len() {
+                LevelOfDetail::Decimated(decimated_path)
+            } else {
+                LevelOfDetail::Full
+            }
+        } else {
+            LevelOfDetail::Full
+        }
+    }
+
+    fn draw_w_level_of_detail(
+        &self,
+        cx: &mut impl piet::RenderContext,
+        level_of_detail: &LevelOfDetail,
+    ) {
+        let path = match level_of_detail {
+            LevelOfDetail::Full => &self.path,
+            LevelOfDetail::Decimated(decimated_path) => decimated_path,
+            LevelOfDetail::BoundsApprox => {
+                if let Some(stroke_color) = self.style.stroke_color() {
+                    cx.fill(
+                        self.bounds().to_kurbo_rect(),
+                        &piet::Color::from(stroke_color),
+                    );
+                }
+                return;
+            }
+        };
+
+        match &self.style {
+            Style::Smooth(options) => path.draw_composed(cx, options),
+            Style::Rough(_) => {
+                // Rough style currently unsupported for pen paths
+            }
+            Style::Textured(options) => path.draw_composed(cx, options),
+            Style::Calligraphy(options) => path.draw_composed(cx, options),
+            Style::Pencil(options) => path.draw_composed(cx, options),
+        };
+    }
+
     pub fn from_penpath(path: PenPath, style: Style) -> Option<Self> {
         if path.is_empty() {
             return None;
@@ -252,6 +431,113 @@ impl BrushStroke {
         self.hitboxes = self.gen_hitboxes();
     }
 
+    /// The path as a closed bezier path, if its start and end are close enough to each other to be considered closed.
+    /// The width and pressure of the stroke are not taken into account.
+    pub fn closed_outline(&self) -> Option<BezierPath> {
+        let start = self.path.front()?.start().pos;
+        let end = self.path.back()?.end().pos;
+
+        let threshold = Self::CLOSED_PATH_THRESHOLD.max(self.style.stroke_width() * 2.0);
+        if (end - start).magnitude() > threshold {
+            return None;
+        }
+
+        let segments = self
+            .path
+            .iter()
+            .filter_map(|segment| match segment {
+                Segment::Dot { .. } => None,
+                Segment::Line { end, .. } => Some(PathSegment::Line { end: end.pos }),
+                Segment::QuadBez { cp, end, .. } => Some(PathSegment::QuadBez {
+                    cp: *cp,
+                    end: end.pos,
+                }),
+                Segment::CubBez { cp1, cp2, end, .. } => Some(PathSegment::CubBez {
+                    cp1: *cp1,
+                    cp2: *cp2,
+                    end: end.pos,
+                }),
+            })
+            .collect::<Vec<PathSegment>>();
+
+        if segments.len() < 2 {
+            return None;
+        }
+
+        Some(BezierPath {
+            subpaths: vec![BezierSubpath {
+                start,
+                segments,
+                closed: true,
+            }],
+        })
+    }
+
+    /// A path shape stroke of the filled outline, as the stroke is drawn with the smooth or calligraphy style.
+    /// The outline is filled with the stroke color.
+    pub fn to_outline(&self) -> Option<ShapeStroke> {
+        let (outlines, stroke_color) = match &self.style {
+            Style::Smooth(options) => (self.path.smooth_outlines(options), options.stroke_color),
+            Style::Rough(options) => (
+                self.path.smooth_outlines(&SmoothOptions {
+                    stroke_width: options.stroke_width,
+                    stroke_color: options.stroke_color,
+                    ..SmoothOptions::default()
+                }),
+                options.stroke_color,
+            ),
+            Style::Textured(options) => (
+                self.path.smooth_outlines(&SmoothOptions {
+                    stroke_width: options.stroke_width,
+                    stroke_color: options.stroke_color,
+                    pressure_curve: options.pressure_curve.clone(),
+                    dynamics: options.dynamics,
+                    ..SmoothOptions::default()
+                }),
+                options.stroke_color,
+            ),
+            Style::Calligraphy(options) => (
+                self.path.calligraphy_outlines(options),
+                options.stroke_color,
+            ),
+            Style::Pencil(options) => (
+                self.path.smooth_outlines(&SmoothOptions {
+                    stroke_width: options.stroke_width,
+                    stroke_color: options.stroke_color,
+                    pressure_curve: options.pressure_curve.clone(),
+                    dynamics: options.dynamics,
+                    ..SmoothOptions::default()
+                }),
+                options.stroke_color,
+            ),
+        };
+
+        // The outlines of the segments overlap, so they are merged into one. Outlines which fail to merge are dropped
+        let outline = outlines
+            .iter()
+            .filter_map(|bez_path| {
+                utils::bezier_path_to_multipolygon(&BezierPath::from_kurbo(bez_path))
+            })
+            .reduce(|acc, next| {
+                utils::multipolygon_boolean_op(&acc, &next, geo::OpType::Union).unwrap_or(acc)
+            })?
+            .simplify(&Self::OUTLINE_SIMPLIFY_EPSILON);
+
+        let path = utils::multipolygon_to_bezier_path(&outline);
+        if path.subpaths.is_empty() {
+            return None;
+        }
+
+        Some(ShapeStroke::new(
+            Shape::Path(path),
+            Style::Smooth(SmoothOptions {
+                stroke_color: None,
+                fill_color: stroke_color,
+                ..SmoothOptions::default()
+            }),
+        ))
+    }
+
     /// Replacing the current path with a new one. the new path must not be empty.
     pub fn replace_path(&mut self, path: PenPath) {
         self.path = path;
@@ -337,6 +623,63 @@ impl BrushStroke {
                     }
                 })
                 .collect::<Vec<render::Image>>(),
+            Style::Calligraphy(options) => self
+                .path
+                .iter()
+                .rev()
+                .take(no_last_segments)
+                .rev()
+                .filter_map(|segment| {
+                    let image = render::Image::gen_with_piet(
+                        |piet_cx| {
+                            segment.draw_composed(piet_cx, options);
+                            Ok(())
+                        },
+                        segment.composed_bounds(options),
+                        image_scale,
+                    );
+
+                    match image {
+                        Ok(image) => Some(image),
+                        Err(e) => {
+                            log::error!("gen_images_for_last_segments() failed with Err {}", e);
+                            None
+                        }
+                    }
+                })
+                .collect::<Vec<render::Image>>(),
+            Style::Pencil(options) => self
+                .path
+                .iter()
+                .enumerate()
+                .rev()
+                .take(no_last_segments)
+                .rev()
+                .filter_map(|(i, segment)| {
+                    let mut options = options.clone();
+
+                    (0..=i).for_each(|_| {
+                        options.seed = options.seed.map(rnote_compose::utils::seed_advance)
+                    });
+
+                    let image = render::Image::gen_with_piet(
+                        |piet_cx| {
+                            segment.draw_composed(piet_cx, &options);
+                            Ok(())
+                        },
+                        segment.composed_bounds(&options),
+                        image_scale,
+                    );
+
+                    match image {
+                        Ok(image) => Some(image),
+                        Err(e) => {
+                            log::error!("gen_images_for_last_segments() failed with Err {}", e);
+                            None
+                        }
+                    }
+                })
+                .collect::<Vec<render::Image>>(),
         };
 
         Ok(images)
@@ -1,13 +1,14 @@
 use super::strokebehaviour::GeneratedStrokeImages;
-use super::StrokeBehaviour;
+use super::{BrushStroke, StrokeBehaviour};
 use crate::{render, DrawBehaviour};
 use piet::RenderContext;
 use rnote_compose::helpers::Vector2Helpers;
-use rnote_compose::shapes::Shape;
 use rnote_compose::shapes::ShapeBehaviour;
-use rnote_compose::style::Composer;
+use rnote_compose::shapes::{BezierPath, Shape};
+use rnote_compose::style::smooth::SmoothOptions;
+use rnote_compose::style::{Composer, PressureCurve};
 use rnote_compose::transform::TransformBehaviour;
-use rnote_compose::Style;
+use rnote_compose::{PenPath, Style};
 
 use p2d::bounding_volume::{BoundingVolume, AABB};
 use serde::{Deserialize, Serialize};
@@ -86,7 +87,7 @@ impl ShapeBehaviour for ShapeStroke {
         match &self.style {
             Style::Smooth(options) => self.shape.composed_bounds(options),
             Style::Rough(options) => self.shape.composed_bounds(options),
-            Style::Textured(_) => self.shape.bounds(),
+            Style::Textured(_) | Style::Calligraphy(_) | Style::Pencil(_) => self.shape.bounds(),
         }
     }
 
@@ -123,6 +124,56 @@ impl ShapeStroke {
         self.hitboxes = self.gen_hitboxes();
     }
 
+    /// The outline of the shape as a closed bezier path. None if the shape is not closed
+    pub fn closed_outline(&self) -> Option<BezierPath> {
+        match &self.shape {
+            Shape::Rectangle(rectangle) => Some(BezierPath::from_kurbo(&rectangle.to_kurbo())),
+            Shape::Ellipse(ellipse) => Some(BezierPath::from_kurbo(&kurbo::Shape::into_path(
+                ellipse.to_kurbo(),
+                BezierPath::FLATTEN_TOLERANCE,
+            ))),
+            Shape::Path(path) => Some(path.clone()),
+            Shape::Line(_)
+            | Shape::QuadraticBezier(_)
+            | Shape::CubicBezier(_)
+            | Shape::Segment(_) => None,
+        }
+    }
+
+    /// A brush stroke along the estimated centerline of the closed outline of the shape, with a width that follows the outline.
+    /// None if the shape is not closed
+    pub fn to_centerline(&self) -> Option<BrushStroke> {
+        // the polygon with the largest area is the body of the outline, the others are holes or detached parts
+        let polygon = self
+            .closed_outline()?
+            .to_polygons(BezierPath::FLATTEN_TOLERANCE)
+            .into_iter()
+            .map(|polygon| (polygon_area(&polygon), polygon))
+            .max_by(|(first_area, _), (second_area, _)| first_area.total_cmp(second_area))
+            .map(|(_, polygon)| polygon)?;
+
+        let (path, max_width) = PenPath::from_closed_outline(&polygon)?;
+        let color = match &self.style {
+            Style::Smooth(options) => options.fill_color.or(options.stroke_color),
+            Style::Rough(options) => options.fill_color.or(options.stroke_color),
+            Style::Textured(options) => options.stroke_color,
+            Style::Calligraphy(options) => options.stroke_color,
+            Style::Pencil(options) => options.stroke_color,
+        };
+
+        BrushStroke::from_penpath(
+            path,
+            Style::Smooth(SmoothOptions {
+                stroke_width: max_width,
+                stroke_color: color,
+                fill_color: None,
+                // the pressure of the elements is the width relative to the maximum width
+                pressure_curve: PressureCurve::Linear,
+                ..SmoothOptions::default()
+            }),
+        )
+    }
+
     fn gen_hitboxes(&self) -> Vec<AABB> {
         let width = self.style.stroke_width();
 
@@ -133,3 +184,63 @@ impl ShapeStroke {
             .collect()
     }
 }
+
+/// The unsigned area of the polygon
+fn polygon_area(polygon: &[na::Vector2<f64>]) -> f64 {
+    let n = polygon.len();
+
+    (0..n)
+        .map(|i| {
+            let (a, b) = (polygon[i], polygon[(i + 1) % n]);
+            a[0] * b[1] - b[0] * a[1]
+        })
+        .sum::<f64>()
+        .abs()
+        * 0.5
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rnote_compose::penpath::{Element, Segment};
+
+    #[test]
+    fn brush_outline_back_to_centerline() {
+        let brushstroke = BrushStroke::new(
+            Segment::Line {
+                start: Element::new(na::vector![0.0, 0.0], 1.0),
+                end: Element::new(na::vector![100.0, 0.0], 1.0),
+            },
+            Style::Smooth(SmoothOptions {
+                stroke_width: 10.0,
+                pressure_curve: PressureCurve::Const,
+                ..SmoothOptions::default()
+            }),
+        );
+
+        let outline = brushstroke.to_outline().unwrap();
+        let outline_bounds = outline.shape.bounds();
+        assert!(outline_bounds.mins[0] <= 0.0 && outline_bounds.maxs[0] >= 100.0);
+        assert!((outline_bounds.extents()[1] - 10.0).abs() < 1.0);
+
+        let centerline = outline.to_centerline().unwrap();
+        assert!((centerline.style.stroke_width() - 10.0).abs() < 1.0);
+        for element in centerline.path.into_elements() {
+            assert!(element.pos[1].abs() < 1.0);
+            assert!(element.pos[0] > -10.0 && element.pos[0] < 110.0);
+        }
+    }
+
+    #[test]
+    fn no_centerline_of_open_shapes() {
+        let shapestroke = ShapeStroke::new(
+            Shape::Line(rnote_compose::shapes::Line {
+                start: na::vector![0.0, 0.0],
+                end: na::vector![100.0, 0.0],
+            }),
+            Style::default(),
+        );
+
+        assert!(shapestroke.to_centerline().is_none());
+    }
+}
@@ -8,7 +8,7 @@ use crate::{render, RnoteEngine};
 use crate::{utils, DrawBehaviour};
 use rnote_compose::helpers::AABBHelpers;
 use rnote_compose::penpath::{Element, Segment};
-use rnote_compose::shapes::{Rectangle, ShapeBehaviour};
+use rnote_compose::shapes::{BezierPath, Rectangle, ShapeBehaviour};
 use rnote_compose::style::smooth::SmoothOptions;
 use rnote_compose::transform::Transform;
 use rnote_compose::transform::TransformBehaviour;
@@ -159,9 +159,39 @@ impl TransformBehaviour for Stroke {
             }
         }
     }
+
+    fn mirror(&mut self, axis_angle: f64, center: na::Point2<f64>) {
+        match self {
+            Self::BrushStroke(brushstroke) => {
+                brushstroke.mirror(axis_angle, center);
+            }
+            Self::ShapeStroke(shapestroke) => {
+                shapestroke.mirror(axis_angle, center);
+            }
+            Self::TextStroke(textstroke) => {
+                textstroke.mirror(axis_angle, center);
+            }
+            Self::VectorImage(vectorimage) => {
+                vectorimage.mirror(axis_angle, center);
+            }
+            Self::BitmapImage(bitmapimage) => {
+                bitmapimage.mirror(axis_angle, center);
+            }
+        }
+    }
 }
 
 impl Stroke {
+    /// The closed outline of the stroke, which is used as operand for boolean operations.
+    /// None if the stroke is not closed
+    pub fn closed_outline(&self) -> Option<BezierPath> {
+        match self {
+            Stroke::BrushStroke(brushstroke) => brushstroke.closed_outline(),
+            Stroke::ShapeStroke(shapestroke) => shapestroke.closed_outline(),
+            Stroke::TextStroke(_) | Stroke::VectorImage(_) | Stroke::BitmapImage(_) => None,
+        }
+    }
+
     pub fn from_xoppstroke(
         stroke: xoppformat::XoppStroke,
         offset: na::Vector2<f64>,
@@ -265,6 +295,10 @@ impl Stroke {
                     Style::Textured(options) => {
                         (options.stroke_width, options.stroke_color?.into())
                     }
+                    Style::Calligraphy(options) => {
+                        (options.stroke_width, options.stroke_color?.into())
+                    }
+                    Style::Pencil(options) => (options.stroke_width, options.stroke_color?.into()),
                 };
 
                 let tool = xoppformat::XoppTool::Pen;
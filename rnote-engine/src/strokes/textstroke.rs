@@ -489,6 +489,17 @@ impl TransformBehaviour for TextStroke {
     fn scale(&mut self, scale: nalgebra::Vector2<f64>) {
         self.transform.append_scale_mut(scale);
     }
+
+    /// Mirrored text would be unreadable, so only its placement gets mirrored.
+    fn mirror(&mut self, axis_angle: f64, center: nalgebra::Point2<f64>) {
+        let bounds_center = self.bounds().center();
+        let axis_dir = na::vector![axis_angle.cos(), axis_angle.sin()];
+        let rel_pos = bounds_center - center;
+        let mirrored_center = center + axis_dir * (2.0 * rel_pos.dot(&axis_dir)) - rel_pos;
+
+        self.transform
+            .append_translation_mut(mirrored_center - bounds_center);
+    }
 }
 
 impl ShapeBehaviour for TextStroke {
@@ -600,6 +611,63 @@ impl TextStroke {
         }
     }
 
+    /// Draws wavy underlines below the given misspelled byte ranges of the text.
+    pub fn draw_misspelled_underlines(
+        &self,
+        cx: &mut impl RenderContext,
+        misspelled_ranges: &[Range<usize>],
+    ) -> anyhow::Result<()> {
+        const UNDERLINE_COLOR: piet::Color = color::GNOME_REDS[3];
+        let amplitude = (self.text_style.font_size * 0.04).max(1.0);
+        let half_wavelength = amplitude * 2.0;
+
+        if misspelled_ranges.is_empty() {
+            return Ok(());
+        }
+
+        let text_layout = self
+            .text_style
+            .build_text_layout(cx.text(), self.text.clone())?;
+
+        cx.save().map_err(|e| anyhow::anyhow!("{}", e))?;
+        cx.transform(self.transform.affine.to_kurbo());
+
+        for range in misspelled_ranges.iter() {
+            if range.end > self.text.len()
+                || !self.text.is_char_boundary(range.start)
+                || !self.text.is_char_boundary(range.end)
+            {
+                continue;
+            }
+
+            for rect in text_layout.rects_for_range(range.clone()) {
+                let y = rect.y1 - amplitude;
+                let mut wave = kurbo::BezPath::new();
+                wave.move_to(kurbo::Point::new(rect.x0, y));
+
+                let mut x = rect.x0;
+                let mut up = true;
+                while x < rect.x1 {
+                    let next_x = (x + half_wavelength).min(rect.x1);
+                    let control_y = if up { y - amplitude } else { y + amplitude };
+
+                    wave.quad_to(
+                        kurbo::Point::new((x + next_x) * 0.5, control_y),
+                        kurbo::Point::new(next_x, y),
+                    );
+
+                    x = next_x;
+                    up = !up;
+                }
+
+                cx.stroke(wave, &UNDERLINE_COLOR, amplitude * 0.6);
+            }
+        }
+
+        cx.restore().map_err(|e| anyhow::anyhow!("{}", e))?;
+        Ok(())
+    }
+
     pub fn get_text_slice_for_range(&self, range: Range<usize>) -> &str {
         &self.text[range]
     }
@@ -218,64 +218,10 @@ impl VectorImage {
             };
 
 
-            let res = || -> anyhow::Result<String> {
-                let svg_stream: Vec<u8> = vec![];
-
-                let mut svg_surface =
-                    cairo::SvgSurface::for_stream(intrinsic_size.0, intrinsic_size.1, svg_stream)
-                        .map_err(|e| {
-                        anyhow::anyhow!(
-                            "create SvgSurface with dimensions ({}, {}) failed in vectorimage import_from_pdf_bytes with Err {}",
-                            intrinsic_size.0,
-                            intrinsic_size.1,
-                            e
-                        )
-                    })?;
-
-                // Popplers page units are in points ( ^= 1 / 72 inch )
-                svg_surface.set_document_unit(cairo::SvgUnit::Pt);
-
-                {
-                    let cx = cairo::Context::new(&svg_surface).map_err(|e| {
-                        anyhow::anyhow!(
-                            "new cairo::Context failed in vectorimage import_from_pdf_bytes() with Err {}",
-                            e
-                        )
-                    })?;
-
-                    // Set margin to white
-                    cx.set_source_rgba(1.0, 1.0, 1.0, 1.0);
-                    cx.paint()?;
-
-                    // Render the poppler page
-                    page.render(&cx);
-
-                    // Draw outline around page
-                    cx.set_source_rgba(color::GNOME_REDS[4].as_rgba().0, color::GNOME_REDS[4].as_rgba().1, color::GNOME_REDS[4].as_rgba().2, 1.0);
-
-                    let line_width = 1.0;
-                    cx.set_line_width(line_width);
-                    cx.rectangle(
-                        line_width * 0.5,
-                        line_width * 0.5,
-                        intrinsic_size.0 - line_width,
-                        intrinsic_size.1 - line_width,
-                    );
-                    cx.stroke()?;
-                }
-
-                let svg_content = String::from_utf8(
-                    *svg_surface.finish_output_stream()
-                        .map_err(|e| anyhow::anyhow!("{}", e))?
-                        .downcast::<Vec<u8>>()
-                        .map_err(|_e| anyhow::anyhow!("failed to downcast svg surface content in VectorImage import_from_pdf_bytes()"))?)?;
-
-                Ok(svg_content)
-            };
-
-            match res() {
-                Ok(svg_data) => Some(render::Svg {
-                    svg_data,
+            // Draw an outline around the page
+            match render::Svg::gen_from_pdf_page(&page, Some(color::GNOME_REDS[4])) {
+                Ok(svg) => Some(render::Svg {
+                    svg_data: svg.svg_data,
                     bounds: AABB::new(na::point![x, y], na::point![x + width, y + height])
                 }),
                 Err(e) => {
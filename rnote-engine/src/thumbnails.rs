@@ -0,0 +1,226 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use p2d::bounding_volume::AABB;
+
+use crate::render;
+use crate::store::StrokeKey;
+
+/// The signature of the content of a page. A thumbnail is outdated when the signature of its page changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PageSignature(u64);
+
+impl PageSignature {
+    /// Generates the signature from the page bounds, the generation of the backgrounds and the strokes on the page.
+    ///
+    /// The strokes are given as (key, generation), where the generation changes every time the stroke is changed.
+    pub(crate) fn new(
+        page_bounds: AABB,
+        background_generation: u64,
+        strokes: impl IntoIterator<Item = (StrokeKey, u64)>,
+    ) -> Self {
+        let mut hasher = DefaultHasher::new();
+
+        for value in [
+            page_bounds.mins[0],
+            page_bounds.mins[1],
+            page_bounds.maxs[0],
+            page_bounds.maxs[1],
+        ] {
+            value.to_bits().hash(&mut hasher);
+        }
+        background_generation.hash(&mut hasher);
+        for (key, generation) in strokes {
+            key.hash(&mut hasher);
+            generation.hash(&mut hasher);
+        }
+
+        Self(hasher.finish())
+    }
+}
+
+/// A generated low resolution thumbnail of a page
+#[derive(Debug, Clone)]
+pub struct PageThumbnail {
+    /// Unique for every generated thumbnail, so that it can be checked if the thumbnail of a page was replaced
+    pub id: u64,
+    pub image: render::Image,
+}
+
+#[derive(Debug)]
+struct CachedThumbnail {
+    signature: PageSignature,
+    width: u32,
+    /// The id of the latest request. Generated thumbnails with another id are outdated
+    request_id: u64,
+    /// The latest generated thumbnail. Might be outdated while a new one is generated
+    thumbnail: Option<PageThumbnail>,
+}
+
+/// Holds the generated low resolution thumbnails of the pages, indexed by the page number
+#[derive(Debug, Default)]
+pub struct PageThumbnails {
+    cache: HashMap<usize, CachedThumbnail>,
+    request_counter: u64,
+}
+
+impl PageThumbnails {
+    /// Requests a new thumbnail for the page if the signature or the width changed since the last request.
+    /// Returns the id of the new request, or None if no new thumbnail is needed
+    pub(crate) fn request_thumbnail(
+        &mut self,
+        page: usize,
+        signature: PageSignature,
+        width: u32,
+    ) -> Option<u64> {
+        if let Some(cached) = self.cache.get(&page) {
+            if cached.signature == signature && cached.width == width {
+                return None;
+            }
+        }
+
+        self.request_counter += 1;
+        let request_id = self.request_counter;
+
+        let cached = self.cache.entry(page).or_insert(CachedThumbnail {
+            signature,
+            width,
+            request_id,
+            thumbnail: None,
+        });
+        cached.signature = signature;
+        cached.width = width;
+        cached.request_id = request_id;
+
+        Some(request_id)
+    }
+
+    /// Inserts a generated thumbnail, if it is still requested with the given id. Returns true if it was inserted
+    pub(crate) fn insert_thumbnail(
+        &mut self,
+        page: usize,
+        request_id: u64,
+        image: render::Image,
+    ) -> bool {
+        match self.cache.get_mut(&page) {
+            Some(cached) if cached.request_id == request_id => {
+                cached.thumbnail = Some(PageThumbnail {
+                    id: request_id,
+                    image,
+                });
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The latest generated thumbnail of the page
+    pub fn thumbnail(&self, page: usize) -> Option<&PageThumbnail> {
+        self.cache
+            .get(&page)
+            .and_then(|cached| cached.thumbnail.as_ref())
+    }
+
+    /// Removes the thumbnails of pages that don't exist anymore
+    pub(crate) fn retain_pages(&mut self, n_pages: usize) {
+        self.cache.retain(|&page, _| page < n_pages);
+    }
+
+    /// Clears all cached thumbnails, e.g. when a new document is loaded
+    pub fn clear(&mut self) {
+        self.cache.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use slotmap::SlotMap;
+
+    fn page_bounds() -> AABB {
+        AABB::new(na::point![0.0, 0.0], na::point![100.0, 150.0])
+    }
+
+    fn image() -> render::Image {
+        render::Image::default()
+    }
+
+    #[test]
+    fn signature_changes_with_content() {
+        let mut keys = SlotMap::<StrokeKey, ()>::with_key();
+        let key = keys.insert(());
+        let signature = PageSignature::new(page_bounds(), 0, [(key, 1)]);
+
+        assert_eq!(signature, PageSignature::new(page_bounds(), 0, [(key, 1)]));
+        assert_ne!(signature, PageSignature::new(page_bounds(), 0, [(key, 2)]));
+        assert_ne!(signature, PageSignature::new(page_bounds(), 1, [(key, 1)]));
+        assert_ne!(signature, PageSignature::new(page_bounds(), 0, []));
+        assert_ne!(
+            signature,
+            PageSignature::new(
+                page_bounds().translate(na::vector![0.0, 150.0]),
+                0,
+                [(key, 1)]
+            )
+        );
+    }
+
+    #[test]
+    fn only_changed_pages_are_requested() {
+        let mut thumbnails = PageThumbnails::default();
+        let signature = PageSignature::new(page_bounds(), 0, []);
+        let changed_signature = PageSignature::new(page_bounds(), 1, []);
+
+        assert!(thumbnails.request_thumbnail(0, signature, 240).is_some());
+        assert!(thumbnails.request_thumbnail(0, signature, 240).is_none());
+        assert!(thumbnails.request_thumbnail(0, signature, 120).is_some());
+        assert!(thumbnails
+            .request_thumbnail(0, changed_signature, 120)
+            .is_some());
+        assert!(thumbnails
+            .request_thumbnail(1, changed_signature, 120)
+            .is_some());
+    }
+
+    #[test]
+    fn outdated_thumbnails_are_discarded() {
+        let mut thumbnails = PageThumbnails::default();
+        let signature = PageSignature::new(page_bounds(), 0, []);
+        let changed_signature = PageSignature::new(page_bounds(), 1, []);
+
+        let outdated_id = thumbnails.request_thumbnail(0, signature, 240).unwrap();
+        let request_id = thumbnails
+            .request_thumbnail(0, changed_signature, 240)
+            .unwrap();
+
+        assert!(!thumbnails.insert_thumbnail(0, outdated_id, image()));
+        assert!(thumbnails.thumbnail(0).is_none());
+
+        assert!(thumbnails.insert_thumbnail(0, request_id, image()));
+        assert_eq!(
+            thumbnails.thumbnail(0).map(|thumbnail| thumbnail.id),
+            Some(request_id)
+        );
+
+        // Not requested pages
+        assert!(!thumbnails.insert_thumbnail(1, request_id, image()));
+    }
+
+    #[test]
+    fn thumbnails_of_removed_pages_are_dropped() {
+        let mut thumbnails = PageThumbnails::default();
+        let signature = PageSignature::new(page_bounds(), 0, []);
+
+        for page in 0..3 {
+            let request_id = thumbnails.request_thumbnail(page, signature, 240).unwrap();
+            thumbnails.insert_thumbnail(page, request_id, image());
+        }
+        thumbnails.retain_pages(2);
+
+        assert!(thumbnails.thumbnail(1).is_some());
+        assert!(thumbnails.thumbnail(2).is_none());
+        // Requested again when the page is added back
+        assert!(thumbnails.request_thumbnail(2, signature, 240).is_some());
+    }
+}
@@ -1,6 +1,8 @@
 use geo::line_string;
+use geo::{Area, BooleanOps};
 use gtk4::{gdk, glib, graphene, gsk, pango};
 use p2d::bounding_volume::AABB;
+use rnote_compose::shapes::BezierPath;
 use rnote_compose::{penhelpers::KeyboardKey, Transform};
 
 pub trait GdkRGBAHelpers
@@ -144,6 +146,87 @@ pub fn p2d_aabb_to_geo_polygon(aabb: AABB) -> geo::Polygon<f64> {
     geo::Polygon::new(line_string, vec![])
 }
 
+/// Converts the path into a multipolygon. The subpaths are combined with xor, matching the even-odd fill rule of the path.
+///
+/// None if combining the subpaths failed
+pub fn bezier_path_to_multipolygon(path: &BezierPath) -> Option<geo::MultiPolygon<f64>> {
+    let mut polygons = path
+        .to_polygons(BezierPath::FLATTEN_TOLERANCE)
+        .into_iter()
+        .filter_map(|polygon| {
+            let mut exterior = polygon
+                .into_iter()
+                .map(|point| geo::Coordinate {
+                    x: point[0],
+                    y: point[1],
+                })
+                .collect::<Vec<geo::Coordinate<f64>>>();
+            exterior.dedup();
+
+            let polygon = geo::Polygon::new(geo::LineString::from(exterior), vec![]);
+            valid_boolean_op_polygon(&polygon).then(|| geo::MultiPolygon(vec![polygon]))
+        });
+
+    match polygons.next() {
+        Some(first) => polygons.try_fold(first, |acc, next| {
+            multipolygon_boolean_op(&acc, &next, geo::OpType::Xor)
+        }),
+        None => Some(geo::MultiPolygon(vec![])),
+    }
+}
+
+/// The boolean operation between the multipolygons.
+///
+/// The geo boolean operations panic on some input which passes `valid_boolean_op_polygon()`, for example self-intersecting rings.
+/// The panic is caught and None is returned, so callers can skip the operand instead of crashing
+pub fn multipolygon_boolean_op(
+    first: &geo::MultiPolygon<f64>,
+    second: &geo::MultiPolygon<f64>,
+    op: geo::OpType,
+) -> Option<geo::MultiPolygon<f64>> {
+    std::panic::catch_unwind(|| first.boolean_op(second, op))
+        .map_err(|_| {
+            log::error!(
+                "boolean operation `{:?}` between multipolygons panicked",
+                op
+            )
+        })
+        .ok()
+}
+
+/// Whether the polygon can be used as operand of the geo boolean operations, which can panic on degenerate input.
+/// It needs finite coordinates, at least three distinct points and an area
+fn valid_boolean_op_polygon(polygon: &geo::Polygon<f64>) -> bool {
+    let exterior = polygon.exterior();
+
+    // The exterior is closed, so the first point is repeated at the end
+    exterior.0.len() > 3
+        && exterior
+            .coords()
+            .all(|coord| coord.x.is_finite() && coord.y.is_finite())
+        && polygon.unsigned_area() > f64::EPSILON
+}
+
+/// Converts the multipolygon back into a path, with a subpath for every exterior and interior ring
+pub fn multipolygon_to_bezier_path(multipolygon: &geo::MultiPolygon<f64>) -> BezierPath {
+    let polygons = multipolygon
+        .iter()
+        .flat_map(|polygon| std::iter::once(polygon.exterior()).chain(polygon.interiors().iter()))
+        .map(|ring| {
+            let mut points = ring
+                .coords()
+                .map(|coord| na::vector![coord.x, coord.y])
+                .collect::<Vec<na::Vector2<f64>>>();
+            // geo rings repeat their first point at the end, the subpaths are closed instead
+            points.pop();
+            points
+        })
+        .filter(|points| points.len() > 2)
+        .collect();
+
+    BezierPath::from_polygons(polygons)
+}
+
 pub fn keyboard_key_from_gdk(gdk_key: gdk::Key) -> KeyboardKey {
     if let Some(keychar) = gdk_key.to_unicode() {
         KeyboardKey::Unicode(keychar).filter_convert_unicode_control_chars()
@@ -184,3 +267,73 @@ pub mod base64 {
         base64::decode(base64.as_bytes()).map_err(serde::de::Error::custom)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn square_path(mins: na::Vector2<f64>, size: f64) -> BezierPath {
+        BezierPath::from_polygons(vec![vec![
+            mins,
+            mins + na::vector![size, 0.0],
+            mins + na::vector![size, size],
+            mins + na::vector![0.0, size],
+        ]])
+    }
+
+    fn path_area(path: &BezierPath) -> f64 {
+        bezier_path_to_multipolygon(path).unwrap().unsigned_area()
+    }
+
+    #[test]
+    fn bezier_path_multipolygon_round_trip() {
+        // A square with a square hole
+        let mut path = square_path(na::vector![0.0, 0.0], 100.0);
+        path.subpaths
+            .extend(square_path(na::vector![25.0, 25.0], 50.0).subpaths);
+
+        let multipolygon = bezier_path_to_multipolygon(&path).unwrap();
+        assert_relative_eq!(multipolygon.unsigned_area(), 7500.0, epsilon = 1e-6);
+
+        let round_tripped = multipolygon_to_bezier_path(&multipolygon);
+        assert_eq!(round_tripped.subpaths.len(), 2);
+        assert_relative_eq!(path_area(&round_tripped), 7500.0, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn degenerate_subpaths_are_skipped() {
+        let path = BezierPath::from_polygons(vec![
+            vec![
+                na::vector![0.0, 0.0],
+                na::vector![10.0, 0.0],
+                na::vector![20.0, 0.0],
+            ],
+            vec![
+                na::vector![f64::NAN, 0.0],
+                na::vector![10.0, 0.0],
+                na::vector![10.0, 10.0],
+            ],
+        ]);
+
+        assert!(bezier_path_to_multipolygon(&path).unwrap().0.is_empty());
+    }
+
+    #[test]
+    fn boolean_ops_of_overlapping_squares() {
+        let first =
+            bezier_path_to_multipolygon(&square_path(na::vector![0.0, 0.0], 100.0)).unwrap();
+        let second =
+            bezier_path_to_multipolygon(&square_path(na::vector![50.0, 50.0], 100.0)).unwrap();
+
+        for (op, area) in [
+            (geo::OpType::Union, 17500.0),
+            (geo::OpType::Intersection, 2500.0),
+            (geo::OpType::Difference, 7500.0),
+            (geo::OpType::Xor, 15000.0),
+        ] {
+            let result = multipolygon_boolean_op(&first, &second, op).unwrap();
+            assert_relative_eq!(result.unsigned_area(), area, epsilon = 1e-6);
+        }
+    }
+}
@@ -14,6 +14,8 @@ pub struct WidgetFlags {
     pub indicate_changed_store: bool,
     /// update the current view offsets and size
     pub update_view: bool,
+    /// new page thumbnails were generated and need to be shown
+    pub update_page_thumbnails: bool,
     /// Is Some when scrollbar visibility should be changed. Is None if should not be changed
     pub hide_scrollbars: Option<bool>,
     /// Is Some when undo button visibility should be changed. Is None if should not be changed
@@ -31,6 +33,7 @@ impl Default for WidgetFlags {
             refresh_ui: false,
             indicate_changed_store: false,
             update_view: false,
+            update_page_thumbnails: false,
             hide_scrollbars: None,
             hide_undo: None,
             hide_redo: None,
@@ -47,6 +50,7 @@ impl WidgetFlags {
         self.refresh_ui |= other.refresh_ui;
         self.indicate_changed_store |= other.indicate_changed_store;
         self.update_view |= other.update_view;
+        self.update_page_thumbnails |= other.update_page_thumbnails;
         self.hide_scrollbars = if other.hide_scrollbars.is_some() {
             other.hide_scrollbars
         } else {
@@ -12,7 +12,8 @@ use crate::{
     penssidebar::SelectorPage, penssidebar::ShaperPage, penssidebar::ToolsPage,
     penssidebar::TypewriterPage, settingspanel::PenShortcutRow, utils, workspacebrowser::FileRow,
     workspacebrowser::WorkspaceRow, AppMenu, CanvasMenu, ColorPicker, IconPicker, MainHeader,
-    PensSideBar, RnoteAppWindow, RnoteCanvas, SettingsPanel, UnitEntry, WorkspaceBrowser,
+    PagesPanel, PensSideBar, PressureCurveEditor, RnoteAppWindow, RnoteCanvas, SettingsPanel,
+    TransformPanel, UnitEntry, WorkspaceBrowser,
 };
 
 mod imp {
@@ -48,6 +49,8 @@ mod imp {
             ColorSetter::static_type();
             CanvasMenu::static_type();
             SettingsPanel::static_type();
+            TransformPanel::static_type();
+            PagesPanel::static_type();
             AppMenu::static_type();
             MainHeader::static_type();
             PensSideBar::static_type();
@@ -64,6 +67,7 @@ mod imp {
             MeasureUnit::static_type();
             UnitEntry::static_type();
             IconPicker::static_type();
+            PressureCurveEditor::static_type();
             PenShortcutRow::static_type();
 
             // Load the resources
@@ -8,6 +8,7 @@ use piet::RenderContext;
 use rnote_compose::helpers::Vector2Helpers;
 use rnote_engine::document::Layout;
 use rnote_engine::pens::penholder::PenStyle;
+use rnote_engine::store::{Alignment, Axis, BooleanOp, Reordering};
 use rnote_engine::{render, Camera, DrawBehaviour, RnoteEngine, Document};
 
 use gettextrs::gettext;
@@ -63,9 +64,18 @@ impl RnoteAppWindow {
         let action_pen_sounds =
             gio::SimpleAction::new_stateful("pen-sounds", None, &false.to_variant());
         self.add_action(&action_pen_sounds);
+        let action_tile_rendering =
+            gio::SimpleAction::new_stateful("tile-rendering", None, &false.to_variant());
+        self.add_action(&action_tile_rendering);
         let action_format_borders =
             gio::SimpleAction::new_stateful("format-borders", None, &true.to_variant());
         self.add_action(&action_format_borders);
+        let action_snap_to_grid =
+            gio::SimpleAction::new_stateful("snap-to-grid", None, &false.to_variant());
+        self.add_action(&action_snap_to_grid);
+        let action_snap_to_strokes =
+            gio::SimpleAction::new_stateful("snap-to-strokes", None, &false.to_variant());
+        self.add_action(&action_snap_to_strokes);
         let action_doc_layout = gio::SimpleAction::new_stateful(
             "doc-layout",
             Some(&glib::VariantType::new("s").unwrap()),
@@ -93,6 +103,20 @@ impl RnoteAppWindow {
         self.add_action(&action_resize_to_fit_strokes);
         let action_return_origin_page = gio::SimpleAction::new("return-origin-page", None);
         self.add_action(&action_return_origin_page);
+        let action_page_insert_before = gio::SimpleAction::new("page-insert-before", None);
+        self.add_action(&action_page_insert_before);
+        let action_page_insert_after = gio::SimpleAction::new("page-insert-after", None);
+        self.add_action(&action_page_insert_after);
+        let action_page_remove = gio::SimpleAction::new("page-remove", None);
+        self.add_action(&action_page_remove);
+        let action_page_move_up = gio::SimpleAction::new("page-move-up", None);
+        self.add_action(&action_page_move_up);
+        let action_page_move_down = gio::SimpleAction::new("page-move-down", None);
+        self.add_action(&action_page_move_down);
+        let action_page_duplicate = gio::SimpleAction::new("page-duplicate", None);
+        self.add_action(&action_page_duplicate);
+        let action_page_settings = gio::SimpleAction::new("page-settings", None);
+        self.add_action(&action_page_settings);
 
         let action_selection_trash = gio::SimpleAction::new("selection-trash", None);
         self.add_action(&action_selection_trash);
@@ -102,6 +126,47 @@ impl RnoteAppWindow {
         self.add_action(&action_selection_select_all);
         let action_selection_deselect_all = gio::SimpleAction::new("selection-deselect-all", None);
         self.add_action(&action_selection_deselect_all);
+        let action_selection_align = gio::SimpleAction::new(
+            "selection-align",
+            Some(&glib::VariantType::new("s").unwrap()),
+        );
+        self.add_action(&action_selection_align);
+        let action_selection_distribute = gio::SimpleAction::new(
+            "selection-distribute",
+            Some(&glib::VariantType::new("s").unwrap()),
+        );
+        self.add_action(&action_selection_distribute);
+        let action_selection_match_extents = gio::SimpleAction::new(
+            "selection-match-extents",
+            Some(&glib::VariantType::new("s").unwrap()),
+        );
+        self.add_action(&action_selection_match_extents);
+        let action_selection_mirror = gio::SimpleAction::new(
+            "selection-mirror",
+            Some(&glib::VariantType::new("s").unwrap()),
+        );
+        self.add_action(&action_selection_mirror);
+        let action_selection_boolean_op = gio::SimpleAction::new(
+            "selection-boolean-op",
+            Some(&glib::VariantType::new("s").unwrap()),
+        );
+        self.add_action(&action_selection_boolean_op);
+        let action_selection_convert = gio::SimpleAction::new(
+            "selection-convert",
+            Some(&glib::VariantType::new("s").unwrap()),
+        );
+        self.add_action(&action_selection_convert);
+        let action_selection_edit_nodes = gio::SimpleAction::new("selection-edit-nodes", None);
+        self.add_action(&action_selection_edit_nodes);
+        let action_selection_reorder = gio::SimpleAction::new(
+            "selection-reorder",
+            Some(&glib::VariantType::new("s").unwrap()),
+        );
+        self.add_action(&action_selection_reorder);
+        let action_selection_group = gio::SimpleAction::new("selection-group", None);
+        self.add_action(&action_selection_group);
+        let action_selection_ungroup = gio::SimpleAction::new("selection-ungroup", None);
+        self.add_action(&action_selection_ungroup);
         let action_clear_doc = gio::SimpleAction::new("clear-doc", None);
         self.add_action(&action_clear_doc);
         let action_new_doc = gio::SimpleAction::new("new-doc", None);
@@ -253,24 +318,20 @@ impl RnoteAppWindow {
             clone!(@weak self as appwindow => move |action_doc_layout, target| {
                 let doc_layout = target.unwrap().str().unwrap();
 
-                match doc_layout {
-                    "fixed-size" => {
-                        appwindow.canvas().engine().borrow_mut().set_doc_layout(Layout::FixedSize);
-                        appwindow.canvas_fixedsize_quickactions_revealer().set_reveal_child(true);
-                    },
-                    "continuous-vertical" => {
-                        appwindow.canvas().engine().borrow_mut().set_doc_layout(Layout::ContinuousVertical);
-                        appwindow.canvas_fixedsize_quickactions_revealer().set_reveal_child(false);
-                    },
-                    "infinite" => {
-                        appwindow.canvas().engine().borrow_mut().set_doc_layout(Layout::Infinite);
-                        appwindow.canvas_fixedsize_quickactions_revealer().set_reveal_child(false);
-                    }
+                let (layout, fixedsize_quickactions) = match doc_layout {
+                    "fixed-size" => (Layout::FixedSize, true),
+                    "continuous-vertical" => (Layout::ContinuousVertical, false),
+                    "continuous-horizontal" => (Layout::ContinuousHorizontal, false),
+                    "book-spread" => (Layout::BookSpread, false),
+                    "infinite" => (Layout::Infinite, false),
                     invalid_str => {
                         log::error!("action doc-layout failed, invalid str: {}", invalid_str);
                         return;
                     }
-                }
+                };
+                let widget_flags = appwindow.canvas().engine().borrow_mut().set_doc_layout(layout);
+                appwindow.canvas_fixedsize_quickactions_revealer().set_reveal_child(fixedsize_quickactions);
+                appwindow.handle_widget_flags(widget_flags);
                 appwindow.canvas().update_engine_rendering();
 
                 action_doc_layout.set_state(&doc_layout.to_variant());
@@ -287,6 +348,18 @@ impl RnoteAppWindow {
             }),
         );
 
+        // Tile rendering
+        action_tile_rendering.connect_change_state(
+            clone!(@weak self as appwindow => move |action_tile_rendering, state_request| {
+                let tile_rendering = state_request.unwrap().get::<bool>().unwrap();
+
+                appwindow.canvas().engine().borrow_mut().set_tile_rendering(tile_rendering);
+                appwindow.canvas().queue_draw();
+
+                action_tile_rendering.set_state(&tile_rendering.to_variant());
+            }),
+        );
+
         // Format borders
         action_format_borders.connect_change_state(
             clone!(@weak self as appwindow => move |action_format_borders, state_request| {
@@ -299,6 +372,28 @@ impl RnoteAppWindow {
             }),
         );
 
+        // Snap to grid
+        action_snap_to_grid.connect_change_state(
+            clone!(@weak self as appwindow => move |action_snap_to_grid, state_request| {
+                let snap_to_grid = state_request.unwrap().get::<bool>().unwrap();
+
+                appwindow.canvas().engine().borrow_mut().document.snap.to_grid = snap_to_grid;
+
+                action_snap_to_grid.set_state(&snap_to_grid.to_variant());
+            }),
+        );
+
+        // Snap to strokes
+        action_snap_to_strokes.connect_change_state(
+            clone!(@weak self as appwindow => move |action_snap_to_strokes, state_request| {
+                let snap_to_strokes = state_request.unwrap().get::<bool>().unwrap();
+
+                appwindow.canvas().engine().borrow_mut().document.snap.to_strokes = snap_to_strokes;
+
+                action_snap_to_strokes.set_state(&snap_to_strokes.to_variant());
+            }),
+        );
+
         // Pen style
         action_pen_style.connect_activate(
             clone!(@weak self as appwindow => move |_action_pen_style, target| {
@@ -392,13 +487,18 @@ impl RnoteAppWindow {
         action_refresh_ui_for_engine.connect_activate(clone!(
             @weak self as appwindow,
             @strong action_pen_sounds,
+            @strong action_tile_rendering,
             @strong action_doc_layout,
             @strong action_format_borders,
+            @strong action_snap_to_grid,
+            @strong action_snap_to_strokes,
             => move |_action_refresh_ui_for_engine, _| {
             // Avoids borrow errors
             let format = appwindow.canvas().engine().borrow().document.format.clone();
+            let snap = appwindow.canvas().engine().borrow().document.snap;
             let doc_layout = appwindow.canvas().engine().borrow().doc_layout();
             let pen_sounds = appwindow.canvas().engine().borrow().pen_sounds();
+            let tile_rendering = appwindow.canvas().engine().borrow().tile_rendering();
             let pen_style = appwindow.canvas().engine().borrow().penholder.current_style_w_override();
 
             {
@@ -406,12 +506,17 @@ impl RnoteAppWindow {
                 let doc_layout = match doc_layout {
                     Layout::FixedSize => "fixed-size",
                     Layout::ContinuousVertical => "continuous-vertical",
+                    Layout::ContinuousHorizontal => "continuous-horizontal",
+                    Layout::BookSpread => "book-spread",
                     Layout::Infinite => "infinite",
                 };
                 // we change the state through the actions, because they themselves hold state. ( e.g. used to display tickboxes for boolean actions )
                 action_doc_layout.activate(Some(&doc_layout.to_variant()));
                 action_pen_sounds.change_state(&pen_sounds.to_variant());
+                action_tile_rendering.change_state(&tile_rendering.to_variant());
                 action_format_borders.change_state(&format.show_borders.to_variant());
+                action_snap_to_grid.change_state(&snap.to_grid.to_variant());
+                action_snap_to_strokes.change_state(&snap.to_strokes.to_variant());
             }
 
 
@@ -469,6 +574,12 @@ impl RnoteAppWindow {
 
             // Settings panel
             appwindow.settings_panel().refresh_ui(&appwindow);
+
+            // Transform panel
+            appwindow.transform_panel().refresh_ui(&appwindow);
+
+            // Pages panel
+            appwindow.pages_panel().refresh_ui(&appwindow);
         }));
 
         // Trash Selection
@@ -535,6 +646,157 @@ impl RnoteAppWindow {
             }),
         );
 
+        // Align selection
+        action_selection_align.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_align, target| {
+                let alignment = match target.unwrap().str().unwrap() {
+                    "left" => Alignment::Left,
+                    "right" => Alignment::Right,
+                    "top" => Alignment::Top,
+                    "bottom" => Alignment::Bottom,
+                    "horizontal-center" => Alignment::HorizontalCenter,
+                    "vertical-center" => Alignment::VerticalCenter,
+                    invalid_str => {
+                        log::error!("action selection-align failed, invalid str: {}", invalid_str);
+                        return;
+                    }
+                };
+
+                let widget_flags = appwindow.canvas().engine().borrow_mut().align_selection(alignment);
+                appwindow.handle_widget_flags(widget_flags);
+            }),
+        );
+
+        // Distribute selection
+        action_selection_distribute.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_distribute, target| {
+                let axis = match target.unwrap().str().unwrap() {
+                    "horizontal" => Axis::Horizontal,
+                    "vertical" => Axis::Vertical,
+                    invalid_str => {
+                        log::error!("action selection-distribute failed, invalid str: {}", invalid_str);
+                        return;
+                    }
+                };
+
+                let widget_flags = appwindow.canvas().engine().borrow_mut().distribute_selection(axis);
+                appwindow.handle_widget_flags(widget_flags);
+            }),
+        );
+
+        // Match the width or height of the selection
+        action_selection_match_extents.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_match_extents, target| {
+                let axis = match target.unwrap().str().unwrap() {
+                    "width" => Axis::Horizontal,
+                    "height" => Axis::Vertical,
+                    invalid_str => {
+                        log::error!("action selection-match-extents failed, invalid str: {}", invalid_str);
+                        return;
+                    }
+                };
+
+                let widget_flags = appwindow.canvas().engine().borrow_mut().match_selection_extents(axis);
+                appwindow.handle_widget_flags(widget_flags);
+            }),
+        );
+
+        // Mirror selection
+        action_selection_mirror.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_mirror, target| {
+                // the angle of the mirror axis
+                let axis_angle = match target.unwrap().str().unwrap() {
+                    "horizontal" => std::f64::consts::FRAC_PI_2,
+                    "vertical" => 0.0,
+                    invalid_str => {
+                        log::error!("action selection-mirror failed, invalid str: {}", invalid_str);
+                        return;
+                    }
+                };
+
+                let widget_flags = appwindow.canvas().engine().borrow_mut().mirror_selection(axis_angle, None);
+                appwindow.handle_widget_flags(widget_flags);
+            }),
+        );
+
+        // Boolean operation between the selected strokes
+        action_selection_boolean_op.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_boolean_op, target| {
+                let op = match target.unwrap().str().unwrap() {
+                    "union" => BooleanOp::Union,
+                    "intersection" => BooleanOp::Intersection,
+                    "difference" => BooleanOp::Difference,
+                    "xor" => BooleanOp::Xor,
+                    invalid_str => {
+                        log::error!("action selection-boolean-op failed, invalid str: {}", invalid_str);
+                        return;
+                    }
+                };
+
+                let widget_flags = appwindow.canvas().engine().borrow_mut().boolean_op_selection(op);
+                appwindow.handle_widget_flags(widget_flags);
+            }),
+        );
+
+        // Convert the selected strokes between brush strokes and outline shapes
+        action_selection_convert.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_convert, target| {
+                let widget_flags = match target.unwrap().str().unwrap() {
+                    "outline" => appwindow.canvas().engine().borrow_mut().convert_selection_to_outlines(),
+                    "centerline" => appwindow.canvas().engine().borrow_mut().convert_selection_to_centerlines(),
+                    invalid_str => {
+                        log::error!("action selection-convert failed, invalid str: {}", invalid_str);
+                        return;
+                    }
+                };
+
+                appwindow.handle_widget_flags(widget_flags);
+            }),
+        );
+
+        // Edit the nodes of the selected shape
+        action_selection_edit_nodes.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_edit_nodes, _| {
+                let widget_flags = appwindow.canvas().engine().borrow_mut().edit_selection_nodes();
+                appwindow.handle_widget_flags(widget_flags);
+            }),
+        );
+
+        // Reorder selection
+        action_selection_reorder.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_reorder, target| {
+                let reordering = match target.unwrap().str().unwrap() {
+                    "bring-forward" => Reordering::BringForward,
+                    "send-backward" => Reordering::SendBackward,
+                    "bring-to-front" => Reordering::BringToFront,
+                    "send-to-back" => Reordering::SendToBack,
+                    invalid_str => {
+                        log::error!("action selection-reorder failed, invalid str: {}", invalid_str);
+                        return;
+                    }
+                };
+
+                let widget_flags = appwindow.canvas().engine().borrow_mut().reorder_selection(reordering);
+                appwindow.handle_widget_flags(widget_flags);
+            }),
+        );
+
+        // Group selection
+        action_selection_group.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_group, _| {
+                let widget_flags = appwindow.canvas().engine().borrow_mut().group_selection();
+                appwindow.handle_widget_flags(widget_flags);
+            }),
+        );
+
+        // Ungroup selection
+        action_selection_ungroup.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_ungroup, _| {
+                let widget_flags = appwindow.canvas().engine().borrow_mut().ungroup_selection();
+                appwindow.handle_widget_flags(widget_flags);
+            }),
+        );
+
         // Clear doc
         action_clear_doc.connect_activate(clone!(@weak self as appwindow => move |_, _| {
             dialogs::dialog_clear_doc(&appwindow);
@@ -605,12 +867,110 @@ impl RnoteAppWindow {
         // Add page to doc in fixed size mode
         action_add_page_to_doc.connect_activate(
             clone!(@weak self as appwindow => move |_action_add_page_to_doc, _target| {
-            let format_height = appwindow.canvas().engine().borrow().document.format.height;
-            let new_doc_height = appwindow.canvas().engine().borrow().document.height + format_height;
-            appwindow.canvas().engine().borrow_mut().document.height = new_doc_height;
+                {
+                    let engine = appwindow.canvas().engine();
+                    let engine = &mut *engine.borrow_mut();
+                    // The new page can have its own size
+                    let n_pages = engine.document.calc_n_pages();
+                    let new_page_size = engine.document.page_size(n_pages as usize);
+
+                    engine.document.height += new_page_size[1];
+                    engine.document.width = engine.document.width.max(new_page_size[0]);
+                }
 
-            appwindow.canvas().update_engine_rendering();
-        }));
+                appwindow.canvas().update_engine_rendering();
+            }),
+        );
+
+        // Insert page before the current page
+        action_page_insert_before.connect_activate(
+            clone!(@weak self as appwindow => move |_action_page_insert_before, _target| {
+                let current_page = match current_manageable_page(&appwindow) {
+                    Some(current_page) => current_page,
+                    None => return,
+                };
+
+                let widget_flags = appwindow.canvas().engine().borrow_mut().insert_page(current_page);
+                appwindow.handle_widget_flags(widget_flags);
+            }),
+        );
+
+        // Insert page after the current page
+        action_page_insert_after.connect_activate(
+            clone!(@weak self as appwindow => move |_action_page_insert_after, _target| {
+                let current_page = match current_manageable_page(&appwindow) {
+                    Some(current_page) => current_page,
+                    None => return,
+                };
+
+                let widget_flags = appwindow.canvas().engine().borrow_mut().insert_page(current_page + 1);
+                appwindow.handle_widget_flags(widget_flags);
+            }),
+        );
+
+        // Remove the current page
+        action_page_remove.connect_activate(
+            clone!(@weak self as appwindow => move |_action_page_remove, _target| {
+                let current_page = match current_manageable_page(&appwindow) {
+                    Some(current_page) => current_page,
+                    None => return,
+                };
+
+                let widget_flags = appwindow.canvas().engine().borrow_mut().remove_page(current_page);
+                appwindow.handle_widget_flags(widget_flags);
+            }),
+        );
+
+        // Move the current page up
+        action_page_move_up.connect_activate(
+            clone!(@weak self as appwindow => move |_action_page_move_up, _target| {
+                let current_page = match current_manageable_page(&appwindow) {
+                    Some(current_page) => current_page,
+                    None => return,
+                };
+
+                let widget_flags = appwindow.canvas().engine().borrow_mut().move_page_up(current_page);
+                appwindow.handle_widget_flags(widget_flags);
+            }),
+        );
+
+        // Move the current page down
+        action_page_move_down.connect_activate(
+            clone!(@weak self as appwindow => move |_action_page_move_down, _target| {
+                let current_page = match current_manageable_page(&appwindow) {
+                    Some(current_page) => current_page,
+                    None => return,
+                };
+
+                let widget_flags = appwindow.canvas().engine().borrow_mut().move_page_down(current_page);
+                appwindow.handle_widget_flags(widget_flags);
+            }),
+        );
+
+        // Duplicate the current page
+        action_page_duplicate.connect_activate(
+            clone!(@weak self as appwindow => move |_action_page_duplicate, _target| {
+                let current_page = match current_manageable_page(&appwindow) {
+                    Some(current_page) => current_page,
+                    None => return,
+                };
+
+                let widget_flags = appwindow.canvas().engine().borrow_mut().duplicate_page(current_page);
+                appwindow.handle_widget_flags(widget_flags);
+            }),
+        );
+
+        // Edit the format and background overrides of the current page
+        action_page_settings.connect_activate(
+            clone!(@weak self as appwindow => move |_action_page_settings, _target| {
+                let current_page = match current_manageable_page(&appwindow) {
+                    Some(current_page) => current_page,
+                    None => return,
+                };
+
+                dialogs::dialog_page_settings(&appwindow, current_page);
+            }),
+        );
 
         // Resize to fit strokes
         action_resize_to_fit_strokes.connect_activate(
@@ -676,7 +1036,6 @@ impl RnoteAppWindow {
         // Print doc
         action_print_doc.connect_activate(clone!(@weak self as appwindow => move |_, _| {
             let doc_bounds = appwindow.canvas().engine().borrow().document.bounds();
-            let format_size = na::vector![appwindow.canvas().engine().borrow().document.format.width, appwindow.canvas().engine().borrow().document.format.height];
             let store_snapshot = appwindow.canvas().engine().borrow().store.take_store_snapshot();
             let pages_bounds = appwindow.canvas().engine().borrow().pages_bounds_w_content();
             let n_pages = pages_bounds.len();
@@ -688,11 +1047,10 @@ impl RnoteAppWindow {
 
             let background_svg = if with_background {
                 appwindow.canvas().engine().borrow().document
-                    .background
-                    .gen_svg(doc_bounds)
+                    .gen_background_svg(doc_bounds)
                     .map_err(|e| {
                         log::error!(
-                            "background.gen_svg() failed in in the print document action, with Err {}",
+                            "gen_background_svg() failed in in the print document action, with Err {}",
                             e
                         )
                     })
@@ -723,8 +1081,9 @@ impl RnoteAppWindow {
                     );
 
                     let print_zoom = {
-                        let width_scale = print_cx.width() / format_size[0];
-                        let height_scale = print_cx.height() / format_size[1];
+                        // Pages can have different sizes
+                        let width_scale = print_cx.width() / page_bounds.extents()[0];
+                        let height_scale = print_cx.height() / page_bounds.extents()[1];
 
                         width_scale.min(height_scale)
                     };
@@ -967,6 +1326,18 @@ impl RnoteAppWindow {
         app.set_accels_for_action("win.redo", &["<Ctrl><Shift>z"]);
         app.set_accels_for_action("win.clipboard-copy", &["<Ctrl>c"]);
         app.set_accels_for_action("win.clipboard-paste", &["<Ctrl>v"]);
+        app.set_accels_for_action("win.selection-group", &["<Ctrl>g"]);
+        app.set_accels_for_action("win.selection-ungroup", &["<Ctrl><Shift>g"]);
+        app.set_accels_for_action("win.selection-reorder::bring-forward", &["<Ctrl>Page_Up"]);
+        app.set_accels_for_action("win.selection-reorder::send-backward", &["<Ctrl>Page_Down"]);
+        app.set_accels_for_action(
+            "win.selection-reorder::bring-to-front",
+            &["<Ctrl><Shift>Page_Up"],
+        );
+        app.set_accels_for_action(
+            "win.selection-reorder::send-to-back",
+            &["<Ctrl><Shift>Page_Down"],
+        );
 
         // shortcuts for devel builds
         if config::PROFILE.to_lowercase().as_str() == "devel" {
@@ -974,3 +1345,21 @@ impl RnoteAppWindow {
         }
     }
 }
+
+/// The current page of the canvas, if pages can be managed in the current document layout. Else notifies the user
+fn current_manageable_page(appwindow: &RnoteAppWindow) -> Option<usize> {
+    let current_page = appwindow.canvas().engine().borrow().current_page();
+
+    if current_page.is_none() {
+        adw::prelude::ActionGroupExt::activate_action(
+            appwindow,
+            "text-toast",
+            Some(
+                &gettext("Pages can only be managed in the fixed size and continuous layouts")
+                    .to_variant(),
+            ),
+        );
+    }
+
+    current_page
+}
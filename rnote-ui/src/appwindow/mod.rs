@@ -25,13 +25,16 @@ use crate::{
     app::RnoteApp,
     canvas::RnoteCanvas,
     config,
+    pagespanel::PagesPanel,
     penssidebar::PensSideBar,
     settingspanel::SettingsPanel,
+    transformpanel::TransformPanel,
     utils,
     workspacebrowser::WorkspaceBrowser,
     {dialogs, mainheader::MainHeader},
 };
 use rnote_engine::{
+    document::background::BackgroundTemplate,
     engine::EngineTask,
     pens::penholder::PenStyle,
     strokes::{BitmapImage, VectorImage},
@@ -78,6 +81,10 @@ mod imp {
         #[template_child]
         pub settings_panel: TemplateChild<SettingsPanel>,
         #[template_child]
+        pub transform_panel: TemplateChild<TransformPanel>,
+        #[template_child]
+        pub pages_panel: TemplateChild<PagesPanel>,
+        #[template_child]
         pub sidebar_scroller: TemplateChild<ScrolledWindow>,
         #[template_child]
         pub sidebar_grid: TemplateChild<Grid>,
@@ -145,6 +152,8 @@ mod imp {
                 canvas_scroller: TemplateChild::<ScrolledWindow>::default(),
                 canvas: TemplateChild::<RnoteCanvas>::default(),
                 settings_panel: TemplateChild::<SettingsPanel>::default(),
+                transform_panel: TemplateChild::<TransformPanel>::default(),
+                pages_panel: TemplateChild::<PagesPanel>::default(),
                 sidebar_scroller: TemplateChild::<ScrolledWindow>::default(),
                 sidebar_grid: TemplateChild::<Grid>::default(),
                 sidebar_sep: TemplateChild::<Separator>::default(),
@@ -629,6 +638,14 @@ mod imp {
                     .settings_panel()
                     .settings_scroller()
                     .set_window_placement(CornerType::TopRight);
+                appwindow
+                    .transform_panel()
+                    .transform_scroller()
+                    .set_window_placement(CornerType::TopRight);
+                appwindow
+                    .pages_panel()
+                    .pages_scroller()
+                    .set_window_placement(CornerType::TopRight);
                 appwindow
                     .penssidebar()
                     .brush_page()
@@ -744,6 +761,14 @@ mod imp {
                     .settings_panel()
                     .settings_scroller()
                     .set_window_placement(CornerType::TopLeft);
+                appwindow
+                    .transform_panel()
+                    .transform_scroller()
+                    .set_window_placement(CornerType::TopLeft);
+                appwindow
+                    .pages_panel()
+                    .pages_scroller()
+                    .set_window_placement(CornerType::TopLeft);
                 appwindow
                     .penssidebar()
                     .brush_page()
@@ -916,6 +941,14 @@ impl RnoteAppWindow {
         self.imp().settings_panel.get()
     }
 
+    pub fn transform_panel(&self) -> TransformPanel {
+        self.imp().transform_panel.get()
+    }
+
+    pub fn pages_panel(&self) -> PagesPanel {
+        self.imp().pages_panel.get()
+    }
+
     pub fn sidebar_scroller(&self) -> ScrolledWindow {
         self.imp().sidebar_scroller.get()
     }
@@ -996,6 +1029,8 @@ impl RnoteAppWindow {
     pub fn init(&self) {
         self.imp().workspacebrowser.get().init(self);
         self.imp().settings_panel.get().init(self);
+        self.imp().transform_panel.get().init(self);
+        self.imp().pages_panel.get().init(self);
         self.imp().mainheader.get().init(self);
         self.imp().mainheader.get().canvasmenu().init(self);
         self.imp().mainheader.get().appmenu().init(self);
@@ -1279,6 +1314,17 @@ impl RnoteAppWindow {
         if widget_flags.indicate_changed_store {
             self.canvas().set_unsaved_changes(true);
             self.canvas().set_empty(false);
+            // The tiles covering changed strokes need to be rerendered
+            self.canvas()
+                .engine()
+                .borrow_mut()
+                .update_tiles_current_viewport();
+        }
+        if widget_flags.redraw || widget_flags.indicate_changed_store {
+            self.pages_panel().queue_refresh(self);
+        }
+        if widget_flags.update_page_thumbnails {
+            self.pages_panel().update_thumbnails(self);
         }
         if widget_flags.update_view {
             let camera_offset = self.canvas().engine().borrow().camera.offset;
@@ -1602,9 +1648,15 @@ impl RnoteAppWindow {
         let app = self.application().unwrap().downcast::<RnoteApp>().unwrap();
 
         let pos = target_pos.unwrap_or_else(|| {
-            (self.canvas().engine().borrow().camera.transform().inverse()
-                * na::Point2::from(VectorImage::IMPORT_OFFSET_DEFAULT))
-            .coords
+            let engine = self.canvas().engine();
+            let engine = engine.borrow();
+
+            // New content is placed inside the page margins
+            engine.document.clamp_to_page_content(
+                (engine.camera.transform().inverse()
+                    * na::Point2::from(VectorImage::IMPORT_OFFSET_DEFAULT))
+                .coords,
+            )
         });
 
         // we need the split the import operation between generate_vectorimage_from_bytes() which returns a receiver and import_generated_strokes(),
@@ -1638,9 +1690,15 @@ impl RnoteAppWindow {
         let app = self.application().unwrap().downcast::<RnoteApp>().unwrap();
 
         let pos = target_pos.unwrap_or_else(|| {
-            (self.canvas().engine().borrow().camera.transform().inverse()
-                * na::Point2::from(BitmapImage::IMPORT_OFFSET_DEFAULT))
-            .coords
+            let engine = self.canvas().engine();
+            let engine = engine.borrow();
+
+            // New content is placed inside the page margins
+            engine.document.clamp_to_page_content(
+                (engine.camera.transform().inverse()
+                    * na::Point2::from(BitmapImage::IMPORT_OFFSET_DEFAULT))
+                .coords,
+            )
         });
 
         let bitmapimage_receiver = self
@@ -1673,9 +1731,15 @@ impl RnoteAppWindow {
         let app = self.application().unwrap().downcast::<RnoteApp>().unwrap();
 
         let pos = target_pos.unwrap_or_else(|| {
-            (self.canvas().engine().borrow().camera.transform().inverse()
-                * na::Point2::from(VectorImage::IMPORT_OFFSET_DEFAULT))
-            .coords
+            let engine = self.canvas().engine();
+            let engine = engine.borrow();
+
+            // New content is placed inside the page margins
+            engine.document.clamp_to_page_content(
+                (engine.camera.transform().inverse()
+                    * na::Point2::from(VectorImage::IMPORT_OFFSET_DEFAULT))
+                .coords,
+            )
         });
 
         let strokes_receiver = self
@@ -1814,6 +1878,75 @@ impl RnoteAppWindow {
         Ok(())
     }
 
+    /// Loads the file as template of the document background. For pdf files the page with the given index is used.
+    pub async fn load_background_template(
+        &self,
+        file: &gio::File,
+        pdf_page_i: u32,
+    ) -> anyhow::Result<()> {
+        let (bytes, _) = file.load_bytes_future().await?;
+
+        let mut template = match utils::FileType::lookup_file_type(file) {
+            utils::FileType::VectorImageFile => {
+                BackgroundTemplate::from_svg_data(&String::from_utf8(bytes.to_vec())?)?
+            }
+            utils::FileType::BitmapImageFile => BackgroundTemplate::from_bitmap_bytes(&bytes)?,
+            utils::FileType::PdfFile => {
+                BackgroundTemplate::from_pdf_page_bytes(&bytes, pdf_page_i)?
+            }
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "file has an unsupported file type for background templates"
+                ))
+            }
+        };
+        template.fit = self.settings_panel().background_template_fit();
+
+        self.canvas()
+            .engine()
+            .borrow_mut()
+            .document
+            .background
+            .template = Some(template);
+
+        self.canvas().set_unsaved_changes(true);
+        self.canvas().background_changed();
+        self.canvas().update_engine_rendering();
+
+        Ok(())
+    }
+
+    /// imports the pen presets from the json file. Existing presets with the same name are replaced.
+    /// Returns the number of imported presets
+    pub async fn import_presets(&self, file: &gio::File) -> anyhow::Result<usize> {
+        let (bytes, _) = file.load_bytes_future().await?;
+        let json = String::from_utf8(bytes.to_vec())?;
+
+        let n_imported = self
+            .canvas()
+            .engine()
+            .borrow_mut()
+            .penholder
+            .import_presets_from_json(&json)?;
+        self.save_engine_config()?;
+
+        Ok(n_imported)
+    }
+
+    /// exports and writes the pen presets as json into the file.
+    pub async fn export_presets(&self, file: &gio::File) -> anyhow::Result<()> {
+        let exported_presets = self
+            .canvas()
+            .engine()
+            .borrow()
+            .penholder
+            .export_presets_as_json()?;
+
+        utils::replace_file_future(exported_presets.into_bytes(), file).await?;
+
+        Ok(())
+    }
+
     /// exports and writes the engine state as json into the file.
     /// Only for debugging!
     pub async fn export_engine_state(&self, file: &gio::File) -> anyhow::Result<()> {
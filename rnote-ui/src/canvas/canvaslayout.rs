@@ -85,7 +85,10 @@ mod imp {
 
             // Update the adjustments
             let (h_lower, h_upper) = match doc_layout {
-                Layout::FixedSize | Layout::ContinuousVertical => (
+                Layout::FixedSize
+                | Layout::ContinuousVertical
+                | Layout::ContinuousHorizontal
+                | Layout::BookSpread => (
                     (canvas.engine().borrow().document.x - Document::SHADOW_WIDTH) * total_zoom,
                     (canvas.engine().borrow().document.x
                         + canvas.engine().borrow().document.width
@@ -100,7 +103,10 @@ mod imp {
             };
 
             let (v_lower, v_upper) = match canvas.engine().borrow().doc_layout() {
-                Layout::FixedSize | Layout::ContinuousVertical => (
+                Layout::FixedSize
+                | Layout::ContinuousVertical
+                | Layout::ContinuousHorizontal
+                | Layout::BookSpread => (
                     (canvas.engine().borrow().document.y - Document::SHADOW_WIDTH) * total_zoom,
                     (canvas.engine().borrow().document.y
                         + canvas.engine().borrow().document.height
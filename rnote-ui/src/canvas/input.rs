@@ -71,14 +71,17 @@ pub fn debug_drag_gesture(drag_gesture: &GestureDrag) {
 /// Retreive elements from a (emulated) pointer
 /// X and Y is already available from closure, and should not retreived from .axis() (because of gtk weirdness)
 pub fn retreive_pointer_elements(
-    _mouse_drawing_gesture: &GestureDrag,
+    mouse_drawing_gesture: &GestureDrag,
     x: f64,
     y: f64,
 ) -> VecDeque<Element> {
     let mut data_entries: VecDeque<Element> = VecDeque::with_capacity(1);
     //std::thread::sleep(std::time::Duration::from_millis(100));
 
-    data_entries.push_back(Element::new(na::vector![x, y], Element::PRESSURE_DEFAULT));
+    data_entries.push_back(
+        Element::new(na::vector![x, y], Element::PRESSURE_DEFAULT)
+            .with_timestamp(event_time_secs(mouse_drawing_gesture.current_event_time())),
+    );
     data_entries
 }
 
@@ -185,11 +188,30 @@ pub fn retreive_stylus_elements(
         Element::PRESSURE_DEFAULT
     };
 
-    data_entries.push_back(Element::new(na::vector![x, y], pressure));
+    let mut element = Element::new(na::vector![x, y], pressure)
+        .with_timestamp(event_time_secs(stylus_drawing_gesture.current_event_time()));
+
+    // gtk reports the tilt axes as angles in degrees in the range [-90.0, 90.0]
+    if let (Some(x_tilt), Some(y_tilt)) = (
+        stylus_drawing_gesture.axis(gdk::AxisUse::Xtilt),
+        stylus_drawing_gesture.axis(gdk::AxisUse::Ytilt),
+    ) {
+        element = element.with_axes_tilt(
+            x_tilt.clamp(-90.0, 90.0).to_radians(),
+            y_tilt.clamp(-90.0, 90.0).to_radians(),
+        );
+    }
+
+    data_entries.push_back(element);
 
     data_entries
 }
 
+/// Converts the event time from gtk ( in milliseconds ) to seconds
+fn event_time_secs(event_time: u32) -> f64 {
+    f64::from(event_time) / 1000.0
+}
+
 /// Process "Pen down"
 pub fn process_pen_down(
     element: Element,
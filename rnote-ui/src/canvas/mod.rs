@@ -254,6 +254,17 @@ mod imp {
                     let output_file = value
                         .get::<Option<gio::File>>()
                         .expect("The value needs to be of type `Option<gio::File>`.");
+
+                    // The title of the document is the file name, used in the page header and footer
+                    self.engine.borrow_mut().document.title = output_file
+                        .as_ref()
+                        .and_then(|file| file.path())
+                        .and_then(|path| {
+                            path.file_stem()
+                                .map(|stem| stem.to_string_lossy().to_string())
+                        })
+                        .unwrap_or_default();
+
                     self.output_file.replace(output_file);
                 }
                 "unsaved-changes" => {
@@ -824,6 +835,26 @@ impl RnoteCanvas {
         self.update_camera_offset(new_offset);
     }
 
+    /// Scrolls to the top of the given page, horizontally centered if it fits into the displayed surface
+    pub fn scroll_to_page(&self, page_bounds: AABB) {
+        let zoom = self.engine().borrow().camera.zoom();
+        let parent_width = f64::from(self.parent().unwrap().width());
+
+        let new_offset = if page_bounds.extents()[0] * zoom <= parent_width {
+            na::vector![
+                page_bounds.center()[0] * zoom - parent_width * 0.5,
+                (page_bounds.mins[1] - Document::SHADOW_WIDTH) * zoom
+            ]
+        } else {
+            na::vector![
+                (page_bounds.mins[0] - Document::SHADOW_WIDTH) * zoom,
+                (page_bounds.mins[1] - Document::SHADOW_WIDTH) * zoom
+            ]
+        };
+
+        self.update_camera_offset(new_offset);
+    }
+
     /// zooms and regenerates the canvas and its contents to a new zoom
     /// is private, zooming from other parts of the app should always be done through the "zoom-to-value" action
     fn zoom_to(&self, new_zoom: f64) {
@@ -923,12 +954,22 @@ impl RnoteCanvas {
             .engine()
             .borrow_mut()
             .document
-            .background
-            .regenerate_pattern(viewport, image_scale)
+            .regenerate_background_patterns(viewport, image_scale)
         {
             log::error!("failed to regenerate background, {}", e)
         };
 
         self.queue_draw();
     }
+
+    /// Marks the backgrounds as changed and regenerates the background pattern.
+    /// to be called after the document or page backgrounds were changed.
+    pub fn background_changed(&self) {
+        self.engine()
+            .borrow_mut()
+            .document
+            .set_backgrounds_changed();
+
+        self.regenerate_background_pattern();
+    }
 }
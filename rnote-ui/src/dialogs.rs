@@ -2,12 +2,16 @@ use adw::prelude::*;
 use gettextrs::gettext;
 use gtk4::MenuButton;
 use gtk4::{
-    gio, glib, glib::clone, AboutDialog, Builder, Button, ColorButton, Dialog, Entry,
+    gdk, gio, glib, glib::clone, AboutDialog, Builder, Button, ColorButton, Dialog, Entry,
     FileChooserAction, FileChooserNative, FileFilter, Label, MessageDialog, ResponseType,
-    ShortcutsWindow, SpinButton, StringList, ToggleButton,
+    ShortcutsWindow, SpinButton, StringList, Switch, ToggleButton,
 };
 use num_traits::ToPrimitive;
+use rnote_engine::document::background::PatternStyle;
+use rnote_engine::document::format::{Format, Orientation};
+use rnote_engine::document::Page;
 use rnote_engine::import::{PdfImportPageSpacing, PdfImportPagesType, PdfImportPrefs};
+use rnote_engine::utils::GdkRGBAHelpers;
 
 use crate::appwindow::RnoteAppWindow;
 use crate::workspacebrowser::WorkspaceRow;
@@ -346,6 +350,106 @@ pub fn dialog_import_pdf_w_prefs(appwindow: &RnoteAppWindow, target_pos: Option<
     }
 }
 
+/// Edits the overrides of the format and background of the page with the given index
+pub fn dialog_page_settings(appwindow: &RnoteAppWindow, page_index: usize) {
+    let builder =
+        Builder::from_resource((String::from(config::APP_IDPATH) + "ui/dialogs.ui").as_str());
+    let dialog_page_settings: Dialog = builder.object("dialog_page_settings").unwrap();
+    let page_settings_size_switch: Switch = builder.object("page_settings_size_switch").unwrap();
+    let page_settings_width_spinbutton: SpinButton =
+        builder.object("page_settings_width_spinbutton").unwrap();
+    let page_settings_height_spinbutton: SpinButton =
+        builder.object("page_settings_height_spinbutton").unwrap();
+    let page_settings_orientation_row: adw::ComboRow =
+        builder.object("page_settings_orientation_row").unwrap();
+    let page_settings_background_switch: Switch =
+        builder.object("page_settings_background_switch").unwrap();
+    let page_settings_pattern_row: adw::ComboRow =
+        builder.object("page_settings_pattern_row").unwrap();
+    let page_settings_background_color_button: ColorButton = builder
+        .object("page_settings_background_color_button")
+        .unwrap();
+
+    let (page, format_size, doc_background) = {
+        let engine = appwindow.canvas().engine();
+        let engine = engine.borrow();
+
+        (
+            engine
+                .document
+                .page(page_index)
+                .cloned()
+                .unwrap_or_default(),
+            na::vector![engine.document.format.width, engine.document.format.height],
+            engine.document.background.clone(),
+        )
+    };
+
+    page_settings_width_spinbutton.set_increments(1.0, 10.0);
+    page_settings_width_spinbutton.set_range(Format::WIDTH_MIN, Format::WIDTH_MAX);
+    page_settings_height_spinbutton.set_increments(1.0, 10.0);
+    page_settings_height_spinbutton.set_range(Format::HEIGHT_MIN, Format::HEIGHT_MAX);
+
+    // Set the widget state from the page overrides
+    let size = page.size.unwrap_or(format_size);
+    page_settings_size_switch.set_active(page.size.is_some());
+    page_settings_width_spinbutton.set_value(size[0]);
+    page_settings_height_spinbutton.set_value(size[1]);
+    page_settings_orientation_row.set_selected(match page.orientation {
+        None => 0,
+        Some(Orientation::Portrait) => 1,
+        Some(Orientation::Landscape) => 2,
+    });
+
+    let background = page
+        .background
+        .clone()
+        .unwrap_or_else(|| doc_background.clone());
+    page_settings_background_switch.set_active(page.background.is_some());
+    page_settings_pattern_row.set_selected(background.pattern.to_u32().unwrap());
+    page_settings_background_color_button
+        .set_rgba(&gdk::RGBA::from_compose_color(background.color));
+
+    dialog_page_settings.set_transient_for(Some(appwindow));
+
+    dialog_page_settings.connect_response(
+        clone!(@weak appwindow => move |dialog_page_settings, responsetype| {
+            dialog_page_settings.close();
+
+            if responsetype != ResponseType::Apply {
+                return;
+            }
+
+            let orientation = match page_settings_orientation_row.selected() {
+                1 => Some(Orientation::Portrait),
+                2 => Some(Orientation::Landscape),
+                _ => None,
+            };
+            let background = if page_settings_background_switch.is_active() {
+                let mut background = page.background.clone().unwrap_or_else(|| doc_background.clone());
+                background.pattern = PatternStyle::try_from(page_settings_pattern_row.selected()).unwrap_or_default();
+                background.color = page_settings_background_color_button.rgba().into_compose_color();
+                Some(background)
+            } else {
+                None
+            };
+            let new_page = Page {
+                size: page_settings_size_switch.is_active().then(|| na::vector![
+                    page_settings_width_spinbutton.value(),
+                    page_settings_height_spinbutton.value()
+                ]),
+                orientation,
+                background,
+            };
+
+            let widget_flags = appwindow.canvas().engine().borrow_mut().set_page_overrides(page_index, new_page);
+            appwindow.handle_widget_flags(widget_flags);
+        }),
+    );
+
+    dialog_page_settings.show();
+}
+
 pub fn dialog_edit_workspace(appwindow: &RnoteAppWindow) {
     let builder =
         Builder::from_resource((String::from(config::APP_IDPATH) + "ui/dialogs.ui").as_str());
@@ -611,6 +715,60 @@ pub fn dialog_import_file(appwindow: &RnoteAppWindow) {
     *appwindow.filechoosernative().borrow_mut() = Some(dialog_import_file);
 }
 
+pub fn dialog_import_background_template(appwindow: &RnoteAppWindow) {
+    let filter = FileFilter::new();
+    filter.add_mime_type("image/svg+xml");
+    filter.add_mime_type("image/png");
+    filter.add_mime_type("image/jpeg");
+    filter.add_mime_type("application/pdf");
+    filter.add_pattern("*.svg");
+    filter.add_pattern("*.png");
+    filter.add_pattern("*.jpg");
+    filter.add_pattern("*.pdf");
+    filter.set_name(Some(&gettext("PNG / SVG / JPG / PDF file")));
+
+    let dialog_import_background_template: FileChooserNative = FileChooserNative::builder()
+        .title(&gettext("Choose background template"))
+        .modal(true)
+        .transient_for(appwindow)
+        .accept_label(&gettext("Choose"))
+        .cancel_label(&gettext("Cancel"))
+        .action(FileChooserAction::Open)
+        .select_multiple(false)
+        .build();
+    dialog_import_background_template.add_filter(&filter);
+
+    dialog_import_background_template.connect_response(
+        clone!(@weak appwindow => move |dialog_import_background_template, responsetype| {
+            match responsetype {
+                ResponseType::Accept => {
+                    if let Some(file) = dialog_import_background_template.file() {
+                        glib::MainContext::default().spawn_local(clone!(@strong appwindow => async move {
+                            let pdf_page_i = appwindow.settings_panel().background_template_pdf_page_i();
+
+                            match appwindow.load_background_template(&file, pdf_page_i).await {
+                                Ok(()) => {
+                                    appwindow.settings_panel().load_background(&appwindow);
+                                    adw::prelude::ActionGroupExt::activate_action(&appwindow, "text-toast", Some(&gettext("Set the background template successfully.").to_variant()));
+                                }
+                                Err(e) => {
+                                    log::error!("loading background template failed with error `{}`", e);
+                                    adw::prelude::ActionGroupExt::activate_action(&appwindow, "error-toast", Some(&gettext("Setting the background template failed.").to_variant()));
+                                }
+                            }
+                        }));
+                    }
+                }
+                _ => {}
+            }
+        }),
+    );
+
+    dialog_import_background_template.show();
+    // keeping the filechooser around because otherwise GTK won't keep it alive
+    *appwindow.filechoosernative().borrow_mut() = Some(dialog_import_background_template);
+}
+
 pub fn dialog_export_selection_as_svg(appwindow: &RnoteAppWindow) {
     let filter = FileFilter::new();
     filter.add_mime_type("image/svg+xml");
@@ -974,3 +1132,93 @@ pub fn dialog_export_engine_config(appwindow: &RnoteAppWindow) {
     // keeping the filechooser around because otherwise GTK won't keep it alive
     *appwindow.filechoosernative().borrow_mut() = Some(dialog_export_engine_config);
 }
+
+pub fn dialog_import_presets(appwindow: &RnoteAppWindow) {
+    let filter = FileFilter::new();
+    filter.add_mime_type("application/json");
+    filter.add_pattern("*.json");
+    filter.set_name(Some(&gettext("JSON file")));
+
+    let dialog_import_presets: FileChooserNative = FileChooserNative::builder()
+        .title(&gettext("Import pen presets"))
+        .modal(true)
+        .transient_for(appwindow)
+        .accept_label(&gettext("Import"))
+        .cancel_label(&gettext("Cancel"))
+        .action(FileChooserAction::Open)
+        .select_multiple(false)
+        .build();
+    dialog_import_presets.add_filter(&filter);
+
+    dialog_import_presets.connect_response(
+        clone!(@weak appwindow => move |dialog_import_presets, responsetype| {
+            match responsetype {
+                ResponseType::Accept => {
+                    if let Some(file) = dialog_import_presets.file() {
+                        glib::MainContext::default().spawn_local(clone!(@strong appwindow => async move {
+                            match appwindow.import_presets(&file).await {
+                                Ok(_n_imported) => {
+                                    appwindow.settings_panel().refresh_ui(&appwindow);
+                                    adw::prelude::ActionGroupExt::activate_action(&appwindow, "text-toast", Some(&gettext("Imported pen presets successfully.").to_variant()));
+                                }
+                                Err(e) => {
+                                    log::error!("importing pen presets failed with error `{}`", e);
+                                    adw::prelude::ActionGroupExt::activate_action(&appwindow, "error-toast", Some(&gettext("Import pen presets failed.").to_variant()));
+                                }
+                            }
+                        }));
+                    }
+                }
+                _ => {}
+            }
+        }),
+    );
+
+    dialog_import_presets.show();
+    // keeping the filechooser around because otherwise GTK won't keep it alive
+    *appwindow.filechoosernative().borrow_mut() = Some(dialog_import_presets);
+}
+
+pub fn dialog_export_presets(appwindow: &RnoteAppWindow) {
+    let filter = FileFilter::new();
+    filter.add_mime_type("application/json");
+    filter.add_pattern("*.json");
+    filter.set_name(Some(&gettext("JSON file")));
+
+    let dialog_export_presets: FileChooserNative = FileChooserNative::builder()
+        .title(&gettext("Export pen presets"))
+        .modal(true)
+        .transient_for(appwindow)
+        .accept_label(&gettext("Export"))
+        .cancel_label(&gettext("Cancel"))
+        .action(FileChooserAction::Save)
+        .select_multiple(false)
+        .build();
+    dialog_export_presets.add_filter(&filter);
+
+    dialog_export_presets.set_current_name(&gettext("pen_presets.json"));
+
+    dialog_export_presets.connect_response(
+        clone!(@weak appwindow => move |dialog_export_presets, responsetype| {
+            match responsetype {
+                ResponseType::Accept => {
+                    if let Some(file) = dialog_export_presets.file() {
+                        glib::MainContext::default().spawn_local(clone!(@strong appwindow => async move {
+                            if let Err(e) = appwindow.export_presets(&file).await {
+                                log::error!("exporting pen presets failed with error `{}`", e);
+                                adw::prelude::ActionGroupExt::activate_action(&appwindow, "error-toast", Some(&gettext("Export pen presets failed.").to_variant()));
+                            } else {
+                                adw::prelude::ActionGroupExt::activate_action(&appwindow, "text-toast", Some(&gettext("Exported pen presets successfully.").to_variant()));
+                            }
+                        }));
+                    }
+                }
+                _ => {}
+            }
+        }),
+    );
+
+    dialog_export_presets.show();
+    // keeping the filechooser around because otherwise GTK won't keep it alive
+    *appwindow.filechoosernative().borrow_mut() = Some(dialog_export_presets);
+}
@@ -15,8 +15,11 @@ mod canvasmenu;
 mod colorpicker;
 mod iconpicker;
 mod mainheader;
+mod pagespanel;
+mod pressurecurveeditor;
 pub mod penssidebar;
 mod settingspanel;
+mod transformpanel;
 mod unitentry;
 mod workspacebrowser;
 
@@ -29,8 +32,11 @@ pub use canvasmenu::CanvasMenu;
 pub use colorpicker::ColorPicker;
 pub use iconpicker::IconPicker;
 pub use mainheader::MainHeader;
+pub use pagespanel::PagesPanel;
+pub use pressurecurveeditor::PressureCurveEditor;
 pub use penssidebar::PensSideBar;
 pub use settingspanel::SettingsPanel;
+pub use transformpanel::TransformPanel;
 pub use unitentry::UnitEntry;
 pub use workspacebrowser::WorkspaceBrowser;
 
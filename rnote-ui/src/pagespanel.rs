@@ -0,0 +1,274 @@
+use adw::prelude::*;
+use gettextrs::gettext;
+use gtk4::{
+    gdk, glib, glib::clone, subclass::prelude::*, CompositeTemplate, DragSource, DropTarget, Label,
+    ListBox, ListBoxRow, Picture, ScrolledWindow, Widget,
+};
+use p2d::bounding_volume::AABB;
+use std::cell::RefCell;
+use std::time;
+
+use super::appwindow::RnoteAppWindow;
+
+mod imp {
+    use super::*;
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/com/github/flxzt/rnote/ui/pagespanel.ui")]
+    pub struct PagesPanel {
+        /// The rows with the picture of the thumbnail and the id of the shown thumbnail
+        pub page_rows: RefCell<Vec<(ListBoxRow, Picture, Option<u64>)>>,
+        pub pages_bounds: RefCell<Vec<AABB>>,
+        pub refresh_timeout_id: RefCell<Option<glib::SourceId>>,
+
+        #[template_child]
+        pub pages_scroller: TemplateChild<ScrolledWindow>,
+        #[template_child]
+        pub pages_unavailable_statuspage: TemplateChild<adw::StatusPage>,
+        #[template_child]
+        pub pages_listbox: TemplateChild<ListBox>,
+        #[template_child]
+        pub pages_hint_label: TemplateChild<Label>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PagesPanel {
+        const NAME: &'static str = "PagesPanel";
+        type Type = super::PagesPanel;
+        type ParentType = gtk4::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            Self::bind_template(klass);
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for PagesPanel {
+        fn constructed(&self, obj: &Self::Type) {
+            self.parent_constructed(obj);
+        }
+
+        fn dispose(&self, obj: &Self::Type) {
+            if let Some(refresh_timeout_id) = self.refresh_timeout_id.take() {
+                refresh_timeout_id.remove();
+            }
+
+            while let Some(child) = obj.first_child() {
+                child.unparent();
+            }
+        }
+    }
+
+    impl WidgetImpl for PagesPanel {}
+}
+
+glib::wrapper! {
+    pub struct PagesPanel(ObjectSubclass<imp::PagesPanel>)
+    @extends Widget;
+}
+
+impl Default for PagesPanel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PagesPanel {
+    /// The width of the page thumbnails in pixels
+    pub const THUMBNAIL_WIDTH: u32 = 240;
+    /// The time after the last change of the document, until the thumbnails are refreshed
+    pub const REFRESH_TIMEOUT: time::Duration = time::Duration::from_millis(500);
+
+    pub fn new() -> Self {
+        glib::Object::new(&[]).expect("Failed to create PagesPanel")
+    }
+
+    pub fn pages_scroller(&self) -> ScrolledWindow {
+        self.imp().pages_scroller.get()
+    }
+
+    pub fn init(&self, appwindow: &RnoteAppWindow) {
+        // Load the thumbnails every time the panel is shown
+        self.connect_map(clone!(@weak appwindow => move |pages_panel| {
+            pages_panel.refresh_ui(&appwindow);
+        }));
+
+        // Jump to the clicked page
+        self.imp().pages_listbox.get().connect_row_activated(
+            clone!(@weak self as pages_panel, @weak appwindow => move |_pages_listbox, row| {
+                let page_bounds = match pages_panel.imp().pages_bounds.borrow().get(row.index() as usize) {
+                    Some(&page_bounds) => page_bounds,
+                    None => return,
+                };
+
+                appwindow.canvas().scroll_to_page(page_bounds);
+            }),
+        );
+
+        // Reorder the pages by dropping a page row onto another
+        let drop_target = DropTarget::builder()
+            .name("pages_panel_drop_target")
+            .actions(gdk::DragAction::MOVE)
+            .build();
+        drop_target.set_types(&[u32::static_type()]);
+        self.imp().pages_listbox.get().add_controller(&drop_target);
+
+        drop_target.connect_drop(
+            clone!(@weak self as pages_panel, @weak appwindow => @default-return false, move |_drop_target, value, _x, y| {
+                let from = match value.get::<u32>() {
+                    Ok(from) => from as usize,
+                    Err(_) => return false,
+                };
+                let to = match pages_panel.imp().pages_listbox.row_at_y(y as i32) {
+                    Some(row) => row.index() as usize,
+                    None => return false,
+                };
+
+                let widget_flags = appwindow.canvas().engine().borrow_mut().move_page(from, to);
+                appwindow.handle_widget_flags(widget_flags);
+
+                pages_panel.refresh_ui(&appwindow);
+                true
+            }),
+        );
+    }
+
+    /// Refreshes the thumbnails after a timeout, restarted on every call. To be called when the document changed
+    pub fn queue_refresh(&self, appwindow: &RnoteAppWindow) {
+        // Only visible panels need to be up to date, it is refreshed when it is shown
+        if !self.is_mapped() {
+            return;
+        }
+
+        if let Some(old_refresh_timeout_id) =
+            self.imp()
+                .refresh_timeout_id
+                .replace(Some(glib::source::timeout_add_local_once(
+                    Self::REFRESH_TIMEOUT,
+                    clone!(@weak self as pages_panel, @weak appwindow => move || {
+                        // The source is removed after it was dispatched
+                        pages_panel.imp().refresh_timeout_id.take();
+
+                        pages_panel.refresh_ui(&appwindow);
+                    }),
+                )))
+        {
+            old_refresh_timeout_id.remove();
+        }
+    }
+
+    pub fn refresh_ui(&self, appwindow: &RnoteAppWindow) {
+        let pages_manageable = appwindow
+            .canvas()
+            .engine()
+            .borrow()
+            .document
+            .pages_manageable();
+
+        self.imp()
+            .pages_unavailable_statuspage
+            .set_visible(!pages_manageable);
+        self.imp().pages_listbox.set_visible(pages_manageable);
+        self.imp().pages_hint_label.set_visible(pages_manageable);
+
+        if !pages_manageable {
+            self.clear_page_rows();
+            return;
+        }
+
+        // The thumbnails are generated in separate threads and are shown when they are received
+        appwindow
+            .canvas()
+            .engine()
+            .borrow_mut()
+            .update_page_thumbnails_threaded(Self::THUMBNAIL_WIDTH);
+        let pages_bounds = appwindow.canvas().engine().borrow().document.pages_bounds();
+
+        // Only rebuild the rows when the number of pages changed, to avoid flickering
+        if pages_bounds.len() != self.imp().page_rows.borrow().len() {
+            self.clear_page_rows();
+
+            for i in 0..pages_bounds.len() {
+                self.append_page_row(i);
+            }
+        }
+        *self.imp().pages_bounds.borrow_mut() = pages_bounds;
+
+        self.update_thumbnails(appwindow);
+    }
+
+    /// Shows the latest generated thumbnails. Only the rows with a new thumbnail are updated
+    pub fn update_thumbnails(&self, appwindow: &RnoteAppWindow) {
+        let engine = appwindow.canvas().engine();
+        let engine = engine.borrow();
+
+        for ((_, picture, shown_id), (_, thumbnail)) in self
+            .imp()
+            .page_rows
+            .borrow_mut()
+            .iter_mut()
+            .zip(engine.page_thumbnails())
+        {
+            let id = thumbnail.map(|thumbnail| thumbnail.id);
+            if *shown_id == id {
+                continue;
+            }
+            *shown_id = id;
+
+            match thumbnail.map(|thumbnail| thumbnail.image.to_memtexture()) {
+                Some(Ok(memtexture)) => picture.set_paintable(Some(&memtexture)),
+                Some(Err(e)) => {
+                    log::error!(
+                        "to_memtexture() failed for page thumbnail in update_thumbnails() of pages panel, Err {}",
+                        e
+                    );
+                    picture.set_paintable(None::<&gdk::Paintable>);
+                }
+                None => picture.set_paintable(None::<&gdk::Paintable>),
+            }
+        }
+    }
+
+    fn clear_page_rows(&self) {
+        for (row, _, _) in self.imp().page_rows.borrow_mut().drain(..) {
+            self.imp().pages_listbox.remove(&row);
+        }
+    }
+
+    fn append_page_row(&self, i: usize) {
+        let picture = Picture::builder()
+            .can_shrink(true)
+            .keep_aspect_ratio(true)
+            .height_request(120)
+            .build();
+        let label = Label::new(Some(&format!("{} {}", gettext("Page"), i + 1)));
+
+        let row_box = gtk4::Box::builder()
+            .orientation(gtk4::Orientation::Vertical)
+            .spacing(6)
+            .margin_top(12)
+            .margin_bottom(12)
+            .margin_start(12)
+            .margin_end(12)
+            .build();
+        row_box.append(&picture);
+        row_box.append(&label);
+
+        let row = ListBoxRow::builder()
+            .child(&row_box)
+            .activatable(true)
+            .build();
+
+        let drag_source = DragSource::builder()
+            .name("pages_panel_row_drag_source")
+            .actions(gdk::DragAction::MOVE)
+            .content(&gdk::ContentProvider::for_value(&(i as u32).to_value()))
+            .build();
+        row.add_controller(&drag_source);
+
+        self.imp().pages_listbox.append(&row);
+        self.imp().page_rows.borrow_mut().push((row, picture, None));
+    }
+}
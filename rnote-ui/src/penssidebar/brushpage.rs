@@ -5,10 +5,12 @@ use gtk4::{
 };
 use num_traits::cast::ToPrimitive;
 
-use rnote_compose::style::PressureCurve;
+use rnote_compose::style::{PressureCurve, StrokeDynamics};
 use rnote_engine::pens::Brush;
 
-use crate::{appwindow::RnoteAppWindow, ColorPicker};
+use crate::{appwindow::RnoteAppWindow, ColorPicker, PressureCurveEditor};
+use rnote_compose::style::calligraphy::CalligraphyOptions;
+use rnote_compose::style::pencil::{PencilGrain, PencilOptions};
 use rnote_compose::style::textured::{TexturedDotsDistribution, TexturedOptions};
 use rnote_engine::pens::brush::BrushStyle;
 use rnote_engine::utils::GdkRGBAHelpers;
@@ -36,12 +38,26 @@ mod imp {
         #[template_child]
         pub brushstyle_textured_row: TemplateChild<adw::ActionRow>,
         #[template_child]
+        pub brushstyle_calligraphy_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub brushstyle_pencil_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
         pub brushconfig_menubutton: TemplateChild<MenuButton>,
         #[template_child]
         pub brushconfig_popover: TemplateChild<Popover>,
         #[template_child]
         pub solidstyle_pressure_curves_row: TemplateChild<adw::ComboRow>,
         #[template_child]
+        pub solidstyle_pressure_curve_editor_row: TemplateChild<adw::PreferencesRow>,
+        #[template_child]
+        pub solidstyle_pressure_curve_editor: TemplateChild<PressureCurveEditor>,
+        #[template_child]
+        pub texturedstyle_pressure_curves_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub texturedstyle_pressure_curve_editor_row: TemplateChild<adw::PreferencesRow>,
+        #[template_child]
+        pub texturedstyle_pressure_curve_editor: TemplateChild<PressureCurveEditor>,
+        #[template_child]
         pub texturedstyle_density_spinbutton: TemplateChild<SpinButton>,
         #[template_child]
         pub texturedstyle_radius_x_spinbutton: TemplateChild<SpinButton>,
@@ -49,6 +65,22 @@ mod imp {
         pub texturedstyle_radius_y_spinbutton: TemplateChild<SpinButton>,
         #[template_child]
         pub texturedstyle_distribution_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub calligraphystyle_nib_angle_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub calligraphystyle_nib_thickness_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub pencilstyle_grain_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub pencilstyle_grain_strength_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub dynamics_velocity_width_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub dynamics_velocity_opacity_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub dynamics_tilt_width_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub dynamics_tilt_opacity_spinbutton: TemplateChild<SpinButton>,
     }
 
     #[glib::object_subclass]
@@ -129,6 +161,14 @@ impl BrushPage {
         self.imp().brushstyle_textured_row.get()
     }
 
+    pub fn brushstyle_calligraphy_row(&self) -> adw::ActionRow {
+        self.imp().brushstyle_calligraphy_row.get()
+    }
+
+    pub fn brushstyle_pencil_row(&self) -> adw::ActionRow {
+        self.imp().brushstyle_pencil_row.get()
+    }
+
     pub fn brushconfig_menubutton(&self) -> MenuButton {
         self.imp().brushconfig_menubutton.get()
     }
@@ -154,18 +194,65 @@ impl BrushPage {
     }
 
     pub fn solidstyle_pressure_curve(&self) -> PressureCurve {
-        PressureCurve::try_from(self.imp().solidstyle_pressure_curves_row.get().selected()).unwrap()
+        match PressureCurve::try_from(self.imp().solidstyle_pressure_curves_row.get().selected())
+            .unwrap()
+        {
+            PressureCurve::Custom(_) => {
+                PressureCurve::Custom(self.imp().solidstyle_pressure_curve_editor.get().spline())
+            }
+            pressure_curve => pressure_curve,
+        }
     }
 
     pub fn set_solidstyle_pressure_curve(&self, pressure_curve: PressureCurve) {
         let position = pressure_curve.to_u32().unwrap();
 
+        if let PressureCurve::Custom(spline) = pressure_curve {
+            self.imp()
+                .solidstyle_pressure_curve_editor
+                .get()
+                .set_spline(spline);
+        }
         self.imp()
             .solidstyle_pressure_curves_row
             .get()
             .set_selected(position);
     }
 
+    pub fn texturedstyle_pressure_curve(&self) -> PressureCurve {
+        match PressureCurve::try_from(
+            self.imp()
+                .texturedstyle_pressure_curves_row
+                .get()
+                .selected(),
+        )
+        .unwrap()
+        {
+            PressureCurve::Custom(_) => PressureCurve::Custom(
+                self.imp()
+                    .texturedstyle_pressure_curve_editor
+                    .get()
+                    .spline(),
+            ),
+            pressure_curve => pressure_curve,
+        }
+    }
+
+    pub fn set_texturedstyle_pressure_curve(&self, pressure_curve: PressureCurve) {
+        let position = pressure_curve.to_u32().unwrap();
+
+        if let PressureCurve::Custom(spline) = pressure_curve {
+            self.imp()
+                .texturedstyle_pressure_curve_editor
+                .get()
+                .set_spline(spline);
+        }
+        self.imp()
+            .texturedstyle_pressure_curves_row
+            .get()
+            .set_selected(position);
+    }
+
     pub fn texturedstyle_dots_distribution(&self) -> TexturedDotsDistribution {
         TexturedDotsDistribution::try_from(
             self.imp().texturedstyle_distribution_row.get().selected(),
@@ -182,6 +269,38 @@ impl BrushPage {
             .set_selected(position);
     }
 
+    pub fn pencilstyle_grain(&self) -> PencilGrain {
+        PencilGrain::try_from(self.imp().pencilstyle_grain_row.get().selected()).unwrap()
+    }
+
+    pub fn set_pencilstyle_grain(&self, grain: PencilGrain) {
+        let position = grain.to_u32().unwrap();
+
+        self.imp()
+            .pencilstyle_grain_row
+            .get()
+            .set_selected(position);
+    }
+
+    pub fn set_dynamics(&self, dynamics: StrokeDynamics) {
+        self.imp()
+            .dynamics_velocity_width_spinbutton
+            .get()
+            .set_value(dynamics.velocity_width);
+        self.imp()
+            .dynamics_velocity_opacity_spinbutton
+            .get()
+            .set_value(dynamics.velocity_opacity);
+        self.imp()
+            .dynamics_tilt_width_spinbutton
+            .get()
+            .set_value(dynamics.tilt_width);
+        self.imp()
+            .dynamics_tilt_opacity_spinbutton
+            .get()
+            .set_value(dynamics.tilt_opacity);
+    }
+
     pub fn init(&self, appwindow: &RnoteAppWindow) {
         self.width_spinbutton().set_increments(0.1, 2.0);
         self.width_spinbutton()
@@ -200,6 +319,8 @@ impl BrushPage {
                     BrushStyle::Marker => appwindow.canvas().engine().borrow_mut().penholder.brush.smooth_options.stroke_color = Some(color),
                     BrushStyle::Solid => appwindow.canvas().engine().borrow_mut().penholder.brush.smooth_options.stroke_color = Some(color),
                     BrushStyle::Textured => appwindow.canvas().engine().borrow_mut().penholder.brush.textured_options.stroke_color = Some(color),
+                    BrushStyle::Calligraphy => appwindow.canvas().engine().borrow_mut().penholder.brush.calligraphy_options.stroke_color = Some(color),
+                    BrushStyle::Pencil => appwindow.canvas().engine().borrow_mut().penholder.brush.pencil_options.stroke_color = Some(color),
                 }
 
                 if let Err(e) = appwindow.save_engine_config() {
@@ -216,6 +337,8 @@ impl BrushPage {
                     BrushStyle::Marker => appwindow.canvas().engine().borrow_mut().penholder.brush.smooth_options.stroke_width = brush_widthscale_spinbutton.value(),
                     BrushStyle::Solid => appwindow.canvas().engine().borrow_mut().penholder.brush.smooth_options.stroke_width = brush_widthscale_spinbutton.value(),
                     BrushStyle::Textured => appwindow.canvas().engine().borrow_mut().penholder.brush.textured_options.stroke_width = brush_widthscale_spinbutton.value(),
+                    BrushStyle::Calligraphy => appwindow.canvas().engine().borrow_mut().penholder.brush.calligraphy_options.stroke_width = brush_widthscale_spinbutton.value(),
+                    BrushStyle::Pencil => appwindow.canvas().engine().borrow_mut().penholder.brush.pencil_options.stroke_width = brush_widthscale_spinbutton.value(),
                 }
 
                 if let Err(e) = appwindow.save_engine_config() {
@@ -236,6 +359,10 @@ impl BrushPage {
                         engine.penholder.brush.smooth_options.stroke_color = Some(brushpage.colorpicker().current_color());
                         engine.penholder.brush.textured_options.stroke_width = brushpage.width_spinbutton().value();
                         engine.penholder.brush.textured_options.stroke_color = Some(brushpage.colorpicker().current_color());
+                        engine.penholder.brush.calligraphy_options.stroke_width = brushpage.width_spinbutton().value();
+                        engine.penholder.brush.calligraphy_options.stroke_color = Some(brushpage.colorpicker().current_color());
+                        engine.penholder.brush.pencil_options.stroke_width = brushpage.width_spinbutton().value();
+                        engine.penholder.brush.pencil_options.stroke_color = Some(brushpage.colorpicker().current_color());
                     }
 
                     if let Err(e) = appwindow.save_engine_config() {
@@ -250,13 +377,31 @@ impl BrushPage {
         // Solid style
         // Pressure curve
         self.imp().solidstyle_pressure_curves_row.get().connect_selected_notify(clone!(@weak self as brushpage, @weak appwindow => move |_smoothstyle_pressure_curves_row| {
-            appwindow.canvas().engine().borrow_mut().penholder.brush.smooth_options.pressure_curve = brushpage.solidstyle_pressure_curve();
+            let pressure_curve = brushpage.solidstyle_pressure_curve();
+
+            brushpage.imp().solidstyle_pressure_curve_editor_row.get().set_visible(matches!(pressure_curve, PressureCurve::Custom(_)));
+            appwindow.canvas().engine().borrow_mut().penholder.brush.smooth_options.pressure_curve = pressure_curve;
 
             if let Err(e) = appwindow.save_engine_config() {
                 log::error!("saving engine config failed after changing brush pressure curve, Err `{}`", e);
             }
         }));
 
+        // Custom pressure curve
+        self.imp().solidstyle_pressure_curve_editor.get().connect_local(
+            "curve-changed",
+            false,
+            clone!(@weak appwindow => @default-return None, move |values| {
+                let curve_editor = values[0].get::<PressureCurveEditor>().unwrap();
+                appwindow.canvas().engine().borrow_mut().penholder.brush.smooth_options.pressure_curve = PressureCurve::Custom(curve_editor.spline());
+
+                if let Err(e) = appwindow.save_engine_config() {
+                    log::error!("saving engine config failed after editing brush custom pressure curve, Err `{}`", e);
+                }
+                None
+            }),
+        );
+
         // Textured style
         // Density
         self.imp()
@@ -340,6 +485,33 @@ impl BrushPage {
                 }),
             );
 
+        // Pressure curve
+        self.imp().texturedstyle_pressure_curves_row.get().connect_selected_notify(clone!(@weak self as brushpage, @weak appwindow => move |_texturedstyle_pressure_curves_row| {
+            let pressure_curve = brushpage.texturedstyle_pressure_curve();
+
+            brushpage.imp().texturedstyle_pressure_curve_editor_row.get().set_visible(matches!(pressure_curve, PressureCurve::Custom(_)));
+            appwindow.canvas().engine().borrow_mut().penholder.brush.textured_options.pressure_curve = pressure_curve;
+
+            if let Err(e) = appwindow.save_engine_config() {
+                log::error!("saving engine config failed after changing brush textured pressure curve, Err `{}`", e);
+            }
+        }));
+
+        // Custom pressure curve
+        self.imp().texturedstyle_pressure_curve_editor.get().connect_local(
+            "curve-changed",
+            false,
+            clone!(@weak appwindow => @default-return None, move |values| {
+                let curve_editor = values[0].get::<PressureCurveEditor>().unwrap();
+                appwindow.canvas().engine().borrow_mut().penholder.brush.textured_options.pressure_curve = PressureCurve::Custom(curve_editor.spline());
+
+                if let Err(e) = appwindow.save_engine_config() {
+                    log::error!("saving engine config failed after editing brush textured custom pressure curve, Err `{}`", e);
+                }
+                None
+            }),
+        );
+
         // dots distribution
         self.imp().texturedstyle_distribution_row.get().connect_selected_notify(clone!(@weak self as brushpage, @weak appwindow => move |_texturedstyle_distribution_row| {
             appwindow.canvas().engine().borrow_mut().penholder.brush.textured_options.distribution = brushpage.texturedstyle_dots_distribution();
@@ -348,12 +520,143 @@ impl BrushPage {
                 log::error!("saving engine config failed after changing brush textured dots distribution, Err `{}`", e);
             }
         }));
+
+        // Calligraphy style
+        // Nib angle
+        self.imp()
+            .calligraphystyle_nib_angle_spinbutton
+            .get()
+            .set_increments(1.0, 15.0);
+        self.imp()
+            .calligraphystyle_nib_angle_spinbutton
+            .get()
+            .set_range(0.0, 180.0);
+        self.imp()
+            .calligraphystyle_nib_angle_spinbutton
+            .get()
+            .set_value(CalligraphyOptions::NIB_ANGLE_DEFAULT.to_degrees());
+
+        self.imp().calligraphystyle_nib_angle_spinbutton.get().connect_value_changed(
+            clone!(@weak appwindow => move |calligraphystyle_nib_angle_spinbutton| {
+                appwindow.canvas().engine().borrow_mut().penholder.brush.calligraphy_options.nib_angle = calligraphystyle_nib_angle_spinbutton.value().to_radians();
+
+                if let Err(e) = appwindow.save_engine_config() {
+                    log::error!("saving engine config failed after changing brush calligraphy nib angle, Err `{}`", e);
+                }
+            }),
+        );
+
+        // Nib thickness
+        self.imp()
+            .calligraphystyle_nib_thickness_spinbutton
+            .get()
+            .set_increments(0.05, 0.2);
+        self.imp()
+            .calligraphystyle_nib_thickness_spinbutton
+            .get()
+            .set_range(0.0, 1.0);
+        self.imp()
+            .calligraphystyle_nib_thickness_spinbutton
+            .get()
+            .set_value(CalligraphyOptions::NIB_THICKNESS_DEFAULT);
+
+        self.imp().calligraphystyle_nib_thickness_spinbutton.get().connect_value_changed(
+            clone!(@weak appwindow => move |calligraphystyle_nib_thickness_spinbutton| {
+                appwindow.canvas().engine().borrow_mut().penholder.brush.calligraphy_options.nib_thickness = calligraphystyle_nib_thickness_spinbutton.value();
+
+                if let Err(e) = appwindow.save_engine_config() {
+                    log::error!("saving engine config failed after changing brush calligraphy nib thickness, Err `{}`", e);
+                }
+            }),
+        );
+
+        // Pencil style
+        // Grain
+        self.imp().pencilstyle_grain_row.get().connect_selected_notify(clone!(@weak self as brushpage, @weak appwindow => move |_pencilstyle_grain_row| {
+            appwindow.canvas().engine().borrow_mut().penholder.brush.pencil_options.grain = brushpage.pencilstyle_grain();
+
+            if let Err(e) = appwindow.save_engine_config() {
+                log::error!("saving engine config failed after changing brush pencil grain, Err `{}`", e);
+            }
+        }));
+
+        // Grain strength
+        self.imp()
+            .pencilstyle_grain_strength_spinbutton
+            .get()
+            .set_increments(0.05, 0.2);
+        self.imp()
+            .pencilstyle_grain_strength_spinbutton
+            .get()
+            .set_range(0.0, 1.0);
+        self.imp()
+            .pencilstyle_grain_strength_spinbutton
+            .get()
+            .set_value(PencilOptions::GRAIN_STRENGTH_DEFAULT);
+
+        self.imp().pencilstyle_grain_strength_spinbutton.get().connect_value_changed(
+            clone!(@weak appwindow => move |pencilstyle_grain_strength_spinbutton| {
+                appwindow.canvas().engine().borrow_mut().penholder.brush.pencil_options.grain_strength = pencilstyle_grain_strength_spinbutton.value();
+
+                if let Err(e) = appwindow.save_engine_config() {
+                    log::error!("saving engine config failed after changing brush pencil grain strength, Err `{}`", e);
+                }
+            }),
+        );
+
+        // Dynamics
+        let dynamics_setters: [(SpinButton, fn(&mut StrokeDynamics, f64)); 4] = [
+            (
+                self.imp().dynamics_velocity_width_spinbutton.get(),
+                |dynamics, value| dynamics.velocity_width = value,
+            ),
+            (
+                self.imp().dynamics_velocity_opacity_spinbutton.get(),
+                |dynamics, value| dynamics.velocity_opacity = value,
+            ),
+            (
+                self.imp().dynamics_tilt_width_spinbutton.get(),
+                |dynamics, value| dynamics.tilt_width = value,
+            ),
+            (
+                self.imp().dynamics_tilt_opacity_spinbutton.get(),
+                |dynamics, value| dynamics.tilt_opacity = value,
+            ),
+        ];
+
+        for (dynamics_spinbutton, set_dynamics) in dynamics_setters {
+            dynamics_spinbutton.set_increments(0.05, 0.2);
+            dynamics_spinbutton.set_range(0.0, 1.0);
+            dynamics_spinbutton.set_value(0.0);
+
+            dynamics_spinbutton.connect_value_changed(
+                clone!(@weak appwindow => move |dynamics_spinbutton| {
+                    {
+                        let engine = appwindow.canvas().engine();
+                        let engine = &mut *engine.borrow_mut();
+                        let brush = &mut engine.penholder.brush;
+
+                        match brush.style {
+                            BrushStyle::Marker | BrushStyle::Solid => set_dynamics(&mut brush.smooth_options.dynamics, dynamics_spinbutton.value()),
+                            BrushStyle::Textured => set_dynamics(&mut brush.textured_options.dynamics, dynamics_spinbutton.value()),
+                            BrushStyle::Calligraphy => set_dynamics(&mut brush.calligraphy_options.dynamics, dynamics_spinbutton.value()),
+                            BrushStyle::Pencil => set_dynamics(&mut brush.pencil_options.dynamics, dynamics_spinbutton.value()),
+                        }
+                    }
+
+                    if let Err(e) = appwindow.save_engine_config() {
+                        log::error!("saving engine config failed after changing brush dynamics, Err `{}`", e);
+                    }
+                }),
+            );
+        }
     }
 
     pub fn refresh_ui(&self, appwindow: &RnoteAppWindow) {
         let brush = appwindow.canvas().engine().borrow().penholder.brush.clone();
 
-        self.set_solidstyle_pressure_curve(brush.smooth_options.pressure_curve);
+        self.set_solidstyle_pressure_curve(brush.smooth_options.pressure_curve.clone());
+        self.set_texturedstyle_pressure_curve(brush.textured_options.pressure_curve.clone());
         self.texturedstyle_density_spinbutton()
             .set_value(brush.textured_options.density);
         self.texturedstyle_radius_x_spinbutton()
@@ -361,6 +664,19 @@ impl BrushPage {
         self.texturedstyle_radius_y_spinbutton()
             .set_value(brush.textured_options.radii[1]);
         self.set_texturedstyle_distribution_variant(brush.textured_options.distribution);
+        self.imp()
+            .calligraphystyle_nib_angle_spinbutton
+            .get()
+            .set_value(brush.calligraphy_options.nib_angle.to_degrees());
+        self.imp()
+            .calligraphystyle_nib_thickness_spinbutton
+            .get()
+            .set_value(brush.calligraphy_options.nib_thickness);
+        self.set_pencilstyle_grain(brush.pencil_options.grain);
+        self.imp()
+            .pencilstyle_grain_strength_spinbutton
+            .get()
+            .set_value(brush.pencil_options.grain_strength);
         match brush.style {
             BrushStyle::Marker => {
                 self.brushstyle_listbox()
@@ -371,6 +687,7 @@ impl BrushPage {
                     .set_current_color(brush.smooth_options.stroke_color);
                 self.brushstyle_image()
                     .set_icon_name(Some("pen-brush-style-marker-symbolic"));
+                self.set_dynamics(brush.smooth_options.dynamics);
             }
             BrushStyle::Solid => {
                 self.brushstyle_listbox()
@@ -381,6 +698,7 @@ impl BrushPage {
                     .set_current_color(brush.smooth_options.stroke_color);
                 self.brushstyle_image()
                     .set_icon_name(Some("pen-brush-style-solid-symbolic"));
+                self.set_dynamics(brush.smooth_options.dynamics);
             }
             BrushStyle::Textured => {
                 self.brushstyle_listbox()
@@ -391,6 +709,29 @@ impl BrushPage {
                     .set_current_color(brush.textured_options.stroke_color);
                 self.brushstyle_image()
                     .set_icon_name(Some("pen-brush-style-textured-symbolic"));
+                self.set_dynamics(brush.textured_options.dynamics);
+            }
+            BrushStyle::Calligraphy => {
+                self.brushstyle_listbox()
+                    .select_row(Some(&self.brushstyle_calligraphy_row()));
+                self.width_spinbutton()
+                    .set_value(brush.calligraphy_options.stroke_width);
+                self.colorpicker()
+                    .set_current_color(brush.calligraphy_options.stroke_color);
+                self.brushstyle_image()
+                    .set_icon_name(Some("pen-brush-style-calligraphy-symbolic"));
+                self.set_dynamics(brush.calligraphy_options.dynamics);
+            }
+            BrushStyle::Pencil => {
+                self.brushstyle_listbox()
+                    .select_row(Some(&self.brushstyle_pencil_row()));
+                self.width_spinbutton()
+                    .set_value(brush.pencil_options.stroke_width);
+                self.colorpicker()
+                    .set_current_color(brush.pencil_options.stroke_color);
+                self.brushstyle_image()
+                    .set_icon_name(Some("pen-brush-style-pencil-symbolic"));
+                self.set_dynamics(brush.pencil_options.dynamics);
             }
         }
     }
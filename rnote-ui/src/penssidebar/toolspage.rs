@@ -9,7 +9,7 @@ mod imp {
     #[template(resource = "/com/github/flxzt/rnote/ui/penssidebar/toolspage.ui")]
     pub struct ToolsPage {
         #[template_child]
-        pub toolstyle_verticalspace_toggle: TemplateChild<ToggleButton>,
+        pub toolstyle_space_toggle: TemplateChild<ToggleButton>,
         #[template_child]
         pub toolstyle_dragproximity_toggle: TemplateChild<ToggleButton>,
         #[template_child]
@@ -62,8 +62,8 @@ impl ToolsPage {
         glib::Object::new(&[]).expect("Failed to create ToolsPage")
     }
 
-    pub fn toolstyle_verticalspace_toggle(&self) -> ToggleButton {
-        self.imp().toolstyle_verticalspace_toggle.get()
+    pub fn toolstyle_space_toggle(&self) -> ToggleButton {
+        self.imp().toolstyle_space_toggle.get()
     }
 
     pub fn toolstyle_dragproximity_toggle(&self) -> ToggleButton {
@@ -75,9 +75,9 @@ impl ToolsPage {
     }
 
     pub fn init(&self, appwindow: &RnoteAppWindow) {
-        self.toolstyle_verticalspace_toggle().connect_toggled(clone!(@weak appwindow => move |toolstyle_verticalspace_toggle| {
-            if toolstyle_verticalspace_toggle.is_active() {
-                appwindow.canvas().engine().borrow_mut().penholder.tools.style = ToolsStyle::VerticalSpace;
+        self.toolstyle_space_toggle().connect_toggled(clone!(@weak appwindow => move |toolstyle_space_toggle| {
+            if toolstyle_space_toggle.is_active() {
+                appwindow.canvas().engine().borrow_mut().penholder.tools.style = ToolsStyle::Space;
 
                 if let Err(e) = appwindow.save_engine_config() {
                     log::error!("saving engine config failed after changing tool style, Err `{}`", e);
@@ -110,7 +110,7 @@ impl ToolsPage {
         let tools = appwindow.canvas().engine().borrow().penholder.tools.clone();
 
         match tools.style {
-            ToolsStyle::VerticalSpace => self.toolstyle_verticalspace_toggle().set_active(true),
+            ToolsStyle::Space => self.toolstyle_space_toggle().set_active(true),
             ToolsStyle::DragProximity => self.toolstyle_dragproximity_toggle().set_active(true),
             ToolsStyle::OffsetCamera => self.toolstyle_offsetcamera_toggle().set_active(true),
         }
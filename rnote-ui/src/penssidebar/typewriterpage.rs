@@ -1,13 +1,16 @@
 use crate::{appwindow::RnoteAppWindow, ColorPicker};
+use gettextrs::gettext;
 use gtk4::pango;
 use gtk4::{
-    gdk, glib, glib::clone, prelude::*, subclass::prelude::*, Button, CompositeTemplate,
+    gdk, glib, glib::clone, prelude::*, subclass::prelude::*, Button, CompositeTemplate, DropDown,
     EmojiChooser, FontChooserLevel, FontChooserWidget, Image, MenuButton, Popover, SpinButton,
-    ToggleButton,
+    StringList, StringObject, ToggleButton,
 };
 use rnote_engine::engine::EngineViewMut;
+use rnote_engine::spellcheck::SpellChecker;
 use rnote_engine::strokes::textstroke::{FontStyle, TextAlignment, TextAttribute};
 use rnote_engine::{strokes::textstroke::TextStyle, utils::GdkRGBAHelpers};
+use std::cell::Cell;
 
 mod imp {
     use super::*;
@@ -51,6 +54,12 @@ mod imp {
         pub text_align_end_togglebutton: TemplateChild<ToggleButton>,
         #[template_child]
         pub text_align_fill_togglebutton: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub spellcheck_togglebutton: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub spellcheck_language_dropdown: TemplateChild<DropDown>,
+        /// Set while the available languages are updated, to not change the language while the dropdown model is replaced
+        pub updating_spellcheck_languages: Cell<bool>,
     }
 
     #[glib::object_subclass]
@@ -134,6 +143,14 @@ impl TypewriterPage {
         self.imp().text_align_fill_togglebutton.get()
     }
 
+    pub fn spellcheck_togglebutton(&self) -> ToggleButton {
+        self.imp().spellcheck_togglebutton.get()
+    }
+
+    pub fn spellcheck_language_dropdown(&self) -> DropDown {
+        self.imp().spellcheck_language_dropdown.get()
+    }
+
     pub fn init(&self, appwindow: &RnoteAppWindow) {
         let fontchooser = self.imp().fontchooser.get();
         let fontchooser_popover = self.imp().fontchooser_popover.get();
@@ -500,6 +517,68 @@ impl TypewriterPage {
                 }
             }),
         );
+
+        // Spell checking
+        self.spellcheck_togglebutton().connect_active_notify(
+            clone!(@weak appwindow => move |spellcheck_togglebutton| {
+                let spellcheck_enabled = spellcheck_togglebutton.is_active();
+
+                {
+                    let engine = appwindow.canvas().engine();
+                    let engine = &mut *engine.borrow_mut();
+
+                    if engine.penholder.typewriter.spellchecker.enabled() == spellcheck_enabled {
+                        return;
+                    }
+
+                    if let Err(e) = engine.penholder.typewriter.spellchecker.set_enabled(spellcheck_enabled) {
+                        log::error!("enabling the spell checker failed, Err `{}`", e);
+                        adw::prelude::ActionGroupExt::activate_action(&appwindow, "error-toast", Some(&gettext("Loading the spell checking dictionary failed.").to_variant()));
+                    }
+                }
+
+                appwindow.canvas().queue_draw();
+
+                if let Err(e) = appwindow.save_engine_config() {
+                    log::error!("saving engine config failed after changing typewriter spell checking, Err `{}`", e);
+                }
+            }),
+        );
+
+        self.spellcheck_language_dropdown().connect_selected_notify(
+            clone!(@weak self as typewriterpage, @weak appwindow => move |spellcheck_language_dropdown| {
+                if typewriterpage.imp().updating_spellcheck_languages.get() {
+                    return;
+                }
+                let language = match spellcheck_language_dropdown
+                    .selected_item()
+                    .and_then(|item| item.downcast::<StringObject>().ok())
+                {
+                    Some(language) => language.string().to_string(),
+                    None => return,
+                };
+
+                {
+                    let engine = appwindow.canvas().engine();
+                    let engine = &mut *engine.borrow_mut();
+
+                    if engine.penholder.typewriter.spellchecker.language() == language {
+                        return;
+                    }
+
+                    if let Err(e) = engine.penholder.typewriter.spellchecker.set_language(language) {
+                        log::error!("changing the spell checker language failed, Err `{}`", e);
+                        adw::prelude::ActionGroupExt::activate_action(&appwindow, "error-toast", Some(&gettext("Loading the spell checking dictionary failed.").to_variant()));
+                    }
+                }
+
+                appwindow.canvas().queue_draw();
+
+                if let Err(e) = appwindow.save_engine_config() {
+                    log::error!("saving engine config failed after changing typewriter spell checking language, Err `{}`", e);
+                }
+            }),
+        );
     }
 
     pub fn refresh_ui(&self, appwindow: &RnoteAppWindow) {
@@ -524,5 +603,29 @@ impl TypewriterPage {
             TextAlignment::End => self.text_align_end_togglebutton().set_active(true),
             TextAlignment::Fill => self.text_align_fill_togglebutton().set_active(true),
         }
+
+        self.spellcheck_togglebutton()
+            .set_active(typewriter.spellchecker.enabled());
+        self.refresh_spellcheck_languages(&typewriter.spellchecker);
+    }
+
+    // Lists the available dictionaries and selects the current language
+    fn refresh_spellcheck_languages(&self, spellchecker: &SpellChecker) {
+        let languages = spellchecker.available_languages();
+        let model = StringList::new(
+            &languages
+                .iter()
+                .map(|language| language.as_str())
+                .collect::<Vec<&str>>(),
+        );
+        let selected = languages
+            .iter()
+            .position(|language| language == spellchecker.language())
+            .map_or(gtk4::INVALID_LIST_POSITION, |i| i as u32);
+
+        self.imp().updating_spellcheck_languages.set(true);
+        self.spellcheck_language_dropdown().set_model(Some(&model));
+        self.spellcheck_language_dropdown().set_selected(selected);
+        self.imp().updating_spellcheck_languages.set(false);
     }
 }
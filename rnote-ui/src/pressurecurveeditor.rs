@@ -0,0 +1,290 @@
+use gtk4::{
+    cairo, gdk, glib, glib::clone, prelude::*, subclass::prelude::*, CompositeTemplate,
+    DrawingArea, GestureClick, GestureDrag, Widget,
+};
+use once_cell::sync::Lazy;
+use rnote_compose::style::PressureSpline;
+use std::cell::{Cell, RefCell};
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, CompositeTemplate)]
+    #[template(resource = "/com/github/flxzt/rnote/ui/pressurecurveeditor.ui")]
+    pub struct PressureCurveEditor {
+        pub spline: RefCell<PressureSpline>,
+        pub dragged_point: Cell<Option<usize>>,
+        pub drag_start: Cell<na::Vector2<f64>>,
+
+        #[template_child]
+        pub curve_area: TemplateChild<DrawingArea>,
+    }
+
+    impl Default for PressureCurveEditor {
+        fn default() -> Self {
+            Self {
+                spline: RefCell::new(PressureSpline::default()),
+                dragged_point: Cell::new(None),
+                drag_start: Cell::new(na::Vector2::zeros()),
+
+                curve_area: TemplateChild::<DrawingArea>::default(),
+            }
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PressureCurveEditor {
+        const NAME: &'static str = "PressureCurveEditor";
+        type Type = super::PressureCurveEditor;
+        type ParentType = Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            Self::bind_template(klass);
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for PressureCurveEditor {
+        fn constructed(&self, obj: &Self::Type) {
+            self.parent_constructed(obj);
+
+            self.curve_area.get().set_draw_func(
+                clone!(@weak obj as curve_editor => move |curve_area, cx, width, height| {
+                    if let Err(e) = curve_editor.draw_curve(curve_area, cx, f64::from(width), f64::from(height)) {
+                        log::error!("drawing the pressure curve failed, Err `{}`", e);
+                    }
+                }),
+            );
+
+            // Dragging moves the point under the pointer, or inserts a new one if there is none
+            let curve_drag_gesture = GestureDrag::builder()
+                .name("curve_drag_gesture")
+                .button(gdk::BUTTON_PRIMARY)
+                .build();
+            self.curve_area.get().add_controller(&curve_drag_gesture);
+
+            curve_drag_gesture.connect_drag_begin(
+                clone!(@weak obj as curve_editor => move |_curve_drag_gesture, x, y| {
+                    let pos = curve_editor.widget_to_curve_coords(na::vector![x, y]);
+                    let dragged_point = curve_editor.point_at(na::vector![x, y]).or_else(|| curve_editor.imp().spline.borrow_mut().insert_point(pos));
+
+                    curve_editor.imp().drag_start.set(na::vector![x, y]);
+                    curve_editor.imp().dragged_point.set(dragged_point);
+
+                    if dragged_point.is_some() {
+                        curve_editor.curve_changed();
+                    }
+                }),
+            );
+
+            curve_drag_gesture.connect_drag_update(
+                clone!(@weak obj as curve_editor => move |_curve_drag_gesture, x, y| {
+                    if let Some(index) = curve_editor.imp().dragged_point.get() {
+                        let pos = curve_editor.widget_to_curve_coords(curve_editor.imp().drag_start.get() + na::vector![x, y]);
+                        curve_editor.imp().spline.borrow_mut().move_point(index, pos);
+
+                        curve_editor.curve_changed();
+                    }
+                }),
+            );
+
+            curve_drag_gesture.connect_drag_end(
+                clone!(@weak obj as curve_editor => move |_curve_drag_gesture, _x, _y| {
+                    curve_editor.imp().dragged_point.set(None);
+                }),
+            );
+
+            // A secondary click removes the point under the pointer
+            let curve_remove_gesture = GestureClick::builder()
+                .name("curve_remove_gesture")
+                .button(gdk::BUTTON_SECONDARY)
+                .build();
+            self.curve_area.get().add_controller(&curve_remove_gesture);
+
+            curve_remove_gesture.connect_pressed(
+                clone!(@weak obj as curve_editor => move |_curve_remove_gesture, _n_press, x, y| {
+                    if let Some(index) = curve_editor.point_at(na::vector![x, y]) {
+                        curve_editor.imp().spline.borrow_mut().remove_point(index);
+
+                        curve_editor.curve_changed();
+                    }
+                }),
+            );
+        }
+
+        fn dispose(&self, obj: &Self::Type) {
+            while let Some(child) = obj.first_child() {
+                child.unparent();
+            }
+        }
+
+        fn signals() -> &'static [glib::subclass::Signal] {
+            static SIGNALS: Lazy<Vec<glib::subclass::Signal>> = Lazy::new(|| {
+                vec![glib::subclass::Signal::builder(
+                    // Signal name
+                    "curve-changed",
+                    // Types of the values which will be sent to the signal handler
+                    &[],
+                    // Type of the value the signal handler sends back
+                    <()>::static_type().into(),
+                )
+                .build()]
+            });
+            SIGNALS.as_ref()
+        }
+    }
+
+    impl WidgetImpl for PressureCurveEditor {}
+}
+
+glib::wrapper! {
+    pub struct PressureCurveEditor(ObjectSubclass<imp::PressureCurveEditor>)
+        @extends Widget;
+}
+
+impl Default for PressureCurveEditor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PressureCurveEditor {
+    /// The margin around the curve, so that the points at the edges are fully visible
+    const CURVE_MARGIN: f64 = 8.0;
+    /// The radius of the drawn control points
+    const POINT_RADIUS: f64 = 4.0;
+    /// The distance in which the pointer grabs a control point
+    const POINT_GRAB_DIST: f64 = 10.0;
+
+    pub fn new() -> Self {
+        glib::Object::new(&[]).expect("Failed to create PressureCurveEditor")
+    }
+
+    pub fn spline(&self) -> PressureSpline {
+        self.imp().spline.borrow().clone()
+    }
+
+    pub fn set_spline(&self, spline: PressureSpline) {
+        *self.imp().spline.borrow_mut() = spline;
+        self.imp().curve_area.get().queue_draw();
+    }
+
+    fn curve_changed(&self) {
+        self.imp().curve_area.get().queue_draw();
+        self.emit_by_name::<()>("curve-changed", &[]);
+    }
+
+    fn curve_size(&self) -> na::Vector2<f64> {
+        let curve_area = self.imp().curve_area.get();
+
+        na::vector![
+            (f64::from(curve_area.width()) - 2.0 * Self::CURVE_MARGIN).max(1.0),
+            (f64::from(curve_area.height()) - 2.0 * Self::CURVE_MARGIN).max(1.0)
+        ]
+    }
+
+    /// Converts coordinates on the drawing area to the coordinate space of the spline
+    fn widget_to_curve_coords(&self, pos: na::Vector2<f64>) -> na::Vector2<f64> {
+        let size = self.curve_size();
+
+        na::vector![
+            (pos[0] - Self::CURVE_MARGIN) / size[0],
+            1.0 - (pos[1] - Self::CURVE_MARGIN) / size[1]
+        ]
+    }
+
+    /// Converts coordinates in the spline coordinate space to coordinates on the drawing area
+    fn curve_to_widget_coords(&self, pos: na::Vector2<f64>) -> na::Vector2<f64> {
+        let size = self.curve_size();
+
+        na::vector![
+            Self::CURVE_MARGIN + pos[0] * size[0],
+            Self::CURVE_MARGIN + (1.0 - pos[1]) * size[1]
+        ]
+    }
+
+    /// The index of the control point that is nearest to the position on the drawing area, if it is in grab distance
+    fn point_at(&self, pos: na::Vector2<f64>) -> Option<usize> {
+        self.imp()
+            .spline
+            .borrow()
+            .points()
+            .iter()
+            .map(|point| (self.curve_to_widget_coords(*point) - pos).magnitude())
+            .enumerate()
+            .filter(|(_, dist)| *dist < Self::POINT_GRAB_DIST)
+            .min_by(|(_, first), (_, second)| first.total_cmp(second))
+            .map(|(i, _)| i)
+    }
+
+    fn draw_curve(
+        &self,
+        curve_area: &DrawingArea,
+        cx: &cairo::Context,
+        width: f64,
+        height: f64,
+    ) -> Result<(), cairo::Error> {
+        let color = curve_area.style_context().color();
+        let spline = self.imp().spline.borrow();
+        let size = self.curve_size();
+
+        // the frame and the linear reference
+        cx.set_source_rgba(
+            f64::from(color.red()),
+            f64::from(color.green()),
+            f64::from(color.blue()),
+            0.2,
+        );
+        cx.set_line_width(1.0);
+        cx.rectangle(
+            Self::CURVE_MARGIN,
+            Self::CURVE_MARGIN,
+            width - 2.0 * Self::CURVE_MARGIN,
+            height - 2.0 * Self::CURVE_MARGIN,
+        );
+        cx.move_to(Self::CURVE_MARGIN, Self::CURVE_MARGIN + size[1]);
+        cx.line_to(Self::CURVE_MARGIN + size[0], Self::CURVE_MARGIN);
+        cx.stroke()?;
+
+        // the curve
+        cx.set_source_rgba(
+            f64::from(color.red()),
+            f64::from(color.green()),
+            f64::from(color.blue()),
+            f64::from(color.alpha()),
+        );
+        cx.set_line_width(2.0);
+        let n_steps = size[0].ceil().max(1.0) as usize;
+        for step in 0..=n_steps {
+            let x = step as f64 / n_steps as f64;
+            let pos = self.curve_to_widget_coords(na::vector![x, spline.eval(x)]);
+
+            if step == 0 {
+                cx.move_to(pos[0], pos[1]);
+            } else {
+                cx.line_to(pos[0], pos[1]);
+            }
+        }
+        cx.stroke()?;
+
+        // the control points
+        for point in spline.points() {
+            let pos = self.curve_to_widget_coords(*point);
+
+            cx.new_sub_path();
+            cx.arc(
+                pos[0],
+                pos[1],
+                Self::POINT_RADIUS,
+                0.0,
+                2.0 * std::f64::consts::PI,
+            );
+        }
+        cx.fill()?;
+
+        Ok(())
+    }
+}
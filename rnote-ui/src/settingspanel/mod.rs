@@ -5,19 +5,22 @@ mod penshortcutrow;
 pub use penshortcutrow::PenShortcutRow;
 
 use adw::prelude::*;
+use gettextrs::gettext;
 use gtk4::{
     gdk, glib, glib::clone, subclass::prelude::*, Adjustment, Button, ColorButton,
-    CompositeTemplate, ScrolledWindow, SpinButton, Switch, ToggleButton, Widget,
+    CompositeTemplate, Entry, Image, ScrolledWindow, SpinButton, Switch, ToggleButton, Widget,
 };
 use num_traits::ToPrimitive;
 use std::cell::RefCell;
 use std::rc::Rc;
 
 use super::appwindow::RnoteAppWindow;
+use crate::dialogs;
 use crate::unitentry::UnitEntry;
 use rnote_compose::penhelpers::ShortcutKey;
-use rnote_engine::document::background::PatternStyle;
-use rnote_engine::document::format::{self, Format, PredefinedFormat};
+use rnote_engine::document::background::{PatternStyle, TemplateFit};
+use rnote_engine::document::format::{self, Format, PageMargins, PredefinedFormat};
+use rnote_engine::pens::penholder::PenStyle;
 use rnote_engine::utils::GdkRGBAHelpers;
 
 mod imp {
@@ -26,6 +29,7 @@ mod imp {
     #[template(resource = "/com/github/flxzt/rnote/ui/settingspanel.ui")]
     pub struct SettingsPanel {
         pub temporary_format: Rc<RefCell<Format>>,
+        pub preset_rows: RefCell<Vec<adw::ActionRow>>,
 
         #[template_child]
         pub settings_scroller: TemplateChild<ScrolledWindow>,
@@ -56,6 +60,18 @@ mod imp {
         #[template_child]
         pub format_dpi_row: TemplateChild<adw::ActionRow>,
         #[template_child]
+        pub format_margin_top_unitentry: TemplateChild<UnitEntry>,
+        #[template_child]
+        pub format_margin_bottom_unitentry: TemplateChild<UnitEntry>,
+        #[template_child]
+        pub format_margin_left_unitentry: TemplateChild<UnitEntry>,
+        #[template_child]
+        pub format_margin_right_unitentry: TemplateChild<UnitEntry>,
+        #[template_child]
+        pub format_header_entry: TemplateChild<Entry>,
+        #[template_child]
+        pub format_footer_entry: TemplateChild<Entry>,
+        #[template_child]
         pub format_dpi_adj: TemplateChild<Adjustment>,
         #[template_child]
         pub format_revert_button: TemplateChild<Button>,
@@ -72,11 +88,31 @@ mod imp {
         #[template_child]
         pub background_pattern_height_unitentry: TemplateChild<UnitEntry>,
         #[template_child]
+        pub background_template_choose_button: TemplateChild<Button>,
+        #[template_child]
+        pub background_template_remove_button: TemplateChild<Button>,
+        #[template_child]
+        pub background_template_fit_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub background_template_pdf_page_adj: TemplateChild<Adjustment>,
+        #[template_child]
         pub penshortcut_stylus_button_primary_row: TemplateChild<PenShortcutRow>,
         #[template_child]
         pub penshortcut_stylus_button_secondary_row: TemplateChild<PenShortcutRow>,
         #[template_child]
         pub penshortcut_mouse_button_secondary_row: TemplateChild<PenShortcutRow>,
+        #[template_child]
+        pub presets_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub presets_name_entry: TemplateChild<Entry>,
+        #[template_child]
+        pub presets_save_brush_button: TemplateChild<Button>,
+        #[template_child]
+        pub presets_save_shaper_button: TemplateChild<Button>,
+        #[template_child]
+        pub presets_import_button: TemplateChild<Button>,
+        #[template_child]
+        pub presets_export_button: TemplateChild<Button>,
     }
 
     #[glib::object_subclass]
@@ -143,6 +179,41 @@ mod imp {
                 .value_spinner()
                 .set_digits(1);
 
+            for margin_unitentry in [
+                &self.format_margin_top_unitentry,
+                &self.format_margin_bottom_unitentry,
+                &self.format_margin_left_unitentry,
+                &self.format_margin_right_unitentry,
+            ] {
+                margin_unitentry.get().value_adj().set_lower(0.0);
+                margin_unitentry
+                    .get()
+                    .value_spinner()
+                    .set_increments(1.0, 10.0);
+                margin_unitentry.get().value_spinner().set_digits(1);
+
+                margin_unitentry.get().connect_local(
+                    "measurement-changed",
+                    false,
+                    clone!(@weak obj as settings_panel => @default-return None, move |_args| {
+                            settings_panel.imp().update_temporary_format_from_rows();
+                            None
+                    }),
+                );
+            }
+
+            self.format_header_entry.connect_changed(
+                clone!(@weak obj as settings_panel => move |_format_header_entry| {
+                    settings_panel.imp().update_temporary_format_from_rows();
+                }),
+            );
+
+            self.format_footer_entry.connect_changed(
+                clone!(@weak obj as settings_panel => move |_format_footer_entry| {
+                    settings_panel.imp().update_temporary_format_from_rows();
+                }),
+            );
+
             self.background_pattern_width_unitentry
                 .get()
                 .value_adj()
@@ -200,6 +271,10 @@ mod imp {
                     settings_panel.imp().update_temporary_format_from_rows();
                     settings_panel.format_width_unitentry().set_dpi(format_dpi_adj.value());
                     settings_panel.format_height_unitentry().set_dpi(format_dpi_adj.value());
+                    settings_panel.imp().format_margin_top_unitentry.set_dpi(format_dpi_adj.value());
+                    settings_panel.imp().format_margin_bottom_unitentry.set_dpi(format_dpi_adj.value());
+                    settings_panel.imp().format_margin_left_unitentry.set_dpi(format_dpi_adj.value());
+                    settings_panel.imp().format_margin_right_unitentry.set_dpi(format_dpi_adj.value());
                 }),
             );
         }
@@ -266,6 +341,34 @@ mod imp {
                 .format_height_unitentry
                 .value_in_px()
                 .clamp(Format::HEIGHT_MIN, Format::HEIGHT_MAX);
+
+            // Margins
+            self.temporary_format.borrow_mut().margins = PageMargins {
+                top: self
+                    .format_margin_top_unitentry
+                    .value_in_px()
+                    .clamp(0.0, Format::MARGIN_MAX),
+                bottom: self
+                    .format_margin_bottom_unitentry
+                    .value_in_px()
+                    .clamp(0.0, Format::MARGIN_MAX),
+                left: self
+                    .format_margin_left_unitentry
+                    .value_in_px()
+                    .clamp(0.0, Format::MARGIN_MAX),
+                right: self
+                    .format_margin_right_unitentry
+                    .value_in_px()
+                    .clamp(0.0, Format::MARGIN_MAX),
+            };
+
+            // Header and footer, empty texts disable them
+            let header = self.format_header_entry.text();
+            self.temporary_format.borrow_mut().header =
+                (!header.is_empty()).then(|| header.to_string());
+            let footer = self.format_footer_entry.text();
+            self.temporary_format.borrow_mut().footer =
+                (!footer.is_empty()).then(|| footer.to_string());
         }
         fn apply_predefined_format(&self) {
             let predefined_format = self.instance().format_predefined_format();
@@ -409,6 +512,24 @@ impl SettingsPanel {
             .set_selected(position);
     }
 
+    pub fn background_template_fit(&self) -> TemplateFit {
+        TemplateFit::try_from(self.imp().background_template_fit_row.get().selected()).unwrap()
+    }
+
+    pub fn set_background_template_fit(&self, fit: TemplateFit) {
+        let position = fit.to_u32().unwrap();
+
+        self.imp()
+            .background_template_fit_row
+            .get()
+            .set_selected(position);
+    }
+
+    /// The index of the pdf page that is used when choosing a pdf file as template
+    pub fn background_template_pdf_page_i(&self) -> u32 {
+        (self.imp().background_template_pdf_page_adj.value().round() as u32).saturating_sub(1)
+    }
+
     pub fn set_format_orientation(&self, orientation: format::Orientation) {
         if orientation == format::Orientation::Portrait {
             self.imp()
@@ -465,6 +586,7 @@ impl SettingsPanel {
         self.load_general(appwindow);
         self.load_format(appwindow);
         self.load_background(appwindow);
+        self.load_presets(appwindow);
         self.load_shortcuts(appwindow);
     }
 
@@ -495,6 +617,37 @@ impl SettingsPanel {
         self.format_height_unitentry()
             .set_unit(format::MeasureUnit::Px);
         self.format_height_unitentry().set_value(format.height);
+
+        self.load_format_margins_and_page_texts(&format);
+    }
+
+    fn load_format_margins_and_page_texts(&self, format: &Format) {
+        for (margin_unitentry, margin) in [
+            (&self.imp().format_margin_top_unitentry, format.margins.top),
+            (
+                &self.imp().format_margin_bottom_unitentry,
+                format.margins.bottom,
+            ),
+            (
+                &self.imp().format_margin_left_unitentry,
+                format.margins.left,
+            ),
+            (
+                &self.imp().format_margin_right_unitentry,
+                format.margins.right,
+            ),
+        ] {
+            margin_unitentry.set_dpi(format.dpi);
+            margin_unitentry.set_unit(format::MeasureUnit::Px);
+            margin_unitentry.set_value(margin);
+        }
+
+        self.imp()
+            .format_header_entry
+            .set_text(format.header.as_deref().unwrap_or(""));
+        self.imp()
+            .format_footer_entry
+            .set_text(format.footer.as_deref().unwrap_or(""));
     }
 
     pub fn load_background(&self, appwindow: &RnoteAppWindow) {
@@ -528,9 +681,89 @@ impl SettingsPanel {
             .set_unit(format::MeasureUnit::Px);
         self.background_pattern_height_unitentry()
             .set_value(background.pattern_size[1]);
+
+        self.imp()
+            .background_template_remove_button
+            .set_sensitive(background.template.is_some());
+        if let Some(template) = &background.template {
+            self.set_background_template_fit(template.fit);
+        }
+    }
+
+    pub fn load_presets(&self, appwindow: &RnoteAppWindow) {
+        let presets = appwindow
+            .canvas()
+            .engine()
+            .borrow()
+            .penholder
+            .list_presets();
+
+        for row in self.imp().preset_rows.borrow_mut().drain(..) {
+            self.imp().presets_group.remove(&row);
+        }
+
+        for (name, pen_style) in presets {
+            let row = adw::ActionRow::builder()
+                .title(&name)
+                .subtitle(&pen_style.name())
+                .activatable(true)
+                .build();
+            row.add_prefix(&Image::from_icon_name(&pen_style.icon_name()));
+
+            let remove_button = Button::builder()
+                .icon_name("trash-symbolic")
+                .tooltip_text(&gettext("Remove the preset"))
+                .valign(gtk4::Align::Center)
+                .build();
+            row.add_suffix(&remove_button);
+
+            row.connect_activated(
+                clone!(@strong name, @weak appwindow => move |_row| {
+                    let widget_flags = appwindow.canvas().engine().borrow_mut().apply_pen_preset(&name);
+
+                    match widget_flags {
+                        Ok(widget_flags) => {
+                            appwindow.handle_widget_flags(widget_flags);
+
+                            if let Err(e) = appwindow.save_engine_config() {
+                                log::error!("saving engine config failed after applying pen preset, Err `{}`", e);
+                            }
+                        }
+                        Err(e) => {
+                            log::error!("applying pen preset `{}` failed, Err {}", name, e);
+                            adw::prelude::ActionGroupExt::activate_action(&appwindow, "error-toast", Some(&gettext("Applying the pen preset failed.").to_variant()));
+                        }
+                    }
+                }),
+            );
+
+            remove_button.connect_clicked(
+                clone!(@strong name, @weak self as settings_panel, @weak appwindow => move |_remove_button| {
+                    appwindow.canvas().engine().borrow_mut().penholder.remove_preset(&name);
+
+                    if let Err(e) = appwindow.save_engine_config() {
+                        log::error!("saving engine config failed after removing pen preset, Err `{}`", e);
+                    }
+                    settings_panel.load_presets(&appwindow);
+                    settings_panel.load_shortcuts(&appwindow);
+                }),
+            );
+
+            self.imp().presets_group.add(&row);
+            self.imp().preset_rows.borrow_mut().push(row);
+        }
     }
 
     pub fn load_shortcuts(&self, appwindow: &RnoteAppWindow) {
+        let preset_names = appwindow
+            .canvas()
+            .engine()
+            .borrow()
+            .penholder
+            .list_presets()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<String>>();
         let current_shortcuts = appwindow
             .canvas()
             .engine()
@@ -538,6 +771,16 @@ impl SettingsPanel {
             .penholder
             .list_current_shortcuts();
 
+        self.imp()
+            .penshortcut_stylus_button_primary_row
+            .set_preset_names(preset_names.clone());
+        self.imp()
+            .penshortcut_stylus_button_secondary_row
+            .set_preset_names(preset_names.clone());
+        self.imp()
+            .penshortcut_mouse_button_secondary_row
+            .set_preset_names(preset_names);
+
         current_shortcuts
             .into_iter()
             .for_each(|(key, action)| match key {
@@ -615,6 +858,8 @@ impl SettingsPanel {
                     .set_value(revert_format.width);
                 settings_panel.format_height_unitentry()
                     .set_value(revert_format.height);
+
+                settings_panel.load_format_margins_and_page_texts(&revert_format);
             }));
 
         // Apply format
@@ -632,7 +877,7 @@ impl SettingsPanel {
         self.imp().background_color_choosebutton.connect_color_set(clone!(@weak appwindow => move |background_color_choosebutton| {
             appwindow.canvas().engine().borrow_mut().document.background.color = background_color_choosebutton.rgba().into_compose_color();
 
-            appwindow.canvas().regenerate_background_pattern();
+            appwindow.canvas().background_changed();
             appwindow.canvas().update_engine_rendering();
         }));
 
@@ -658,16 +903,58 @@ impl SettingsPanel {
                     settings_panel.background_pattern_width_unitentry().set_sensitive(true);
                     settings_panel.background_pattern_height_unitentry().set_sensitive(true);
                 },
+                PatternStyle::IsometricDots | PatternStyle::IsometricGrid | PatternStyle::HexGrid => {
+                    settings_panel.background_pattern_width_unitentry().set_sensitive(true);
+                    settings_panel.background_pattern_height_unitentry().set_sensitive(false);
+                },
+                PatternStyle::Staves | PatternStyle::Cornell => {
+                    settings_panel.background_pattern_width_unitentry().set_sensitive(false);
+                    settings_panel.background_pattern_height_unitentry().set_sensitive(true);
+                },
             }
 
-            appwindow.canvas().regenerate_background_pattern();
+            appwindow.canvas().background_changed();
             appwindow.canvas().update_engine_rendering();
         }));
 
         self.imp().background_pattern_color_choosebutton.connect_color_set(clone!(@weak appwindow => move |background_pattern_color_choosebutton| {
             appwindow.canvas().engine().borrow_mut().document.background.pattern_color = background_pattern_color_choosebutton.rgba().into_compose_color();
 
-            appwindow.canvas().regenerate_background_pattern();
+            appwindow.canvas().background_changed();
+            appwindow.canvas().update_engine_rendering();
+        }));
+
+        self.imp()
+            .background_template_choose_button
+            .get()
+            .connect_clicked(
+                clone!(@weak appwindow => move |_background_template_choose_button| {
+                    dialogs::dialog_import_background_template(&appwindow);
+                }),
+            );
+
+        self.imp()
+            .background_template_remove_button
+            .get()
+            .connect_clicked(
+                clone!(@weak appwindow => move |background_template_remove_button| {
+                    appwindow.canvas().engine().borrow_mut().document.background.template = None;
+                    background_template_remove_button.set_sensitive(false);
+
+                    appwindow.canvas().set_unsaved_changes(true);
+                    appwindow.canvas().background_changed();
+                    appwindow.canvas().update_engine_rendering();
+                }),
+            );
+
+        self.imp().background_template_fit_row.get().connect_selected_item_notify(clone!(@weak self as settings_panel, @weak appwindow => move |_background_template_fit_row| {
+            let fit = settings_panel.background_template_fit();
+
+            if let Some(template) = appwindow.canvas().engine().borrow_mut().document.background.template.as_mut() {
+                template.fit = fit;
+            }
+
+            appwindow.canvas().background_changed();
             appwindow.canvas().update_engine_rendering();
         }));
 
@@ -690,7 +977,7 @@ impl SettingsPanel {
 
                     appwindow.canvas().engine().borrow_mut().document.background.pattern_size = pattern_size;
 
-                    appwindow.canvas().regenerate_background_pattern();
+                    appwindow.canvas().background_changed();
                     appwindow.canvas().update_engine_rendering();
                     None
             }),
@@ -705,7 +992,7 @@ impl SettingsPanel {
 
                     appwindow.canvas().engine().borrow_mut().document.background.pattern_size = pattern_size;
 
-                    appwindow.canvas().regenerate_background_pattern();
+                    appwindow.canvas().background_changed();
                     appwindow.canvas().update_engine_rendering();
                     None
             }),
@@ -738,5 +1025,76 @@ impl SettingsPanel {
             appwindow.canvas().engine().borrow_mut().penholder.register_new_shortcut(ShortcutKey::MouseSecondaryButton, action);
             None
         }));
+
+        // Presets
+        self.imp().presets_save_brush_button.get().connect_clicked(
+            clone!(@weak self as settings_panel, @weak appwindow => move |_presets_save_brush_button| {
+                settings_panel.save_preset(&appwindow, PenStyle::Brush);
+            }),
+        );
+
+        self.imp().presets_save_shaper_button.get().connect_clicked(
+            clone!(@weak self as settings_panel, @weak appwindow => move |_presets_save_shaper_button| {
+                settings_panel.save_preset(&appwindow, PenStyle::Shaper);
+            }),
+        );
+
+        self.imp().presets_import_button.get().connect_clicked(
+            clone!(@weak appwindow => move |_presets_import_button| {
+                dialogs::dialog_import_presets(&appwindow);
+            }),
+        );
+
+        self.imp().presets_export_button.get().connect_clicked(
+            clone!(@weak appwindow => move |_presets_export_button| {
+                dialogs::dialog_export_presets(&appwindow);
+            }),
+        );
+    }
+
+    /// Saves the configuration of the pen as preset with the name from the name entry
+    fn save_preset(&self, appwindow: &RnoteAppWindow, pen_style: PenStyle) {
+        let name = self
+            .imp()
+            .presets_name_entry
+            .get()
+            .text()
+            .trim()
+            .to_string();
+        if name.is_empty() {
+            adw::prelude::ActionGroupExt::activate_action(
+                appwindow,
+                "error-toast",
+                Some(&gettext("The preset needs a name.").to_variant()),
+            );
+            return;
+        }
+
+        match pen_style {
+            PenStyle::Brush => appwindow
+                .canvas()
+                .engine()
+                .borrow_mut()
+                .penholder
+                .save_brush_as_preset(name),
+            PenStyle::Shaper => appwindow
+                .canvas()
+                .engine()
+                .borrow_mut()
+                .penholder
+                .save_shaper_as_preset(name),
+            _ => return,
+        }
+
+        if let Err(e) = appwindow.save_engine_config() {
+            log::error!(
+                "saving engine config failed after saving pen preset, Err `{}`",
+                e
+            );
+        }
+
+        self.imp().presets_name_entry.get().set_text("");
+        self.load_presets(appwindow);
+        self.load_shortcuts(appwindow);
     }
 }
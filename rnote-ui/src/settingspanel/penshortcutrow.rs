@@ -2,14 +2,16 @@ use crate::settingspanel::penshortcutmodels::{
     ChangePenStyleIconFactory, ChangePenStyleListFactory, ChangePenStyleListModel,
 };
 use adw::{prelude::*, subclass::prelude::*};
+use gettextrs::gettext;
 use gtk4::{
     glib, glib::clone, glib::subclass::*, subclass::prelude::*, CheckButton, CompositeTemplate,
+    DropDown, StringList,
 };
 use once_cell::sync::Lazy;
 use rnote_compose::penhelpers::ShortcutKey;
 use rnote_engine::pens::penholder::PenStyle;
 use rnote_engine::pens::shortcuts::ShortcutAction;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 
 mod imp {
     use super::*;
@@ -19,9 +21,14 @@ mod imp {
         pub key: RefCell<Option<ShortcutKey>>,
         pub action: RefCell<ShortcutAction>,
         pub changepenstyle_model: ChangePenStyleListModel,
+        pub preset_names: RefCell<Vec<String>>,
+        /// Set while the preset dropdown is updated programmatically, so that it does not change the action
+        pub updating_presets: Cell<bool>,
 
         #[template_child]
         pub permanent_checker: TemplateChild<CheckButton>,
+        #[template_child]
+        pub preset_dropdown: TemplateChild<DropDown>,
     }
 
     impl Default for PenShortcutRow {
@@ -34,6 +41,9 @@ mod imp {
                 }),
                 permanent_checker: TemplateChild::<CheckButton>::default(),
                 changepenstyle_model: ChangePenStyleListModel::default(),
+                preset_names: RefCell::new(vec![]),
+                updating_presets: Cell::new(false),
+                preset_dropdown: TemplateChild::<DropDown>::default(),
             }
         }
     }
@@ -66,14 +76,8 @@ mod imp {
             obj.set_factory(Some(&*icon_factory));
 
             obj.connect_selected_item_notify(move |obj| {
-                if let Some(selected_item) = obj.selected_item() {
-                    let new_pen_style = PenStyle::try_from(
-                        selected_item
-                            .downcast::<adw::EnumListItem>()
-                            .unwrap()
-                            .value() as u32,
-                    )
-                    .unwrap();
+                if let Some(new_pen_style) = obj.selected_pen_style() {
+                    let permanent = obj.imp().permanent_checker.get().is_active();
 
                     match &mut *obj.imp().action.borrow_mut() {
                         ShortcutAction::ChangePenStyle {
@@ -82,6 +86,13 @@ mod imp {
                         } => {
                             *style = new_pen_style;
                         }
+                        // Choosing a pen replaces the preset
+                        action @ ShortcutAction::ApplyPreset { .. } => {
+                            *action = ShortcutAction::ChangePenStyle {
+                                style: new_pen_style,
+                                permanent,
+                            };
+                        }
                     }
                     obj.emit_by_name::<()>("action-changed", &[]);
                 }
@@ -93,11 +104,39 @@ mod imp {
                         ShortcutAction::ChangePenStyle { style: _, ref mut permanent } => {
                             *permanent = permanent_checker.is_active();
                         }
+                        ShortcutAction::ApplyPreset { .. } => return,
                     }
                     obj.emit_by_name::<()>("action-changed", &[]);
                 }),
             );
 
+            self.preset_dropdown.get().connect_selected_notify(
+                clone!(@weak obj => move |preset_dropdown| {
+                    if obj.imp().updating_presets.get() {
+                        return;
+                    }
+
+                    // The first item is the entry for no preset
+                    let new_action = match preset_dropdown.selected() {
+                        0 | gtk4::INVALID_LIST_POSITION => match obj.action() {
+                            ShortcutAction::ApplyPreset { .. } => ShortcutAction::ChangePenStyle {
+                                style: obj.selected_pen_style().unwrap_or_default(),
+                                permanent: obj.imp().permanent_checker.get().is_active(),
+                            },
+                            action @ ShortcutAction::ChangePenStyle { .. } => action,
+                        },
+                        i => match obj.imp().preset_names.borrow().get(i as usize - 1) {
+                            Some(name) => ShortcutAction::ApplyPreset { name: name.clone() },
+                            None => return,
+                        },
+                    };
+
+                    if new_action != obj.action() {
+                        obj.set_action(new_action);
+                    }
+                }),
+            );
+
             obj.connect_local(
                 "key-changed",
                 false,
@@ -156,12 +195,57 @@ impl PenShortcutRow {
 
     fn update_ui(&self) {
         let action = self.action();
+
+        self.imp().updating_presets.set(true);
         match action {
             ShortcutAction::ChangePenStyle { style, permanent } => {
                 self.set_selected(self.imp().changepenstyle_model.find_position(style as i32));
                 self.imp().permanent_checker.set_active(permanent);
+                self.imp().permanent_checker.set_sensitive(true);
+                self.imp().preset_dropdown.set_selected(0);
+            }
+            ShortcutAction::ApplyPreset { name } => {
+                let position = self
+                    .imp()
+                    .preset_names
+                    .borrow()
+                    .iter()
+                    .position(|preset_name| *preset_name == name)
+                    .map(|i| i as u32 + 1)
+                    .unwrap_or(0);
+
+                self.imp().permanent_checker.set_sensitive(false);
+                self.imp().preset_dropdown.set_selected(position);
             }
         }
+        self.imp().updating_presets.set(false);
+    }
+
+    fn selected_pen_style(&self) -> Option<PenStyle> {
+        self.selected_item().map(|selected_item| {
+            PenStyle::try_from(
+                selected_item
+                    .downcast::<adw::EnumListItem>()
+                    .unwrap()
+                    .value() as u32,
+            )
+            .unwrap()
+        })
+    }
+
+    /// Sets the names of the presets that can be chosen as action
+    pub fn set_preset_names(&self, preset_names: Vec<String>) {
+        let model = StringList::new(&[gettext("No preset").as_str()]);
+        for name in preset_names.iter() {
+            model.append(name);
+        }
+
+        self.imp().updating_presets.set(true);
+        *self.imp().preset_names.borrow_mut() = preset_names;
+        self.imp().preset_dropdown.set_model(Some(&model));
+        self.imp().updating_presets.set(false);
+
+        self.update_ui();
     }
 
     pub fn key(&self) -> Option<ShortcutKey> {
@@ -174,7 +258,7 @@ impl PenShortcutRow {
     }
 
     pub fn action(&self) -> ShortcutAction {
-        *self.imp().action.borrow()
+        self.imp().action.borrow().clone()
     }
 
     pub fn set_action(&self, action: ShortcutAction) {
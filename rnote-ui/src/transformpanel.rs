@@ -0,0 +1,222 @@
+use adw::prelude::*;
+use gtk4::{
+    glib, glib::clone, subclass::prelude::*, Adjustment, Button, CompositeTemplate, ScrolledWindow,
+    Switch, Widget,
+};
+use p2d::bounding_volume::AABB;
+
+use super::appwindow::RnoteAppWindow;
+use crate::unitentry::UnitEntry;
+use rnote_engine::document::format::MeasureUnit;
+
+mod imp {
+    use super::*;
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/com/github/flxzt/rnote/ui/transformpanel.ui")]
+    pub struct TransformPanel {
+        #[template_child]
+        pub transform_scroller: TemplateChild<ScrolledWindow>,
+        #[template_child]
+        pub transform_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub transform_x_unitentry: TemplateChild<UnitEntry>,
+        #[template_child]
+        pub transform_y_unitentry: TemplateChild<UnitEntry>,
+        #[template_child]
+        pub transform_width_unitentry: TemplateChild<UnitEntry>,
+        #[template_child]
+        pub transform_height_unitentry: TemplateChild<UnitEntry>,
+        #[template_child]
+        pub transform_lock_aspectratio_switch: TemplateChild<Switch>,
+        #[template_child]
+        pub transform_rotation_adj: TemplateChild<Adjustment>,
+        #[template_child]
+        pub transform_mirror_axis_adj: TemplateChild<Adjustment>,
+        #[template_child]
+        pub transform_mirror_button: TemplateChild<Button>,
+        #[template_child]
+        pub transform_revert_button: TemplateChild<Button>,
+        #[template_child]
+        pub transform_apply_button: TemplateChild<Button>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for TransformPanel {
+        const NAME: &'static str = "TransformPanel";
+        type Type = super::TransformPanel;
+        type ParentType = gtk4::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            Self::bind_template(klass);
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for TransformPanel {
+        fn constructed(&self, obj: &Self::Type) {
+            self.parent_constructed(obj);
+
+            for unitentry in [
+                self.transform_x_unitentry.get(),
+                self.transform_y_unitentry.get(),
+                self.transform_width_unitentry.get(),
+                self.transform_height_unitentry.get(),
+            ] {
+                unitentry.value_spinner().set_increments(1.0, 10.0);
+                unitentry.value_spinner().set_digits(1);
+            }
+        }
+
+        fn dispose(&self, obj: &Self::Type) {
+            while let Some(child) = obj.first_child() {
+                child.unparent();
+            }
+        }
+    }
+
+    impl WidgetImpl for TransformPanel {}
+}
+
+glib::wrapper! {
+    pub struct TransformPanel(ObjectSubclass<imp::TransformPanel>)
+    @extends Widget;
+}
+
+impl Default for TransformPanel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TransformPanel {
+    pub fn new() -> Self {
+        glib::Object::new(&[]).expect("Failed to create TransformPanel")
+    }
+
+    pub fn transform_scroller(&self) -> ScrolledWindow {
+        self.imp().transform_scroller.get()
+    }
+
+    pub fn transform_x_unitentry(&self) -> UnitEntry {
+        self.imp().transform_x_unitentry.get()
+    }
+
+    pub fn transform_y_unitentry(&self) -> UnitEntry {
+        self.imp().transform_y_unitentry.get()
+    }
+
+    pub fn transform_width_unitentry(&self) -> UnitEntry {
+        self.imp().transform_width_unitentry.get()
+    }
+
+    pub fn transform_height_unitentry(&self) -> UnitEntry {
+        self.imp().transform_height_unitentry.get()
+    }
+
+    pub fn init(&self, appwindow: &RnoteAppWindow) {
+        // Load the current selection every time the panel is shown
+        self.connect_map(clone!(@weak appwindow => move |transform_panel| {
+            transform_panel.refresh_ui(&appwindow);
+        }));
+
+        self.imp().transform_lock_aspectratio_switch.get().connect_state_notify(
+            clone!(@weak appwindow => move |transform_lock_aspectratio_switch| {
+                let lock_aspectratio = transform_lock_aspectratio_switch.state();
+
+                appwindow.canvas().engine().borrow_mut().penholder.selector.resize_lock_aspectratio = lock_aspectratio;
+                appwindow.penssidebar().selector_page().resize_lock_aspectratio_togglebutton().set_active(lock_aspectratio);
+            }),
+        );
+
+        self.imp().transform_revert_button.get().connect_clicked(
+            clone!(@weak self as transform_panel, @weak appwindow => move |_transform_revert_button| {
+                transform_panel.refresh_ui(&appwindow);
+            }),
+        );
+
+        self.imp().transform_mirror_button.get().connect_clicked(
+            clone!(@weak self as transform_panel, @weak appwindow => move |_transform_mirror_button| {
+                let axis_angle = transform_panel.imp().transform_mirror_axis_adj.value().to_radians();
+
+                let widget_flags = appwindow.canvas().engine().borrow_mut().mirror_selection(axis_angle, None);
+                appwindow.handle_widget_flags(widget_flags);
+
+                transform_panel.refresh_ui(&appwindow);
+            }),
+        );
+
+        self.imp().transform_apply_button.get().connect_clicked(
+            clone!(@weak self as transform_panel, @weak appwindow => move |_transform_apply_button| {
+                let mins = na::vector![
+                    transform_panel.transform_x_unitentry().value_in_px(),
+                    transform_panel.transform_y_unitentry().value_in_px()
+                ];
+                let extents = na::vector![
+                    transform_panel.transform_width_unitentry().value_in_px(),
+                    transform_panel.transform_height_unitentry().value_in_px()
+                ];
+                let angle = transform_panel.imp().transform_rotation_adj.value().to_radians();
+
+                let widget_flags = appwindow.canvas().engine().borrow_mut().transform_selection(
+                    AABB::new(na::Point2::from(mins), na::Point2::from(mins + extents)),
+                    angle,
+                );
+                appwindow.handle_widget_flags(widget_flags);
+
+                transform_panel.refresh_ui(&appwindow);
+            }),
+        );
+    }
+
+    pub fn refresh_ui(&self, appwindow: &RnoteAppWindow) {
+        let dpi = appwindow.canvas().engine().borrow().document.format.dpi;
+        let selection_bounds = appwindow.canvas().engine().borrow().selection_bounds();
+        let lock_aspectratio = appwindow
+            .canvas()
+            .engine()
+            .borrow()
+            .penholder
+            .selector
+            .resize_lock_aspectratio;
+
+        self.imp()
+            .transform_lock_aspectratio_switch
+            .set_active(lock_aspectratio);
+        // The rotation is applied relative to the current orientation
+        self.imp().transform_rotation_adj.set_value(0.0);
+
+        self.imp()
+            .transform_group
+            .set_sensitive(selection_bounds.is_some());
+
+        let selection_bounds = match selection_bounds {
+            Some(selection_bounds) => selection_bounds,
+            None => return,
+        };
+
+        for (unitentry, value) in [
+            (self.transform_x_unitentry(), selection_bounds.mins[0]),
+            (self.transform_y_unitentry(), selection_bounds.mins[1]),
+            (
+                self.transform_width_unitentry(),
+                selection_bounds.extents()[0],
+            ),
+            (
+                self.transform_height_unitentry(),
+                selection_bounds.extents()[1],
+            ),
+        ] {
+            unitentry.set_dpi(dpi);
+            unitentry.set_value(MeasureUnit::convert_measurement(
+                value,
+                MeasureUnit::Px,
+                dpi,
+                unitentry.unit(),
+                dpi,
+            ));
+        }
+    }
+}